@@ -0,0 +1,26 @@
+//! Links the Graphviz C library for the `graphviz-lib` feature, found through pkg-config
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "graphviz-lib")]
+    link_graphviz();
+}
+
+/// `render::graphviz_lib` is written against the Graphviz 3 C interface, where `gvRenderData`
+/// gives the length as a `size_t` instead of an `unsigned int`. Linking an older version would
+/// build fine and then corrupt memory, so it is refused here.
+#[cfg(feature = "graphviz-lib")]
+fn link_graphviz() {
+    for library in ["libgvc", "libcgraph"] {
+        if let Err(error) = pkg_config::Config::new()
+            .atleast_version("3.0")
+            .probe(library)
+        {
+            panic!(
+                "The graphviz-lib feature needs {} from Graphviz 3.0 or later, with its \
+                 development files (e.g. libgraphviz-dev): {}",
+                library, error
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! On-disk cache of the extracted model, so repeated scans only re-parse the files that changed
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::model::FlowModel;
+
+/// Name of the cache directory created in the root of a scanned project
+pub const CACHE_DIR_NAME: &str = ".flowchart-cache";
+
+const CACHE_FILE_NAME: &str = "model.json";
+
+/// How much of a scan could be taken from the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheUse {
+    /// There was no usable cache, so every file was parsed
+    Rebuilt,
+    /// The cached model was used, re-parsing this many changed, added or deleted files
+    Reused { reparsed: usize },
+}
+
+/// The model of a previous scan, with the content hash of every file it was extracted from
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedModel<'a> {
+    version: String, // Version of this tool; extraction may differ between versions
+    root: PathBuf,
+    hashes: Cow<'a, HashMap<PathBuf, String>>, // Keyed by the file path, as in `model.files`
+    model: Cow<'a, FlowModel>,
+}
+
+impl CachedModel<'_> {
+    /// True if the cached model was extracted from `root` with the same tool version and
+    /// settings, and from files the current scan still covers. A file that exists but is no longer
+    /// scanned means the include or exclude globs changed, which the cache cannot follow.
+    pub fn is_usable(
+        &self,
+        root: &Path,
+        config: &Config,
+        hashes: &HashMap<PathBuf, String>,
+    ) -> bool {
+        self.version == env!("CARGO_PKG_VERSION")
+            && self.root == root
+            && self.model.config == *config
+            && self
+                .hashes
+                .keys()
+                .all(|file| hashes.contains_key(file) || !file.exists())
+    }
+
+    /// The files that were added, changed or deleted since the model was cached
+    pub fn changed_files(&self, hashes: &HashMap<PathBuf, String>) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = hashes
+            .iter()
+            .filter(|(file, hash)| self.hashes.get(*file) != Some(*hash))
+            .map(|(file, _)| file.clone())
+            .collect();
+        changed.extend(
+            self.hashes
+                .keys()
+                .filter(|file| !hashes.contains_key(*file))
+                .cloned(),
+        );
+        changed.sort();
+        changed
+    }
+
+    pub fn into_model(self) -> FlowModel {
+        self.model.into_owned()
+    }
+}
+
+/// Hash the content of `files`, calling `progress` with the number done and the total after each
+pub fn hash_files(
+    files: &[PathBuf],
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<HashMap<PathBuf, String>> {
+    let mut hashes = HashMap::new();
+    for (done, file) in files.iter().enumerate() {
+        let content =
+            fs::read(file).with_context(|| format!("Failed to read file: {}", file.display()))?;
+        let hash: String = Sha256::digest(&content)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        hashes.insert(file.clone(), hash);
+        progress(done + 1, files.len());
+    }
+    Ok(hashes)
+}
+
+/// Read the cache of the project in `root`. A missing, unreadable or outdated cache file is no
+/// error; the project is then simply scanned in full.
+pub fn load(root: &Path) -> Option<CachedModel<'static>> {
+    let text = fs::read_to_string(root.join(CACHE_DIR_NAME).join(CACHE_FILE_NAME)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Write the model and the hashes of the files it was extracted from to the cache of `root`
+pub fn save(root: &Path, hashes: &HashMap<PathBuf, String>, model: &FlowModel) -> Result<()> {
+    let dir = root.join(CACHE_DIR_NAME);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+    // Keep the cache out of version control without touching the project's own .gitignore
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n")
+            .with_context(|| format!("Failed to write {}", gitignore.display()))?;
+    }
+
+    let cached = CachedModel {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        root: root.to_path_buf(),
+        hashes: Cow::Borrowed(hashes),
+        model: Cow::Borrowed(model),
+    };
+    let json = serde_json::to_string(&cached).context("Failed to serialize the cache")?;
+    // Written next to the cache and renamed, so an interrupted run never leaves half a file
+    let path = dir.join(CACHE_FILE_NAME);
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, json)
+        .with_context(|| format!("Failed to write cache file: {}", partial.display()))?;
+    fs::rename(&partial, &path)
+        .with_context(|| format!("Failed to write cache file: {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Project settings read from a `.behandling-flow.toml` in the project root
+
+use anyhow::{Context, Result};
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Name of the config file looked up in the root of a scanned project
+pub const CONFIG_FILE_NAME: &str = ".behandling-flow.toml";
+
+/// Settings of a scanned project. Every field has a default, so the file may be left out or
+/// only set what differs.
+///
+/// ```toml
+/// [discovery]
+/// aktivitet-annotations = ["Aktivitet"]
+/// processor-annotations = ["Prosessor", "no.nav.flyt.AktivitetHandler"]
+///
+/// [conventions]
+/// neste-aktivitet = "nextActivity"
+///
+/// [lint]
+/// max-complexity = 25
+///
+/// [[lint.rules]]
+/// name = "every sak is decided"
+/// must-reach = ["IverksettVedtakAktivitet", "AvbrytAktivitet"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub discovery: DiscoveryConfig,
+    pub conventions: ConventionsConfig,
+    pub lint: LintConfig,
+}
+
+/// How aktiviteter and processors are recognized, besides their class names
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    /// Annotations that mark a class as an aktivitet, whatever its name
+    pub aktivitet_annotations: Vec<String>,
+    /// Annotations that mark a class as a processor, e.g. `@Prosessor(VurderAktivitet::class)`.
+    /// A class literal argument names the aktivitet; without one it is taken from the supertype.
+    pub processor_annotations: Vec<String>,
+    /// Functions that bind a processor to its aktivitet, wherever they are called:
+    /// `registrer(VurderAktivitet::class, VurderProcessor())`
+    pub registry_functions: Vec<String>,
+    /// Spring annotations that make a class the processor of the aktivitet its bean is named
+    /// after: `@Component("VurderAktivitet")`
+    pub component_annotations: Vec<String>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        DiscoveryConfig {
+            aktivitet_annotations: vec!["Aktivitet".to_string()],
+            processor_annotations: vec!["Prosessor".to_string()],
+            registry_functions: vec!["registrer".to_string(), "register".to_string()],
+            component_annotations: vec!["Component".to_string(), "Service".to_string()],
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    /// True if an annotation named `name` marks an aktivitet
+    pub fn is_aktivitet_annotation(&self, name: &str) -> bool {
+        matches_annotation(&self.aktivitet_annotations, name)
+    }
+
+    /// True if an annotation named `name` marks a processor
+    pub fn is_processor_annotation(&self, name: &str) -> bool {
+        matches_annotation(&self.processor_annotations, name)
+    }
+
+    /// True if an annotation named `name` makes a bean named after an aktivitet its processor
+    pub fn is_component_annotation(&self, name: &str) -> bool {
+        matches_annotation(&self.component_annotations, name)
+    }
+}
+
+/// Names of the framework functions that make transitions and drive a processor, for forks of the
+/// Behandling framework that call them differently
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConventionsConfig {
+    /// Starts the next aktivitet: `nesteAktivitet(VurderAktivitet())`
+    pub neste_aktivitet: String,
+    /// Starts several aktiviteter at once: `nesteAktiviteter(listOf(...))`
+    pub neste_aktiviteter: String,
+    /// The processor function that runs the aktivitet
+    pub do_process: String,
+    /// The processor function called once the aktivitet is done
+    pub on_finished: String,
+    /// The Behandling function that creates the first aktivitet
+    pub opprett_initiell_aktivitet: String,
+    /// The property a manuell behandling is assigned to: `manuellBehandling = ManuellBehandling(...)`
+    pub manuell_behandling: String,
+    /// Functions that create a manuell behandling: `opprettManuellBehandling(krav, ...)`
+    pub manuell_behandling_factories: Vec<String>,
+    /// Functions that make a processor wait until another Behandling is done, which is named by
+    /// class literal, constructor or type argument: `ventPaaBehandling(UforetrygdBehandling::class)`
+    pub vent_paa_behandling: Vec<String>,
+    /// Functions that resume another Behandling that waits, named as for `vent-paa-behandling`:
+    /// `gjenopptaBehandling<UforetrygdBehandling>(sakId)`
+    pub gjenoppta_behandling: Vec<String>,
+}
+
+impl Default for ConventionsConfig {
+    fn default() -> Self {
+        ConventionsConfig {
+            neste_aktivitet: "nesteAktivitet".to_string(),
+            neste_aktiviteter: "nesteAktiviteter".to_string(),
+            do_process: "doProcess".to_string(),
+            on_finished: "onFinished".to_string(),
+            opprett_initiell_aktivitet: "opprettInitiellAktivitet".to_string(),
+            manuell_behandling: "manuellBehandling".to_string(),
+            manuell_behandling_factories: vec!["opprettManuellBehandling".to_string()],
+            vent_paa_behandling: vec!["ventPaaBehandling".to_string()],
+            gjenoppta_behandling: vec!["gjenopptaBehandling".to_string()],
+        }
+    }
+}
+
+impl ConventionsConfig {
+    /// The processor functions whose transitions make up the processor's flow
+    pub fn processor_functions(&self) -> [&str; 2] {
+        [&self.do_process, &self.on_finished]
+    }
+
+    /// True if `name` is one of the [`processor_functions`](Self::processor_functions)
+    pub fn is_processor_function(&self, name: &str) -> bool {
+        self.processor_functions().contains(&name)
+    }
+
+    /// The class assigned to the manuell behandling property: `ManuellBehandling`
+    pub fn manuell_behandling_class(&self) -> String {
+        let mut chars = self.manuell_behandling.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Thresholds of the `lint` checks that are a matter of taste rather than a mistake
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LintConfig {
+    /// Flows with a higher complexity (transitions − nodes + 2 · components) are reported as
+    /// `complex-flow`. Not checked if unset.
+    pub max_complexity: Option<usize>,
+    /// Project rules every flow must follow, reported as `flow-rule`
+    pub rules: Vec<FlowRule>,
+}
+
+/// A rule about the shape of a flow, checked by `lint`. Each rule sets exactly one of
+/// `must-reach`, `must-pass` and `in-cycle`. Aktiviteter and Behandlinger are given by class
+/// name, with or without the package, or a glob like `*Vent*`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FlowRule {
+    /// Shown in violations instead of the rule itself
+    pub name: Option<String>,
+    /// Behandlinger the rule applies to; all of them if empty
+    pub behandlinger: Vec<String>,
+    /// Every path through the flow must reach one of these aktiviteter before it ends, whether
+    /// it completes, aborts or stops at a dead end
+    pub must_reach: Vec<String>,
+    /// No path to a completion (`aktivitetFullfort()`) may skip any of these aktiviteter
+    pub must_pass: Vec<String>,
+    /// Aktiviteter matching these must be inside a cycle, like a wait that is retried
+    pub in_cycle: Vec<String>,
+}
+
+impl FlowRule {
+    /// The name of the rule, or what it checks: `must-reach A or B`
+    pub fn describe(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        if !self.must_reach.is_empty() {
+            format!("must-reach {}", self.must_reach.join(" or "))
+        } else if !self.must_pass.is_empty() {
+            format!("must-pass {}", self.must_pass.join(" and "))
+        } else {
+            format!("in-cycle {}", self.in_cycle.join(", "))
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let kinds = [&self.must_reach, &self.must_pass, &self.in_cycle];
+        if kinds.iter().filter(|patterns| !patterns.is_empty()).count() != 1 {
+            anyhow::bail!("Set exactly one of must-reach, must-pass and in-cycle");
+        }
+        for pattern in kinds.into_iter().flatten().chain(&self.behandlinger) {
+            Glob::new(pattern).with_context(|| format!("Invalid pattern: {}", pattern))?;
+        }
+        Ok(())
+    }
+}
+
+/// Annotations are compared by simple name, so both `Prosessor` and `no.nav.Prosessor` may be
+/// configured for `@Prosessor`
+fn matches_annotation(configured: &[String], name: &str) -> bool {
+    let simple_name = |name: &str| name.rsplit('.').next().unwrap_or(name).to_string();
+    let name = simple_name(name);
+    configured
+        .iter()
+        .any(|annotation| simple_name(annotation) == name)
+}
+
+impl Config {
+    /// Read the config file in `root`, or the defaults if there is none
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CONFIG_FILE_NAME);
+        if !path.is_file() {
+            return Ok(Config::default());
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let config: Config = toml::from_str(&text)
+            .with_context(|| format!("Invalid config file: {}", path.display()))?;
+        for (index, rule) in config.lint.rules.iter().enumerate() {
+            rule.validate()
+                .with_context(|| format!("Invalid lint rule {} ({})", index + 1, rule.describe()))
+                .with_context(|| format!("Invalid config file: {}", path.display()))?;
+        }
+        Ok(config)
+    }
+}
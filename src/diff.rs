@@ -0,0 +1,560 @@
+//! Comparison of two flow models, per Behandling
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::graph::{
+    collect_flow_edges, escape_label, flow_aktiviteter, graph_header, shorten_aktivitet_name,
+    DotOptions,
+};
+use crate::model::{ClassInfo, FlowModel};
+
+/// Colors of the diff graph: (outline, fill) of added and removed aktiviteter, and the color of
+/// transitions whose condition changed
+const ADDED_COLORS: (&str, &str) = ("#2E7D32", "#C8E6C9");
+const REMOVED_COLORS: (&str, &str) = ("#C62828", "#FFCDD2");
+const CHANGED_COLOR: &str = "#FF8F00";
+const UNCHANGED_COLOR: &str = "#9E9E9E";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Transition {
+    pub from: String,
+    pub to: String,
+    pub condition: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionChange {
+    pub from: String,
+    pub to: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Changes to a single Behandling flow
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BehandlingDiff {
+    pub behandling: String,
+    pub kind: ChangeKind,
+    pub added_aktiviteter: Vec<String>,
+    pub removed_aktiviteter: Vec<String>,
+    pub added_transitions: Vec<Transition>,
+    pub removed_transitions: Vec<Transition>,
+    pub changed_conditions: Vec<ConditionChange>,
+}
+
+impl BehandlingDiff {
+    /// True if nothing changed in the flow
+    pub fn is_empty(&self) -> bool {
+        self.added_aktiviteter.is_empty()
+            && self.removed_aktiviteter.is_empty()
+            && self.added_transitions.is_empty()
+            && self.removed_transitions.is_empty()
+            && self.changed_conditions.is_empty()
+    }
+}
+
+/// Aktiviteter and transitions reachable in one flow. The transitions are keyed by from/to, with
+/// the conditions of every transition between the two; `None` for one without a condition.
+#[derive(Default)]
+struct FlowContents {
+    initial_aktivitet: Option<String>,
+    aktiviteter: BTreeSet<String>,
+    transitions: BTreeMap<(String, String), BTreeSet<Option<String>>>,
+}
+
+/// How one transition between two aktiviteter compares between two flows
+enum TransitionChange<'a> {
+    Unchanged(&'a Option<String>),
+    Added(&'a Option<String>),
+    Removed(&'a Option<String>),
+    Changed(&'a Option<String>, &'a Option<String>),
+}
+
+/// Compare the conditions of the transitions between the same two aktiviteter. Conditions that
+/// are only in `before` and only in `after` are paired up as changed, in order; the ones left
+/// over were removed or added.
+fn transition_changes<'a>(
+    before: Option<&'a BTreeSet<Option<String>>>,
+    after: Option<&'a BTreeSet<Option<String>>>,
+) -> Vec<TransitionChange<'a>> {
+    static NONE: BTreeSet<Option<String>> = BTreeSet::new();
+    let before = before.unwrap_or(&NONE);
+    let after = after.unwrap_or(&NONE);
+    let mut removed = before.difference(after);
+    let mut added = after.difference(before);
+
+    let mut changes: Vec<TransitionChange> = before
+        .intersection(after)
+        .map(TransitionChange::Unchanged)
+        .collect();
+    loop {
+        match (removed.next(), added.next()) {
+            (Some(old), Some(new)) => changes.push(TransitionChange::Changed(old, new)),
+            (Some(old), None) => changes.push(TransitionChange::Removed(old)),
+            (None, Some(new)) => changes.push(TransitionChange::Added(new)),
+            (None, None) => break,
+        }
+    }
+    changes
+}
+
+/// The flows of a project as they are expected to be, committed as JSON so CI can fail on any
+/// change to them that was not meant, like a snapshot test
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub behandlinger: BTreeMap<String, BaselineFlow>,
+}
+
+/// The aktiviteter and transitions of one Behandling in a [`Baseline`], sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaselineFlow {
+    pub initial_aktivitet: Option<String>,
+    pub aktiviteter: Vec<String>,
+    pub transitions: Vec<Transition>,
+}
+
+impl Baseline {
+    /// The flows of every Behandling in the model
+    pub fn from_model(model: &FlowModel) -> Self {
+        let behandlinger = flows(model)
+            .into_iter()
+            .map(|(name, contents)| {
+                let flow = BaselineFlow {
+                    transitions: transitions(&contents),
+                    aktiviteter: contents.aktiviteter.into_iter().collect(),
+                    initial_aktivitet: contents.initial_aktivitet,
+                };
+                (name, flow)
+            })
+            .collect();
+        Baseline { behandlinger }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid baseline: {}", path.display()))
+    }
+
+    /// Write the baseline as pretty JSON, so changes to it read well in a review
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize the baseline")?;
+        fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write baseline: {}", path.display()))
+    }
+
+    fn flows(&self) -> BTreeMap<String, FlowContents> {
+        self.behandlinger
+            .iter()
+            .map(|(name, flow)| {
+                let mut contents = FlowContents {
+                    initial_aktivitet: flow.initial_aktivitet.clone(),
+                    aktiviteter: flow.aktiviteter.iter().cloned().collect(),
+                    transitions: BTreeMap::new(),
+                };
+                for t in &flow.transitions {
+                    contents
+                        .transitions
+                        .entry((t.from.clone(), t.to.clone()))
+                        .or_default()
+                        .insert(t.condition.clone());
+                }
+                (name.clone(), contents)
+            })
+            .collect()
+    }
+}
+
+fn flow_contents(model: &FlowModel, info: &ClassInfo) -> FlowContents {
+    let mut contents = FlowContents {
+        initial_aktivitet: info.initial_aktivitet.clone(),
+        ..FlowContents::default()
+    };
+    let Some(initial_aktivitet) = &info.initial_aktivitet else {
+        return contents;
+    };
+
+    let edges = collect_flow_edges(
+        initial_aktivitet,
+        &model.processor_index,
+        &model.class_index,
+    );
+    contents.aktiviteter = flow_aktiviteter(initial_aktivitet, &edges)
+        .into_iter()
+        .collect();
+
+    // Use the raw conditions from the processors so that any change to them is visible
+    for aktivitet in &contents.aktiviteter {
+        if let Some(processor) = model.processor_index.get(aktivitet) {
+            for next in &processor.next_aktiviteter {
+                let condition = match &next.error_type {
+                    Some(error_type) => Some(format!("catch {}", error_type)),
+                    None => next.condition.as_ref().map(ToString::to_string),
+                };
+                contents
+                    .transitions
+                    .entry((aktivitet.clone(), next.aktivitet_name.clone()))
+                    .or_default()
+                    .insert(condition);
+            }
+            if processor.has_manuell_behandling {
+                contents
+                    .transitions
+                    .entry((aktivitet.clone(), "manuell".to_string()))
+                    .or_default()
+                    .insert(None);
+            }
+        }
+    }
+
+    // How the flow ends, the manuelle behandlinger of its error paths and the Behandlinger it
+    // starts, with the result or reason it ends with as the condition
+    for edge in edges.iter().filter(|edge| exit_label(&edge.to).is_some()) {
+        let condition = Some(edge.label.clone()).filter(|label| !label.is_empty());
+        contents
+            .transitions
+            .entry((edge.from.clone(), edge.to.clone()))
+            .or_default()
+            .insert(condition);
+    }
+
+    contents
+}
+
+/// The label of the node a flow exits to, or `None` for an aktivitet
+fn exit_label(node: &str) -> Option<String> {
+    match node {
+        "end" => Some("END".to_string()),
+        "abort" => Some("ABORT".to_string()),
+        "unresolved" => Some("END?".to_string()),
+        "manuell" => Some("📋 Manuell behandling".to_string()),
+        _ => node
+            .strip_prefix("spawn_")
+            .map(|behandling| format!("starts ▷ {}", behandling)),
+    }
+}
+
+fn transitions(contents: &FlowContents) -> Vec<Transition> {
+    contents
+        .transitions
+        .iter()
+        .flat_map(|((from, to), conditions)| {
+            conditions.iter().map(|condition| Transition {
+                from: from.clone(),
+                to: to.clone(),
+                condition: condition.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The contents of every Behandling flow in the model, by Behandling
+fn flows(model: &FlowModel) -> BTreeMap<String, FlowContents> {
+    model
+        .behandlinger()
+        .into_iter()
+        .map(|(name, info)| (name.clone(), flow_contents(model, info)))
+        .collect()
+}
+
+/// Compare every Behandling flow in `old` with the one in `new`; unchanged flows are left out
+pub fn diff_models(old: &FlowModel, new: &FlowModel) -> Vec<BehandlingDiff> {
+    diff_flows(flows(old), flows(new))
+}
+
+/// Compare the flows in a baseline with the ones in the model, as [`diff_models`] does: what the
+/// model adds to the baseline is added
+pub fn diff_baseline(baseline: &Baseline, model: &FlowModel) -> Vec<BehandlingDiff> {
+    diff_flows(baseline.flows(), flows(model))
+}
+
+fn diff_flows(
+    mut old_flows: BTreeMap<String, FlowContents>,
+    mut new_flows: BTreeMap<String, FlowContents>,
+) -> Vec<BehandlingDiff> {
+    let names: BTreeSet<String> = old_flows.keys().chain(new_flows.keys()).cloned().collect();
+
+    let mut diffs = Vec::new();
+    for name in &names {
+        let old_contents = old_flows.remove(name);
+        let new_contents = new_flows.remove(name);
+
+        let diff = match (old_contents, new_contents) {
+            (None, Some(added)) => BehandlingDiff {
+                behandling: name.clone(),
+                kind: ChangeKind::Added,
+                added_aktiviteter: added.aktiviteter.iter().cloned().collect(),
+                removed_aktiviteter: Vec::new(),
+                added_transitions: transitions(&added),
+                removed_transitions: Vec::new(),
+                changed_conditions: Vec::new(),
+            },
+            (Some(removed), None) => BehandlingDiff {
+                behandling: name.clone(),
+                kind: ChangeKind::Removed,
+                added_aktiviteter: Vec::new(),
+                removed_aktiviteter: removed.aktiviteter.iter().cloned().collect(),
+                added_transitions: Vec::new(),
+                removed_transitions: transitions(&removed),
+                changed_conditions: Vec::new(),
+            },
+            (Some(before), Some(after)) => {
+                let mut diff = BehandlingDiff {
+                    behandling: name.clone(),
+                    kind: ChangeKind::Changed,
+                    added_aktiviteter: after
+                        .aktiviteter
+                        .difference(&before.aktiviteter)
+                        .cloned()
+                        .collect(),
+                    removed_aktiviteter: before
+                        .aktiviteter
+                        .difference(&after.aktiviteter)
+                        .cloned()
+                        .collect(),
+                    added_transitions: Vec::new(),
+                    removed_transitions: Vec::new(),
+                    changed_conditions: Vec::new(),
+                };
+                let keys: BTreeSet<&(String, String)> = before
+                    .transitions
+                    .keys()
+                    .chain(after.transitions.keys())
+                    .collect();
+                for key in keys {
+                    let (from, to) = key.clone();
+                    let transition = |condition: &Option<String>| Transition {
+                        from: from.clone(),
+                        to: to.clone(),
+                        condition: condition.clone(),
+                    };
+                    let changes =
+                        transition_changes(before.transitions.get(key), after.transitions.get(key));
+                    for change in changes {
+                        match change {
+                            TransitionChange::Unchanged(_) => {}
+                            TransitionChange::Added(condition) => {
+                                diff.added_transitions.push(transition(condition))
+                            }
+                            TransitionChange::Removed(condition) => {
+                                diff.removed_transitions.push(transition(condition))
+                            }
+                            TransitionChange::Changed(old, new) => {
+                                diff.changed_conditions.push(ConditionChange {
+                                    from: from.clone(),
+                                    to: to.clone(),
+                                    old: old.clone(),
+                                    new: new.clone(),
+                                })
+                            }
+                        }
+                    }
+                }
+                if diff.is_empty() {
+                    continue;
+                }
+                diff
+            }
+            (None, None) => continue,
+        };
+        diffs.push(diff);
+    }
+
+    diffs
+}
+
+/// One DOT graph of the Behandling as it is in `old` and `new` together: added aktiviteter and
+/// transitions are green, removed ones red and dashed, and transitions whose condition changed
+/// amber, labeled with the old and the new condition. Only the edge style, condition labels,
+/// legend and resolution of `options` apply.
+pub fn generate_diff_graph(
+    old: &FlowModel,
+    new: &FlowModel,
+    behandling: &str,
+    options: &DotOptions,
+) -> String {
+    let contents = |model: &FlowModel| {
+        model
+            .behandlinger()
+            .into_iter()
+            .find(|(name, _)| name.as_str() == behandling)
+            .map(|(_, info)| flow_contents(model, info))
+            .unwrap_or_default()
+    };
+    let before = contents(old);
+    let after = contents(new);
+
+    let mut dot = graph_header(options.edge_style, options.dpi);
+    dot.push_str(&format!(
+        "  labelloc=\"t\";\n  label=\"{} Flow Changes\";\n  fontsize=16;\n\n",
+        escape_label(behandling)
+    ));
+
+    dot.push_str("  start [label=\"START\", shape=circle, style=filled, fillcolor=\"#90EE90\"];\n");
+    for aktivitet in before.aktiviteter.union(&after.aktiviteter) {
+        let style = match (
+            before.aktiviteter.contains(aktivitet),
+            after.aktiviteter.contains(aktivitet),
+        ) {
+            (false, true) => format!(
+                ", style=\"filled,rounded\", color=\"{}\", fillcolor=\"{}\", penwidth=2",
+                ADDED_COLORS.0, ADDED_COLORS.1
+            ),
+            (true, false) => format!(
+                ", style=\"filled,rounded,dashed\", color=\"{}\", fillcolor=\"{}\", fontcolor=\"{}\"",
+                REMOVED_COLORS.0, REMOVED_COLORS.1, REMOVED_COLORS.0
+            ),
+            _ => String::new(),
+        };
+        dot.push_str(&format!(
+            "  \"{}\" [label=\"{}\"{}];\n",
+            escape_label(aktivitet),
+            escape_label(&shorten_aktivitet_name(aktivitet)),
+            style
+        ));
+    }
+    let exits: BTreeSet<&String> = before
+        .transitions
+        .keys()
+        .chain(after.transitions.keys())
+        .map(|(_, to)| to)
+        .collect();
+    for exit in exits {
+        if let Some(label) = exit_label(exit) {
+            dot.push_str(&format!(
+                "  \"{}\" [label=\"{}\", shape=box, style=\"rounded,dashed\"];\n",
+                escape_label(exit),
+                escape_label(&label)
+            ));
+        }
+    }
+    dot.push('\n');
+
+    let added = format!("color=\"{}\", penwidth=2", ADDED_COLORS.0);
+    let removed = format!("color=\"{}\", style=dashed", REMOVED_COLORS.0);
+    let unchanged = format!("color=\"{}\"", UNCHANGED_COLOR);
+
+    if before.initial_aktivitet == after.initial_aktivitet {
+        if let Some(initial) = &after.initial_aktivitet {
+            dot.push_str(&diff_edge("start", initial, &unchanged, None));
+        }
+    } else {
+        if let Some(initial) = &before.initial_aktivitet {
+            dot.push_str(&diff_edge("start", initial, &removed, None));
+        }
+        if let Some(initial) = &after.initial_aktivitet {
+            dot.push_str(&diff_edge("start", initial, &added, None));
+        }
+    }
+
+    let keys: BTreeSet<&(String, String)> = before
+        .transitions
+        .keys()
+        .chain(after.transitions.keys())
+        .collect();
+    for key in keys {
+        let (from, to) = key;
+        let changes = transition_changes(before.transitions.get(key), after.transitions.get(key));
+        for change in changes {
+            let edge = match change {
+                TransitionChange::Added(condition) => {
+                    let label = condition.as_deref().map(short_condition);
+                    diff_edge(from, to, &added, label.as_deref())
+                }
+                TransitionChange::Removed(condition) => {
+                    let label = condition.as_deref().map(short_condition);
+                    diff_edge(from, to, &removed, label.as_deref())
+                }
+                TransitionChange::Changed(old_condition, new_condition) => {
+                    let label = format!(
+                        "− {}\n+ {}",
+                        short_condition(old_condition.as_deref().unwrap_or("always")),
+                        short_condition(new_condition.as_deref().unwrap_or("always"))
+                    );
+                    let style = format!(
+                        "color=\"{}\", fontcolor=\"{}\", penwidth=2",
+                        CHANGED_COLOR, CHANGED_COLOR
+                    );
+                    diff_edge(from, to, &style, Some(&label))
+                }
+                TransitionChange::Unchanged(condition) => {
+                    let label = condition
+                        .as_deref()
+                        .filter(|_| options.show_conditions)
+                        .map(short_condition);
+                    diff_edge(from, to, &unchanged, label.as_deref())
+                }
+            };
+            dot.push_str(&edge);
+        }
+    }
+
+    if options.show_legend {
+        dot.push_str("\n  // Legend\n");
+        dot.push_str("  {rank=sink;\n");
+        dot.push_str("    Legend [shape=none, margin=0, label=<\n");
+        dot.push_str(
+            "      <TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\">\n",
+        );
+        dot.push_str("        <TR>\n");
+        dot.push_str("          <TD COLSPAN=\"2\" BGCOLOR=\"#E8E8E8\"><B>Legend</B></TD>\n");
+        dot.push_str("        </TR>\n");
+        for (color, description) in [
+            (ADDED_COLORS.1, "Added"),
+            (REMOVED_COLORS.1, "Removed"),
+            (CHANGED_COLOR, "Condition changed"),
+        ] {
+            dot.push_str("        <TR>\n");
+            dot.push_str(&format!("          <TD BGCOLOR=\"{}\">  </TD>\n", color));
+            dot.push_str(&format!(
+                "          <TD ALIGN=\"LEFT\">{}</TD>\n",
+                description
+            ));
+            dot.push_str("        </TR>\n");
+        }
+        dot.push_str("      </TABLE>\n");
+        dot.push_str("    >];\n");
+        dot.push_str("  }\n");
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// A transition of the diff graph
+fn diff_edge(from: &str, to: &str, style: &str, label: Option<&str>) -> String {
+    let label = label
+        .map(|label| format!(", label=\"{}\"", escape_label(label)))
+        .unwrap_or_default();
+    format!(
+        "  \"{}\" -> \"{}\" [{}{}];\n",
+        escape_label(from),
+        escape_label(to),
+        style,
+        label
+    )
+}
+
+/// A condition cut at 80 characters, as on flow graphs
+fn short_condition(condition: &str) -> String {
+    if condition.chars().count() > 80 {
+        format!("{}...", condition.chars().take(77).collect::<String>())
+    } else {
+        condition.to_string()
+    }
+}
@@ -0,0 +1,620 @@
+//! Text exports of the flows: Markdown, Mermaid, AsciiDoc, Confluence and Cypher
+
+use std::collections::HashMap;
+
+use crate::graph::{
+    collect_flow_edges, creates_manuell_behandling, detect_cycles, flow_aktiviteter, format_cycle,
+    is_deprecated_aktivitet, is_marker_node, mandatory_steps, node_color, reachability_matrix,
+    shorten_aktivitet_name, strike_through, summarize_flow, LEGEND_ENTRIES,
+};
+use crate::model::{ClassInfo, Edge, NextAktivitet, ProcessorInfo};
+
+pub fn generate_markdown_report(
+    behandling_name: &str,
+    behandling_info: &ClassInfo,
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    show_conditions: bool,
+) -> String {
+    let edges = collect_flow_edges(initial_aktivitet, processor_index, class_index);
+    let summary = summarize_flow(initial_aktivitet, processor_index, class_index, &edges);
+
+    let mut md = String::new();
+    md.push_str(&format!("# {} Flow\n\n", behandling_name));
+    md.push_str(&format!(
+        "_Generated by behandling-flow from `{}`. Do not edit by hand._\n\n",
+        behandling_info.file.display()
+    ));
+
+    md.push_str("## Summary\n\n");
+    md.push_str("| Metric | Value |\n");
+    md.push_str("|--------|-------|\n");
+    md.push_str(&format!(
+        "| Initial aktivitet | `{}` |\n",
+        initial_aktivitet
+    ));
+    md.push_str(&format!("| Aktiviteter | {} |\n", summary.aktivitet_count));
+    md.push_str(&format!("| Transitions | {} |\n", summary.transition_count));
+    md.push_str(&format!("| Branch points | {} |\n", summary.branch_points));
+    md.push_str(&format!("| Complexity | {} |\n", summary.complexity));
+    md.push_str(&format!("| Cycles | {} |\n", summary.cycles));
+    md.push_str(&format!(
+        "| Longest path to END | {} steps |\n",
+        summary.longest_to_end()
+    ));
+    md.push_str(&format!(
+        "| Manual steps | {} |\n\n",
+        summary.manual_steps.len()
+    ));
+
+    if let Some(steps) = mandatory_steps(initial_aktivitet, &edges) {
+        md.push_str("### Mandatory steps\n\n");
+        md.push_str("Every completed behandling passes through:\n\n");
+        for step in &steps.mandatory {
+            md.push_str(&format!("1. `{}`\n", step));
+        }
+        md.push('\n');
+        if !steps.skippable.is_empty() {
+            md.push_str(&format!(
+                "Skippable: {}\n\n",
+                steps
+                    .skippable
+                    .iter()
+                    .map(|step| format!("`{}`", step))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    if !summary.cycle_paths.is_empty() {
+        md.push_str("### Cycles\n\n");
+        for cycle in &summary.cycle_paths {
+            md.push_str(&format!("- 🔄 {}\n", format_cycle(cycle)));
+        }
+        md.push('\n');
+    }
+
+    if !summary.deprecated_steps.is_empty() {
+        md.push_str("### Deprecated steps still reached\n\n");
+        for step in &summary.deprecated_steps {
+            md.push_str(&format!("- ⊘ `{}`\n", step));
+        }
+        md.push('\n');
+    }
+
+    if !summary.manual_steps.is_empty() {
+        md.push_str("### Manual steps\n\n");
+        for step in &summary.manual_steps {
+            // The oppgave it creates, if its kategori or frist could be read
+            let details: Vec<String> = processor_index
+                .get(step)
+                .into_iter()
+                .flat_map(|processor| &processor.manuell_behandlinger)
+                .filter_map(|manuell| manuell.details())
+                .collect();
+            if details.is_empty() {
+                md.push_str(&format!("- 📋 `{}`\n", step));
+            } else {
+                md.push_str(&format!("- 📋 `{}`: {}\n", step, details.join("; ")));
+            }
+        }
+        md.push('\n');
+    }
+
+    md.push_str("## Feature toggles\n\n");
+    if summary.feature_toggles.is_empty() {
+        md.push_str("_None_\n\n");
+    } else {
+        for toggle in &summary.feature_toggles {
+            md.push_str(&format!("- 🚩 `{}`\n", toggle));
+        }
+        md.push('\n');
+    }
+
+    md.push_str("## Flow\n\n");
+    md.push_str("```mermaid\n");
+    md.push_str(&generate_mermaid_diagram(
+        initial_aktivitet,
+        &edges,
+        processor_index,
+        class_index,
+        show_conditions,
+    ));
+    md.push_str("```\n");
+
+    md
+}
+
+pub fn generate_mermaid_diagram(
+    initial_aktivitet: &str,
+    edges: &[Edge],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    show_conditions: bool,
+) -> String {
+    let mut mermaid = String::new();
+    mermaid.push_str("flowchart TD\n");
+    mermaid.push_str("    flow_start((START))\n");
+
+    // Node definitions
+    for aktivitet in flow_aktiviteter(initial_aktivitet, edges) {
+        let display_name = shorten_aktivitet_name(&aktivitet).replace('\n', " ");
+        let deprecated = is_deprecated_aktivitet(&aktivitet, class_index);
+        let display_name = if deprecated {
+            format!("{} ⊘ deprecated", strike_through(&display_name))
+        } else {
+            display_name
+        };
+        let label = if creates_manuell_behandling(&aktivitet, processor_index) {
+            format!("📋 {}", display_name)
+        } else {
+            display_name
+        };
+        mermaid.push_str(&format!(
+            "    {}[\"{}\"]\n",
+            mermaid_id(&aktivitet),
+            escape_mermaid_label(label.trim())
+        ));
+        mermaid.push_str(&format!(
+            "    style {} fill:{}{}\n",
+            mermaid_id(&aktivitet),
+            node_color(&aktivitet, processor_index, class_index),
+            if deprecated {
+                ",color:#777777,stroke-dasharray: 5 5"
+            } else {
+                ""
+            }
+        ));
+    }
+    if edges.iter().any(|e| e.to == "end") {
+        mermaid.push_str("    flow_end((END))\n");
+    }
+    if edges.iter().any(|e| e.to == "abort") {
+        mermaid.push_str("    abort{{\"ABORT\"}}\n");
+        mermaid.push_str("    style abort fill:#FF4444,color:#FFFFFF\n");
+    }
+    if edges.iter().any(|e| e.to == "unresolved") {
+        mermaid.push_str("    unresolved((\"END?\"))\n");
+        mermaid.push_str("    style unresolved fill:#EEEEEE,stroke-dasharray: 5 5\n");
+    }
+    for edge in edges.iter().filter(|e| e.to.starts_with("unknown_")) {
+        mermaid.push_str(&format!("    {}{{\"?\"}}\n", mermaid_id(&edge.to)));
+    }
+    let spread: std::collections::BTreeSet<&str> = edges
+        .iter()
+        .filter_map(|e| e.to.strip_prefix("dynamic_"))
+        .collect();
+    for collection in spread {
+        let id = mermaid_id(&format!("dynamic_{}", collection));
+        mermaid.push_str(&format!(
+            "    {}[\"*{}\"]\n",
+            id,
+            escape_mermaid_label(collection)
+        ));
+        mermaid.push_str(&format!(
+            "    style {} fill:#EEEEEE,stroke-dasharray: 5 5\n",
+            id
+        ));
+    }
+    let spawned: std::collections::BTreeSet<&str> = edges
+        .iter()
+        .filter_map(|e| e.to.strip_prefix("spawn_"))
+        .collect();
+    for behandling in spawned {
+        let id = mermaid_id(&format!("spawn_{}", behandling));
+        mermaid.push_str(&format!(
+            "    {}([\"starts ▷ {}\"])\n",
+            id,
+            escape_mermaid_label(behandling)
+        ));
+        mermaid.push_str(&format!("    style {} fill:#E6E6FA\n", id));
+    }
+    if edges.iter().any(|e| e.to == "manuell") {
+        mermaid.push_str("    manuell[\"📋 Manuell behandling\"]\n");
+        mermaid.push_str("    style manuell fill:#FFA500\n");
+    }
+    mermaid.push_str("    style flow_start fill:#90EE90\n");
+    if edges.iter().any(|e| e.to == "end") {
+        mermaid.push_str("    style flow_end fill:#FFB6C1\n");
+    }
+
+    // Edges, one per (from, to) pair
+    mermaid.push_str(&format!(
+        "    flow_start --> {}\n",
+        mermaid_id(initial_aktivitet)
+    ));
+
+    let cycle_edges: std::collections::HashSet<(String, String)> =
+        detect_cycles(initial_aktivitet, processor_index)
+            .into_iter()
+            .collect();
+    let mut seen = std::collections::HashSet::new();
+
+    for edge in edges {
+        if !seen.insert((edge.from.clone(), edge.to.clone())) {
+            continue;
+        }
+
+        let arrow = if edge.to.starts_with("unknown_")
+            || edge.to.starts_with("spawn_")
+            || edge.to.starts_with("dynamic_")
+            || edge.to == "unresolved"
+            || edge.is_error
+            || cycle_edges.contains(&(edge.from.clone(), edge.to.clone()))
+        {
+            "-.->"
+        } else if edge.is_collection {
+            "==>"
+        } else {
+            "-->"
+        };
+
+        let label = if edge.is_error {
+            // Error paths always name the exception
+            format!("⚡ {}", edge.label)
+        } else if edge.is_collection {
+            if show_conditions && !edge.label.is_empty() && edge.label != "else" {
+                format!("{} (multiple)", edge.label)
+            } else {
+                "multiple".to_string()
+            }
+        } else if show_conditions && !edge.label.is_empty() && edge.label != "else" {
+            edge.label.clone()
+        } else {
+            String::new()
+        };
+
+        if label.is_empty() {
+            mermaid.push_str(&format!(
+                "    {} {} {}\n",
+                mermaid_id(&edge.from),
+                arrow,
+                mermaid_id(&edge.to)
+            ));
+        } else {
+            mermaid.push_str(&format!(
+                "    {} {}|\"{}\"| {}\n",
+                mermaid_id(&edge.from),
+                arrow,
+                escape_mermaid_label(&label),
+                mermaid_id(&edge.to)
+            ));
+        }
+    }
+
+    mermaid
+}
+
+pub fn generate_asciidoc(
+    behandling_name: &str,
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    image_name: &str,
+) -> String {
+    let edges = collect_flow_edges(initial_aktivitet, processor_index, class_index);
+    let summary = summarize_flow(initial_aktivitet, processor_index, class_index, &edges);
+    let aktiviteter = flow_aktiviteter(initial_aktivitet, &edges);
+
+    let mut adoc = String::new();
+    adoc.push_str(&format!("= {} Flow\n", behandling_name));
+    adoc.push_str(&format!(":behandling: {}\n", behandling_name));
+    adoc.push_str(&format!(":initial-aktivitet: {}\n", initial_aktivitet));
+    adoc.push_str(&format!(":aktivitet-count: {}\n", summary.aktivitet_count));
+    adoc.push_str(&format!(
+        ":transition-count: {}\n",
+        summary.transition_count
+    ));
+    adoc.push_str(&format!(":branch-point-count: {}\n", summary.branch_points));
+    adoc.push_str(&format!(":cycle-count: {}\n", summary.cycles));
+    adoc.push_str(&format!(
+        ":manual-step-count: {}\n\n",
+        summary.manual_steps.len()
+    ));
+    adoc.push_str("// Generated by behandling-flow. Do not edit by hand.\n\n");
+
+    adoc.push_str("== Diagram\n\n");
+    adoc.push_str(&format!(
+        "image::{}[{} flow]\n\n",
+        image_name, behandling_name
+    ));
+
+    adoc.push_str("== Aktiviteter\n\n");
+    adoc.push_str("[cols=\"3,3,1,3\",options=\"header\"]\n");
+    adoc.push_str("|===\n");
+    adoc.push_str("|Aktivitet |Processor |Manuell behandling |Next\n\n");
+    for aktivitet in &aktiviteter {
+        let (processor, manuell, next) = match processor_index.get(aktivitet) {
+            Some(processor) => {
+                let next = if processor.is_unresolved() {
+                    "END? (no transition found)".to_string()
+                } else {
+                    processor
+                        .next_aktiviteter
+                        .iter()
+                        .map(|n| format!("`{}`", n.aktivitet_name))
+                        .chain(processor.terminals.iter().map(|t| t.describe()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                (
+                    format!("`{}`", processor.processor_class),
+                    if processor.has_manuell_behandling {
+                        "Yes"
+                    } else {
+                        "No"
+                    },
+                    next,
+                )
+            }
+            None => ("_not found_".to_string(), "-", "?".to_string()),
+        };
+        adoc.push_str(&format!(
+            "|`{}` |{} |{} |{}\n",
+            aktivitet, processor, manuell, next
+        ));
+    }
+    adoc.push_str("|===\n\n");
+
+    adoc.push_str("== Conditions\n\n");
+    let conditions: Vec<(&String, &NextAktivitet)> = aktiviteter
+        .iter()
+        .filter_map(|a| processor_index.get(a).map(|p| (a, p)))
+        .flat_map(|(a, p)| {
+            p.next_aktiviteter
+                .iter()
+                .filter(|n| n.condition.is_some())
+                .map(move |n| (a, n))
+        })
+        .collect();
+
+    if conditions.is_empty() {
+        adoc.push_str("_No conditional transitions._\n");
+    } else {
+        adoc.push_str("[cols=\"2,2,5\",options=\"header\"]\n");
+        adoc.push_str("|===\n");
+        adoc.push_str("|From |To |Condition\n\n");
+        for (from, next) in conditions {
+            adoc.push_str(&format!(
+                "|`{}` |`{}` |`{}`\n",
+                from,
+                next.aktivitet_name,
+                escape_asciidoc_cell(
+                    &next
+                        .condition
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default()
+                )
+            ));
+        }
+        adoc.push_str("|===\n");
+    }
+
+    adoc
+}
+
+fn escape_asciidoc_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Page body in Confluence storage format: summary, diagram and legend
+pub fn generate_confluence_page_body(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    attachment_name: &str,
+) -> String {
+    let edges = collect_flow_edges(initial_aktivitet, processor_index, class_index);
+    let summary = summarize_flow(initial_aktivitet, processor_index, class_index, &edges);
+
+    let mut body = String::new();
+    body.push_str(
+        "<p><em>Generated by behandling-flow. Manual edits are overwritten on the next publish.</em></p>",
+    );
+
+    body.push_str("<h2>Summary</h2><table><tbody>");
+    body.push_str("<tr><th>Metric</th><th>Value</th></tr>");
+    let rows = [
+        ("Initial aktivitet", initial_aktivitet.to_string()),
+        ("Aktiviteter", summary.aktivitet_count.to_string()),
+        ("Transitions", summary.transition_count.to_string()),
+        ("Branch points", summary.branch_points.to_string()),
+        ("Cycles", summary.cycles.to_string()),
+        ("Manual steps", summary.manual_steps.len().to_string()),
+        ("Feature toggles", summary.feature_toggles.join(", ")),
+    ];
+    for (metric, value) in rows {
+        body.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>",
+            metric,
+            escape_html(&value)
+        ));
+    }
+    body.push_str("</tbody></table>");
+
+    body.push_str("<h2>Diagram</h2>");
+    body.push_str(&format!(
+        "<p><ac:image><ri:attachment ri:filename=\"{}\" /></ac:image></p>",
+        escape_html(attachment_name)
+    ));
+
+    body.push_str("<h2>Legend</h2><table><tbody>");
+    for (color, description) in LEGEND_ENTRIES {
+        body.push_str(&format!(
+            "<tr><td data-highlight-colour=\"{}\"></td><td>{}</td></tr>",
+            color,
+            escape_html(description)
+        ));
+    }
+    body.push_str("</tbody></table>");
+
+    body
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Cypher MERGE statements for all flows: (:Behandling)-[:STARTS_WITH]->(:Aktivitet)-[:NEXT]->(:Aktivitet)
+pub fn generate_cypher(
+    main_behandling_classes: &[(&String, &ClassInfo)],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+) -> String {
+    let mut cypher = String::new();
+    cypher.push_str("// Generated by behandling-flow\n");
+    cypher.push_str("CREATE CONSTRAINT aktivitet_name IF NOT EXISTS FOR (a:Aktivitet) REQUIRE a.name IS UNIQUE;\n");
+    cypher.push_str("CREATE CONSTRAINT behandling_name IF NOT EXISTS FOR (b:Behandling) REQUIRE b.name IS UNIQUE;\n\n");
+
+    for (name, info) in main_behandling_classes {
+        let Some(initial_aktivitet) = &info.initial_aktivitet else {
+            continue;
+        };
+
+        let edges = collect_flow_edges(initial_aktivitet, processor_index, class_index);
+        let cycle_edges: std::collections::HashSet<(String, String)> =
+            detect_cycles(initial_aktivitet, processor_index)
+                .into_iter()
+                .collect();
+
+        cypher.push_str(&format!("// {}\n", name));
+        cypher.push_str(&format!(
+            "MERGE (b:Behandling {{name: '{}'}});\n",
+            escape_cypher(name)
+        ));
+
+        for aktivitet in flow_aktiviteter(initial_aktivitet, &edges) {
+            let processor = processor_index.get(&aktivitet);
+            let terminal = edges.iter().any(|e| e.from == aktivitet && e.to == "end");
+            cypher.push_str(&format!(
+                "MERGE (a:Aktivitet {{name: '{}'}}) SET a.processor = {}, a.manuellBehandling = {}, a.terminal = {};\n",
+                escape_cypher(&aktivitet),
+                processor
+                    .map(|p| format!("'{}'", escape_cypher(&p.processor_class)))
+                    .unwrap_or_else(|| "null".to_string()),
+                processor.map(|p| p.has_manuell_behandling).unwrap_or(false),
+                terminal
+            ));
+        }
+
+        cypher.push_str(&format!(
+            "MATCH (b:Behandling {{name: '{}'}}), (a:Aktivitet {{name: '{}'}}) MERGE (b)-[:STARTS_WITH]->(a);\n",
+            escape_cypher(name),
+            escape_cypher(initial_aktivitet)
+        ));
+
+        let mut seen = std::collections::HashSet::new();
+        for edge in &edges {
+            if is_marker_node(&edge.to) {
+                continue;
+            }
+            if !seen.insert((edge.from.clone(), edge.to.clone())) {
+                continue;
+            }
+
+            let condition = if edge.label.is_empty() {
+                "null".to_string()
+            } else {
+                format!("'{}'", escape_cypher(&edge.label))
+            };
+            cypher.push_str(&format!(
+                "MATCH (a:Aktivitet {{name: '{}'}}), (b:Aktivitet {{name: '{}'}}) MERGE (a)-[r:NEXT {{behandling: '{}'}}]->(b) SET r.condition = {}, r.collection = {}, r.error = {}, r.cycle = {};\n",
+                escape_cypher(&edge.from),
+                escape_cypher(&edge.to),
+                escape_cypher(name),
+                condition,
+                edge.is_collection,
+                edge.is_error,
+                cycle_edges.contains(&(edge.from.clone(), edge.to.clone()))
+            ));
+        }
+        cypher.push('\n');
+    }
+
+    cypher
+}
+
+/// The reachability matrix of one flow as CSV: a header row with every aktivitet, then a row per
+/// aktivitet with `1` under each aktivitet that can follow it and `0` elsewhere
+pub fn generate_reachability_csv(initial_aktivitet: &str, edges: &[Edge]) -> String {
+    let matrix = reachability_matrix(initial_aktivitet, edges);
+    let mut csv = String::from("from");
+    for aktivitet in &matrix.aktiviteter {
+        csv.push_str(&format!(",{}", escape_csv(aktivitet)));
+    }
+    csv.push('\n');
+    for (aktivitet, row) in matrix.aktiviteter.iter().zip(&matrix.reachable) {
+        csv.push_str(&escape_csv(aktivitet));
+        for &reachable in row {
+            csv.push_str(if reachable { ",1" } else { ",0" });
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+/// The reachability matrices of all flows as JSON, keyed by Behandling:
+/// `{"aktiviteter": [...], "reachable": [[true, false, ...], ...]}`
+pub fn generate_reachability_json(
+    main_behandling_classes: &[(&String, &ClassInfo)],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+) -> String {
+    let mut flows = serde_json::Map::new();
+    for (name, info) in main_behandling_classes {
+        let Some(initial_aktivitet) = &info.initial_aktivitet else {
+            continue;
+        };
+        let edges = collect_flow_edges(initial_aktivitet, processor_index, class_index);
+        let matrix = reachability_matrix(initial_aktivitet, &edges);
+        flows.insert(
+            name.to_string(),
+            serde_json::json!({
+                "aktiviteter": matrix.aktiviteter,
+                "reachable": matrix.reachable,
+            }),
+        );
+    }
+    let mut json = serde_json::to_string_pretty(&flows).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+/// A CSV field, quoted if it contains a separator, quote or line break
+fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn escape_cypher(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Mermaid node ids must be plain identifiers, and `end` is a reserved word
+fn mermaid_id(name: &str) -> String {
+    if name == "end" {
+        return "flow_end".to_string();
+    }
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn escape_mermaid_label(s: &str) -> String {
+    s.replace('"', "#quot;").replace('\n', " ")
+}
@@ -0,0 +1,4008 @@
+//! Extraction of Behandling classes and aktivitet processors from the Kotlin syntax tree
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Parser, Tree};
+
+use crate::config::{Config, ConventionsConfig};
+use crate::model::{
+    AktivitetConstant, ClassInfo, Condition, FlowModel, FunctionInfo, ManuellBehandling,
+    NextAktivitet, ParseError, ProcessorInfo, ProcessorRegistration, SourceLocation, Terminal,
+    TerminalKind, TypeAlias,
+};
+
+/// The project-wide indexes that transitions in one file may refer to
+#[derive(Debug, Clone, Copy)]
+pub struct ProjectIndexes<'a> {
+    pub functions: &'a HashMap<String, Vec<FunctionInfo>>,
+    pub constants: &'a HashMap<String, AktivitetConstant>,
+    pub classes: &'a HashMap<String, ClassInfo>,
+    pub registrations: &'a HashMap<String, ProcessorRegistration>, // Keyed by processor class
+    pub config: &'a Config,
+}
+
+impl ProjectIndexes<'_> {
+    /// True if `name` looks like an aktivitet class, or the class carries a configured
+    /// aktivitet annotation
+    fn is_aktivitet_class(&self, name: &str) -> bool {
+        is_likely_aktivitet_class(name)
+            || self.classes.get(name).is_some_and(|info| {
+                info.annotations
+                    .iter()
+                    .any(|annotation| self.config.discovery.is_aktivitet_annotation(annotation))
+            })
+    }
+}
+
+/// A Kotlin file read and parsed once, so that every index is built from the same tree
+pub struct ParsedFile {
+    pub path: PathBuf,
+    pub source: String,
+    pub tree: Tree,
+}
+
+/// Read and parse `files`, adding the parts Tree-sitter could not parse to `parse_errors`.
+/// Calls `progress` with the number of files done and the total after each file.
+pub fn parse_files(
+    parser: &mut Parser,
+    files: &[PathBuf],
+    parse_errors: &mut Vec<ParseError>,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<Vec<ParsedFile>> {
+    let mut parsed = Vec::with_capacity(files.len());
+
+    for (done, file) in files.iter().enumerate() {
+        let source = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {}", file.display()))?;
+        let tree = parser
+            .parse(&source, None)
+            .with_context(|| format!("Failed to parse file: {}", file.display()))?;
+
+        extract_parse_errors(&source, tree.root_node(), file, parse_errors);
+        parsed.push(ParsedFile {
+            path: file.clone(),
+            source,
+            tree,
+        });
+        progress(done + 1, files.len());
+    }
+
+    Ok(parsed)
+}
+
+/// Index all classes in `files`, with the initial aktivitet of Behandlinger found by the names in
+/// `conventions`. `progress` is called with the work done and the total after each file; every
+/// tree is walked twice, so the total is twice the number of files.
+pub fn build_class_index(
+    files: &[ParsedFile],
+    conventions: &ConventionsConfig,
+    progress: &mut dyn FnMut(usize, usize),
+) -> HashMap<String, ClassInfo> {
+    let mut classes = Vec::new();
+    let total = files.len() * 2;
+
+    for (done, file) in files.iter().enumerate() {
+        // Extract all class declarations
+        extract_classes(
+            &file.source,
+            file.tree.root_node(),
+            &file.path,
+            &mut classes,
+        );
+        progress(done + 1, total);
+    }
+
+    let mut index = HashMap::new();
+    add_classes(&mut index, classes);
+
+    // Second pass: extract opprettInitiellAktivitet for Behandling classes
+    for (done, file) in files.iter().enumerate() {
+        extract_initial_aktivitet(&file.source, file.tree.root_node(), conventions, &mut index);
+        progress(files.len() + done + 1, total);
+    }
+
+    index
+}
+
+/// Extract classes and processors from a single source that is not on disk (e.g. stdin)
+pub fn extract_source(
+    parser: &mut Parser,
+    file: &Path,
+    source_code: &str,
+    model: &mut FlowModel,
+) -> Result<()> {
+    let tree = parser
+        .parse(source_code, None)
+        .context("Failed to parse source")?;
+    let root_node = tree.root_node();
+
+    let mut classes = Vec::new();
+    extract_classes(source_code, root_node, &file.to_path_buf(), &mut classes);
+    add_classes(&mut model.class_index, classes);
+    extract_parse_errors(source_code, root_node, file, &mut model.parse_errors);
+    extract_initial_aktivitet(
+        source_code,
+        root_node,
+        &model.config.conventions,
+        &mut model.class_index,
+    );
+    extract_type_aliases(
+        source_code,
+        root_node,
+        file,
+        &model.class_index,
+        &mut model.type_aliases,
+    );
+    let mut constant_index = HashMap::new();
+    let mut registrations = HashMap::new();
+    let project = ProjectIndexes {
+        functions: &HashMap::new(),
+        constants: &HashMap::new(),
+        classes: &model.class_index,
+        registrations: &HashMap::new(),
+        config: &model.config,
+    };
+    extract_registrations(source_code, root_node, file, project, &mut registrations);
+    model.registrations.extend(registrations);
+    let project = ProjectIndexes {
+        registrations: &model.registrations,
+        ..project
+    };
+    extract_constants(source_code, root_node, file, project, &mut constant_index);
+    model.constant_index.extend(constant_index);
+    let project = ProjectIndexes {
+        constants: &model.constant_index,
+        ..project
+    };
+    extract_functions(
+        source_code,
+        root_node,
+        file,
+        project,
+        &mut model.function_index,
+    );
+    let project = ProjectIndexes {
+        functions: &model.function_index,
+        ..project
+    };
+    extract_processors(
+        source_code,
+        root_node,
+        file,
+        project,
+        &mut model.processor_index,
+    );
+    model.resolve_type_aliases();
+
+    Ok(())
+}
+
+/// Add classes to the index under their simple name. Classes that share a simple name but are
+/// declared in different packages are all keyed by their qualified name instead, so neither
+/// overwrites the other; [`FileScope::resolve`] picks the right one for a reference.
+pub fn add_classes(index: &mut HashMap<String, ClassInfo>, classes: Vec<ClassInfo>) {
+    // Simple names that are already declared in more than one package
+    let mut ambiguous: std::collections::HashSet<String> = index
+        .iter()
+        .filter(|(key, info)| **key != info.name)
+        .map(|(_, info)| info.name.clone())
+        .collect();
+
+    for info in classes {
+        match index.get(&info.name) {
+            Some(existing) if existing.package != info.package => {
+                if let Some(existing) = index.remove(&info.name) {
+                    index.insert(existing.qualified_name(), existing);
+                }
+                ambiguous.insert(info.name.clone());
+                index.insert(info.qualified_name(), info);
+            }
+            None if ambiguous.contains(&info.name) => {
+                index.insert(info.qualified_name(), info);
+            }
+            _ => {
+                index.insert(info.name.clone(), info);
+            }
+        }
+    }
+}
+
+/// Remove the classes declared in `files` from the index. A simple name whose remaining classes
+/// are all in one package goes back to being the key, as it would be in a full scan.
+pub fn remove_classes(
+    index: &mut HashMap<String, ClassInfo>,
+    files: &std::collections::HashSet<&PathBuf>,
+) {
+    let mut removed = std::collections::HashSet::new();
+    index.retain(|_, info| {
+        let keep = !files.contains(&info.file);
+        if !keep {
+            removed.insert(info.name.clone());
+        }
+        keep
+    });
+
+    for name in removed {
+        let packages: std::collections::HashSet<&str> = index
+            .values()
+            .filter(|info| info.name == name)
+            .map(|info| info.package.as_str())
+            .collect();
+        if packages.len() != 1 {
+            continue;
+        }
+        let qualified: Vec<String> = index
+            .iter()
+            .filter(|(key, info)| info.name == name && **key != info.name)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in qualified {
+            if let Some(info) = index.remove(&key) {
+                index.insert(info.name.clone(), info);
+            }
+        }
+    }
+}
+
+fn extract_classes(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &PathBuf,
+    classes: &mut Vec<ClassInfo>,
+) {
+    let mut cursor = node.walk();
+
+    // Recursively traverse the tree
+    fn visit_node(
+        cursor: &mut tree_sitter::TreeCursor,
+        source: &str,
+        file: &PathBuf,
+        package: &str,
+        classes: &mut Vec<ClassInfo>,
+    ) {
+        let node = cursor.node();
+
+        // `object Foo : AktivitetProcessor<...>()` is indexed like a class
+        if is_class_declaration(node) {
+            // Extract class name and supertypes
+            if let Some(class_info) = extract_class_info(node, source, file, package) {
+                classes.push(class_info);
+            }
+        }
+
+        // Recurse into children
+        if cursor.goto_first_child() {
+            loop {
+                visit_node(cursor, source, file, package, classes);
+                if !cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+            cursor.goto_parent();
+        }
+    }
+
+    let package = FileScope::of(node, source).package;
+    visit_node(&mut cursor, source, file, &package, classes);
+}
+
+/// Package and imports of a Kotlin file, for telling classes with the same simple name apart
+struct FileScope {
+    package: String,
+    imports: HashMap<String, String>, // Simple name or alias -> qualified name
+    wildcard_imports: Vec<String>,    // Packages imported with `.*`
+}
+
+impl FileScope {
+    /// The scope of the file that `node` is in
+    fn of(node: tree_sitter::Node, source: &str) -> Self {
+        let mut root = node;
+        while let Some(parent) = root.parent() {
+            root = parent;
+        }
+
+        let mut scope = FileScope {
+            package: String::new(),
+            imports: HashMap::new(),
+            wildcard_imports: Vec::new(),
+        };
+        let mut cursor = root.walk();
+        for child in root.children(&mut cursor) {
+            let text = child.utf8_text(source.as_bytes()).unwrap_or_default();
+            match child.kind() {
+                "package_header" => {
+                    scope.package = header_path(text, "package").to_string();
+                }
+                "import_list" => {
+                    let mut import_cursor = child.walk();
+                    for import in child.children(&mut import_cursor) {
+                        scope.add_import(import.utf8_text(source.as_bytes()).unwrap_or_default());
+                    }
+                }
+                "import_header" => scope.add_import(text),
+                _ => {}
+            }
+        }
+        scope
+    }
+
+    fn add_import(&mut self, text: &str) {
+        let path = header_path(text, "import");
+        if let Some(package) = path.strip_suffix(".*") {
+            self.wildcard_imports.push(package.to_string());
+            return;
+        }
+        let (path, alias) = match path.split_once(" as ") {
+            Some((path, alias)) => (path.trim(), Some(alias.trim())),
+            None => (path, None),
+        };
+        let name = alias.unwrap_or_else(|| path.rsplit('.').next().unwrap_or(path));
+        if !name.is_empty() {
+            self.imports.insert(name.to_string(), path.to_string());
+        }
+    }
+
+    /// The class index key of the class that `name` refers to in this file: the simple name if
+    /// only one package declares it, otherwise the qualified name picked by an explicit import,
+    /// the file's own package or a wildcard import, in that order
+    fn resolve(&self, name: &str, class_index: &HashMap<String, ClassInfo>) -> String {
+        if let Some(path) = self.imports.get(name) {
+            if class_index.contains_key(path) {
+                return path.clone();
+            }
+            // Imported under an alias
+            let simple = path.rsplit('.').next().unwrap_or(path);
+            if class_index.contains_key(simple) {
+                return simple.to_string();
+            }
+        }
+        if class_index.contains_key(name) {
+            return name.to_string();
+        }
+
+        std::iter::once(&self.package)
+            .chain(&self.wildcard_imports)
+            .map(|package| format!("{}.{}", package, name))
+            .find(|qualified| class_index.contains_key(qualified))
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+/// The dotted path of a `package` or `import` header
+fn header_path<'s>(text: &'s str, keyword: &str) -> &'s str {
+    text.trim()
+        .trim_start_matches(keyword)
+        .trim()
+        .trim_end_matches(';')
+        .trim()
+}
+
+fn extract_class_info(
+    class_node: tree_sitter::Node,
+    source: &str,
+    file: &PathBuf,
+    package: &str,
+) -> Option<ClassInfo> {
+    let mut class_name = None;
+    let mut supertypes = Vec::new();
+    let mut supertype_arguments = HashMap::new();
+    let mut type_parameters = Vec::new();
+    let mut is_abstract = false;
+
+    let mut cursor = class_node.walk();
+
+    // Look for simple_identifier (class name) and delegation_specifier (supertypes)
+    for child in class_node.children(&mut cursor) {
+        match child.kind() {
+            "simple_identifier" | "type_identifier" => {
+                if class_name.is_none() {
+                    let name = child.utf8_text(source.as_bytes()).ok()?.to_string();
+                    class_name = Some(name);
+                }
+            }
+            "delegation_specifier" => {
+                if let Some(supertype) = extract_single_supertype(child, source) {
+                    let arguments = supertype_type_arguments(child, source);
+                    if !arguments.is_empty() {
+                        supertype_arguments.insert(supertype.clone(), arguments);
+                    }
+                    supertypes.push(supertype);
+                }
+            }
+            "type_parameters" => {
+                let mut parameter_cursor = child.walk();
+                type_parameters = child
+                    .children(&mut parameter_cursor)
+                    .filter(|parameter| parameter.kind() == "type_parameter")
+                    .filter_map(|parameter| {
+                        let mut name_cursor = parameter.walk();
+                        let name = parameter.children(&mut name_cursor).find(|name| {
+                            name.kind() == "type_identifier" || name.kind() == "simple_identifier"
+                        });
+                        name.and_then(|name| name.utf8_text(source.as_bytes()).ok())
+                            .map(|name| name.to_string())
+                    })
+                    .collect();
+            }
+            "interface" => is_abstract = true,
+            _ => {}
+        }
+    }
+
+    is_abstract |= has_class_modifier(class_node, source, &["abstract", "sealed"]);
+    let position = class_node.start_position();
+    class_name.map(|name| ClassInfo {
+        name,
+        package: package.to_string(),
+        file: file.clone(),
+        line: position.row + 1,
+        column: position.column + 1,
+        supertypes,
+        initial_aktivitet: None,
+        is_abstract,
+        type_parameters,
+        supertype_arguments,
+        annotations: class_annotations(class_node, source)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+    })
+}
+
+/// The annotations of a class by simple name, each with the class named by its first class
+/// literal argument: `@Prosessor(VurderAktivitet::class)` gives (Prosessor, Some(VurderAktivitet)).
+/// Without one, a string argument that reads as a name is taken, as in `@Component("Vurder")`.
+fn class_annotations(class_node: tree_sitter::Node, source: &str) -> Vec<(String, Option<String>)> {
+    let mut annotations = Vec::new();
+    let mut cursor = class_node.walk();
+    for modifiers in class_node
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "modifiers")
+    {
+        let mut modifier_cursor = modifiers.walk();
+        for annotation in modifiers
+            .children(&mut modifier_cursor)
+            .filter(|modifier| modifier.kind() == "annotation")
+        {
+            let text = annotation.utf8_text(source.as_bytes()).unwrap_or_default();
+            let text = text.trim_start_matches('@');
+            let (path, arguments) = text.split_once('(').unwrap_or((text, ""));
+            // Skip a use-site target like `@field:`
+            let path = path.rsplit(':').next().unwrap_or(path).trim();
+            let name = path.rsplit('.').next().unwrap_or(path);
+            if name.is_empty() {
+                continue;
+            }
+            let class_argument = arguments
+                .split_once("::class")
+                .and_then(|(before, _)| {
+                    before
+                        .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                        .next()
+                        .map(|class| class.rsplit('.').next().unwrap_or(class).to_string())
+                        .filter(|class| !class.is_empty())
+                })
+                .or_else(|| {
+                    arguments
+                        .split('"')
+                        .nth(1)
+                        .filter(|name| {
+                            !name.is_empty()
+                                && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+                        })
+                        .map(|name| name.to_string())
+                });
+            annotations.push((name.to_string(), class_argument));
+        }
+    }
+    annotations
+}
+
+/// The aktivitet that `class_node` processes, as a class index key, if it is a processor: a
+/// class named `...Processor` or one with a configured processor annotation. The aktivitet is
+/// the annotation's class literal argument, or else found through the processor's supertypes.
+fn processor_aktivitet(
+    class_node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<String> {
+    let name = class_name(class_node, source)?;
+    // Bound to its aktivitet in a registry, e.g. `registrer(VurderAktivitet::class, ...)`
+    if let Some(registration) = project.registrations.get(&name) {
+        return Some(registration.aktivitet_class.clone());
+    }
+
+    let annotations = class_annotations(class_node, source);
+    // A Spring bean named after the aktivitet it processes: `@Component("VurderAktivitet")`
+    let component = annotations.iter().find_map(|(annotation, argument)| {
+        argument.as_ref().filter(|aktivitet| {
+            project.config.discovery.is_component_annotation(annotation)
+                && project.is_aktivitet_class(aktivitet)
+        })
+    });
+    if let Some(aktivitet) = component {
+        return Some(FileScope::of(class_node, source).resolve(aktivitet, project.classes));
+    }
+
+    let annotation = annotations
+        .into_iter()
+        .find(|(annotation, _)| project.config.discovery.is_processor_annotation(annotation));
+    if annotation.is_none() && !name.ends_with("Processor") {
+        return None;
+    }
+
+    let aktivitet = match annotation {
+        Some((_, Some(aktivitet))) => aktivitet,
+        _ => extract_aktivitet_from_processor(class_node, source, project.classes)?,
+    };
+    Some(FileScope::of(class_node, source).resolve(&aktivitet, project.classes))
+}
+
+/// True if the class is declared with one of `modifiers`, like `abstract` or `open`
+fn has_class_modifier(class_node: tree_sitter::Node, source: &str, modifiers: &[&str]) -> bool {
+    let mut cursor = class_node.walk();
+    let declared = class_node
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "modifiers")
+        .any(|child| {
+            let mut modifier_cursor = child.walk();
+            let found = child.children(&mut modifier_cursor).any(|modifier| {
+                modifier
+                    .utf8_text(source.as_bytes())
+                    .is_ok_and(|text| modifiers.contains(&text))
+            });
+            found
+        });
+    declared
+}
+
+/// True for classes other classes can extend: abstract, open and sealed classes and interfaces
+fn is_base_class(class_node: tree_sitter::Node, source: &str) -> bool {
+    let mut cursor = class_node.walk();
+    let is_interface = class_node
+        .children(&mut cursor)
+        .any(|child| child.kind() == "interface");
+    is_interface || has_class_modifier(class_node, source, &["abstract", "open", "sealed"])
+}
+
+fn extract_single_supertype(delegation_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = delegation_node.walk();
+
+    for child in delegation_node.children(&mut cursor) {
+        if child.kind() == "user_type"
+            || child.kind() == "type_identifier"
+            || child.kind() == "constructor_invocation"
+        {
+            return Some(extract_type_name(child, source));
+        }
+    }
+
+    None
+}
+
+fn extract_type_name(node: tree_sitter::Node, source: &str) -> String {
+    match node.kind() {
+        "user_type" => {
+            // For user_type, concatenate all type_identifier children
+            let mut cursor = node.walk();
+            let mut parts = Vec::new();
+
+            for child in node.children(&mut cursor) {
+                if child.kind() == "type_identifier" || child.kind() == "simple_identifier" {
+                    if let Ok(text) = child.utf8_text(source.as_bytes()) {
+                        parts.push(text.to_string());
+                    }
+                }
+            }
+
+            if !parts.is_empty() {
+                parts.join(".")
+            } else {
+                node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
+            }
+        }
+        "constructor_invocation" => {
+            // For constructor invocations like "Behandling()", extract the type
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                if child.kind() == "user_type"
+                    || child.kind() == "type_identifier"
+                    || child.kind() == "simple_identifier"
+                {
+                    return extract_type_name(child, source);
+                }
+            }
+            node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
+        }
+        _ => node.utf8_text(source.as_bytes()).unwrap_or("").to_string(),
+    }
+}
+
+fn extract_initial_aktivitet(
+    source: &str,
+    node: tree_sitter::Node,
+    conventions: &ConventionsConfig,
+    index: &mut HashMap<String, ClassInfo>,
+) {
+    let mut cursor = node.walk();
+
+    fn visit_node(
+        cursor: &mut tree_sitter::TreeCursor,
+        source: &str,
+        conventions: &ConventionsConfig,
+        index: &mut HashMap<String, ClassInfo>,
+        current_class: &mut Option<String>,
+    ) {
+        let node = cursor.node();
+        // A nested class is only in scope while its own body is visited
+        let outer_class = is_class_declaration(node).then(|| current_class.clone());
+
+        match node.kind() {
+            "class_declaration" | "object_declaration" => {
+                // Track which class we're in
+                let mut class_cursor = node.walk();
+                for child in node.children(&mut class_cursor) {
+                    if child.kind() == "type_identifier" || child.kind() == "simple_identifier" {
+                        if let Ok(name) = child.utf8_text(source.as_bytes()) {
+                            *current_class = Some(name.to_string());
+                            break;
+                        }
+                    }
+                }
+            }
+            "function_declaration" => {
+                // Check if this is opprettInitiellAktivitet
+                if let Some(class_name) = current_class {
+                    if function_name(node, source)
+                        .is_some_and(|name| name == conventions.opprett_initiell_aktivitet)
+                    {
+                        if let Some(aktivitet_name) =
+                            extract_return_type_from_function(node, source)
+                        {
+                            let scope = FileScope::of(node, source);
+                            let aktivitet_name = scope.resolve(&aktivitet_name, index);
+                            if let Some(class_info) =
+                                index.get_mut(&scope.resolve(class_name, index))
+                            {
+                                class_info.initial_aktivitet = Some(aktivitet_name);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Recurse into children
+        if cursor.goto_first_child() {
+            loop {
+                visit_node(cursor, source, conventions, index, current_class);
+                if !cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+            cursor.goto_parent();
+        }
+
+        if let Some(outer_class) = outer_class {
+            *current_class = outer_class;
+        }
+    }
+
+    let mut current_class = None;
+    visit_node(&mut cursor, source, conventions, index, &mut current_class);
+}
+
+fn extract_return_type_from_function(func_node: tree_sitter::Node, source: &str) -> Option<String> {
+    // The first call_expression in the function body, also for `fun f() = VurderAktivitet()`
+    function_body(func_node).and_then(|body| find_constructor_call(body, source))
+}
+
+/// The body of a function: its block, or the expression of an expression body like
+/// `override fun onFinished(...) = nesteAktivitet(Foo())`. An expression body is not always
+/// wrapped in a `function_body` node; then it is the expression after the `=`.
+fn function_body(func_node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    let mut cursor = func_node.walk();
+    let children: Vec<_> = func_node.children(&mut cursor).collect();
+    children
+        .iter()
+        .find(|child| child.kind() == "function_body")
+        .or_else(|| {
+            let equals = children.iter().position(|child| child.kind() == "=")?;
+            children[equals + 1..].iter().find(|child| child.is_named())
+        })
+        .copied()
+}
+
+/// Index the `typealias` declarations, with the class each one stands for resolved against
+/// `class_index`. Calls `progress` with the number of files done and the total after each file.
+pub fn build_type_alias_index(
+    files: &[ParsedFile],
+    class_index: &HashMap<String, ClassInfo>,
+    progress: &mut dyn FnMut(usize, usize),
+) -> HashMap<String, TypeAlias> {
+    let mut index = HashMap::new();
+
+    for (done, file) in files.iter().enumerate() {
+        if file.source.contains("typealias") {
+            extract_type_aliases(
+                &file.source,
+                file.tree.root_node(),
+                &file.path,
+                class_index,
+                &mut index,
+            );
+        }
+        progress(done + 1, files.len());
+    }
+
+    index
+}
+
+fn extract_type_aliases(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &Path,
+    class_index: &HashMap<String, ClassInfo>,
+    index: &mut HashMap<String, TypeAlias>,
+) {
+    if node.kind() == "type_alias" {
+        let mut cursor = node.walk();
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        let alias = children
+            .iter()
+            .find(|child| child.kind() == "type_identifier" || child.kind() == "simple_identifier")
+            .and_then(|name| name.utf8_text(source.as_bytes()).ok());
+        // The aliased type comes after `=`; type parameters of generic targets are dropped
+        let target = children
+            .iter()
+            .rev()
+            .find(|child| child.kind() == "user_type")
+            .map(|user_type| extract_type_name(*user_type, source));
+        if let (Some(alias), Some(target)) = (alias, target) {
+            let target = FileScope::of(node, source).resolve(&target, class_index);
+            index.insert(
+                alias.to_string(),
+                TypeAlias {
+                    target,
+                    file: file.to_path_buf(),
+                },
+            );
+        }
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        extract_type_aliases(source, child, file, class_index, index);
+    }
+}
+
+/// Index the top-level and object constants, the enum entries and the object factory functions
+/// that stand for an aktivitet, so `nesteAktivitet(NESTE_STEG)` can be resolved. Calls `progress` with the number of files
+/// done and the total after each file.
+pub fn build_constant_index(
+    files: &[ParsedFile],
+    project: ProjectIndexes,
+    progress: &mut dyn FnMut(usize, usize),
+) -> HashMap<String, AktivitetConstant> {
+    let mut index = HashMap::new();
+
+    // No file is skipped by its text: an aktivitet found by annotation need not be named one
+    for (done, file) in files.iter().enumerate() {
+        extract_constants(
+            &file.source,
+            file.tree.root_node(),
+            &file.path,
+            project,
+            &mut index,
+        );
+        progress(done + 1, files.len());
+    }
+
+    index
+}
+
+/// Index the functions outside processors that make transitions, keyed by function name, so
+/// processors delegating to them can be resolved. Calls `progress` with the number of files
+/// done and the total after each file.
+pub fn build_function_index(
+    files: &[ParsedFile],
+    project: ProjectIndexes,
+    progress: &mut dyn FnMut(usize, usize),
+) -> HashMap<String, Vec<FunctionInfo>> {
+    let mut index = HashMap::new();
+    // Calls to other classes are only followed from processors
+    let no_functions = HashMap::new();
+    let project = ProjectIndexes {
+        functions: &no_functions,
+        ..project
+    };
+
+    // Every file is walked: a base processor may be sealed or an interface, and a shared function
+    // may only end the flow or start aktiviteter under configured names
+    for (done, file) in files.iter().enumerate() {
+        extract_functions(
+            &file.source,
+            file.tree.root_node(),
+            &file.path,
+            project,
+            &mut index,
+        );
+        progress(done + 1, files.len());
+    }
+
+    index
+}
+
+/// Longest snippet of unparsable source shown in a parse error
+const MAX_ERROR_SNIPPET_LEN: usize = 40;
+
+/// Record the error and missing nodes below `node`. An error node is reported once, not for
+/// every error inside it.
+fn extract_parse_errors(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &Path,
+    errors: &mut Vec<ParseError>,
+) {
+    if !node.has_error() {
+        return;
+    }
+    if node.is_missing() {
+        errors.push(ParseError {
+            location: source_location(node, file),
+            message: format!("missing `{}`", node.kind()),
+        });
+        return;
+    }
+    if node.is_error() {
+        let text = node.utf8_text(source.as_bytes()).unwrap_or_default().trim();
+        let first_line = text.lines().next().unwrap_or_default().trim();
+        let snippet: String = first_line.chars().take(MAX_ERROR_SNIPPET_LEN).collect();
+        let message = if snippet.is_empty() {
+            "syntax error".to_string()
+        } else if snippet.len() < text.len() {
+            format!("unexpected `{}...`", snippet)
+        } else {
+            format!("unexpected `{}`", snippet)
+        };
+        errors.push(ParseError {
+            location: source_location(node, file),
+            message,
+        });
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        extract_parse_errors(source, child, file, errors);
+    }
+}
+
+/// Index the processors that are bound to their aktivitet outside the processor class, keyed by
+/// processor class. Calls `progress` with the number of files done and the total after each file.
+pub fn build_registration_index(
+    files: &[ParsedFile],
+    project: ProjectIndexes,
+    progress: &mut dyn FnMut(usize, usize),
+) -> HashMap<String, ProcessorRegistration> {
+    let mut index = HashMap::new();
+
+    for (done, file) in files.iter().enumerate() {
+        // Every registration names its aktivitet by class literal
+        if file.source.contains("::class") {
+            extract_registrations(
+                &file.source,
+                file.tree.root_node(),
+                &file.path,
+                project,
+                &mut index,
+            );
+        }
+        progress(done + 1, files.len());
+    }
+
+    index
+}
+
+/// Add the registrations below `node`: calls to a configured registry function, like
+/// `registrer(VurderAktivitet::class, VurderProcessor())`, and map entries like
+/// `VurderAktivitet::class to VurderProcessor()`. A processor registered for more than one
+/// aktivitet is indexed for the first.
+fn extract_registrations(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &Path,
+    project: ProjectIndexes,
+    index: &mut HashMap<String, ProcessorRegistration>,
+) {
+    let mut cursor = node.walk();
+    let arguments: Vec<_> = match node.kind() {
+        "call_expression"
+            if called_function_name(node, source).is_some_and(|function| {
+                project
+                    .config
+                    .discovery
+                    .registry_functions
+                    .iter()
+                    .any(|registry| registry == function)
+            }) =>
+        {
+            call_arguments(node, source)
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect()
+        }
+        "infix_expression" => {
+            let parts: Vec<_> = node.named_children(&mut cursor).collect();
+            match parts[..] {
+                [left, operator, right] if operator.utf8_text(source.as_bytes()) == Ok("to") => {
+                    vec![left, right]
+                }
+                _ => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    };
+    if let Some((processor, aktivitet)) = registered_processor(&arguments, source, project) {
+        let aktivitet_class = FileScope::of(node, source).resolve(&aktivitet, project.classes);
+        index
+            .entry(processor)
+            .or_insert_with(|| ProcessorRegistration {
+                aktivitet_class,
+                location: source_location(node, file),
+            });
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        extract_registrations(source, child, file, project, index);
+    }
+}
+
+/// The processor and the aktivitet bound to it by the arguments of a registration. The
+/// aktivitet is given by class literal; the processor is constructed or given by class literal.
+fn registered_processor(
+    arguments: &[tree_sitter::Node],
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<(String, String)> {
+    let class_literals: Vec<String> = arguments
+        .iter()
+        .filter(|argument| argument.kind() == "class_literal")
+        .filter_map(|argument| argument.utf8_text(source.as_bytes()).ok())
+        .map(|text| {
+            let name = text.trim_end_matches("::class").trim();
+            name.rsplit('.').next().unwrap_or(name).to_string()
+        })
+        .collect();
+    let aktivitet = class_literals
+        .iter()
+        .find(|name| project.is_aktivitet_class(name))?;
+    let processor = arguments
+        .iter()
+        .filter(|argument| argument.kind() == "call_expression")
+        .find_map(|argument| extract_constructor_name(*argument, source))
+        .or_else(|| {
+            class_literals
+                .iter()
+                .find(|name| *name != aktivitet)
+                .cloned()
+        })
+        .filter(|processor| processor != aktivitet)?;
+    Some((processor, aktivitet.clone()))
+}
+
+/// Index all aktivitet processors in `files`, resolving calls and constants through `project`.
+/// Calls `progress` with the number of files done and the total after each file.
+pub fn build_processor_index(
+    files: &[ParsedFile],
+    project: ProjectIndexes,
+    progress: &mut dyn FnMut(usize, usize),
+) -> HashMap<String, ProcessorInfo> {
+    let mut index = HashMap::new();
+
+    for (done, file) in files.iter().enumerate() {
+        extract_processors(
+            &file.source,
+            file.tree.root_node(),
+            &file.path,
+            project,
+            &mut index,
+        );
+        progress(done + 1, files.len());
+    }
+
+    index
+}
+
+fn extract_processors(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &Path,
+    project: ProjectIndexes,
+    index: &mut HashMap<String, ProcessorInfo>,
+) {
+    let mut cursor = node.walk();
+
+    fn visit_node(
+        cursor: &mut tree_sitter::TreeCursor,
+        source: &str,
+        file: &Path,
+        project: ProjectIndexes,
+        index: &mut HashMap<String, ProcessorInfo>,
+        current_class: &mut Option<String>,
+        current_aktivitet_class: &mut Option<String>,
+    ) {
+        let node = cursor.node();
+        // Functions belong to the innermost class declaring them, so the enclosing processor is
+        // restored once a nested class has been visited
+        let outer_scope = is_class_declaration(node)
+            .then(|| (current_class.clone(), current_aktivitet_class.clone()));
+
+        match node.kind() {
+            // Processors may be singletons: `object FooProcessor : AktivitetProcessor<...>()`
+            "class_declaration" | "object_declaration" => {
+                // Extract class name
+                let mut class_cursor = node.walk();
+                for child in node.children(&mut class_cursor) {
+                    if child.kind() == "type_identifier" || child.kind() == "simple_identifier" {
+                        if let Ok(name) = child.utf8_text(source.as_bytes()) {
+                            *current_class = Some(name.to_string());
+
+                            // Check if this is a processor (ends with Processor or is annotated
+                            // as one). Abstract ones never run themselves; their subclasses
+                            // inherit their logic.
+                            *current_aktivitet_class =
+                                if has_class_modifier(node, source, &["abstract", "sealed"]) {
+                                    None
+                                } else {
+                                    processor_aktivitet(node, source, project)
+                                };
+                            break;
+                        }
+                    }
+                }
+            }
+            "function_declaration" => {
+                // Check if this is doProcess or onFinished
+                if let Some(processor_class) = current_class {
+                    if let Some(aktivitet_class) = current_aktivitet_class {
+                        if is_processor_function(node, source, &project.config.conventions) {
+                            let function = extract_function_info(node, source, file, project);
+                            // Always add to index, even without transitions or terminals, so
+                            // the graph can show that none were found
+                            // Check if we already have an entry for this aktivitet
+                            if let Some(existing) = index.get_mut(aktivitet_class) {
+                                note_other_processor(existing, processor_class, file);
+                                merge_processor_logic(existing, function);
+                            } else {
+                                // Create new entry
+                                index.insert(
+                                    aktivitet_class.clone(),
+                                    ProcessorInfo {
+                                        processor_class: processor_class.clone(),
+                                        file: file.to_path_buf(),
+                                        next_aktiviteter: function.next_aktiviteter,
+                                        has_manuell_behandling: function.has_manuell_behandling,
+                                        manuell_behandlinger: function.manuell_behandlinger,
+                                        manuell_on_error: function.manuell_on_error,
+                                        terminals: function.terminals,
+                                        waits_until: function.waits_until,
+                                        spawns: function.spawns,
+                                        waits_for: function.waits_for,
+                                        resumes: function.resumes,
+                                        other_processors: Vec::new(),
+                                    },
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Recurse into children
+        if cursor.goto_first_child() {
+            loop {
+                visit_node(
+                    cursor,
+                    source,
+                    file,
+                    project,
+                    index,
+                    current_class,
+                    current_aktivitet_class,
+                );
+                if !cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+            cursor.goto_parent();
+        }
+
+        // Once the processor's own functions are in, add what it inherits
+        if let Some((outer_class, outer_aktivitet_class)) = outer_scope {
+            inherit_processor_functions(node, source, file, project, index);
+            *current_class = outer_class;
+            *current_aktivitet_class = outer_aktivitet_class;
+        }
+    }
+
+    let mut current_class = None;
+    let mut current_aktivitet_class = None;
+    visit_node(
+        &mut cursor,
+        source,
+        file,
+        project,
+        index,
+        &mut current_class,
+        &mut current_aktivitet_class,
+    );
+}
+
+/// Remember a second processor class for the aktivitet of `existing`. Its transitions are merged
+/// into the first, but two processors for one aktivitet is usually a mistake, so it is reported.
+fn note_other_processor(existing: &mut ProcessorInfo, processor_class: &str, file: &Path) {
+    let is_other = existing.processor_class != processor_class || existing.file != file;
+    let other = (processor_class.to_string(), file.to_path_buf());
+    if is_other && !existing.other_processors.contains(&other) {
+        existing.other_processors.push(other);
+    }
+}
+
+/// Add transitions found in another doProcess or onFinished of the same processor. Only the same
+/// call found twice, as in base logic inherited along two routes, is left out; another transition
+/// to an aktivitet that is already there is kept with its condition, so `lint` can compare them.
+fn merge_processor_logic(existing: &mut ProcessorInfo, function: FunctionInfo) {
+    for next in function.next_aktiviteter {
+        if !existing
+            .next_aktiviteter
+            .iter()
+            .any(|n| n.aktivitet_name == next.aktivitet_name && n.location == next.location)
+        {
+            existing.next_aktiviteter.push(next);
+        }
+    }
+    if function.has_manuell_behandling {
+        existing.has_manuell_behandling = true;
+    }
+    for manuell in function.manuell_behandlinger {
+        if !existing.manuell_behandlinger.contains(&manuell) {
+            existing.manuell_behandlinger.push(manuell);
+        }
+    }
+    for error_type in function.manuell_on_error {
+        if !existing.manuell_on_error.contains(&error_type) {
+            existing.manuell_on_error.push(error_type);
+        }
+    }
+    for terminal in function.terminals {
+        if !existing.terminals.contains(&terminal) {
+            existing.terminals.push(terminal);
+        }
+    }
+    for until in function.waits_until {
+        if !existing.waits_until.contains(&until) {
+            existing.waits_until.push(until);
+        }
+    }
+    for behandling in function.spawns {
+        if !existing.spawns.contains(&behandling) {
+            existing.spawns.push(behandling);
+        }
+    }
+    for behandling in function.waits_for {
+        if !existing.waits_for.contains(&behandling) {
+            existing.waits_for.push(behandling);
+        }
+    }
+    for behandling in function.resumes {
+        if !existing.resumes.contains(&behandling) {
+            existing.resumes.push(behandling);
+        }
+    }
+}
+
+/// Add the doProcess and onFinished logic a concrete processor inherits from its base classes,
+/// e.g. a shared onFinished in `FellesAktivitetProcessor`. A function declared closer to the
+/// processor overrides the ones further up the hierarchy.
+fn inherit_processor_functions(
+    class_node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    project: ProjectIndexes,
+    index: &mut HashMap<String, ProcessorInfo>,
+) {
+    let Some(processor_class) = class_name(class_node, source) else {
+        return;
+    };
+    if has_class_modifier(class_node, source, &["abstract", "sealed"]) {
+        return;
+    }
+    let Some(aktivitet_class) = processor_aktivitet(class_node, source, project) else {
+        return;
+    };
+    let scope = FileScope::of(class_node, source);
+
+    let mut declared: Vec<String> = Vec::new();
+    let mut cursor = class_node.walk();
+    for body in class_node
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "class_body")
+    {
+        let mut body_cursor = body.walk();
+        declared.extend(
+            body.children(&mut body_cursor)
+                .filter(|child| child.kind() == "function_declaration")
+                .filter_map(|function| function_name(function, source)),
+        );
+    }
+
+    let mut inherited = Vec::new();
+    let mut pending: Vec<String> = project
+        .classes
+        .get(&scope.resolve(&processor_class, project.classes))
+        .map(|info| info.supertypes.clone())
+        .unwrap_or_default();
+    let mut seen = std::collections::HashSet::new();
+    while let Some(supertype) = pending.pop() {
+        let simple_name = supertype
+            .rsplit('.')
+            .next()
+            .unwrap_or(&supertype)
+            .to_string();
+        if !seen.insert(simple_name.clone()) {
+            continue;
+        }
+        for function_name in project.config.conventions.processor_functions() {
+            if declared.iter().any(|name| name == function_name) {
+                continue;
+            }
+            let found: Vec<&FunctionInfo> = project
+                .functions
+                .get(function_name)
+                .into_iter()
+                .flatten()
+                .filter(|function| function.class_name == simple_name)
+                .collect();
+            if !found.is_empty() {
+                declared.push(function_name.to_string());
+                inherited.extend(found);
+            }
+        }
+        if let Some(info) = project
+            .classes
+            .get(&scope.resolve(&simple_name, project.classes))
+        {
+            pending.extend(info.supertypes.iter().cloned());
+        }
+    }
+    if inherited.is_empty() {
+        return;
+    }
+
+    let existing = index
+        .entry(aktivitet_class)
+        .or_insert_with(|| ProcessorInfo {
+            processor_class: processor_class.clone(),
+            file: file.to_path_buf(),
+            next_aktiviteter: Vec::new(),
+            has_manuell_behandling: false,
+            manuell_behandlinger: Vec::new(),
+            manuell_on_error: Vec::new(),
+            terminals: Vec::new(),
+            waits_until: Vec::new(),
+            spawns: Vec::new(),
+            waits_for: Vec::new(),
+            resumes: Vec::new(),
+            other_processors: Vec::new(),
+        });
+    note_other_processor(existing, &processor_class, file);
+    for function in inherited {
+        merge_processor_logic(existing, function.clone());
+    }
+}
+
+/// Base classes of all processors, with the position of the aktivitet among their type
+/// arguments: `AktivitetProcessor<Behandling, Aktivitet>`
+const PROCESSOR_BASE_CLASSES: &[(&str, usize)] =
+    &[("AktivitetProcessor", 1), ("AldeAktivitetProcessor", 1)];
+
+/// Where a class passes the aktivitet on to the processor base class
+#[derive(Debug, Clone, PartialEq)]
+enum AktivitetSlot {
+    Parameter(usize), // The class's own type parameter at this position
+    Fixed(String),    // A concrete aktivitet class
+}
+
+/// The aktivitet a processor class handles, found by following its type arguments up to
+/// [`PROCESSOR_BASE_CLASSES`] through the base classes in `class_index`. Falls back to the
+/// second (or only) type argument when the hierarchy leaves the project.
+fn extract_aktivitet_from_processor(
+    class_node: tree_sitter::Node,
+    source: &str,
+    class_index: &HashMap<String, ClassInfo>,
+) -> Option<String> {
+    let mut fallback = None;
+    let mut cursor = class_node.walk();
+
+    for child in class_node.children(&mut cursor) {
+        if child.kind() != "delegation_specifier" {
+            continue;
+        }
+        let Some(supertype) = extract_single_supertype(child, source) else {
+            continue;
+        };
+        let arguments = supertype_type_arguments(child, source);
+
+        match aktivitet_slot(&supertype, class_index, 0) {
+            Some(AktivitetSlot::Fixed(aktivitet)) => return Some(aktivitet),
+            Some(AktivitetSlot::Parameter(position)) => {
+                if let Some(aktivitet) = arguments.get(position) {
+                    return Some(aktivitet.clone());
+                }
+            }
+            None => {}
+        }
+
+        // For AktivitetProcessor<Behandling, Aktivitet>, we want the second one (index 1)
+        if fallback.is_none() {
+            fallback = match arguments.as_slice() {
+                [only] => Some(only.clone()),
+                [_, second, ..] => Some(second.clone()),
+                [] => None,
+            };
+        }
+    }
+
+    fallback
+}
+
+/// Where `class` passes the aktivitet on to a processor base class, if it extends one
+fn aktivitet_slot(
+    class: &str,
+    class_index: &HashMap<String, ClassInfo>,
+    depth: usize,
+) -> Option<AktivitetSlot> {
+    let simple_name = class.rsplit('.').next().unwrap_or(class);
+    if let Some((_, position)) = PROCESSOR_BASE_CLASSES
+        .iter()
+        .find(|(base, _)| *base == simple_name)
+    {
+        return Some(AktivitetSlot::Parameter(*position));
+    }
+    // Guard against cyclic hierarchies in broken sources
+    if depth > 20 {
+        return None;
+    }
+
+    let info = class_index
+        .get(class)
+        .or_else(|| class_index.get(simple_name))?;
+    info.supertypes.iter().find_map(|supertype| {
+        let slot = aktivitet_slot(supertype, class_index, depth + 1)?;
+        match slot {
+            AktivitetSlot::Fixed(_) => Some(slot),
+            AktivitetSlot::Parameter(position) => {
+                let argument = info.supertype_arguments.get(supertype)?.get(position)?;
+                Some(
+                    match info.type_parameters.iter().position(|p| p == argument) {
+                        Some(own_position) => AktivitetSlot::Parameter(own_position),
+                        None => AktivitetSlot::Fixed(argument.clone()),
+                    },
+                )
+            }
+        }
+    })
+}
+
+/// The type arguments of a supertype, e.g. [Behandling, VurderAktivitet] for
+/// `AktivitetProcessor<Behandling, VurderAktivitet>()`
+fn supertype_type_arguments(delegation_node: tree_sitter::Node, source: &str) -> Vec<String> {
+    let mut cursor = delegation_node.walk();
+    let user_type = delegation_node
+        .children(&mut cursor)
+        .find_map(|child| match child.kind() {
+            "user_type" => Some(child),
+            "constructor_invocation" => {
+                let mut invocation_cursor = child.walk();
+                let user_type = child
+                    .children(&mut invocation_cursor)
+                    .find(|c| c.kind() == "user_type");
+                user_type
+            }
+            _ => None,
+        });
+    let Some(user_type) = user_type else {
+        return Vec::new();
+    };
+
+    let mut type_projections = Vec::new();
+    let mut user_type_cursor = user_type.walk();
+    for arguments in user_type
+        .children(&mut user_type_cursor)
+        .filter(|child| child.kind() == "type_arguments")
+    {
+        let mut args_cursor = arguments.walk();
+        for type_arg in arguments
+            .children(&mut args_cursor)
+            .filter(|child| child.kind() == "type_projection")
+        {
+            let mut proj_cursor = type_arg.walk();
+            for type_node in type_arg.children(&mut proj_cursor) {
+                if type_node.kind() == "user_type" || type_node.kind() == "type_identifier" {
+                    type_projections.push(extract_type_name(type_node, source));
+                }
+            }
+        }
+    }
+    type_projections
+}
+
+/// True for the doProcess and onFinished functions, as named in `conventions`
+fn is_processor_function(
+    func_node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> bool {
+    function_name(func_node, source).is_some_and(|name| conventions.is_processor_function(&name))
+}
+
+/// True if a manuell behandling is created below `node`. Only the syntax tree is searched, so
+/// comments and strings that mention it do not count.
+fn has_manuell_behandling_call(
+    node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> bool {
+    if creates_manuell_behandling(node, source, conventions) {
+        return true;
+    }
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.children(&mut cursor).collect();
+    children
+        .into_iter()
+        .any(|child| has_manuell_behandling_call(child, source, conventions))
+}
+
+/// True if `node` itself creates a manuell behandling: it is assigned to the property
+/// (`manuellBehandling = lagOppgave(krav)`, not `= null`), constructed (`ManuellBehandling(...)`)
+/// or made by one of the configured factories (`opprettManuellBehandling(...)`)
+fn creates_manuell_behandling(
+    node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> bool {
+    match node.kind() {
+        "assignment" => {
+            let mut cursor = node.walk();
+            let children: Vec<_> = node.children(&mut cursor).collect();
+            let [target, operator, value, ..] = children[..] else {
+                return false;
+            };
+            let property = target
+                .utf8_text(source.as_bytes())
+                .ok()
+                .and_then(|target| target.rsplit('.').next())
+                .map(str::trim);
+            operator.kind() == "="
+                && property == Some(conventions.manuell_behandling.as_str())
+                && value.utf8_text(source.as_bytes()).ok() != Some("null")
+        }
+        "call_expression" => called_function_name(node, source).is_some_and(|function| {
+            function == conventions.manuell_behandling_class()
+                || conventions
+                    .manuell_behandling_factories
+                    .iter()
+                    .any(|factory| factory == function)
+        }),
+        _ => false,
+    }
+}
+
+/// Argument names of a manuell behandling that give the kind of oppgave
+const MANUELL_KATEGORI_ARGUMENTS: &[&str] = &["kategori", "oppgavetype", "oppgaveType", "type"];
+
+/// Argument names of a manuell behandling that give its deadline
+const MANUELL_FRIST_ARGUMENTS: &[&str] = &["frist", "fristDato", "fristFerdigstillelse"];
+
+/// The manuelle behandlinger created below `node`, with the kategori and frist passed to the
+/// constructor or factory by name. An assignment from anything else, like
+/// `manuellBehandling = lagOppgave(krav)`, is recorded without details.
+fn extract_manuell_behandlinger(
+    node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    conventions: &ConventionsConfig,
+) -> Vec<ManuellBehandling> {
+    fn search_node(
+        node: tree_sitter::Node,
+        source: &str,
+        file: &Path,
+        conventions: &ConventionsConfig,
+        found: &mut Vec<ManuellBehandling>,
+    ) {
+        if creates_manuell_behandling(node, source, conventions) {
+            // `manuellBehandling = ManuellBehandling(...)` has its details in the constructor
+            let mut cursor = node.walk();
+            let call = if node.kind() == "assignment" {
+                node.children(&mut cursor)
+                    .skip_while(|child| child.kind() != "=")
+                    .find(|child| {
+                        child.kind() == "call_expression"
+                            && creates_manuell_behandling(*child, source, conventions)
+                    })
+            } else {
+                Some(node)
+            };
+            let arguments = call
+                .map(|call| call_arguments(call, source))
+                .unwrap_or_default();
+            let named = |names: &[&str]| {
+                arguments
+                    .iter()
+                    .find(|(name, _)| name.as_deref().is_some_and(|name| names.contains(&name)))
+                    .and_then(|(_, value)| argument_text(*value, source))
+            };
+            found.push(ManuellBehandling {
+                kategori: named(MANUELL_KATEGORI_ARGUMENTS),
+                frist: named(MANUELL_FRIST_ARGUMENTS),
+                location: source_location(node, file),
+            });
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            search_node(child, source, file, conventions, found);
+        }
+    }
+
+    let mut found = Vec::new();
+    search_node(node, source, file, conventions, &mut found);
+    found
+}
+
+/// The exception type caught by a catch block, e.g. `IOException` in `catch (e: IOException)`
+fn catch_exception_type(catch_node: tree_sitter::Node, source: &str) -> String {
+    let mut cursor = catch_node.walk();
+    let exception_type = catch_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "user_type" || child.kind() == "type_identifier")
+        .map(|child| extract_type_name(child, source));
+    exception_type.unwrap_or_else(|| "Exception".to_string())
+}
+
+/// Exception types of the catch blocks in a function that create a manuell behandling
+fn catch_blocks_creating_manuell(
+    func_node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> Vec<String> {
+    fn search_node(
+        node: tree_sitter::Node,
+        source: &str,
+        conventions: &ConventionsConfig,
+        error_types: &mut Vec<String>,
+    ) {
+        if node.kind() == "catch_block" && has_manuell_behandling_call(node, source, conventions) {
+            let error_type = catch_exception_type(node, source);
+            if !error_types.contains(&error_type) {
+                error_types.push(error_type);
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            search_node(child, source, conventions, error_types);
+        }
+    }
+
+    let mut error_types = Vec::new();
+    search_node(func_node, source, conventions, &mut error_types);
+    error_types
+}
+
+/// How a call ends the flow, if it does: `aktivitetFullfort(Resultat.INNVILGET)`, or an abort
+/// like `avbrytBehandling("Bruker er død")` or `behandling.avbryt(AvbruttArsak.DOD)`
+fn terminal_call(call_node: tree_sitter::Node, source: &str) -> Option<Terminal> {
+    let kind = match called_function_name(call_node, source)? {
+        "aktivitetFullfort" => TerminalKind::Fullfort,
+        "avbrytBehandling" | "avbryt" => TerminalKind::Avbrutt,
+        _ => return None,
+    };
+    // Plain variables like `behandling` say nothing about the result or reason
+    let detail = call_arguments(call_node, source)
+        .into_iter()
+        .find(|(_, value)| value.kind() != "simple_identifier")
+        .and_then(|(_, value)| argument_text(value, source));
+    Some(Terminal { kind, detail })
+}
+
+/// The ways a function ends the flow, also through the helpers of its class and the functions
+/// it delegates to
+fn extract_terminals(
+    func_node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    project: ProjectIndexes,
+) -> Vec<Terminal> {
+    fn search_node<'t>(
+        node: tree_sitter::Node<'t>,
+        source: &str,
+        helpers: &mut HelperFunctions<'t, '_>,
+        terminals: &mut Vec<Terminal>,
+    ) {
+        if node.kind() == "call_expression" {
+            if let Some(terminal) = terminal_call(node, source) {
+                if !terminals.contains(&terminal) {
+                    terminals.push(terminal);
+                }
+                return;
+            }
+            if let Some(body) = helpers.enter(node, source) {
+                search_node(body, source, helpers, terminals);
+                helpers.leave();
+            } else if let Some(delegate) = helpers.delegate(node, source) {
+                for terminal in &delegate.terminals {
+                    if !terminals.contains(terminal) {
+                        terminals.push(terminal.clone());
+                    }
+                }
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            search_node(child, source, helpers, terminals);
+        }
+    }
+
+    let mut terminals = Vec::new();
+    let mut helpers = HelperFunctions::of_class(func_node, source, file, project);
+    search_node(func_node, source, &mut helpers, &mut terminals);
+    terminals
+}
+
+/// The Behandlinger a function starts, like `opprettBehandling(AnnenBehandling(krav))`. Any
+/// Behandling constructed in it counts, also through the helpers of its class and the functions
+/// it delegates to.
+fn extract_spawned_behandlinger(
+    func_node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    project: ProjectIndexes,
+) -> Vec<String> {
+    fn search_node<'t>(
+        node: tree_sitter::Node<'t>,
+        source: &str,
+        scope: &FileScope,
+        helpers: &mut HelperFunctions<'t, '_>,
+        spawns: &mut Vec<String>,
+    ) {
+        if node.kind() == "call_expression" {
+            let behandling = extract_constructor_name(node, source)
+                .map(|name| scope.resolve(&name, helpers.project.classes))
+                .filter(|name| {
+                    helpers
+                        .project
+                        .classes
+                        .get(name)
+                        .is_some_and(|class| class.is_behandling())
+                });
+            if let Some(behandling) = behandling {
+                if !spawns.contains(&behandling) {
+                    spawns.push(behandling);
+                }
+            } else if let Some(body) = helpers.enter(node, source) {
+                search_node(body, source, scope, helpers, spawns);
+                helpers.leave();
+            } else if let Some(delegate) = helpers.delegate(node, source) {
+                for behandling in &delegate.spawns {
+                    if !spawns.contains(behandling) {
+                        spawns.push(behandling.clone());
+                    }
+                }
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            search_node(child, source, scope, helpers, spawns);
+        }
+    }
+
+    let mut spawns = Vec::new();
+    let scope = FileScope::of(func_node, source);
+    let mut helpers = HelperFunctions::of_class(func_node, source, file, project);
+    search_node(func_node, source, &scope, &mut helpers, &mut spawns);
+    spawns
+}
+
+/// The Behandlinger a function names in calls to one of `functions`, like
+/// `ventPaaBehandling(AnnenBehandling::class)`, also through the helpers of its class and the
+/// functions it delegates to, whose Behandlinger `delegated` gives
+fn extract_behandling_calls(
+    func_node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    project: ProjectIndexes,
+    functions: &[String],
+    delegated: fn(&FunctionInfo) -> &Vec<String>,
+) -> Vec<String> {
+    fn search_node<'t>(
+        node: tree_sitter::Node<'t>,
+        source: &str,
+        scope: &FileScope,
+        functions: &[String],
+        delegated: fn(&FunctionInfo) -> &Vec<String>,
+        helpers: &mut HelperFunctions<'t, '_>,
+        found: &mut Vec<String>,
+    ) {
+        if node.kind() == "call_expression" {
+            let is_configured = called_function_name(node, source)
+                .is_some_and(|name| functions.iter().any(|function| function == name));
+            if is_configured {
+                if let Some(behandling) =
+                    find_behandling_in_node(node, source, scope, helpers.project)
+                {
+                    if !found.contains(&behandling) {
+                        found.push(behandling);
+                    }
+                }
+            } else if let Some(body) = helpers.enter(node, source) {
+                search_node(body, source, scope, functions, delegated, helpers, found);
+                helpers.leave();
+            } else if let Some(delegate) = helpers.delegate(node, source) {
+                for behandling in delegated(delegate) {
+                    if !found.contains(behandling) {
+                        found.push(behandling.clone());
+                    }
+                }
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            search_node(child, source, scope, functions, delegated, helpers, found);
+        }
+    }
+
+    let mut found = Vec::new();
+    if functions.is_empty() {
+        return found;
+    }
+    let scope = FileScope::of(func_node, source);
+    let mut helpers = HelperFunctions::of_class(func_node, source, file, project);
+    search_node(
+        func_node,
+        source,
+        &scope,
+        functions,
+        delegated,
+        &mut helpers,
+        &mut found,
+    );
+    found
+}
+
+/// The first Behandling class named below `node`, by class literal (`AnnenBehandling::class`),
+/// constructor (`AnnenBehandling()`) or type argument (`<AnnenBehandling>`)
+fn find_behandling_in_node(
+    node: tree_sitter::Node,
+    source: &str,
+    scope: &FileScope,
+    project: ProjectIndexes,
+) -> Option<String> {
+    let name = match node.kind() {
+        "class_literal" | "callable_reference" | "type_identifier" | "simple_identifier" => {
+            node.utf8_text(source.as_bytes()).ok().map(|text| {
+                text.trim_end_matches("::class")
+                    .trim_start_matches("::")
+                    .trim()
+            })
+        }
+        _ => None,
+    };
+    if let Some(name) = name.filter(|name| name.starts_with(|c: char| c.is_ascii_uppercase())) {
+        let behandling = scope.resolve(name, project.classes);
+        if project
+            .classes
+            .get(&behandling)
+            .is_some_and(|class| class.is_behandling())
+        {
+            return Some(behandling);
+        }
+    }
+
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.children(&mut cursor).collect();
+    children
+        .into_iter()
+        .find_map(|child| find_behandling_in_node(child, source, scope, project))
+}
+
+/// Calls and `nesteAktivitet` arguments that postpone an aktivitet until a given time
+const WAIT_NAMES: &[&str] = &["utsettTil", "ventTil", "utsattTil", "venterTil"];
+
+/// The name of a called function, also when it is called on an object: `behandling.utsettTil`
+fn called_function_name<'s>(call_node: tree_sitter::Node, source: &'s str) -> Option<&'s str> {
+    let callee = call_node.child(0)?;
+    match callee.kind() {
+        "simple_identifier" => callee.utf8_text(source.as_bytes()).ok(),
+        "navigation_expression" => {
+            let suffix = callee.child(callee.child_count() - 1)?;
+            let mut cursor = suffix.walk();
+            let name = suffix
+                .children(&mut cursor)
+                .find(|child| child.kind() == "simple_identifier")?;
+            name.utf8_text(source.as_bytes()).ok()
+        }
+        _ => None,
+    }
+}
+
+/// What a wait call like `ventTil(virkningsdato)` waits for
+fn wait_call(call_node: tree_sitter::Node, source: &str) -> Option<String> {
+    if !WAIT_NAMES.contains(&called_function_name(call_node, source)?) {
+        return None;
+    }
+    let (_, value) = call_arguments(call_node, source).into_iter().next()?;
+    argument_text(value, source)
+}
+
+/// A wait called on a `nesteAktivitet` call, as in `nesteAktivitet(A()).utsettTil(dato)`
+fn chained_wait(
+    call_node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> Option<String> {
+    let callee = call_node.child(0)?;
+    let receiver = callee.child(0)?;
+    let chained = callee.kind() == "navigation_expression"
+        && receiver.kind() == "call_expression"
+        && is_neste_aktivitet_call(receiver, source, conventions);
+    chained.then(|| wait_call(call_node, source)).flatten()
+}
+
+/// A wait passed to `nesteAktivitet` as a named argument: `nesteAktivitet(A(), ventTil = dato)`
+fn call_wait(call_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let (_, value) = call_arguments(call_node, source)
+        .into_iter()
+        .find(|(name, _)| {
+            name.as_deref()
+                .is_some_and(|name| WAIT_NAMES.contains(&name))
+        })?;
+    argument_text(value, source)
+}
+
+/// What a function waits for before its aktivitet runs again, like `return ventTil(virkningsdato)`.
+/// Waits chained on a `nesteAktivitet` call belong to that transition instead.
+fn extract_waits(
+    func_node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> Vec<String> {
+    fn search_node(
+        node: tree_sitter::Node,
+        source: &str,
+        conventions: &ConventionsConfig,
+        waits: &mut Vec<String>,
+    ) {
+        if node.kind() == "call_expression" && chained_wait(node, source, conventions).is_none() {
+            if let Some(until) = wait_call(node, source) {
+                if !waits.contains(&until) {
+                    waits.push(until);
+                }
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            search_node(child, source, conventions, waits);
+        }
+    }
+
+    let mut waits = Vec::new();
+    search_node(func_node, source, conventions, &mut waits);
+    waits
+}
+
+/// How many helper calls deep transitions are looked for, e.g. doProcess → bestemNesteAktivitet
+const MAX_HELPER_DEPTH: usize = 5;
+
+/// Functions of the processor class that doProcess may leave the choice of the next aktivitet
+/// to, and the helper calls currently being followed. Calls to other classes and constants are
+/// resolved through the project indexes, using the declared types of the class properties.
+struct HelperFunctions<'t, 'p> {
+    functions: HashMap<String, tree_sitter::Node<'t>>,
+    property_types: HashMap<String, String>, // Property or constructor parameter -> type name
+    project: ProjectIndexes<'p>,
+    call_stack: Vec<String>,
+    scope_bindings: Vec<(String, String)>, // Lambda parameter -> aktivitet it is bound to
+    file: PathBuf,                         // Source file, for the location of transitions
+}
+
+impl<'t, 'p> HelperFunctions<'t, 'p> {
+    /// The other functions and the properties of the class that `func_node` is declared in
+    fn of_class(
+        func_node: tree_sitter::Node<'t>,
+        source: &str,
+        file: &Path,
+        project: ProjectIndexes<'p>,
+    ) -> Self {
+        let mut functions = HashMap::new();
+        let mut property_types = HashMap::new();
+
+        if let Some(class_node) = enclosing_class(func_node) {
+            let mut cursor = class_node.walk();
+            for body in class_node
+                .children(&mut cursor)
+                .filter(|child| child.kind() == "class_body")
+            {
+                let mut body_cursor = body.walk();
+                for function in body
+                    .children(&mut body_cursor)
+                    .filter(|child| child.kind() == "function_declaration")
+                {
+                    if let Some(name) = function_name(function, source) {
+                        functions.insert(name, function);
+                    }
+                }
+            }
+            collect_property_types(class_node, source, &mut property_types);
+        }
+
+        HelperFunctions {
+            functions,
+            property_types,
+            project,
+            call_stack: function_name(func_node, source).into_iter().collect(),
+            scope_bindings: Vec::new(),
+            file: file.to_path_buf(),
+        }
+    }
+
+    /// If `call_node` calls a helper of the class that is not already being followed, start
+    /// following it and return its body
+    fn enter(
+        &mut self,
+        call_node: tree_sitter::Node<'t>,
+        source: &str,
+    ) -> Option<tree_sitter::Node<'t>> {
+        if self.call_stack.len() > MAX_HELPER_DEPTH {
+            return None;
+        }
+
+        // Only plain calls like bestemNesteAktivitet(krav), not calls on another object
+        let callee = call_node.child(0)?;
+        if callee.kind() != "simple_identifier" {
+            return None;
+        }
+        let name = callee.utf8_text(source.as_bytes()).ok()?;
+        if self.call_stack.iter().any(|called| called == name) {
+            return None;
+        }
+
+        let body = function_body(*self.functions.get(name)?)?;
+        self.call_stack.push(name.to_string());
+        Some(body)
+    }
+
+    fn leave(&mut self) {
+        self.call_stack.pop();
+    }
+
+    /// The function of another class that `call_node` calls, like `overgangService.nesteSteg()`.
+    /// Matched by name and the receiver's type; if the type is not indexed (e.g. an interface),
+    /// a function name that only one class has is enough. Plain calls match top-level functions.
+    fn delegate(&self, call_node: tree_sitter::Node, source: &str) -> Option<&'p FunctionInfo> {
+        let callee = call_node.child(0)?;
+        let (receiver, name) = match callee.kind() {
+            "navigation_expression" => {
+                let receiver = callee.child(0)?.utf8_text(source.as_bytes()).ok()?;
+                let suffix = callee.child(callee.child_count() - 1)?;
+                let mut cursor = suffix.walk();
+                let name = suffix
+                    .children(&mut cursor)
+                    .find(|child| child.kind() == "simple_identifier")?;
+                (Some(receiver), name.utf8_text(source.as_bytes()).ok()?)
+            }
+            "simple_identifier" => (None, callee.utf8_text(source.as_bytes()).ok()?),
+            _ => return None,
+        };
+        let candidates = self.project.functions.get(name)?;
+
+        let Some(receiver) = receiver else {
+            return candidates.iter().find(|f| f.class_name.is_empty());
+        };
+        // A property of the class, or an object / companion called by its type name
+        let receiver_type = match self.property_types.get(receiver) {
+            Some(receiver_type) => Some(receiver_type.as_str()),
+            None if receiver.starts_with(|c: char| c.is_ascii_uppercase()) => Some(receiver),
+            None => None,
+        };
+
+        if let Some(receiver_type) = receiver_type {
+            if let Some(function) = candidates.iter().find(|f| f.class_name == receiver_type) {
+                return Some(function);
+            }
+            // A project class without the function: not one of the candidates either
+            if self.project.classes.contains_key(receiver_type) {
+                return None;
+            }
+        }
+        // The receiver's type is unknown, or from outside the project; the only function by
+        // that name is the best guess
+        match candidates.as_slice() {
+            [only] => Some(only),
+            _ => None,
+        }
+    }
+
+    /// The aktivitet a constant, enum entry or factory passed to `nesteAktivitet` stands for,
+    /// like `NESTE_STEG`, `Konstanter.NESTE_STEG`, `Steg.VURDER.aktivitet()` or
+    /// `VurderAktivitet.forKrav(krav)`. Checked before constructors, which may be arguments.
+    fn constant_aktivitet(&self, call_node: tree_sitter::Node, source: &str) -> Option<String> {
+        let argument = *aktivitet_arguments(call_node, source).first()?;
+        let text = argument.utf8_text(source.as_bytes()).ok()?;
+        let parts: Vec<&str> = text
+            .split('.')
+            .map(|part| part.split('(').next().unwrap_or(part).trim())
+            .collect();
+
+        // The longest prefix that is a known constant, so a member called on it is ignored
+        (1..=parts.len()).rev().find_map(|end| {
+            self.project
+                .constants
+                .get(&parts[..end].join("."))
+                .map(|constant| constant.aktivitet_name.clone())
+        })
+    }
+
+    /// The aktivitet passed to `nesteAktivitet` as the parameter of an enclosing scope function,
+    /// like `it` in `VurderAktivitet(krav).let { nesteAktivitet(it) }`
+    fn bound_aktivitet(&self, call_node: tree_sitter::Node, source: &str) -> Option<String> {
+        let argument = *aktivitet_arguments(call_node, source).first()?;
+        let text = argument.utf8_text(source.as_bytes()).ok()?.trim();
+        self.scope_bindings
+            .iter()
+            .rev()
+            .find(|(parameter, _)| parameter == text)
+            .map(|(_, aktivitet)| aktivitet.clone())
+    }
+}
+
+/// The arguments of a call with their names, `None` for positional ones. The value is the last
+/// child, also for named arguments like `aktivitet = NESTE_STEG`.
+fn call_arguments<'t>(
+    call_node: tree_sitter::Node<'t>,
+    source: &str,
+) -> Vec<(Option<String>, tree_sitter::Node<'t>)> {
+    let mut cursor = call_node.walk();
+    let Some(suffix) = call_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "call_suffix")
+    else {
+        return Vec::new();
+    };
+    let mut suffix_cursor = suffix.walk();
+    let Some(arguments) = suffix
+        .children(&mut suffix_cursor)
+        .find(|child| child.kind() == "value_arguments")
+    else {
+        return Vec::new();
+    };
+
+    let mut arguments_cursor = arguments.walk();
+    let arguments = arguments
+        .children(&mut arguments_cursor)
+        .filter(|child| child.kind() == "value_argument")
+        .filter_map(|argument| {
+            let mut argument_cursor = argument.walk();
+            let children: Vec<_> = argument.children(&mut argument_cursor).collect();
+            let value = *children.iter().rfind(|child| child.is_named())?;
+            // `name = value`: the name is an identifier followed by `=`
+            let name = match children[..] {
+                [name, equals, ..] if equals.kind() == "=" => name
+                    .utf8_text(source.as_bytes())
+                    .ok()
+                    .map(|name| name.to_string()),
+                _ => None,
+            };
+            Some((name, value))
+        })
+        .collect();
+    arguments
+}
+
+/// The arguments of a `nesteAktivitet` call that may hold the next aktivitet, in order. A named
+/// `aktivitet` argument is the only one; otherwise every argument but the `begrunnelse`, so
+/// `nesteAktivitet(begrunnelse = "...", VurderAktivitet(krav))` does not depend on the order.
+fn aktivitet_arguments<'t>(
+    call_node: tree_sitter::Node<'t>,
+    source: &str,
+) -> Vec<tree_sitter::Node<'t>> {
+    let arguments = call_arguments(call_node, source);
+    if let Some((_, value)) = arguments
+        .iter()
+        .find(|(name, _)| name.as_deref() == Some("aktivitet"))
+    {
+        return vec![*value];
+    }
+    arguments
+        .into_iter()
+        .filter(|(name, _)| name.as_deref() != Some("begrunnelse"))
+        .map(|(_, value)| value)
+        .collect()
+}
+
+/// The `begrunnelse` argument of a `nesteAktivitet` call, without the quotes if it is a string
+fn call_begrunnelse(call_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let (_, value) = call_arguments(call_node, source)
+        .into_iter()
+        .find(|(name, _)| name.as_deref() == Some("begrunnelse"))?;
+    argument_text(value, source)
+}
+
+/// An argument as it is shown on an edge: strings without the quotes, and multiline strings and
+/// expressions on one line
+fn argument_text(value: tree_sitter::Node, source: &str) -> Option<String> {
+    let text = value.utf8_text(source.as_bytes()).ok()?;
+    let text = if value.kind() == "string_literal" {
+        text.trim_matches('"')
+    } else {
+        text
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn is_class_declaration(node: tree_sitter::Node) -> bool {
+    node.kind() == "class_declaration" || node.kind() == "object_declaration"
+}
+
+/// The class or object declaration a node is inside of
+fn enclosing_class(node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    let mut current = node.parent();
+    while let Some(candidate) = current {
+        if is_class_declaration(candidate) {
+            return Some(candidate);
+        }
+        current = candidate.parent();
+    }
+    None
+}
+
+fn class_name(class_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = class_node.walk();
+    let name = class_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "type_identifier" || child.kind() == "simple_identifier")?;
+    name.utf8_text(source.as_bytes())
+        .ok()
+        .map(|name| name.to_string())
+}
+
+/// Declared types of constructor parameters, properties and local variables below `node`,
+/// by simple type name: `private val overgangService: OvergangService` gives
+/// overgangService -> OvergangService
+fn collect_property_types(
+    node: tree_sitter::Node,
+    source: &str,
+    property_types: &mut HashMap<String, String>,
+) {
+    if node.kind() == "class_parameter" || node.kind() == "variable_declaration" {
+        let mut cursor = node.walk();
+        let mut name = None;
+        let mut declared_type = None;
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "simple_identifier" if name.is_none() => {
+                    name = child.utf8_text(source.as_bytes()).ok();
+                }
+                "user_type" => declared_type = Some(extract_type_name(child, source)),
+                "nullable_type" => {
+                    let mut nullable_cursor = child.walk();
+                    declared_type = child
+                        .children(&mut nullable_cursor)
+                        .find(|c| c.kind() == "user_type")
+                        .map(|user_type| extract_type_name(user_type, source));
+                }
+                _ => {}
+            }
+        }
+        if let (Some(name), Some(declared_type)) = (name, declared_type) {
+            let simple_type = declared_type.rsplit('.').next().unwrap_or_default();
+            property_types.insert(name.to_string(), simple_type.to_string());
+        }
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_property_types(child, source, property_types);
+    }
+}
+
+fn function_name(func_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = func_node.walk();
+    let name = func_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "simple_identifier")?;
+    name.utf8_text(source.as_bytes())
+        .ok()
+        .map(|name| name.to_string())
+}
+
+/// Everything a function does to the flow: transitions, manuell behandlinger, terminals, waits
+/// and the Behandlinger it starts, waits on and resumes
+fn extract_function_info(
+    func_node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    project: ProjectIndexes,
+) -> FunctionInfo {
+    let conventions = &project.config.conventions;
+    FunctionInfo {
+        class_name: enclosing_class(func_node)
+            .and_then(|class_node| class_name(class_node, source))
+            .unwrap_or_default(),
+        function_name: function_name(func_node, source).unwrap_or_default(),
+        file: file.to_path_buf(),
+        next_aktiviteter: extract_neste_aktivitet_calls(func_node, source, file, project),
+        has_manuell_behandling: has_manuell_behandling_call(func_node, source, conventions),
+        manuell_behandlinger: extract_manuell_behandlinger(func_node, source, file, conventions),
+        manuell_on_error: catch_blocks_creating_manuell(func_node, source, conventions),
+        terminals: extract_terminals(func_node, source, file, project),
+        waits_until: extract_waits(func_node, source, conventions),
+        spawns: extract_spawned_behandlinger(func_node, source, file, project),
+        waits_for: extract_behandling_calls(
+            func_node,
+            source,
+            file,
+            project,
+            &conventions.vent_paa_behandling,
+            |function| &function.waits_for,
+        ),
+        resumes: extract_behandling_calls(
+            func_node,
+            source,
+            file,
+            project,
+            &conventions.gjenoppta_behandling,
+            |function| &function.resumes,
+        ),
+    }
+}
+
+/// Add every function that makes transitions to the index, except the doProcess and onFinished
+/// of processors, which are indexed as processors
+fn extract_functions(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &Path,
+    project: ProjectIndexes,
+    index: &mut HashMap<String, Vec<FunctionInfo>>,
+) {
+    if node.kind() == "function_declaration" {
+        // doProcess and onFinished of concrete processors are indexed as processors; the ones of
+        // base classes are kept, even without transitions, for the processors that inherit them
+        let is_processor_function =
+            is_processor_function(node, source, &project.config.conventions);
+        let class_node = enclosing_class(node);
+        let in_base_class = class_node.is_some_and(|class_node| is_base_class(class_node, source));
+
+        if !is_processor_function || in_base_class {
+            let function = extract_function_info(node, source, file, project);
+            let affects_flow = !function.next_aktiviteter.is_empty()
+                || !function.terminals.is_empty()
+                || !function.spawns.is_empty()
+                || !function.waits_for.is_empty()
+                || !function.resumes.is_empty();
+            if !function.function_name.is_empty() && (affects_flow || is_processor_function) {
+                index
+                    .entry(function.function_name.clone())
+                    .or_default()
+                    .push(function);
+            }
+        }
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        extract_functions(source, child, file, project, index);
+    }
+}
+
+/// Add the constants and enum entries that stand for an aktivitet to the index, both by name
+/// and qualified by their object, class or enum: `NESTE_STEG` and `Konstanter.NESTE_STEG`.
+/// Only top-level and object properties count; instance properties vary per processor.
+/// Factory functions of objects and companion objects are added by qualified name only.
+fn extract_constants(
+    source: &str,
+    node: tree_sitter::Node,
+    file: &Path,
+    project: ProjectIndexes,
+    index: &mut HashMap<String, AktivitetConstant>,
+) {
+    let mut add = |owner: Option<String>, name: &str, aktivitet_name: String| {
+        let constant = AktivitetConstant {
+            aktivitet_name,
+            file: file.to_path_buf(),
+        };
+        if let Some(owner) = owner {
+            index.insert(format!("{}.{}", owner, name), constant.clone());
+        }
+        // The first one wins when the plain name is declared more than once
+        index.entry(name.to_string()).or_insert(constant);
+    };
+
+    match node.kind() {
+        "property_declaration" => {
+            let shared = node
+                .parent()
+                .is_some_and(|parent| parent.kind() == "source_file")
+                || is_object_member(node);
+            let mut cursor = node.walk();
+            let name = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "variable_declaration")
+                .and_then(|declaration| function_name(declaration, source));
+            if let (true, Some(name), Some(aktivitet_name)) =
+                (shared, name, find_aktivitet_in_node(node, source, project))
+            {
+                add(constant_owner(node, source), &name, aktivitet_name);
+            }
+        }
+        "function_declaration" if is_object_member(node) => {
+            // A factory like `companion object { fun forKrav(krav: Krav) = VurderAktivitet(krav) }`,
+            // only indexed qualified as `VurderAktivitet.forKrav`. The created aktivitet is used
+            // over the declared return type, which may be an abstract base class.
+            let return_type = declared_return_type(node, source);
+            let body = function_body(node);
+            let aktivitet_name = match &return_type {
+                Some(return_type) if !project.is_aktivitet_class(return_type) => None,
+                _ => body
+                    .and_then(|body| find_aktivitet_in_node(body, source, project))
+                    .or(return_type),
+            };
+            if let (Some(owner), Some(name), Some(aktivitet_name)) = (
+                constant_owner(node, source),
+                function_name(node, source),
+                aktivitet_name,
+            ) {
+                index.insert(
+                    format!("{}.{}", owner, name),
+                    AktivitetConstant {
+                        aktivitet_name,
+                        file: file.to_path_buf(),
+                    },
+                );
+            }
+        }
+        "enum_entry" => {
+            // VURDER({ VurderAktivitet() }), VURDER(VurderAktivitet::class) or an entry body
+            if let (Some(name), Some(aktivitet_name)) = (
+                function_name(node, source),
+                find_aktivitet_in_node(node, source, project),
+            ) {
+                add(constant_owner(node, source), &name, aktivitet_name);
+            }
+        }
+        "when_expression" if when_subject(node, source).as_deref() == Some("this") => {
+            // fun aktivitet() = when (this) { VURDER -> VurderAktivitet() ... } in an enum
+            let mut cursor = node.walk();
+            for entry in node
+                .children(&mut cursor)
+                .filter(|child| child.kind() == "when_entry")
+            {
+                let mut entry_cursor = entry.walk();
+                let children: Vec<_> = entry.children(&mut entry_cursor).collect();
+                let Some(aktivitet_name) = children
+                    .iter()
+                    .find(|child| child.kind() == "control_structure_body")
+                    .and_then(|body| find_aktivitet_in_node(*body, source, project))
+                else {
+                    continue;
+                };
+                for condition in children
+                    .iter()
+                    .filter(|child| child.kind() == "when_condition")
+                {
+                    let text = condition.utf8_text(source.as_bytes()).unwrap_or_default();
+                    let name = text.rsplit('.').next().unwrap_or_default().trim();
+                    if !name.is_empty() {
+                        add(constant_owner(node, source), name, aktivitet_name.clone());
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        extract_constants(source, child, file, project, index);
+    }
+}
+
+/// True for members of an object or companion object, which are shared by all instances
+fn is_object_member(node: tree_sitter::Node) -> bool {
+    node.parent()
+        .filter(|parent| parent.kind() == "class_body")
+        .and_then(|body| body.parent())
+        .is_some_and(|owner| {
+            owner.kind() == "object_declaration" || owner.kind() == "companion_object"
+        })
+}
+
+/// The simple name of a function's declared return type, e.g. `VurderAktivitet` for
+/// `fun forKrav(krav: Krav): VurderAktivitet`; None for an inferred type
+fn declared_return_type(func_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = func_node.walk();
+    let children: Vec<_> = func_node.children(&mut cursor).collect();
+    // The type after the parameters; an extension receiver type comes before them
+    let parameters = children
+        .iter()
+        .position(|child| child.kind() == "function_value_parameters")?;
+    let return_type = children[parameters..]
+        .iter()
+        .take_while(|child| child.kind() != "function_body" && child.kind() != "=")
+        .find(|child| child.kind() == "user_type" || child.kind() == "nullable_type")?;
+    let name = extract_type_name(*return_type, source);
+    let name = name.trim_end_matches('?');
+    let name = name.split('<').next().unwrap_or(name);
+    Some(name.rsplit('.').next().unwrap_or(name).to_string())
+}
+
+/// The name a constant is qualified with: the object or enum it is declared in, or the class of
+/// its companion object
+fn constant_owner(node: tree_sitter::Node, source: &str) -> Option<String> {
+    class_name(enclosing_class(node)?, source)
+}
+
+/// The aktivitet created or referenced below `node`: `VurderAktivitet()`, `VurderAktivitet::class`
+/// or `::VurderAktivitet`
+fn find_aktivitet_in_node(
+    node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<String> {
+    if let Some(name) =
+        find_constructor_in_node(node, source).filter(|name| project.is_aktivitet_class(name))
+    {
+        return Some(name);
+    }
+    if node.kind() == "callable_reference" || node.kind() == "class_literal" {
+        let text = node.utf8_text(source.as_bytes()).ok()?;
+        let name = text.trim_end_matches("::class").trim_start_matches("::");
+        return project.is_aktivitet_class(name).then(|| name.to_string());
+    }
+
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.children(&mut cursor).collect();
+    children
+        .into_iter()
+        .find_map(|child| find_aktivitet_in_node(child, source, project))
+}
+
+fn extract_neste_aktivitet_calls(
+    func_node: tree_sitter::Node,
+    source: &str,
+    file: &Path,
+    project: ProjectIndexes,
+) -> Vec<NextAktivitet> {
+    let mut aktiviteter = Vec::new();
+    let mut helpers = HelperFunctions::of_class(func_node, source, file, project);
+
+    // A block body, or an expression body like `= nesteAktivitet(Foo())`
+    if let Some(body) = function_body(func_node) {
+        find_neste_aktivitet_in_node(body, source, &mut aktiviteter, None, &mut helpers);
+    }
+
+    let scope = FileScope::of(func_node, source);
+    for next in aktiviteter.iter_mut().filter(|next| !next.is_dynamic) {
+        next.aktivitet_name = scope.resolve(&next.aktivitet_name, project.classes);
+    }
+
+    // aktivitetFullfort() is found separately, see extract_terminals
+    aktiviteter
+}
+
+fn find_neste_aktivitet_in_node<'t>(
+    node: tree_sitter::Node<'t>,
+    source: &str,
+    aktiviteter: &mut Vec<NextAktivitet>,
+    condition: Option<Condition>,
+    helpers: &mut HelperFunctions<'t, '_>,
+) {
+    let mut cursor = node.walk();
+
+    match node.kind() {
+        "call_expression" => {
+            // Check if this is a nesteAktivitet call
+            if is_neste_aktivitet_call(node, source, &helpers.project.config.conventions) {
+                if let Some(alternatives) = extract_elvis_aktiviteter_from_call(node, source) {
+                    // nesteAktivitet(krav?.let { A() } ?: B())
+                    for (aktivitet_name, branch_condition) in alternatives {
+                        aktiviteter.push(NextAktivitet {
+                            aktivitet_name,
+                            location: source_location(node, &helpers.file),
+                            condition: Some(Condition::and(condition.clone(), branch_condition)),
+                            is_collection: false,
+                            error_type: None,
+                            begrunnelse: call_begrunnelse(node, source),
+                            wait_until: call_wait(node, source),
+                            loop_context: None,
+                            is_dynamic: false,
+                        });
+                    }
+                } else if let Some(aktivitet_name) = helpers
+                    .constant_aktivitet(node, source)
+                    .or_else(|| extract_aktivitet_from_call(node, source))
+                    .or_else(|| helpers.bound_aktivitet(node, source))
+                {
+                    aktiviteter.push(NextAktivitet {
+                        aktivitet_name,
+                        location: source_location(node, &helpers.file),
+                        condition: condition.clone(),
+                        is_collection: false,
+                        error_type: None,
+                        begrunnelse: call_begrunnelse(node, source),
+                        wait_until: call_wait(node, source),
+                        loop_context: None,
+                        is_dynamic: false,
+                    });
+                } else {
+                    // `val neste = if (...) A() else B()` followed by `nesteAktivitet(neste)`
+                    for (aktivitet_name, value_condition) in
+                        local_aktiviteter(node, source, helpers.project)
+                    {
+                        aktiviteter.push(NextAktivitet {
+                            aktivitet_name,
+                            location: source_location(node, &helpers.file),
+                            condition: and_branch(condition.clone(), value_condition),
+                            is_collection: false,
+                            error_type: None,
+                            begrunnelse: call_begrunnelse(node, source),
+                            wait_until: call_wait(node, source),
+                            loop_context: None,
+                            is_dynamic: false,
+                        });
+                    }
+                }
+            }
+            // `krav.linjer.forEach { linje -> nesteAktivitet(OpprettAktivitet(linje)) }` makes its
+            // transitions once per element, whatever the lambda parameter is called
+            else if let Some(operation) = collection_operation(node, source) {
+                let start = aktiviteter.len();
+                let mut lambda_cursor = node.walk();
+                for child in node.children(&mut lambda_cursor) {
+                    find_neste_aktivitet_in_node(
+                        child,
+                        source,
+                        aktiviteter,
+                        condition.clone(),
+                        helpers,
+                    );
+                }
+                // Without a transition in it, the lambda may create the aktiviteter that are
+                // returned: `return krav.linjer.map { linje -> OpprettAktivitet(linje) }`
+                if aktiviteter.len() == start {
+                    if let Some(aktivitet_name) =
+                        extract_aktivitet_from_collection_call(node, source, helpers.project)
+                    {
+                        aktiviteter.push(NextAktivitet {
+                            aktivitet_name,
+                            location: source_location(node, &helpers.file),
+                            condition: condition.clone(),
+                            is_collection: true,
+                            error_type: None,
+                            begrunnelse: None,
+                            wait_until: None,
+                            loop_context: None,
+                            is_dynamic: false,
+                        });
+                    }
+                }
+                for next in &mut aktiviteter[start..] {
+                    if !returned_in_loop(node, &next.location, &helpers.file) {
+                        next.is_collection = true;
+                        next.loop_context.get_or_insert_with(|| operation.clone());
+                    }
+                }
+                return;
+            }
+            // Check if this is a nesteAktiviteter() call with a collection pattern
+            else if is_neste_aktiviteter_call(node, source, &helpers.project.config.conventions) {
+                if let Some(started) =
+                    extract_aktiviteter_from_collection_pattern(node, source, helpers.project)
+                {
+                    for started in started {
+                        aktiviteter.push(NextAktivitet {
+                            aktivitet_name: started.name,
+                            location: source_location(node, &helpers.file),
+                            condition: and_branch(condition.clone(), started.condition),
+                            is_collection: started.is_collection,
+                            error_type: None,
+                            begrunnelse: call_begrunnelse(node, source),
+                            wait_until: call_wait(node, source),
+                            loop_context: None,
+                            is_dynamic: started.is_dynamic,
+                        });
+                    }
+                }
+            }
+            // `nesteAktivitet(VurderAktivitet()).utsettTil(virkningsdato)` postpones the transitions
+            // made in the receiver
+            else if let Some(until) =
+                chained_wait(node, source, &helpers.project.config.conventions)
+            {
+                let start = aktiviteter.len();
+                let mut wait_cursor = node.walk();
+                for child in node.children(&mut wait_cursor) {
+                    find_neste_aktivitet_in_node(
+                        child,
+                        source,
+                        aktiviteter,
+                        condition.clone(),
+                        helpers,
+                    );
+                }
+                for next in &mut aktiviteter[start..] {
+                    next.wait_until.get_or_insert_with(|| until.clone());
+                }
+                return;
+            }
+            // `behandling.let { ... }`, `run { ... }` and `with(krav) { ... }` always run their
+            // lambda, so transitions in it are made under the same condition
+            else if let Some(function) = scope_function_name(node, source) {
+                let binding = matches!(function, "let" | "also")
+                    .then(|| scope_binding(node, source, helpers.project))
+                    .flatten();
+                let bound = binding.is_some();
+                helpers.scope_bindings.extend(binding);
+                let mut scope_cursor = node.walk();
+                for child in node.children(&mut scope_cursor) {
+                    find_neste_aktivitet_in_node(
+                        child,
+                        source,
+                        aktiviteter,
+                        condition.clone(),
+                        helpers,
+                    );
+                }
+                if bound {
+                    helpers.scope_bindings.pop();
+                }
+                return;
+            }
+            // A helper function of the class, e.g. `return bestemNesteAktivitet(krav)`
+            else if let Some(body) = helpers.enter(node, source) {
+                find_neste_aktivitet_in_node(body, source, aktiviteter, condition.clone(), helpers);
+                helpers.leave();
+            }
+            // A function of another class, e.g. `return overgangService.nesteSteg(behandling)`; its
+            // own conditions only apply within the branch it is called in
+            else if let Some(delegate) = helpers.delegate(node, source) {
+                for next in &delegate.next_aktiviteter {
+                    aktiviteter.push(NextAktivitet {
+                        condition: and_branch(condition.clone(), next.condition.clone()),
+                        ..next.clone()
+                    });
+                }
+            }
+            // `krav?.let { ... }` only runs the lambda when krav is not null
+            else if let Some(receiver) = safe_scope_receiver(node, source) {
+                let branch_condition = Some(Condition::and(
+                    condition.clone(),
+                    null_check(&receiver, "!="),
+                ));
+                let mut scope_cursor = node.walk();
+                for child in node.children(&mut scope_cursor) {
+                    find_neste_aktivitet_in_node(
+                        child,
+                        source,
+                        aktiviteter,
+                        branch_condition.clone(),
+                        helpers,
+                    );
+                }
+                return;
+            }
+            // Note: aktivitetFullfort() calls are ignored here, they are terminals rather than
+            // transitions
+        }
+        "for_statement" | "while_statement" | "do_while_statement" => {
+            // A transition made on every round of a loop fans out, like one in `.forEach { }`,
+            // unless the loop is left with it
+            let start = aktiviteter.len();
+            let mut loop_cursor = node.walk();
+            for child in node.children(&mut loop_cursor) {
+                find_neste_aktivitet_in_node(
+                    child,
+                    source,
+                    aktiviteter,
+                    condition.clone(),
+                    helpers,
+                );
+            }
+            let context = loop_header(node, source);
+            for next in &mut aktiviteter[start..] {
+                if !returned_in_loop(node, &next.location, &helpers.file) {
+                    next.is_collection = true;
+                    next.loop_context.get_or_insert_with(|| context.clone());
+                }
+            }
+            return;
+        }
+        "catch_block" => {
+            // Transitions made while handling an exception are error paths
+            let error_type = catch_exception_type(node, source);
+            let mut catch_aktiviteter = Vec::new();
+            let mut catch_cursor = node.walk();
+            for child in node.children(&mut catch_cursor) {
+                find_neste_aktivitet_in_node(
+                    child,
+                    source,
+                    &mut catch_aktiviteter,
+                    condition.clone(),
+                    helpers,
+                );
+            }
+            for mut next in catch_aktiviteter {
+                next.error_type = Some(error_type.clone());
+                aktiviteter.push(next);
+            }
+            return;
+        }
+        "elvis_expression" => {
+            // `value ?: fallback`: the fallback, often `return nesteAktivitet(...)`, only runs
+            // when the value is null
+            let mut elvis_cursor = node.walk();
+            let parts: Vec<_> = node.named_children(&mut elvis_cursor).collect();
+            if let [value, fallback] = parts[..] {
+                let fallback_condition = Some(Condition::and(
+                    condition.clone(),
+                    null_check(&elvis_subject(value, source), "=="),
+                ));
+                find_neste_aktivitet_in_node(
+                    value,
+                    source,
+                    aktiviteter,
+                    condition.clone(),
+                    helpers,
+                );
+                find_neste_aktivitet_in_node(
+                    fallback,
+                    source,
+                    aktiviteter,
+                    fallback_condition,
+                    helpers,
+                );
+                return;
+            }
+        }
+        "if_expression" => {
+            for (branch_condition, branch) in if_branches(node, source) {
+                find_neste_aktivitet_in_node(
+                    branch,
+                    source,
+                    aktiviteter,
+                    branch_condition,
+                    helpers,
+                );
+            }
+        }
+        "when_expression" => {
+            for (branch_condition, body) in when_branches(node, source) {
+                find_neste_aktivitet_in_node(body, source, aktiviteter, branch_condition, helpers);
+            }
+
+            // The branches are already searched with their own conditions
+            return;
+        }
+        "return_expression" => {
+            // Look for nesteAktivitet in return statement
+            if cursor.goto_first_child() {
+                loop {
+                    find_neste_aktivitet_in_node(
+                        cursor.node(),
+                        source,
+                        aktiviteter,
+                        condition.clone(),
+                        helpers,
+                    );
+                    if !cursor.goto_next_sibling() {
+                        break;
+                    }
+                }
+                cursor.goto_parent();
+            }
+        }
+        _ => {
+            // For other node types, recursively search children without duplicate processing
+        }
+    }
+
+    // Recursively search all children, but avoid duplicate processing
+    if cursor.goto_first_child() {
+        loop {
+            find_neste_aktivitet_in_node(
+                cursor.node(),
+                source,
+                aktiviteter,
+                condition.clone(),
+                helpers,
+            );
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+}
+
+/// The head of a loop as written, with the whitespace collapsed: `for (krav in kravListe)`,
+/// `while (iterator.hasNext())`, and the `while (...)` that ends a `do { } while (...)`
+fn loop_header(loop_node: tree_sitter::Node, source: &str) -> String {
+    let mut cursor = loop_node.walk();
+    let body = loop_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "control_structure_body");
+    let range = match body {
+        Some(body) if loop_node.kind() == "do_while_statement" => {
+            body.end_byte()..loop_node.end_byte()
+        }
+        Some(body) => loop_node.start_byte()..body.start_byte(),
+        None => loop_node.byte_range(),
+    };
+    let text = source.get(range).unwrap_or_default();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// True if the call at `location` is inside `loop_node` and returned from, as in
+/// `for (krav in kravListe) { if (krav.avslatt) return nesteAktivitet(AvslagAktivitet()) }`,
+/// which makes the transition once instead of on every round. `loop_node` may also be a
+/// `forEach { }`, since a return in its lambda leaves the enclosing function too.
+fn returned_in_loop(loop_node: tree_sitter::Node, location: &SourceLocation, file: &Path) -> bool {
+    if location.file != file {
+        return false;
+    }
+    let point = tree_sitter::Point {
+        row: location.line - 1,
+        column: location.column - 1,
+    };
+    let mut node = loop_node.descendant_for_point_range(point, point);
+    while let Some(current) = node {
+        if current == loop_node {
+            return false;
+        }
+        if current.kind() == "return_expression" {
+            return true;
+        }
+        node = current.parent();
+    }
+    false
+}
+
+/// Scope functions that always run their lambda, as in `behandling.let { }` or `with(krav) { }`
+const SCOPE_FUNCTIONS: &[&str] = &["let", "run", "also", "apply", "with"];
+
+/// The scope function a call passes a lambda to, like `let` in `behandling.let { ... }` or
+/// `run { ... }`. Safe calls like `krav?.let { }` are not included; see [`SAFE_SCOPE_FUNCTIONS`].
+fn scope_function_name(call_node: tree_sitter::Node, source: &str) -> Option<&'static str> {
+    let callee = call_node.child(0)?;
+    let name = match callee.kind() {
+        "simple_identifier" => callee,
+        "navigation_expression" => {
+            let suffix = callee.child(callee.child_count() - 1)?;
+            let text = suffix.utf8_text(source.as_bytes()).ok()?;
+            if !text.trim_start().starts_with('.') {
+                return None;
+            }
+            let mut cursor = suffix.walk();
+            let name = suffix
+                .children(&mut cursor)
+                .find(|child| child.kind() == "simple_identifier")?;
+            name
+        }
+        _ => return None,
+    };
+    let name = name.utf8_text(source.as_bytes()).ok()?;
+    let function = SCOPE_FUNCTIONS.iter().find(|function| **function == name)?;
+    // `with` takes its receiver as an argument, the others are called on it
+    let called_on_receiver = callee.kind() == "navigation_expression";
+    let valid = match *function {
+        "with" => !called_on_receiver,
+        "run" => true,
+        _ => called_on_receiver,
+    };
+    if !valid {
+        return None;
+    }
+
+    let mut cursor = call_node.walk();
+    let has_lambda = call_node
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "call_suffix")
+        .any(|suffix| {
+            let mut suffix_cursor = suffix.walk();
+            let found = suffix
+                .children(&mut suffix_cursor)
+                .any(|child| child.kind() == "annotated_lambda");
+            found
+        });
+    has_lambda.then_some(*function)
+}
+
+/// The lambda parameter of `let` or `also` and the aktivitet it is bound to, when the receiver
+/// creates one: (it, VurderAktivitet) for `VurderAktivitet(krav).let { nesteAktivitet(it) }`
+fn scope_binding(
+    call_node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<(String, String)> {
+    let mut receiver = call_node.child(0)?.child(0)?;
+    if receiver.kind() != "call_expression" {
+        return None;
+    }
+    // `VurderAktivitet().apply { ... }` is still the aktivitet
+    while matches!(
+        scope_function_name(receiver, source),
+        Some("apply" | "also")
+    ) {
+        receiver = receiver.child(0)?.child(0)?;
+    }
+    let aktivitet = extract_constructor_name(receiver, source)
+        .filter(|name| project.is_aktivitet_class(name))?;
+
+    let mut cursor = call_node.walk();
+    let suffix = call_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "call_suffix")?;
+    let mut suffix_cursor = suffix.walk();
+    let lambda = suffix
+        .children(&mut suffix_cursor)
+        .find(|child| child.kind() == "annotated_lambda")?;
+    let mut lambda_cursor = lambda.walk();
+    let literal = lambda
+        .children(&mut lambda_cursor)
+        .find(|child| child.kind() == "lambda_literal")?;
+    let mut literal_cursor = literal.walk();
+    let parameters = literal
+        .children(&mut literal_cursor)
+        .find(|child| child.kind() == "lambda_parameters");
+    let parameter = match parameters {
+        Some(parameters) => parameters.utf8_text(source.as_bytes()).ok()?.trim(),
+        None => "it",
+    };
+    // `steg: VurderAktivitet ->` names the parameter before the type
+    let parameter = parameter.split(':').next().unwrap_or(parameter).trim();
+    Some((parameter.to_string(), aktivitet))
+}
+
+/// Scope functions that only run their lambda when the receiver is not null, as in `krav?.let { }`
+const SAFE_SCOPE_FUNCTIONS: &[&str] = &["?.let", "?.run", "?.also", "?.apply"];
+
+/// The receiver of a safe-call scope function, e.g. `krav` in `krav?.let { ... }`
+fn safe_scope_receiver(call_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = call_node.walk();
+    let navigation = call_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "navigation_expression")?;
+    let text = navigation.utf8_text(source.as_bytes()).ok()?;
+    SAFE_SCOPE_FUNCTIONS
+        .iter()
+        .find_map(|function| text.strip_suffix(function))
+        .map(|receiver| receiver.replace("?.", "."))
+}
+
+/// What an elvis expression checks for null: the receiver of `krav?.let { ... } ?: ...`,
+/// otherwise the value itself
+fn elvis_subject(value: tree_sitter::Node, source: &str) -> String {
+    if value.kind() == "call_expression" {
+        if let Some(receiver) = safe_scope_receiver(value, source) {
+            return receiver;
+        }
+    }
+    readable_condition(value, source)
+}
+
+/// Condition source text for a label. Safe calls read as plain calls (`krav?.harUttak` becomes
+/// `krav.harUttak`), and boolean elvis defaults are folded in: `x ?: false` becomes `x`, and
+/// `x ?: true` becomes `x != false`.
+fn readable_condition(node: tree_sitter::Node, source: &str) -> String {
+    if node.kind() == "elvis_expression" {
+        let mut cursor = node.walk();
+        let parts: Vec<_> = node.named_children(&mut cursor).collect();
+        if let [value, default] = parts[..] {
+            match default.utf8_text(source.as_bytes()) {
+                Ok("false") => return readable_condition(value, source),
+                Ok("true") => return format!("{} != false", readable_condition(value, source)),
+                _ => {}
+            }
+        }
+    }
+
+    node.utf8_text(source.as_bytes())
+        .unwrap_or("")
+        .replace("?.", ".")
+}
+
+/// Read a condition into its parts: `&&`, `||` and `!` are followed into their operands,
+/// comparisons are split at the operator, and `unleashNextService.isEnabled(...)` becomes a
+/// feature toggle. Anything else is kept as written.
+fn parse_condition(node: tree_sitter::Node, source: &str) -> Condition {
+    let mut cursor = node.walk();
+    let operands: Vec<_> = node.named_children(&mut cursor).collect();
+    match (node.kind(), &operands[..]) {
+        ("parenthesized_expression", [inner]) => parse_condition(*inner, source),
+        ("prefix_expression", [operand])
+            if node
+                .child(0)
+                .and_then(|op| op.utf8_text(source.as_bytes()).ok())
+                == Some("!") =>
+        {
+            parse_condition(*operand, source).negate()
+        }
+        ("conjunction_expression", [left, right]) => Condition::and(
+            Some(parse_condition(*left, source)),
+            parse_condition(*right, source),
+        ),
+        ("disjunction_expression", [left, right]) => Condition::or(vec![
+            parse_condition(*left, source),
+            parse_condition(*right, source),
+        ]),
+        ("equality_expression" | "comparison_expression" | "check_expression", [left, right]) => {
+            let op = source
+                .get(left.end_byte()..right.start_byte())
+                .unwrap_or_default()
+                .trim();
+            Condition::Compare {
+                left: readable_condition(*left, source),
+                op: op.to_string(),
+                right: readable_condition(*right, source),
+            }
+        }
+        ("call_expression", _) if called_function_name(node, source) == Some("isEnabled") => {
+            match call_arguments(node, source).first() {
+                Some((_, flag)) => Condition::Toggle(feature_toggle_name(*flag, source)),
+                None => Condition::Expr(readable_condition(node, source)),
+            }
+        }
+        ("elvis_expression", [value, default]) => match default.utf8_text(source.as_bytes()) {
+            Ok("false") => parse_condition(*value, source),
+            Ok("true") => Condition::Compare {
+                left: readable_condition(*value, source),
+                op: "!=".to_string(),
+                right: "false".to_string(),
+            },
+            _ => Condition::Expr(readable_condition(node, source)),
+        },
+        _ => Condition::Expr(readable_condition(node, source)),
+    }
+}
+
+/// The name of a feature flag argument: `PenFeature.NY_FLYT` is named `NY_FLYT`, a string
+/// literal by its contents
+fn feature_toggle_name(flag: tree_sitter::Node, source: &str) -> String {
+    let text = argument_text(flag, source).unwrap_or_default();
+    if flag.kind() == "string_literal" {
+        text
+    } else {
+        text.rsplit('.').next().unwrap_or_default().to_string()
+    }
+}
+
+/// The aktiviteter on both sides of an elvis argument, as in `nesteAktivitet(krav?.let { A() } ?: B())`,
+/// with the null check that selects each of them
+fn extract_elvis_aktiviteter_from_call(
+    call_node: tree_sitter::Node,
+    source: &str,
+) -> Option<Vec<(String, Condition)>> {
+    let elvis = *aktivitet_arguments(call_node, source).first()?;
+    if elvis.kind() != "elvis_expression" {
+        return None;
+    }
+
+    let mut elvis_cursor = elvis.walk();
+    let parts: Vec<_> = elvis.named_children(&mut elvis_cursor).collect();
+    let [value, fallback] = parts[..] else {
+        return None;
+    };
+    let subject = elvis_subject(value, source);
+
+    let alternatives: Vec<(String, Condition)> = [
+        (value, null_check(&subject, "!=")),
+        (fallback, null_check(&subject, "==")),
+    ]
+    .into_iter()
+    .filter_map(|(side, condition)| {
+        find_constructor_in_node(side, source).map(|name| (name, condition))
+    })
+    .collect();
+
+    (!alternatives.is_empty()).then_some(alternatives)
+}
+
+/// The aktiviteter a local variable passed to `nesteAktivitet` may hold, with the condition that
+/// selects each: `val neste = if (krav.erRevurdering) A() else B()` before `nesteAktivitet(neste)`.
+/// Every value assigned to the variable before the call in the enclosing function is followed.
+fn local_aktiviteter(
+    call_node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Vec<(String, Option<Condition>)> {
+    let Some(argument) = aktivitet_arguments(call_node, source).first().copied() else {
+        return Vec::new();
+    };
+    if argument.kind() != "simple_identifier" {
+        return Vec::new();
+    }
+    let Ok(variable) = argument.utf8_text(source.as_bytes()) else {
+        return Vec::new();
+    };
+
+    let Some(body) = enclosing_function_body(call_node) else {
+        return Vec::new();
+    };
+
+    let mut values = Vec::new();
+    find_local_values(body, source, variable, call_node.start_byte(), &mut values);
+    values
+        .into_iter()
+        .flat_map(|value| {
+            // `if (x) { neste = B() }` only assigns under the condition of its branch
+            let (assigned_condition, _) = branch_context(value, body, source);
+            aktivitet_alternatives(value, source, project)
+                .into_iter()
+                .map(move |(name, condition)| {
+                    (name, and_branch(assigned_condition.clone(), condition))
+                })
+        })
+        .collect()
+}
+
+/// The body of the function `node` is in
+fn enclosing_function_body(node: tree_sitter::Node) -> Option<tree_sitter::Node> {
+    let mut function = node.parent();
+    while let Some(candidate) = function {
+        if candidate.kind() == "function_declaration" {
+            return function_body(candidate);
+        }
+        function = candidate.parent();
+    }
+    None
+}
+
+/// The branch conditions `node` is nested in below `scope`, and whether it is in a loop and so may
+/// run once per element
+fn branch_context(
+    node: tree_sitter::Node,
+    scope: tree_sitter::Node,
+    source: &str,
+) -> (Option<Condition>, bool) {
+    let mut conditions = Vec::new();
+    let mut per_element = false;
+    let mut child = node;
+    while let Some(parent) = child.parent() {
+        if parent == scope {
+            break;
+        }
+        let branches = match parent.kind() {
+            "if_expression" => if_branches(parent, source),
+            "when_entry" => parent
+                .parent()
+                .map(|when| when_branches(when, source))
+                .unwrap_or_default(),
+            "for_statement" | "while_statement" | "do_while_statement" => {
+                per_element = true;
+                Vec::new()
+            }
+            "call_expression" => {
+                per_element |= is_collection_operation(parent, source);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+        if let Some((condition, _)) = branches.into_iter().find(|(_, branch)| *branch == child) {
+            conditions.extend(condition);
+        }
+        child = parent;
+    }
+
+    // Collected from the inside out
+    let condition = conditions
+        .into_iter()
+        .rev()
+        .fold(None, |outer, inner| Some(Condition::and(outer, inner)));
+    (condition, per_element)
+}
+
+/// The values assigned to `variable` below `node` before `before`, by `val variable = value` or
+/// `variable = value`. Nested functions and classes have their own locals and are skipped.
+fn find_local_values<'t>(
+    node: tree_sitter::Node<'t>,
+    source: &str,
+    variable: &str,
+    before: usize,
+    values: &mut Vec<tree_sitter::Node<'t>>,
+) {
+    let assigned = match node.kind() {
+        "property_declaration" => {
+            let mut cursor = node.walk();
+            let name = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "variable_declaration")
+                .and_then(|declaration| function_name(declaration, source));
+            name.as_deref() == Some(variable)
+        }
+        "assignment" => node
+            .child(0)
+            .and_then(|target| target.utf8_text(source.as_bytes()).ok())
+            .is_some_and(|target| target.trim() == variable),
+        _ => false,
+    };
+    if assigned {
+        // The value is the part after `=`
+        let mut cursor = node.walk();
+        let value = node
+            .children(&mut cursor)
+            .skip_while(|child| child.kind() != "=")
+            .find(|child| child.is_named());
+        values.extend(value);
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        let nested_scope = matches!(
+            child.kind(),
+            "function_declaration" | "class_declaration" | "object_declaration"
+        );
+        if child.start_byte() < before && !nested_scope {
+            find_local_values(child, source, variable, before, values);
+        }
+    }
+}
+
+/// The aktiviteter an expression may evaluate to, with the condition that selects each. `if`,
+/// `when` and elvis expressions are followed into their branches, a block to its last expression.
+fn aktivitet_alternatives(
+    expression: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Vec<(String, Option<Condition>)> {
+    let branches = match expression.kind() {
+        "if_expression" => if_branches(expression, source),
+        "when_expression" => when_branches(expression, source),
+        "elvis_expression" => {
+            let mut cursor = expression.walk();
+            let parts: Vec<_> = expression.named_children(&mut cursor).collect();
+            match parts[..] {
+                [value, fallback] => {
+                    let subject = elvis_subject(value, source);
+                    vec![
+                        (Some(null_check(&subject, "!=")), value),
+                        (Some(null_check(&subject, "==")), fallback),
+                    ]
+                }
+                _ => Vec::new(),
+            }
+        }
+        "parenthesized_expression" | "control_structure_body" | "block" | "statements" => {
+            let mut cursor = expression.walk();
+            let last = expression.named_children(&mut cursor).last();
+            last.into_iter().map(|last| (None, last)).collect()
+        }
+        _ => {
+            return find_aktivitet_in_node(expression, source, project)
+                .map(|name| vec![(name, None)])
+                .unwrap_or_default();
+        }
+    };
+
+    branches
+        .into_iter()
+        .flat_map(|(branch_condition, branch)| {
+            aktivitet_alternatives(branch, source, project)
+                .into_iter()
+                .map(move |(name, condition)| {
+                    (name, and_branch(branch_condition.clone(), condition))
+                })
+        })
+        .collect()
+}
+
+/// `condition` narrowed by the condition of a branch within it, if the branch has one
+fn and_branch(condition: Option<Condition>, branch: Option<Condition>) -> Option<Condition> {
+    match branch {
+        Some(branch) => Some(Condition::and(condition, branch)),
+        None => condition,
+    }
+}
+
+/// Where `node` starts in `file`
+fn source_location(node: tree_sitter::Node, file: &Path) -> SourceLocation {
+    let position = node.start_position();
+    SourceLocation {
+        file: file.to_path_buf(),
+        line: position.row + 1,
+        column: position.column + 1,
+    }
+}
+
+/// `subject == null` or `subject != null`
+fn null_check(subject: &str, op: &str) -> Condition {
+    Condition::Compare {
+        left: subject.to_string(),
+        op: op.to_string(),
+        right: "null".to_string(),
+    }
+}
+
+/// The expression a `when` switches on, e.g. `krav.type` in `when (krav.type) { ... }`
+fn when_subject(when_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = when_node.walk();
+    let subject = when_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "when_subject")?;
+
+    // The expression is the last part, also in `when (val type = krav.type)`
+    let mut subject_cursor = subject.walk();
+    let expression = subject.named_children(&mut subject_cursor).last()?;
+    expression
+        .utf8_text(source.as_bytes())
+        .ok()
+        .map(|text| text.to_string())
+}
+
+/// The branches of an `if`, with the condition that selects each: the condition for the first
+/// branch, and the condition negated for the `else` branch
+fn if_branches<'t>(
+    if_node: tree_sitter::Node<'t>,
+    source: &str,
+) -> Vec<(Option<Condition>, tree_sitter::Node<'t>)> {
+    let mut cursor = if_node.walk();
+    let condition_node = if_node
+        .children(&mut cursor)
+        .find(|child| child.is_named() && child.kind() != "control_structure_body");
+    let condition = condition_node.map(|child| parse_condition(child, source));
+
+    // A call as the condition, like `if (skalVurderes())`, is no branch
+    let mut cursor = if_node.walk();
+    let branches: Vec<_> = if_node
+        .children(&mut cursor)
+        .filter(|child| Some(*child) != condition_node)
+        .filter(|child| {
+            child.kind() == "control_structure_body" || child.kind() == "call_expression"
+        })
+        .collect();
+    branches
+        .into_iter()
+        .enumerate()
+        .map(|(index, branch)| {
+            let branch_condition = if index == 0 {
+                condition.clone()
+            } else {
+                condition.clone().map(Condition::negate)
+            };
+            (branch_condition, branch)
+        })
+        .collect()
+}
+
+/// The branch bodies of a `when`, with the condition that selects each. The `else` branch is
+/// selected when none of the branches before it are; alone it has no condition.
+fn when_branches<'t>(
+    when_node: tree_sitter::Node<'t>,
+    source: &str,
+) -> Vec<(Option<Condition>, tree_sitter::Node<'t>)> {
+    let subject = when_subject(when_node, source);
+
+    // Conditions of the branches so far, negated for the else branch
+    let mut previous_conditions = Vec::new();
+    let mut branches = Vec::new();
+    let mut when_cursor = when_node.walk();
+    for entry in when_node.children(&mut when_cursor) {
+        if entry.kind() != "when_entry" {
+            continue;
+        }
+
+        let mut conditions = Vec::new();
+        let mut is_else = false;
+        let mut body = None;
+        let mut entry_cursor = entry.walk();
+        for child in entry.children(&mut entry_cursor) {
+            match child.kind() {
+                "when_condition" => {
+                    conditions.push(when_condition(child, subject.as_deref(), source))
+                }
+                "else" => is_else = true,
+                "control_structure_body" => body = Some(child),
+                _ => {}
+            }
+        }
+
+        let branch_condition = if is_else {
+            (!previous_conditions.is_empty())
+                .then(|| Condition::or(previous_conditions.clone()).negate())
+        } else {
+            let branch_condition = Condition::or(conditions);
+            previous_conditions.push(branch_condition.clone());
+            Some(branch_condition)
+        };
+
+        if let Some(body) = body {
+            branches.push((branch_condition, body));
+        }
+    }
+    branches
+}
+
+/// One condition of a `when` branch, combined with the subject if there is one:
+/// `type is Revurdering`, `alder in 62..67`, `alder == 67`. An enum entry is kept as an entry,
+/// so `KravType.UT ->` under `when (krav.type)` is labelled `UT`.
+fn when_condition(
+    condition_node: tree_sitter::Node,
+    subject: Option<&str>,
+    source: &str,
+) -> Condition {
+    let mut cursor = condition_node.walk();
+    let expression = condition_node.named_children(&mut cursor).next();
+    let Some(subject) = subject else {
+        return match expression {
+            Some(expression) => parse_condition(expression, source),
+            None => Condition::Expr(readable_condition(condition_node, source)),
+        };
+    };
+
+    let text = condition_node.utf8_text(source.as_bytes()).unwrap_or("");
+    let is_test =
+        expression.is_some_and(|child| child.kind() == "type_test" || child.kind() == "range_test");
+    if is_test {
+        // `is Revurdering`, `!in 62..67`
+        let (op, right) = text
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((text, ""));
+        Condition::Compare {
+            left: subject.to_string(),
+            op: op.to_string(),
+            right: right.trim().to_string(),
+        }
+    } else if let Some(entry) = enum_entry_name(text) {
+        Condition::Entry {
+            subject: subject.to_string(),
+            entry: entry.to_string(),
+        }
+    } else {
+        Condition::Compare {
+            left: subject.to_string(),
+            op: "==".to_string(),
+            right: text.trim().to_string(),
+        }
+    }
+}
+
+/// The entry name if `text` looks like an enum constant, `KravType.ALDER` or a bare `ALDER`
+fn enum_entry_name(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let mut segments = text.split('.');
+    let entry = segments.next_back()?;
+    let is_entry = entry.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && entry
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    let is_qualifier = |segment: &str| {
+        !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    (is_entry && segments.all(is_qualifier)).then_some(entry)
+}
+
+/// Collection functions whose lambda runs once for each element
+const COLLECTION_OPERATIONS: &[&str] = &[
+    "map",
+    "mapNotNull",
+    "mapIndexed",
+    "flatMap",
+    "forEach",
+    "forEachIndexed",
+    "onEach",
+];
+
+/// The receiver and function of a collection operation with a lambda, as in
+/// `krav.linjer.map { linje -> ... }` or `kravListe?.forEach { ... }`. Only the function name
+/// and the receiver matter, not what the lambda parameter is called.
+fn collection_operation(node: tree_sitter::Node, source: &str) -> Option<String> {
+    let function = called_function_name(node, source)?;
+    if !COLLECTION_OPERATIONS.contains(&function) {
+        return None;
+    }
+    let callee = node.child(0)?;
+    if callee.kind() != "navigation_expression" {
+        return None;
+    }
+
+    let mut cursor = node.walk();
+    let has_lambda = node
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "call_suffix")
+        .any(|suffix| {
+            let mut suffix_cursor = suffix.walk();
+            let has_lambda = suffix
+                .children(&mut suffix_cursor)
+                .any(|child| child.kind() == "annotated_lambda");
+            has_lambda
+        });
+    if !has_lambda {
+        return None;
+    }
+
+    let receiver = callee.child(0)?.utf8_text(source.as_bytes()).ok()?;
+    let receiver = receiver.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(format!("{}.{}", receiver.replace("?.", "."), function))
+}
+
+/// Check if a call expression is a collection operation that might create multiple aktiviteter
+fn is_collection_operation(node: tree_sitter::Node, source: &str) -> bool {
+    collection_operation(node, source).is_some()
+}
+
+/// Extract aktivitet name from a collection operation using pure AST traversal
+fn extract_aktivitet_from_collection_call(
+    node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<String> {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() == "call_suffix" {
+            if let Some(name) = extract_from_lambda_in_suffix(child, source, project) {
+                return Some(name);
+            }
+        }
+    }
+    None
+}
+
+/// Extract activity name from lambda within call suffix using pure AST traversal
+fn extract_from_lambda_in_suffix(
+    node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<String> {
+    let mut activities = Vec::new();
+    extract_activities_from_ast_node(node, source, project, &mut activities);
+    activities.into_iter().next()
+}
+
+/// Check if a call expression is a nesteAktiviteter() call
+fn is_neste_aktiviteter_call(
+    node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> bool {
+    is_call_to(node, source, &conventions.neste_aktiviteter)
+}
+
+/// True if `call_node` calls the function `name` directly, as in `name(...)`
+fn is_call_to(call_node: tree_sitter::Node, source: &str, name: &str) -> bool {
+    let mut cursor = call_node.walk();
+    let callee = call_node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "simple_identifier");
+    callee.and_then(|callee| callee.utf8_text(source.as_bytes()).ok()) == Some(name)
+}
+
+/// The aktiviteter a `nesteAktiviteter(...)` call starts, and whether each is created once per
+/// element of a collection, as in `nesteAktiviteter(krav.map { VurderAktivitet(it) } + LukkAktivitet())`
+fn extract_aktiviteter_from_collection_pattern(
+    node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+) -> Option<Vec<StartedAktivitet>> {
+    let mut aktiviteter = Vec::new();
+    for argument in aktivitet_arguments(node, source) {
+        if is_spread(argument) {
+            // `*ekstraSteg.toTypedArray()`: the aktiviteter are only known at runtime, unless
+            // they are created right there, as in `*arrayOf(A(), B())`
+            let mut started = Vec::new();
+            collect_started_aktiviteter(argument, source, project, false, &mut started);
+            if started.is_empty() {
+                aktiviteter.push(StartedAktivitet {
+                    name: spread_name(argument, source),
+                    is_collection: true,
+                    condition: None,
+                    is_dynamic: true,
+                });
+            }
+            add_started(&mut aktiviteter, started, None, false);
+            continue;
+        }
+        match (argument.kind(), argument.utf8_text(source.as_bytes())) {
+            // `nesteAktiviteter(steg)` with a list built up in the function
+            ("simple_identifier", Ok(variable)) => {
+                let mut visited = Vec::new();
+                collect_local_collection(
+                    node,
+                    variable,
+                    source,
+                    project,
+                    &mut visited,
+                    &mut aktiviteter,
+                );
+            }
+            _ => {
+                let mut started = Vec::new();
+                collect_started_aktiviteter(argument, source, project, false, &mut started);
+                add_started(&mut aktiviteter, started, None, false);
+            }
+        }
+    }
+    (!aktiviteter.is_empty()).then_some(aktiviteter)
+}
+
+/// An aktivitet started by `nesteAktiviteter`, and the condition it is put in the list under
+struct StartedAktivitet {
+    name: String,
+    is_collection: bool, // Started once per element of a collection
+    condition: Option<Condition>,
+    is_dynamic: bool, // A spread argument; the name is the spread expression
+}
+
+/// True for the value of a spread argument: `ekstraSteg.toTypedArray()` in
+/// `nesteAktiviteter(A(), *ekstraSteg.toTypedArray())`
+fn is_spread(value: tree_sitter::Node) -> bool {
+    value
+        .prev_sibling()
+        .is_some_and(|sibling| sibling.kind() == "*")
+}
+
+/// What a spread argument is named on its edge: the collection it spreads, on one line and
+/// without the conversion to an array
+fn spread_name(value: tree_sitter::Node, source: &str) -> String {
+    let text = value.utf8_text(source.as_bytes()).unwrap_or_default();
+    let text = text.split_whitespace().collect::<Vec<_>>().join("");
+    text.strip_suffix(".toTypedArray()")
+        .unwrap_or(&text)
+        .replace("?.", ".")
+}
+
+/// Add aktiviteter found under `condition`, merging those already started under the same one
+fn add_started(
+    aktiviteter: &mut Vec<StartedAktivitet>,
+    found: Vec<(String, bool)>,
+    condition: Option<Condition>,
+    per_element: bool,
+) {
+    for (name, is_collection) in found {
+        let is_collection = is_collection || per_element;
+        match aktiviteter.iter_mut().find(|started| {
+            started.name == name && started.condition == condition && !started.is_dynamic
+        }) {
+            Some(started) => started.is_collection |= is_collection,
+            None => aktiviteter.push(StartedAktivitet {
+                name,
+                is_collection,
+                condition: condition.clone(),
+                is_dynamic: false,
+            }),
+        }
+    }
+}
+
+/// Functions that put elements in a local list: `steg.add(VurderAktivitet())`
+const LIST_ADD_FUNCTIONS: &[&str] = &["add", "addAll", "plusAssign"];
+
+/// Collect the aktiviteter put in the local list `variable` before `call_node`: its initial
+/// value, `add` and `addAll` calls and `+=`, each under the conditions of the branch it is done
+/// in. A list built from another local one, like `val alle = steg + B()`, includes that list.
+fn collect_local_collection(
+    call_node: tree_sitter::Node,
+    variable: &str,
+    source: &str,
+    project: ProjectIndexes,
+    visited: &mut Vec<String>,
+    aktiviteter: &mut Vec<StartedAktivitet>,
+) {
+    let Some(body) = enclosing_function_body(call_node) else {
+        return;
+    };
+    if visited.iter().any(|seen| seen == variable) {
+        return;
+    }
+    visited.push(variable.to_string());
+
+    let mut values = Vec::new();
+    find_local_values(body, source, variable, call_node.start_byte(), &mut values);
+    let mut additions = Vec::new();
+    find_list_additions(
+        body,
+        source,
+        variable,
+        call_node.start_byte(),
+        &mut additions,
+    );
+
+    for value in values.iter().chain(&additions) {
+        let (condition, per_element) = branch_context(*value, body, source);
+        let mut started = Vec::new();
+        collect_started_aktiviteter(*value, source, project, false, &mut started);
+        add_started(aktiviteter, started, condition, per_element);
+    }
+
+    // `steg + B()`, `steg.plus(B())` or `steg.toMutableList()` start from another list
+    for value in values {
+        let mut base = value;
+        while let Some(first) = base.named_child(0) {
+            base = first;
+        }
+        if base.kind() != "simple_identifier" {
+            continue;
+        }
+        if let Ok(other) = base.utf8_text(source.as_bytes()) {
+            collect_local_collection(value, other, source, project, visited, aktiviteter);
+        }
+    }
+}
+
+/// The elements added to the list `variable` below `node` before `before`: the arguments of
+/// `variable.add(...)` and the value of `variable += ...`
+fn find_list_additions<'t>(
+    node: tree_sitter::Node<'t>,
+    source: &str,
+    variable: &str,
+    before: usize,
+    additions: &mut Vec<tree_sitter::Node<'t>>,
+) {
+    let is_variable = |target: Option<tree_sitter::Node>| {
+        target
+            .and_then(|target| target.utf8_text(source.as_bytes()).ok())
+            .is_some_and(|target| target.trim() == variable)
+    };
+    match node.kind() {
+        "call_expression"
+            if called_function_name(node, source)
+                .is_some_and(|function| LIST_ADD_FUNCTIONS.contains(&function))
+                && is_variable(
+                    node.child(0)
+                        .filter(|callee| callee.kind() == "navigation_expression")
+                        .and_then(|callee| callee.child(0)),
+                ) =>
+        {
+            additions.extend(
+                call_arguments(node, source)
+                    .into_iter()
+                    .map(|(_, value)| value),
+            );
+            return;
+        }
+        "assignment" if is_variable(node.child(0)) => {
+            let mut cursor = node.walk();
+            let children: Vec<_> = node.children(&mut cursor).collect();
+            if let [_, operator, value, ..] = children[..] {
+                if operator.kind() == "+=" {
+                    additions.push(value);
+                }
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        let nested_scope = matches!(
+            child.kind(),
+            "function_declaration" | "class_declaration" | "object_declaration"
+        );
+        if child.start_byte() < before && !nested_scope {
+            find_list_additions(child, source, variable, before, additions);
+        }
+    }
+}
+
+/// Collect the aktiviteter constructed in an argument of `nesteAktiviteter`. Only the syntax
+/// tree is searched, so names in strings and comments are not mistaken for constructors, and
+/// the arguments of an aktivitet's own constructor are left alone.
+fn collect_started_aktiviteter(
+    node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+    per_element: bool,
+    aktiviteter: &mut Vec<(String, bool)>,
+) {
+    let mut per_element = per_element;
+    match node.kind() {
+        "string_literal" | "line_comment" | "multiline_comment" => return,
+        "call_expression" => {
+            if let Some(name) = extract_constructor_name(node, source) {
+                if project.is_aktivitet_class(&name) {
+                    match aktiviteter
+                        .iter_mut()
+                        .find(|(existing, _)| *existing == name)
+                    {
+                        Some((_, is_collection)) => *is_collection |= per_element,
+                        None => aktiviteter.push((name, per_element)),
+                    }
+                    return;
+                }
+            }
+            // The lambda of `map`, `flatMap` or `forEach` creates its aktiviteter per element
+            per_element |= is_collection_operation(node, source);
+        }
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_started_aktiviteter(child, source, project, per_element, aktiviteter);
+    }
+}
+
+/// Extract activities from any AST node recursively
+fn extract_activities_from_ast_node(
+    node: tree_sitter::Node,
+    source: &str,
+    project: ProjectIndexes,
+    aktivitet_names: &mut Vec<String>,
+) {
+    match node.kind() {
+        "string_literal" | "line_comment" | "multiline_comment" => {}
+        "call_expression" => {
+            // Check if this is a direct activity constructor call
+            if let Some(activity_name) = extract_constructor_name(node, source) {
+                if project.is_aktivitet_class(&activity_name) {
+                    aktivitet_names.push(activity_name);
+                }
+            } else {
+                // Not a constructor, recursively search children
+                let mut cursor = node.walk();
+                for child in node.children(&mut cursor) {
+                    extract_activities_from_ast_node(child, source, project, aktivitet_names);
+                }
+            }
+        }
+        "lambda_literal" | "function_literal" => {
+            // Search inside lambda expressions for activity constructors
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                extract_activities_from_ast_node(child, source, project, aktivitet_names);
+            }
+        }
+        _ => {
+            // For all other node types, recursively search children
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                extract_activities_from_ast_node(child, source, project, aktivitet_names);
+            }
+        }
+    }
+}
+
+/// Heuristic to determine if a class name looks like an Aktivitet
+fn is_likely_aktivitet_class(class_name: &str) -> bool {
+    // Must be a valid identifier (alphanumeric + underscore)
+    if !class_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return false;
+    }
+
+    // Must start with uppercase letter (class naming convention)
+    if !class_name
+        .chars()
+        .next()
+        .unwrap_or('a')
+        .is_ascii_uppercase()
+    {
+        return false;
+    }
+
+    // Must be reasonable length for a class name
+    if class_name.len() < 3 || class_name.len() > 100 {
+        return false;
+    }
+
+    // Check for aktivitet patterns
+    class_name.ends_with("Aktivitet")
+        || class_name.ends_with("Activity")
+        || class_name.contains("Aktivitet")
+}
+
+/// Check if a call expression is a nesteAktivitet() call. nesteAktiviteter(...) starts several
+/// and is handled on its own.
+fn is_neste_aktivitet_call(
+    call_node: tree_sitter::Node,
+    source: &str,
+    conventions: &ConventionsConfig,
+) -> bool {
+    is_call_to(call_node, source, &conventions.neste_aktivitet)
+}
+
+fn extract_aktivitet_from_call(call_node: tree_sitter::Node, source: &str) -> Option<String> {
+    // Direct constructor call: nesteAktivitet(ActivityName()), or a call that wraps one, like
+    // krav?.let { ActivityName() }
+    aktivitet_arguments(call_node, source)
+        .into_iter()
+        .find_map(|argument| find_constructor_in_node(argument, source))
+}
+
+fn extract_constructor_name(call_node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = call_node.walk();
+    for child in call_node.children(&mut cursor) {
+        if child.kind() == "simple_identifier" || child.kind() == "type_identifier" {
+            if let Ok(name) = child.utf8_text(source.as_bytes()) {
+                // Only return if this looks like a class constructor (starts with uppercase)
+                if name.chars().next().unwrap_or('a').is_ascii_uppercase() {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+fn find_constructor_in_node(node: tree_sitter::Node, source: &str) -> Option<String> {
+    if node.kind() == "call_expression" {
+        if let Some(name) = extract_constructor_name(node, source) {
+            return Some(name);
+        }
+        // Only a scope function like `krav?.let { A() }` gives the value of its lambda; the
+        // arguments of other calls, like `KravId(x)` in `lagSteg(KravId(x))`, are not what
+        // the call returns
+        if scope_function_name(node, source).is_none()
+            && safe_scope_receiver(node, source).is_none()
+        {
+            return None;
+        }
+    }
+
+    let mut cursor = node.walk();
+    if cursor.goto_first_child() {
+        loop {
+            if let Some(name) = find_constructor_in_node(cursor.node(), source) {
+                return Some(name);
+            }
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+
+    None
+}
+
+fn find_constructor_call(node: tree_sitter::Node, source: &str) -> Option<String> {
+    let mut cursor = node.walk();
+
+    if node.kind() == "call_expression" {
+        // This is a constructor call
+        for child in node.children(&mut cursor) {
+            if child.kind() == "simple_identifier" || child.kind() == "type_identifier" {
+                if let Ok(name) = child.utf8_text(source.as_bytes()) {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+
+    // Recurse into children
+    if cursor.goto_first_child() {
+        loop {
+            if let Some(result) = find_constructor_call(cursor.node(), source) {
+                return Some(result);
+            }
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+        cursor.goto_parent();
+    }
+
+    None
+}
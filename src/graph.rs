@@ -0,0 +1,3134 @@
+//! Flow traversal, cycle detection and DOT graph generation
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::metrics::{format_duration, AktivitetDurations, ExecutionCounts};
+use crate::model::{ClassInfo, Condition, Edge, ProcessorInfo, TerminalKind};
+
+/// Node colors and their meaning, shown in the graph legend
+pub const LEGEND_ENTRIES: &[(&str, &str)] = &[
+    ("#90EE90", "START"),
+    ("#9370DB", "AldeAktivitet"),
+    ("#FFA500", "📋 Creates Oppgave"),
+    ("#87CEEB", "Regular"),
+    ("#FFD700", "Waiting"),
+    ("#FF6B6B", "Manual"),
+    ("#FF4444", "Abort"),
+    ("#4CAF50", "Decision"),
+    ("#FFB6C1", "END"),
+    ("#EEEEEE", "END? (no transition found)"),
+    ("#CCCCCC", "Unknown"),
+    ("#DDDDDD", "Deprecated"),
+];
+
+/// Rendering options for a single flow graph
+pub struct DotOptions<'a> {
+    pub edge_style: &'a str,
+    pub show_conditions: bool,
+    pub show_legend: bool,
+    pub deduplicate: bool,
+    pub subtitle: Option<String>, // Extra line shown below the graph title
+    pub dpi: Option<f64>,         // Output resolution for raster formats (graphviz default is 96)
+    pub start_from: Option<&'a str>, // Root the graph at this aktivitet instead of the initial one
+    pub focus: Option<&'a str>,   // Only show the aktiviteter around this one
+    pub focus_radius: usize,      // Number of transitions up- and downstream of `focus` to show
+    pub max_depth: Option<usize>, // Cut the graph this many transitions after the start
+    pub collapse_chains: bool,    // Merge straight runs of aktiviteter into one summary node
+    pub expand_subtypes: bool, // Fan transitions to abstract or sealed aktiviteter out to their subclasses
+    pub flow_link_format: Option<&'a str>, // Link started Behandlinger to their `<Name>_flow.<format>` file
+    pub highlight_path: Option<&'a [String]>, // Draw the aktiviteter and transitions of this path in bold
+    pub critical_path: bool, // Without `highlight_path`, draw the longest acyclic path to END in bold
+    pub hub_threshold: Option<usize>, // Mark aktiviteter with at least this many ways in as hubs
+    pub changed_files: Option<&'a HashSet<PathBuf>>, // Mark what these files define, and everything downstream of it
+    pub execution_counts: Option<&'a ExecutionCounts>, // Color and size aktiviteter by how often they ran
+    pub durations: Option<&'a AktivitetDurations>, // Color aktiviteter by how long a sak stays in them
+    pub merge_points: bool, // Mark the aktiviteter where the branches of a split meet again
+}
+
+impl Default for DotOptions<'_> {
+    fn default() -> Self {
+        DotOptions {
+            edge_style: "straight",
+            show_conditions: false,
+            show_legend: false,
+            deduplicate: true,
+            subtitle: None,
+            dpi: None,
+            start_from: None,
+            focus: None,
+            focus_radius: 2,
+            max_depth: None,
+            collapse_chains: false,
+            expand_subtypes: false,
+            flow_link_format: None,
+            highlight_path: None,
+            critical_path: false,
+            hub_threshold: None,
+            changed_files: None,
+            execution_counts: None,
+            durations: None,
+            merge_points: false,
+        }
+    }
+}
+
+/// Node and edge attributes for the aktiviteter and transitions of a highlighted path
+const HIGHLIGHT_ATTRIBUTES: &str = "color=\"#1565C0\", penwidth=3";
+
+/// Node and edge attributes for what changed files define, and for what is downstream of it
+const CHANGED_ATTRIBUTES: &str = "color=\"#D32F2F\", penwidth=3";
+const AFFECTED_ATTRIBUTES: &str = "color=\"#F57C00\", penwidth=2";
+
+#[derive(Debug, Clone)]
+struct IterationGroup {
+    trigger_node: String,        // Node that starts the iteration
+    iterated_nodes: Vec<String>, // All nodes that are part of the iteration path
+}
+
+/// One step on a path: the aktivitet reached and the condition on the edge leading to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStep {
+    pub aktivitet: String,
+    pub condition: Option<Condition>,
+}
+
+/// All simple paths from `from` to `to`, shortest first. `max_length` limits the number of
+/// transitions per path and `max_paths` the number of paths returned.
+pub fn find_paths(
+    from: &str,
+    to: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    max_paths: usize,
+    max_length: usize,
+) -> Vec<Vec<PathStep>> {
+    // Keeps the `max_paths` shortest paths found so far, the earliest found of equally long
+    // ones; once there are that many, a branch that cannot end in a shorter one is cut off
+    fn visit(
+        current: &str,
+        to: &str,
+        processor_index: &HashMap<String, ProcessorInfo>,
+        max_length: usize,
+        max_paths: usize,
+        path: &mut Vec<PathStep>,
+        paths: &mut Vec<Vec<PathStep>>,
+    ) {
+        let transitions = path.len() - 1;
+        if current == to && transitions > 0 {
+            paths.push(path.clone());
+            if paths.len() > max_paths {
+                // The longest path, the last found of those, makes way
+                let longest = paths
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(index, path)| (path.len(), *index))
+                    .map(|(index, _)| index);
+                if let Some(longest) = longest {
+                    paths.remove(longest);
+                }
+            }
+            return;
+        }
+        if transitions >= max_length {
+            return;
+        }
+        if paths.len() >= max_paths {
+            let longest = paths.iter().map(Vec::len).max().unwrap_or(0);
+            if path.len() + 1 >= longest {
+                return;
+            }
+        }
+
+        if let Some(processor) = processor_index.get(current) {
+            for next in &processor.next_aktiviteter {
+                // Simple paths only: never revisit an aktivitet (the target may close a loop)
+                if next.aktivitet_name != to
+                    && path
+                        .iter()
+                        .any(|step| step.aktivitet == next.aktivitet_name)
+                {
+                    continue;
+                }
+                path.push(PathStep {
+                    aktivitet: next.aktivitet_name.clone(),
+                    condition: next.condition.clone(),
+                });
+                visit(
+                    &next.aktivitet_name,
+                    to,
+                    processor_index,
+                    max_length,
+                    max_paths,
+                    path,
+                    paths,
+                );
+                path.pop();
+            }
+        }
+    }
+
+    let mut path = vec![PathStep {
+        aktivitet: from.to_string(),
+        condition: None,
+    }];
+    let mut paths = Vec::new();
+    if max_paths == 0 {
+        return paths;
+    }
+    visit(
+        from,
+        to,
+        processor_index,
+        max_length,
+        max_paths,
+        &mut path,
+        &mut paths,
+    );
+    // Stable, so equally long paths stay in the order they were found
+    paths.sort_by_key(Vec::len);
+    paths
+}
+
+/// What must hold for a path to be taken: the conditions of its transitions joined with `&&`,
+/// each once, or `None` if every transition is unconditional
+pub fn path_condition(steps: &[PathStep]) -> Option<Condition> {
+    let mut conditions: Vec<&Condition> = Vec::new();
+    for condition in steps.iter().filter_map(|step| step.condition.as_ref()) {
+        if !conditions.contains(&condition) {
+            conditions.push(condition);
+        }
+    }
+    conditions.into_iter().fold(None, |path, condition| {
+        Some(Condition::and(path, condition.clone()))
+    })
+}
+
+pub fn detect_cycles(
+    start: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+) -> Vec<(String, String)> {
+    let mut cycles = Vec::new();
+    let mut visited = std::collections::HashSet::new();
+    let mut rec_stack = std::collections::HashSet::new();
+    let mut parent_map: HashMap<String, Vec<String>> = HashMap::new();
+
+    fn dfs(
+        node: &str,
+        processor_index: &HashMap<String, ProcessorInfo>,
+        visited: &mut std::collections::HashSet<String>,
+        rec_stack: &mut std::collections::HashSet<String>,
+        parent_map: &mut HashMap<String, Vec<String>>,
+        cycles: &mut Vec<(String, String)>,
+    ) {
+        visited.insert(node.to_string());
+        rec_stack.insert(node.to_string());
+
+        if let Some(processor) = processor_index.get(node) {
+            for next in &processor.next_aktiviteter {
+                let next_name = &next.aktivitet_name;
+
+                // Track parent relationships
+                parent_map
+                    .entry(next_name.clone())
+                    .or_insert_with(Vec::new)
+                    .push(node.to_string());
+
+                if rec_stack.contains(next_name) {
+                    // Back edge found - this is a cycle
+                    cycles.push((node.to_string(), next_name.clone()));
+                } else if !visited.contains(next_name) {
+                    dfs(
+                        next_name,
+                        processor_index,
+                        visited,
+                        rec_stack,
+                        parent_map,
+                        cycles,
+                    );
+                }
+            }
+        }
+
+        rec_stack.remove(node);
+    }
+
+    dfs(
+        start,
+        processor_index,
+        &mut visited,
+        &mut rec_stack,
+        &mut parent_map,
+        &mut cycles,
+    );
+
+    cycles
+}
+
+/// Upper bound on the cycles [`find_cycles`] lists for a summary; densely connected flows can
+/// have more elementary cycles than anyone would read
+pub const MAX_CYCLE_PATHS: usize = 100;
+
+/// Every elementary cycle reachable from `start`, as the aktiviteter around it: `[A, B, C]` for
+/// `A → B → C → A`. Each cycle starts at the aktivitet on it that is reached first, and at most
+/// `max_cycles` are returned. Unlike [`detect_cycles`], which finds one back edge per loop, this
+/// lists every distinct way around, using Johnson's algorithm.
+pub fn find_cycles(
+    start: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    max_cycles: usize,
+) -> Vec<Vec<String>> {
+    // Number the aktiviteter in the order they are reached, so cycles start where the flow enters
+    let mut nodes = vec![start.to_string()];
+    let mut index: HashMap<String, usize> = HashMap::from([(start.to_string(), 0)]);
+    let mut successors: Vec<Vec<usize>> = Vec::new();
+    while successors.len() < nodes.len() {
+        let mut next_nodes = Vec::new();
+        let processor = processor_index.get(&nodes[successors.len()]);
+        for next in processor.into_iter().flat_map(|p| &p.next_aktiviteter) {
+            if next.is_dynamic {
+                continue;
+            }
+            let next_index = *index.entry(next.aktivitet_name.clone()).or_insert_with(|| {
+                nodes.push(next.aktivitet_name.clone());
+                nodes.len() - 1
+            });
+            if !next_nodes.contains(&next_index) {
+                next_nodes.push(next_index);
+            }
+        }
+        successors.push(next_nodes);
+    }
+
+    let mut search = CycleSearch {
+        successors: &successors,
+        component: Vec::new(),
+        blocked: vec![false; nodes.len()],
+        blocked_by: vec![Vec::new(); nodes.len()],
+        stack: Vec::new(),
+        cycles: Vec::new(),
+        max_cycles,
+    };
+    for first in 0..nodes.len() {
+        if search.cycles.len() >= max_cycles {
+            break;
+        }
+        // Cycles through `first` that do not pass an aktivitet before it, which were all found
+        search.component = strongly_connected_component(first, &successors);
+        let component_size = search
+            .component
+            .iter()
+            .filter(|in_component| **in_component)
+            .count();
+        if component_size < 2 && !successors[first].contains(&first) {
+            continue;
+        }
+        search.blocked.fill(false);
+        search.blocked_by.iter_mut().for_each(Vec::clear);
+        search.circuit(first, first);
+    }
+
+    search
+        .cycles
+        .into_iter()
+        .map(|cycle| cycle.into_iter().map(|node| nodes[node].clone()).collect())
+        .collect()
+}
+
+/// The nodes numbered `first` or higher that `first` can reach and be reached from, using only
+/// such nodes
+fn strongly_connected_component(first: usize, successors: &[Vec<usize>]) -> Vec<bool> {
+    let reach = |edges: &dyn Fn(usize) -> Vec<usize>| {
+        let mut reached = vec![false; successors.len()];
+        reached[first] = true;
+        let mut queue = vec![first];
+        while let Some(node) = queue.pop() {
+            for next in edges(node) {
+                if next >= first && !reached[next] {
+                    reached[next] = true;
+                    queue.push(next);
+                }
+            }
+        }
+        reached
+    };
+    let forward = reach(&|node| successors[node].clone());
+    let backward = reach(&|node| {
+        (0..successors.len())
+            .filter(|&from| successors[from].contains(&node))
+            .collect()
+    });
+    forward
+        .iter()
+        .zip(backward)
+        .map(|(forward, backward)| *forward && backward)
+        .collect()
+}
+
+/// State of Johnson's cycle search from one start node
+struct CycleSearch<'a> {
+    successors: &'a [Vec<usize>],
+    component: Vec<bool>, // Nodes the cycles through the current start node may pass
+    blocked: Vec<bool>,
+    blocked_by: Vec<Vec<usize>>, // Nodes to unblock once the node is unblocked
+    stack: Vec<usize>,
+    cycles: Vec<Vec<usize>>,
+    max_cycles: usize,
+}
+
+impl CycleSearch<'_> {
+    /// Find the cycles back to `start` through `node`; true if there is any
+    fn circuit(&mut self, node: usize, start: usize) -> bool {
+        let mut found = false;
+        self.stack.push(node);
+        self.blocked[node] = true;
+
+        for &next in &self.successors[node] {
+            if !self.component[next] || self.cycles.len() >= self.max_cycles {
+                continue;
+            }
+            if next == start {
+                self.cycles.push(self.stack.clone());
+                found = true;
+            } else if !self.blocked[next] && self.circuit(next, start) {
+                found = true;
+            }
+        }
+
+        if found {
+            self.unblock(node);
+        } else {
+            for &next in &self.successors[node] {
+                if self.component[next] && !self.blocked_by[next].contains(&node) {
+                    self.blocked_by[next].push(node);
+                }
+            }
+        }
+        self.stack.pop();
+        found
+    }
+
+    fn unblock(&mut self, node: usize) {
+        self.blocked[node] = false;
+        for other in std::mem::take(&mut self.blocked_by[node]) {
+            if self.blocked[other] {
+                self.unblock(other);
+            }
+        }
+    }
+}
+
+/// A cycle for text output: `A → B → C → A`, with shortened names
+pub fn format_cycle(cycle: &[String]) -> String {
+    cycle
+        .iter()
+        .chain(cycle.first())
+        .map(|aktivitet| shorten_aktivitet_name(aktivitet).replace('\n', " "))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// The loops of a graph: its strongly connected components with more than one node, or a node
+/// with a transition to itself, found with Tarjan's algorithm. Nodes come in the order they first
+/// appear in `edges`, and so do the groups.
+fn group_cycles(edges: &[Edge]) -> Vec<Vec<String>> {
+    let mut nodes: Vec<&str> = Vec::new();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    for edge in edges {
+        for node in [edge.from.as_str(), edge.to.as_str()] {
+            index.entry(node).or_insert_with(|| {
+                nodes.push(node);
+                nodes.len() - 1
+            });
+        }
+    }
+    let mut successors = vec![Vec::new(); nodes.len()];
+    for edge in edges {
+        successors[index[edge.from.as_str()]].push(index[edge.to.as_str()]);
+    }
+
+    let mut search = ComponentSearch {
+        successors: &successors,
+        index: vec![None; nodes.len()],
+        lowlink: vec![0; nodes.len()],
+        on_stack: vec![false; nodes.len()],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+    for node in 0..nodes.len() {
+        if search.index[node].is_none() {
+            search.connect(node);
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = search
+        .components
+        .into_iter()
+        .filter(|component| component.len() > 1 || successors[component[0]].contains(&component[0]))
+        .map(|mut component| {
+            component.sort_unstable();
+            component
+        })
+        .collect();
+    groups.sort();
+    groups
+        .into_iter()
+        .map(|group| {
+            group
+                .into_iter()
+                .map(|node| nodes[node].to_string())
+                .collect()
+        })
+        .collect()
+}
+
+/// State of Tarjan's strongly connected components search
+struct ComponentSearch<'a> {
+    successors: &'a [Vec<usize>],
+    index: Vec<Option<usize>>, // Order in which the search reached each node
+    lowlink: Vec<usize>,       // Lowest index reachable from the node's subtree
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl ComponentSearch<'_> {
+    fn connect(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.lowlink[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &next in &self.successors[node] {
+            match self.index[next] {
+                None => {
+                    self.connect(next);
+                    self.lowlink[node] = self.lowlink[node].min(self.lowlink[next]);
+                }
+                Some(next_index) if self.on_stack[next] => {
+                    self.lowlink[node] = self.lowlink[node].min(next_index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        // The root of a component: everything above it on the stack belongs to it
+        if Some(self.lowlink[node]) == self.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+pub fn generate_dot_graph(
+    behandling_name: &str,
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    options: &DotOptions,
+) -> Result<String> {
+    let edge_style = options.edge_style;
+    let show_conditions = options.show_conditions;
+    let show_legend = options.show_legend;
+    let deduplicate = options.deduplicate;
+    let initial_aktivitet = options.start_from.unwrap_or(initial_aktivitet);
+
+    let mut dot = graph_header(edge_style, options.dpi);
+
+    // Add title
+    let mut title = format!("{} Flow", behandling_name);
+    if options.start_from.is_some() {
+        title.push_str(&format!(
+            " from {}",
+            escape_label(&shorten_aktivitet_name(initial_aktivitet))
+        ));
+    }
+    if let Some(focus) = options.focus {
+        title.push_str(&format!(
+            " around {}",
+            escape_label(&shorten_aktivitet_name(focus))
+        ));
+    }
+    if let Some(subtitle) = &options.subtitle {
+        title.push_str(&format!("\\n{}", escape_label(subtitle)));
+    }
+    dot.push_str(&format!(
+        "  labelloc=\"t\";\n  label=\"{}\";\n  fontsize=16;\n\n",
+        title
+    ));
+
+    let (mut node_definitions, mut edges) =
+        drawn_flow(initial_aktivitet, processor_index, class_index, options);
+
+    // Hubs: aktiviteter that many transitions converge on get a double border and their fan-in
+    if let Some(threshold) = options.hub_threshold {
+        for degree in node_degrees(initial_aktivitet, &edges) {
+            if degree.fan_in < threshold {
+                continue;
+            }
+            if let Some((_, definition)) = node_definitions
+                .iter_mut()
+                .find(|(id, _)| *id == degree.aktivitet)
+            {
+                definition.insert_str(
+                    definition.len() - 1,
+                    &format!(", peripheries=2, xlabel=\"⇶ {} in\"", degree.fan_in),
+                );
+            }
+        }
+    }
+
+    // Merge points: the aktiviteter where the branches of a split meet again, with the splits
+    if options.merge_points {
+        let mut joined: Vec<(String, Vec<String>)> = Vec::new();
+        for point in merge_points(initial_aktivitet, &edges) {
+            let Some(merge) = point.merge else {
+                continue;
+            };
+            match joined.iter_mut().find(|(aktivitet, _)| *aktivitet == merge) {
+                Some((_, branches)) => branches.push(point.branch),
+                None => joined.push((merge, vec![point.branch])),
+            }
+        }
+        for (merge, branches) in joined {
+            if let Some((_, definition)) = node_definitions.iter_mut().find(|(id, _)| *id == merge)
+            {
+                let branches: Vec<String> = branches
+                    .iter()
+                    .map(|branch| shorten_aktivitet_name(branch).replace('\n', " "))
+                    .collect();
+                definition.insert_str(
+                    definition.len() - 1,
+                    &format!(
+                        ", xlabel=\"⋈ merge\", tooltip=\"Branches of {} meet again here\"",
+                        escape_label(&branches.join(", "))
+                    ),
+                );
+            }
+        }
+    }
+
+    // Heat map of the execution counts: the more often an aktivitet ran, the redder and larger it
+    // is drawn. Aktiviteter that never ran, and the transitions to them, are dashed and grey.
+    let metric_nodes: HashSet<String> =
+        if options.execution_counts.is_some() || options.durations.is_some() {
+            flow_aktiviteter(initial_aktivitet, &edges)
+                .into_iter()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+    let mut never_executed = HashSet::new();
+    if let Some(counts) = options.execution_counts {
+        let max = counts.max();
+        for (id, definition) in node_definitions.iter_mut() {
+            if !metric_nodes.contains(id) {
+                continue;
+            }
+            let attributes = match counts.get(id) {
+                Some(count) if count > 0 => {
+                    let heat = (count as f64).ln_1p() / (max as f64).ln_1p();
+                    format!(
+                        ", fillcolor=\"{}\", fontsize={}, xlabel=\"{}×\"",
+                        heat_color(heat, COUNT_HEAT_SCALE),
+                        12 + (heat * 10.0).round() as u32,
+                        count
+                    )
+                }
+                _ => {
+                    never_executed.insert(id.clone());
+                    ", fillcolor=\"#F5F5F5\", style=\"filled,rounded,dashed\", fontcolor=\"#9E9E9E\", xlabel=\"never run\"".to_string()
+                }
+            };
+            definition.insert_str(definition.len() - 1, &attributes);
+        }
+    }
+
+    // Heat map of the durations, from green for the quickest aktivitet to red for the one a sak
+    // waits in longest; it takes precedence over the execution count colors. Transitions are
+    // labeled at their tail with how long a sak typically stays before it is made.
+    if let Some(durations) = options.durations {
+        let max = durations
+            .max_entry()
+            .map_or(0.0, |(_, max)| max.as_secs_f64());
+        for (id, definition) in node_definitions.iter_mut() {
+            let Some(duration) = durations.get(id).filter(|_| metric_nodes.contains(id)) else {
+                continue;
+            };
+            let heat = if max > 0.0 {
+                duration.as_secs_f64().ln_1p() / max.ln_1p()
+            } else {
+                0.0
+            };
+            definition.insert_str(
+                definition.len() - 1,
+                &format!(
+                    ", fillcolor=\"{}\", tooltip=\"⏱ {}\"",
+                    heat_color(heat, DURATION_HEAT_SCALE),
+                    format_duration(duration)
+                ),
+            );
+        }
+    }
+    let dwell_time = |from: &str| {
+        options
+            .durations
+            .filter(|_| metric_nodes.contains(from))
+            .and_then(|durations| durations.get(from))
+    };
+
+    // Draw the highlighted path in bold; edges get a second attribute list, which DOT merges
+    let critical_path = if options.critical_path && options.highlight_path.is_none() {
+        longest_paths(initial_aktivitet, &edges)
+            .into_iter()
+            .find(|path| path.terminal == "end")
+            .map(|path| {
+                let mut aktiviteter = path.aktiviteter;
+                aktiviteter.push(path.terminal);
+                aktiviteter
+            })
+    } else {
+        None
+    };
+    let highlight_path = options.highlight_path.or(critical_path.as_deref());
+    let highlighted_edges: std::collections::HashSet<(&str, &str)> = highlight_path
+        .unwrap_or_default()
+        .windows(2)
+        .map(|step| (step[0].as_str(), step[1].as_str()))
+        .collect();
+    if let Some(path) = highlight_path {
+        for (id, definition) in node_definitions.iter_mut() {
+            if path.contains(id) {
+                definition.insert_str(definition.len() - 1, &format!(", {}", HIGHLIGHT_ATTRIBUTES));
+            }
+        }
+    }
+
+    // Outline what the changed files define in red, and what is downstream of it in orange
+    let impact = options.changed_files.map(|changed_files| {
+        change_impact(
+            initial_aktivitet,
+            processor_index,
+            class_index,
+            &edges,
+            changed_files,
+        )
+    });
+    if let Some(impact) = &impact {
+        for (id, definition) in node_definitions.iter_mut() {
+            if impact.changed.contains(id) {
+                definition.insert_str(
+                    definition.len() - 1,
+                    &format!(", {}, xlabel=\"✎ changed\"", CHANGED_ATTRIBUTES),
+                );
+            } else if impact.affected.contains(id) {
+                definition.insert_str(definition.len() - 1, &format!(", {}", AFFECTED_ATTRIBUTES));
+            }
+        }
+    }
+
+    let highlight = |from: &str, to: &str| {
+        let mut attributes = String::new();
+        if let Some(impact) = &impact {
+            if impact
+                .changed_transitions
+                .iter()
+                .any(|(changed_from, changed_to)| changed_from == from && changed_to == to)
+            {
+                attributes.push_str(&format!(" [{}]", CHANGED_ATTRIBUTES));
+            } else if impact.affected.iter().any(|affected| affected == to) {
+                attributes.push_str(&format!(" [{}]", AFFECTED_ATTRIBUTES));
+            }
+        }
+        if never_executed.contains(to) {
+            attributes.push_str(" [color=\"#BDBDBD\", style=dashed]");
+        }
+        if let Some(duration) = dwell_time(from) {
+            attributes.push_str(&format!(
+                " [taillabel=\"⏱ {}\", labelfontsize=9, labelfontcolor=\"#616161\"]",
+                format_duration(duration)
+            ));
+        }
+        if highlighted_edges.contains(&(from, to)) {
+            attributes.push_str(&format!(" [{}]", HIGHLIGHT_ATTRIBUTES));
+        }
+        attributes
+    };
+
+    // Start node ("…" when the graph starts in the middle of the flow)
+    if node_definitions
+        .iter()
+        .any(|(id, _)| id == initial_aktivitet)
+    {
+        dot.push_str(&format!(
+            "  start [label=\"{}\", shape=circle, style=filled, fillcolor=\"#90EE90\"];\n",
+            if options.start_from.is_some() {
+                "…"
+            } else {
+                "START"
+            }
+        ));
+        dot.push_str(&format!(
+            "  start -> \"{}\";\n\n",
+            escape_label(initial_aktivitet)
+        ));
+    }
+
+    // Detect iteration groups
+    let iteration_groups = detect_iteration_groups(processor_index, &edges);
+
+    // Detect cycles
+    let mut cycles = detect_cycles(initial_aktivitet, processor_index);
+
+    if options.collapse_chains {
+        let collapsed = collapse_chains(
+            initial_aktivitet,
+            options.focus,
+            &mut node_definitions,
+            &mut edges,
+        );
+        let rename = |name: String| collapsed.get(&name).cloned().unwrap_or(name);
+        cycles = cycles
+            .into_iter()
+            .map(|(from, to)| (rename(from), rename(to)))
+            .collect();
+    }
+
+    // Group the aktiviteter of the shown graph into its loops
+    let cycle_groups = group_cycles(&edges);
+
+    // Create a set of all nodes in cycles for easy lookup
+    let mut nodes_in_cycles = std::collections::HashSet::new();
+    for group in &cycle_groups {
+        for node in group {
+            nodes_in_cycles.insert(node.clone());
+        }
+    }
+
+    // Create a set of cycle edges (back edges)
+    let cycle_edges: std::collections::HashSet<(String, String)> = cycles.iter().cloned().collect();
+
+    // Add iteration clusters
+    for (idx, iteration_group) in iteration_groups.iter().enumerate() {
+        if iteration_group.iterated_nodes.len() > 1 {
+            dot.push_str(&format!("  subgraph cluster_iteration_{} {{\n", idx));
+            dot.push_str("    style=\"rounded,dashed\";\n");
+            dot.push_str("    color=\"#4CAF50\";\n");
+            dot.push_str("    penwidth=2.5;\n");
+            dot.push_str("    bgcolor=\"#F0FFF0\";\n");
+            dot.push_str(&format!(
+                "    label=\"Loop (triggered by {})\";\n",
+                iteration_group.trigger_node
+            ));
+            dot.push_str("    fontcolor=\"#2E7D32\";\n");
+            dot.push_str("    fontsize=12;\n");
+
+            // Add all nodes in the iteration path to the cluster
+            for node in &iteration_group.iterated_nodes {
+                // Only add if the node has a definition (avoid duplicates and unknown nodes)
+                if node_definitions
+                    .iter()
+                    .any(|(_, def)| def.contains(&format!("\"{}\"", escape_label(node))))
+                {
+                    dot.push_str(&format!("    \"{}\";\n", escape_label(node)));
+                }
+            }
+
+            dot.push_str("  }\n\n");
+        }
+    }
+
+    // Add cycle clusters (leaving out nodes that are not shown)
+    for (idx, cycle_nodes) in cycle_groups.iter().enumerate() {
+        let cycle_nodes: Vec<&String> = cycle_nodes
+            .iter()
+            .filter(|node| node_definitions.iter().any(|(id, _)| id == *node))
+            .collect();
+        if cycle_nodes.len() > 1 {
+            dot.push_str(&format!("\n  subgraph cluster_{} {{\n", idx));
+            dot.push_str("    style=\"rounded,dashed\";\n");
+            dot.push_str("    color=\"#FF6B6B\";\n");
+            dot.push_str("    penwidth=2.5;\n");
+            dot.push_str("    bgcolor=\"#FFF5F5\";\n");
+            dot.push_str("    label=\"🔄 Waiting/Retry Loop\";\n");
+            dot.push_str("    fontcolor=\"#FF6B6B\";\n");
+            dot.push_str("    fontsize=12;\n");
+            dot.push_str("    fontname=\"Arial Bold\";\n");
+
+            // Add nodes in this cycle to the cluster
+            for node in cycle_nodes {
+                dot.push_str(&format!("    \"{}\";\n", escape_label(node)));
+            }
+
+            dot.push_str("  }\n");
+        }
+    }
+
+    // Add node definitions
+    for (_, node_def) in node_definitions {
+        dot.push_str(&format!("  {};\n", node_def));
+    }
+
+    // Consolidate and add edges (if deduplication enabled)
+    if deduplicate {
+        let consolidated = consolidate_edges(&edges, &cycle_edges, show_conditions);
+        for ((from, to), edge) in consolidated {
+            let tooltip = transition_tooltip(&from, &to, processor_index);
+            dot.push_str(&format!(
+                "  {}{}{};\n",
+                edge,
+                tooltip,
+                highlight(&from, &to)
+            ));
+        }
+    } else {
+        // Add edges without consolidation
+        for edge in &edges {
+            let dot_edge = if edge.to.starts_with("unknown_")
+                || edge.to.starts_with("spawn_")
+                || edge.to.starts_with("dynamic_")
+                || edge.to == "unresolved"
+            {
+                format!(
+                    "\"{}\" -> {} [style=dashed]",
+                    escape_label(&edge.from),
+                    escape_label(&edge.to)
+                )
+            } else if edge.is_error {
+                error_edge(&edge.from, &edge.to, &edge.label)
+            } else if cycle_edges.contains(&(edge.from.clone(), edge.to.clone())) {
+                format!(
+                    "\"{}\" -> \"{}\" [color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false{}]",
+                    escape_label(&edge.from),
+                    escape_label(&edge.to),
+                    if show_conditions && !edge.label.is_empty() {
+                        format!(", label=\"{}\"", escape_label(&edge.label))
+                    } else {
+                        String::new()
+                    }
+                )
+            } else if edge.is_collection {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#4CAF50\", penwidth=2, style=bold]",
+                    escape_label(&edge.from),
+                    escape_label(&edge.to),
+                    if show_conditions && !edge.label.is_empty() {
+                        format!("{} (multiple)", escape_label(&edge.label))
+                    } else {
+                        "multiple".to_string()
+                    }
+                )
+            } else if show_conditions && !edge.label.is_empty() {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\"]",
+                    escape_label(&edge.from),
+                    escape_label(&edge.to),
+                    escape_label(&edge.label)
+                )
+            } else {
+                format!(
+                    "\"{}\" -> \"{}\"",
+                    escape_label(&edge.from),
+                    escape_label(&edge.to)
+                )
+            };
+            let tooltip = transition_tooltip(&edge.from, &edge.to, processor_index);
+            dot.push_str(&format!(
+                "  {}{}{};\n",
+                dot_edge,
+                tooltip,
+                highlight(&edge.from, &edge.to)
+            ));
+        }
+    }
+
+    // Add legend as HTML table (if enabled)
+    if show_legend {
+        dot.push_str("\n  // Legend\n");
+        dot.push_str("  {rank=sink;\n");
+        dot.push_str("    Legend [shape=none, margin=0, label=<\n");
+        dot.push_str(
+            "      <TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\">\n",
+        );
+        dot.push_str("        <TR>\n");
+        dot.push_str("          <TD COLSPAN=\"2\" BGCOLOR=\"#E8E8E8\"><B>Legend</B></TD>\n");
+        dot.push_str("        </TR>\n");
+        for (color, description) in LEGEND_ENTRIES {
+            dot.push_str("        <TR>\n");
+            dot.push_str(&format!("          <TD BGCOLOR=\"{}\">  </TD>\n", color));
+            dot.push_str(&format!(
+                "          <TD ALIGN=\"LEFT\">{}</TD>\n",
+                description
+            ));
+            dot.push_str("        </TR>\n");
+        }
+        dot.push_str("      </TABLE>\n");
+        dot.push_str("    >];\n");
+        dot.push_str("  }\n");
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// The node definitions and edges of the graph of a flow as [`generate_dot_graph`] draws it:
+/// with the subtypes expanded, cut at the maximum depth and around the focused aktivitet as the
+/// options ask
+fn drawn_flow(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    options: &DotOptions,
+) -> (Vec<(String, String)>, Vec<Edge>) {
+    // Track all nodes and edges to avoid duplicates
+    let mut visited_nodes = std::collections::HashSet::new();
+    let mut node_definitions = Vec::new();
+    let mut edges: Vec<Edge> = Vec::new();
+
+    // Build graph recursively
+    build_dot_nodes(
+        initial_aktivitet,
+        processor_index,
+        class_index,
+        &mut visited_nodes,
+        &mut node_definitions,
+        &mut edges,
+        &mut std::collections::HashSet::new(),
+    );
+
+    if options.expand_subtypes {
+        expand_subtypes(
+            processor_index,
+            class_index,
+            &mut visited_nodes,
+            &mut node_definitions,
+            &mut edges,
+        );
+    }
+
+    if let Some(max_depth) = options.max_depth {
+        truncate_to_depth(
+            initial_aktivitet,
+            max_depth,
+            &mut node_definitions,
+            &mut edges,
+        );
+    }
+
+    // Started Behandlinger link to the graph generated for them
+    if let Some(format) = options.flow_link_format {
+        for (id, definition) in node_definitions.iter_mut() {
+            if let Some(behandling) = id.strip_prefix("spawn_") {
+                definition.insert_str(
+                    definition.len() - 1,
+                    &format!(", URL=\"{}_flow.{}\"", escape_label(behandling), format),
+                );
+            }
+        }
+    }
+
+    // Keep only the neighborhood of the focused aktivitet
+    if let Some(focus) = options.focus {
+        let kept = neighborhood(focus, &edges, options.focus_radius);
+        node_definitions.retain(|(id, _)| kept.contains(id));
+        edges.retain(|edge| kept.contains(&edge.from) && kept.contains(&edge.to));
+        if let Some((_, definition)) = node_definitions.iter_mut().find(|(id, _)| id == focus) {
+            definition.insert_str(definition.len() - 1, ", penwidth=3");
+        }
+    }
+
+    (node_definitions, edges)
+}
+
+/// The edges of the graph of a flow as [`generate_dot_graph`] draws it, from `--start-from` if
+/// given
+pub fn drawn_flow_edges(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    options: &DotOptions,
+) -> Vec<Edge> {
+    let initial_aktivitet = options.start_from.unwrap_or(initial_aktivitet);
+    drawn_flow(initial_aktivitet, processor_index, class_index, options).1
+}
+
+/// The opening of a flow graph: layout direction, resolution, edge style and default styles
+pub fn graph_header(edge_style: &str, dpi: Option<f64>) -> String {
+    let mut dot = String::new();
+    dot.push_str("digraph BehandlingFlow {\n");
+    dot.push_str("  rankdir=TB;\n");
+    if let Some(dpi) = dpi {
+        dot.push_str(&format!("  dpi={};\n", dpi));
+    }
+
+    // Set splines based on edge style preference
+    match edge_style {
+        "straight" | "polyline" => dot.push_str("  splines=polyline;\n"),
+        "ortho" | "orthogonal" => dot.push_str("  splines=ortho;\n"),
+        "curved" | "spline" => dot.push_str("  splines=spline;\n"),
+        _ => dot.push_str("  splines=polyline;\n"), // default to straight
+    }
+
+    dot.push_str("  node [shape=box, style=rounded, fontname=\"Arial\"];\n");
+    dot.push_str("  edge [fontname=\"Arial\", fontsize=10];\n\n");
+    dot
+}
+
+/// One graph of several Behandling flows, given as (Behandling, initial aktivitet), with every
+/// Behandling and aktivitet drawn inside a cluster for the Gradle module in `modules` that
+/// declares it. Flows sharing aktiviteter are joined. Only the edge style, condition labels and
+/// resolution of `options` apply.
+pub fn generate_module_overview(
+    flows: &[(&str, &str)],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    modules: &HashMap<String, String>, // Class name -> module name
+    options: &DotOptions,
+) -> Result<String> {
+    let mut dot = graph_header(options.edge_style, options.dpi);
+    dot.push_str("  labelloc=\"t\";\n  label=\"Module Overview\";\n  fontsize=16;\n\n");
+
+    let mut visited_nodes = std::collections::HashSet::new();
+    let mut node_definitions = Vec::new();
+    let mut edges: Vec<Edge> = Vec::new();
+    for (behandling, initial_aktivitet) in flows {
+        let start_id = format!("start_{}", behandling);
+        node_definitions.push((
+            start_id.clone(),
+            format!(
+                "\"{}\" [label=\"▶ {}\", shape=box, style=\"filled,rounded\", fillcolor=\"#90EE90\"]",
+                escape_label(&start_id),
+                escape_label(behandling)
+            ),
+        ));
+        edges.push(Edge {
+            from: start_id,
+            to: initial_aktivitet.to_string(),
+            label: String::new(),
+            is_collection: false,
+            is_error: false,
+        });
+        build_dot_nodes(
+            initial_aktivitet,
+            processor_index,
+            class_index,
+            &mut visited_nodes,
+            &mut node_definitions,
+            &mut edges,
+            &mut std::collections::HashSet::new(),
+        );
+    }
+    // END and the manuell behandling node are added once per flow that reaches them
+    let mut seen = std::collections::HashSet::new();
+    node_definitions.retain(|(id, _)| seen.insert(id.clone()));
+
+    let module_of = |id: &str| {
+        let class = id.strip_prefix("start_").unwrap_or(id);
+        modules.get(class).cloned()
+    };
+    let mut clusters: std::collections::BTreeMap<String, Vec<&String>> =
+        std::collections::BTreeMap::new();
+    let mut outside = Vec::new();
+    for (id, definition) in &node_definitions {
+        match module_of(id) {
+            Some(module) => clusters.entry(module).or_default().push(definition),
+            None => outside.push(definition),
+        }
+    }
+
+    for (idx, (module, definitions)) in clusters.iter().enumerate() {
+        dot.push_str(&format!("  subgraph cluster_module_{} {{\n", idx));
+        dot.push_str("    style=\"rounded\";\n");
+        dot.push_str("    color=\"#607D8B\";\n");
+        dot.push_str("    bgcolor=\"#F5F7F8\";\n");
+        dot.push_str(&format!("    label=\"📦 {}\";\n", escape_label(module)));
+        dot.push_str("    fontsize=12;\n");
+        for definition in definitions {
+            dot.push_str(&format!("    {};\n", definition));
+        }
+        dot.push_str("  }\n\n");
+    }
+    for definition in outside {
+        dot.push_str(&format!("  {};\n", definition));
+    }
+
+    for ((from, to), edge) in consolidate_edges(
+        &edges,
+        &std::collections::HashSet::new(),
+        options.show_conditions,
+    ) {
+        let tooltip = transition_tooltip(&from, &to, processor_index);
+        dot.push_str(&format!("  {}{};\n", edge, tooltip));
+    }
+
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// How one Behandling depends on another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BehandlingLinkKind {
+    /// `opprettBehandling(AnnenBehandling())`
+    Starts,
+    /// A call to one of the `vent-paa-behandling` functions
+    WaitsOn,
+    /// A call to one of the `gjenoppta-behandling` functions
+    Resumes,
+}
+
+impl BehandlingLinkKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BehandlingLinkKind::Starts => "starts",
+            BehandlingLinkKind::WaitsOn => "waits on",
+            BehandlingLinkKind::Resumes => "resumes",
+        }
+    }
+}
+
+/// Aktiviteter in the flow of `from` whose processors start, wait on or resume `to`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BehandlingLink {
+    pub from: String,
+    pub to: String,
+    pub kind: BehandlingLinkKind,
+    pub aktiviteter: Vec<String>, // In the order they are reached
+}
+
+/// The links between the Behandlinger of several flows, given as (Behandling, initial
+/// aktivitet), sorted by Behandling and kind
+pub fn behandling_links(
+    flows: &[(&str, &str)],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+) -> Vec<BehandlingLink> {
+    let mut links: std::collections::BTreeMap<(String, String, BehandlingLinkKind), Vec<String>> =
+        std::collections::BTreeMap::new();
+    for (behandling, initial_aktivitet) in flows {
+        let edges = collect_flow_edges(initial_aktivitet, processor_index, class_index);
+        for aktivitet in flow_aktiviteter(initial_aktivitet, &edges) {
+            let Some(processor) = processor_index.get(&aktivitet) else {
+                continue;
+            };
+            let targets = [
+                (BehandlingLinkKind::Starts, &processor.spawns),
+                (BehandlingLinkKind::WaitsOn, &processor.waits_for),
+                (BehandlingLinkKind::Resumes, &processor.resumes),
+            ];
+            for (kind, others) in targets {
+                for other in others {
+                    links
+                        .entry((behandling.to_string(), other.clone(), kind))
+                        .or_default()
+                        .push(aktivitet.clone());
+                }
+            }
+        }
+    }
+    links
+        .into_iter()
+        .map(|((from, to, kind), aktiviteter)| BehandlingLink {
+            from,
+            to,
+            kind,
+            aktiviteter,
+        })
+        .collect()
+}
+
+/// One graph with a node per Behandling of the given flows and an edge for every Behandling one
+/// starts, waits on or resumes, labeled with the aktiviteter that do it in the tooltip.
+/// Behandlinger that are linked to but not among the flows are drawn dashed. Only the edge style
+/// and resolution of `options` apply.
+pub fn generate_behandling_overview(
+    flows: &[(&str, &str)],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    options: &DotOptions,
+) -> String {
+    let mut dot = graph_header(options.edge_style, options.dpi);
+    dot.push_str("  labelloc=\"t\";\n  label=\"Behandling Overview\";\n  fontsize=16;\n\n");
+
+    let links = behandling_links(flows, processor_index, class_index);
+    for (behandling, _) in flows {
+        dot.push_str(&format!(
+            "  \"{}\" [label=\"{}\", shape=box, style=\"filled,rounded\", fillcolor=\"#E6E6FA\"];\n",
+            escape_label(behandling),
+            escape_label(behandling)
+        ));
+    }
+    let mut outside: Vec<&str> = links
+        .iter()
+        .map(|link| link.to.as_str())
+        .filter(|to| !flows.iter().any(|(behandling, _)| behandling == to))
+        .collect();
+    outside.sort_unstable();
+    outside.dedup();
+    for behandling in outside {
+        dot.push_str(&format!(
+            "  \"{}\" [label=\"{}\", shape=box, style=\"rounded,dashed\", color=\"#999999\", fontcolor=\"#666666\"];\n",
+            escape_label(behandling),
+            escape_label(behandling)
+        ));
+    }
+    dot.push('\n');
+
+    for link in &links {
+        let style = match link.kind {
+            BehandlingLinkKind::Starts => "solid",
+            BehandlingLinkKind::WaitsOn => "dashed",
+            BehandlingLinkKind::Resumes => "dotted",
+        };
+        let aktiviteter: Vec<String> = link
+            .aktiviteter
+            .iter()
+            .map(|aktivitet| shorten_aktivitet_name(aktivitet).replace('\n', " "))
+            .collect();
+        dot.push_str(&format!(
+            "  \"{}\" -> \"{}\" [label=\"{}\", style={}, tooltip=\"{}\"];\n",
+            escape_label(&link.from),
+            escape_label(&link.to),
+            link.kind.name(),
+            style,
+            escape_label(&format!(
+                "{} {} {} in {}",
+                link.from,
+                link.kind.name(),
+                link.to,
+                aktiviteter.join(", ")
+            ))
+        ));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Nodes reachable within `radius` transitions downstream of `focus`, plus the ones that reach
+/// `focus` within `radius` transitions
+fn neighborhood(focus: &str, edges: &[Edge], radius: usize) -> std::collections::HashSet<String> {
+    let mut kept = std::collections::HashSet::from([focus.to_string()]);
+
+    for downstream in [true, false] {
+        let mut seen = std::collections::HashSet::from([focus.to_string()]);
+        let mut frontier = vec![focus.to_string()];
+        for _ in 0..radius {
+            let mut next_frontier = Vec::new();
+            for node in &frontier {
+                for edge in edges {
+                    let (from, to) = if downstream {
+                        (&edge.from, &edge.to)
+                    } else {
+                        (&edge.to, &edge.from)
+                    };
+                    if from == node && seen.insert(to.clone()) {
+                        next_frontier.push(to.clone());
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+        kept.extend(seen);
+    }
+
+    kept
+}
+
+/// Detect iteration groups where one aktivitet creates multiple instances of subsequent aktiviteter
+fn detect_iteration_groups(
+    processor_index: &HashMap<String, ProcessorInfo>,
+    edges: &[Edge],
+) -> Vec<IterationGroup> {
+    let mut iteration_groups = Vec::new();
+
+    // Find all collection edges (fan-out edges)
+    let collection_edges: Vec<&Edge> = edges.iter().filter(|e| e.is_collection).collect();
+
+    for collection_edge in collection_edges {
+        let trigger_node = collection_edge.from.clone();
+        let first_iterated_node = collection_edge.to.clone();
+
+        // Trace the path from the first iterated node to find all nodes in the iteration
+        let mut iterated_nodes = vec![first_iterated_node.clone()];
+        let mut current_nodes = vec![first_iterated_node];
+        let mut visited = std::collections::HashSet::new();
+
+        // Follow the path until we reach an end or cycle back to a known node
+        while !current_nodes.is_empty() {
+            let mut next_nodes = Vec::new();
+
+            for current_node in &current_nodes {
+                if visited.contains(current_node) {
+                    continue;
+                }
+                visited.insert(current_node.clone());
+
+                if let Some(processor) = processor_index.get(current_node) {
+                    for next_aktivitet in &processor.next_aktiviteter {
+                        // Only include in iteration if it's a direct single path (not conditional)
+                        if processor.next_aktiviteter.len() == 1 {
+                            next_nodes.push(next_aktivitet.aktivitet_name.clone());
+                            iterated_nodes.push(next_aktivitet.aktivitet_name.clone());
+                        }
+                    }
+                }
+            }
+
+            current_nodes = next_nodes;
+
+            // Prevent infinite loops
+            if visited.len() > 20 {
+                break;
+            }
+        }
+
+        // Only create a group if we have multiple nodes in the iteration path
+        if iterated_nodes.len() > 1 {
+            iteration_groups.push(IterationGroup {
+                trigger_node,
+                iterated_nodes,
+            });
+        }
+    }
+
+    iteration_groups
+}
+
+fn build_dot_nodes(
+    aktivitet_name: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    visited_nodes: &mut std::collections::HashSet<String>,
+    node_definitions: &mut Vec<(String, String)>, // Node id and its DOT definition
+    edges: &mut Vec<Edge>,
+    visiting: &mut std::collections::HashSet<String>,
+) {
+    if visited_nodes.contains(aktivitet_name) {
+        return;
+    }
+
+    if visiting.contains(aktivitet_name) {
+        // Cycle detected
+        return;
+    }
+
+    visiting.insert(aktivitet_name.to_string());
+    visited_nodes.insert(aktivitet_name.to_string());
+
+    // Shorten the name for display, struck through if the aktivitet is deprecated
+    let deprecated = is_deprecated_aktivitet(aktivitet_name, class_index);
+    let display_name = shorten_aktivitet_name(aktivitet_name);
+    let display_name = if deprecated {
+        format!("{}\n⊘ deprecated", strike_through(&display_name))
+    } else {
+        display_name
+    };
+
+    // Check if this aktivitet creates a manuell behandling
+    let creates_oppgave = creates_manuell_behandling(aktivitet_name, processor_index);
+
+    // Determine node color based on name patterns and type
+    let color = node_color(aktivitet_name, processor_index, class_index);
+
+    // Add node definition with oppgave indicator if applicable
+    let label = if creates_oppgave {
+        format!("📋 {}", display_name)
+    } else {
+        display_name
+    };
+    // What the aktivitet waits for before it runs again, e.g. `ventTil(virkningsdato)`
+    let label = match processor_index.get(aktivitet_name) {
+        Some(processor) if !processor.waits_until.is_empty() => {
+            format!("{}\n⏳ until {}", label, processor.waits_until.join(", "))
+        }
+        _ => label,
+    };
+
+    // Hovering the node in SVG output shows where the aktivitet is declared, and the oppgave of a
+    // manuell behandling it creates
+    let tooltip_lines: Vec<String> = class_index
+        .get(aktivitet_name)
+        .map(|class| class.location().to_string())
+        .into_iter()
+        .chain(
+            processor_index
+                .get(aktivitet_name)
+                .into_iter()
+                .flat_map(|processor| &processor.manuell_behandlinger)
+                .filter_map(|manuell| manuell.details())
+                .map(|details| format!("📋 {}", details)),
+        )
+        .collect();
+    let tooltip = if tooltip_lines.is_empty() {
+        String::new()
+    } else {
+        format!(", tooltip=\"{}\"", escape_label(&tooltip_lines.join("\n")))
+    };
+    let style = if deprecated {
+        "style=\"filled,dashed\", fontcolor=\"#777777\""
+    } else {
+        "style=filled"
+    };
+    node_definitions.push((
+        aktivitet_name.to_string(),
+        format!(
+            "\"{}\" [label=\"{}\", {}, fillcolor=\"{}\"{}]",
+            escape_label(aktivitet_name),
+            escape_label(&label),
+            style,
+            color,
+            tooltip
+        ),
+    ));
+
+    if let Some(processor) = processor_index.get(aktivitet_name) {
+        // Error paths from catch blocks are not a way for the flow to continue normally
+        let branch_count = processor
+            .next_aktiviteter
+            .iter()
+            .filter(|next| next.error_type.is_none())
+            .count();
+
+        // END or ABORT, once for each result or reason the flow is ended with
+        for terminal in &processor.terminals {
+            let (id, definition) = match terminal.kind {
+                TerminalKind::Fullfort => (
+                    "end",
+                    "end [label=\"END\", shape=circle, style=filled, fillcolor=\"#FFB6C1\"]",
+                ),
+                TerminalKind::Avbrutt => (
+                    "abort",
+                    "abort [label=\"ABORT\", shape=octagon, style=filled, fillcolor=\"#FF4444\", fontcolor=white]",
+                ),
+            };
+            node_definitions.push((id.to_string(), definition.to_string()));
+            edges.push(Edge {
+                from: aktivitet_name.to_string(),
+                to: id.to_string(),
+                label: terminal.detail.clone().unwrap_or_default(),
+                is_collection: false,
+                is_error: false,
+            });
+        }
+
+        // Hand-offs to other flows, which are drawn in their own graphs
+        for behandling in &processor.spawns {
+            let id = format!("spawn_{}", behandling);
+            node_definitions.push((
+                id.clone(),
+                format!(
+                    "\"{}\" [label=\"starts ▷ {}\", shape=box, style=\"filled,rounded,dashed\", fillcolor=\"#E6E6FA\"]",
+                    escape_label(&id),
+                    escape_label(behandling)
+                ),
+            ));
+            edges.push(Edge {
+                from: aktivitet_name.to_string(),
+                to: id,
+                label: "".to_string(),
+                is_collection: false,
+                is_error: false,
+            });
+        }
+
+        // Neither a transition nor aktivitetFullfort() was found: not the same as completing
+        if branch_count == 0 && processor.terminals.is_empty() {
+            node_definitions.push((
+                "unresolved".to_string(),
+                "unresolved [label=\"END?\", shape=circle, style=\"filled,dashed\", fillcolor=\"#EEEEEE\", tooltip=\"No transition found\"]"
+                    .to_string(),
+            ));
+            edges.push(Edge {
+                from: aktivitet_name.to_string(),
+                to: "unresolved".to_string(),
+                label: "".to_string(),
+                is_collection: false,
+                is_error: false,
+            });
+        }
+
+        for next in processor.next_aktiviteter.iter() {
+            // `nesteAktiviteter(*ekstraSteg.toTypedArray())`: whatever is in the collection
+            if next.is_dynamic {
+                let id = format!("dynamic_{}", next.aktivitet_name);
+                node_definitions.push((
+                    id.clone(),
+                    format!(
+                        "\"{}\" [label=\"*{}\", shape=box, style=\"filled,dashed\", fillcolor=\"#EEEEEE\", tooltip=\"Aktiviteter only known at runtime\"]",
+                        escape_label(&id),
+                        escape_label(&next.aktivitet_name)
+                    ),
+                ));
+                edges.push(Edge {
+                    from: aktivitet_name.to_string(),
+                    to: id,
+                    label: next
+                        .condition
+                        .as_ref()
+                        .map(Condition::label)
+                        .unwrap_or_default(),
+                    is_collection: true,
+                    is_error: false,
+                });
+                continue;
+            }
+
+            let label = match (&next.error_type, &next.condition) {
+                (Some(error_type), _) => format!("catch {}", error_type),
+                (None, Some(condition)) => condition.label(),
+                // Multiple branches - conditional
+                (None, None) if branch_count > 1 => "else".to_string(),
+                (None, None) => "".to_string(),
+            };
+            // The loop a fan-out is made in, the reason given for the transition and how long
+            // it is postponed are shown under its condition
+            let annotations = [
+                next.loop_context
+                    .as_ref()
+                    .map(|context| format!("↻ {}", context)),
+                next.begrunnelse.as_ref().map(|b| format!("«{}»", b)),
+                next.wait_until
+                    .as_ref()
+                    .map(|until| format!("⏳ until {}", until)),
+            ];
+            let label = std::iter::once(label)
+                .chain(annotations.into_iter().flatten())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            edges.push(Edge {
+                from: aktivitet_name.to_string(),
+                to: next.aktivitet_name.clone(),
+                label,
+                is_collection: next.is_collection,
+                is_error: next.error_type.is_some(),
+            });
+
+            build_dot_nodes(
+                &next.aktivitet_name,
+                processor_index,
+                class_index,
+                visited_nodes,
+                node_definitions,
+                edges,
+                visiting,
+            );
+        }
+
+        for error_type in &processor.manuell_on_error {
+            node_definitions.push((
+                "manuell".to_string(),
+                "manuell [label=\"📋 Manuell behandling\", shape=box, style=\"filled,rounded\", fillcolor=\"#FFA500\"]"
+                    .to_string(),
+            ));
+            edges.push(Edge {
+                from: aktivitet_name.to_string(),
+                to: "manuell".to_string(),
+                label: format!("catch {}", error_type),
+                is_collection: false,
+                is_error: true,
+            });
+        }
+    } else {
+        // No processor found - mark as unknown
+        let unknown_id = format!("unknown_{}", aktivitet_name);
+        node_definitions.push((
+            unknown_id.clone(),
+            format!(
+                "{} [label=\"?\", shape=diamond, style=filled, fillcolor=\"#CCCCCC\"]",
+                escape_label(&unknown_id)
+            ),
+        ));
+        edges.push(Edge {
+            from: aktivitet_name.to_string(),
+            to: unknown_id,
+            label: "".to_string(),
+            is_collection: false,
+            is_error: false,
+        });
+    }
+
+    visiting.remove(aktivitet_name);
+}
+
+/// Replace every aktivitet without a processor that is an abstract or sealed base type with a
+/// small fan out to its concrete subclasses, and follow the flows of those. Repeated until the
+/// flows of the subclasses reach no more such base types.
+fn expand_subtypes(
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    visited_nodes: &mut std::collections::HashSet<String>,
+    node_definitions: &mut Vec<(String, String)>,
+    edges: &mut Vec<Edge>,
+) {
+    let mut expanded = std::collections::HashSet::new();
+
+    while let Some((base, subtypes)) = edges
+        .iter()
+        .map(|edge| &edge.to)
+        .filter(|to| !is_marker_node(to) && !processor_index.contains_key(to.as_str()))
+        .filter(|to| !expanded.contains(to.as_str()))
+        .find_map(|to| {
+            let subtypes = concrete_subtypes(to, class_index);
+            (!subtypes.is_empty()).then(|| (to.clone(), subtypes))
+        })
+    {
+        expanded.insert(base.clone());
+
+        // The base type node and its unknown marker make way for the fan
+        let unknown_id = format!("unknown_{}", base);
+        let fan_id = format!("subtypes_{}", base);
+        node_definitions.retain(|(id, _)| *id != base && *id != unknown_id);
+        edges.retain(|edge| edge.from != base);
+        for edge in edges.iter_mut().filter(|edge| edge.to == base) {
+            edge.to = fan_id.clone();
+        }
+        node_definitions.push((
+            fan_id.clone(),
+            format!(
+                "{} [label=\"\", xlabel=\"{}\", shape=point, width=0.12, fontsize=9]",
+                escape_label(&fan_id),
+                escape_label(&shorten_aktivitet_name(&base))
+            ),
+        ));
+
+        for subtype in subtypes {
+            edges.push(Edge {
+                from: fan_id.clone(),
+                to: subtype.clone(),
+                label: "".to_string(),
+                is_collection: false,
+                is_error: false,
+            });
+            build_dot_nodes(
+                &subtype,
+                processor_index,
+                class_index,
+                visited_nodes,
+                node_definitions,
+                edges,
+                &mut std::collections::HashSet::new(),
+            );
+        }
+    }
+
+    // Subclass flows may lead back to a base type that is already fanned out
+    for edge in edges.iter_mut() {
+        if expanded.contains(&edge.to) {
+            edge.to = format!("subtypes_{}", edge.to);
+        }
+    }
+}
+
+/// The non-abstract classes that extend `base`, directly or through abstract classes between
+/// them, sorted by name
+pub fn concrete_subtypes(base: &str, class_index: &HashMap<String, ClassInfo>) -> Vec<String> {
+    if !class_index.get(base).is_some_and(|info| info.is_abstract) {
+        return Vec::new();
+    }
+
+    let mut subtypes = Vec::new();
+    let mut pending = vec![base.to_string()];
+    let mut seen = std::collections::HashSet::new();
+    while let Some(current) = pending.pop() {
+        if !seen.insert(current.clone()) {
+            continue;
+        }
+        let current_name = class_index
+            .get(&current)
+            .map_or(current.as_str(), |info| info.name.as_str());
+        for (key, info) in class_index {
+            let extends_current = info
+                .supertypes
+                .iter()
+                .any(|supertype| supertype.rsplit('.').next() == Some(current_name));
+            if !extends_current {
+                continue;
+            }
+            if info.is_abstract {
+                pending.push(key.clone());
+            } else if !subtypes.contains(key) {
+                subtypes.push(key.clone());
+            }
+        }
+    }
+
+    subtypes.sort();
+    subtypes
+}
+
+pub fn creates_manuell_behandling(
+    aktivitet_name: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+) -> bool {
+    processor_index
+        .get(aktivitet_name)
+        .map(|p| p.has_manuell_behandling)
+        .unwrap_or(false)
+}
+
+/// True if the processor of an aktivitet postpones it, like `ventTil(virkningsdato)`
+fn waits(aktivitet_name: &str, processor_index: &HashMap<String, ProcessorInfo>) -> bool {
+    processor_index
+        .get(aktivitet_name)
+        .is_some_and(|p| !p.waits_until.is_empty())
+}
+
+pub fn node_color(
+    aktivitet_name: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+) -> &'static str {
+    if is_deprecated_aktivitet(aktivitet_name, class_index) {
+        "#DDDDDD" // Grey for deprecated activities, whatever else they do
+    } else if is_alde_aktivitet(aktivitet_name, class_index) {
+        "#9370DB" // Medium purple for AldeAktivitet (important)
+    } else if creates_manuell_behandling(aktivitet_name, processor_index) {
+        "#FFA500" // Orange for activities that create manual tasks
+    } else if waits(aktivitet_name, processor_index)
+        || aktivitet_name.contains("Vent")
+        || aktivitet_name.contains("Wait")
+    {
+        "#FFD700" // Gold for waiting activities
+    } else if aktivitet_name.contains("Manuell") || aktivitet_name.contains("Oppgave") {
+        "#FF6B6B" // Red for manual activities
+    } else if aktivitet_name.contains("Avbryt") || aktivitet_name.contains("Avslag") {
+        "#FF4444" // Dark red for abort/rejection
+    } else if aktivitet_name.contains("Iverksett") || aktivitet_name.contains("Vedtak") {
+        "#4CAF50" // Green for decision/execution
+    } else {
+        "#87CEEB" // Sky blue for regular activities
+    }
+}
+
+fn consolidate_edges(
+    edges: &[Edge],
+    cycle_edges: &std::collections::HashSet<(String, String)>,
+    show_conditions: bool,
+) -> Vec<((String, String), String)> {
+    // Group edges by (from, to) pair
+    let mut edge_groups: HashMap<(String, String), Vec<String>> = HashMap::new();
+    let mut collection_edges: HashMap<(String, String), bool> = HashMap::new();
+
+    // Error paths are drawn separately, with all exceptions caught on the way to the same target
+    let mut error_groups: std::collections::BTreeMap<(&str, &str), Vec<&str>> =
+        std::collections::BTreeMap::new();
+    for edge in edges.iter().filter(|e| e.is_error) {
+        let labels = error_groups.entry((&edge.from, &edge.to)).or_default();
+        if !labels.contains(&edge.label.as_str()) {
+            labels.push(&edge.label);
+        }
+    }
+
+    for edge in edges.iter().filter(|e| !e.is_error) {
+        let key = (edge.from.clone(), edge.to.clone());
+        edge_groups
+            .entry(key.clone())
+            .or_insert_with(Vec::new)
+            .push(edge.label.clone());
+
+        // Track if any edge in this group is a collection edge
+        if edge.is_collection {
+            collection_edges.insert(key, true);
+        }
+    }
+
+    let mut result = Vec::new();
+
+    for ((from, to), labels) in edge_groups.iter() {
+        // Filter out empty labels and "else" labels, and get unique ones in the order the
+        // transitions are made, so the sample shown below is always the first
+        let non_empty_labels: Vec<String> = if show_conditions {
+            let mut unique: Vec<String> = Vec::new();
+            for label in labels.iter().filter(|l| !l.is_empty() && *l != "else") {
+                if !unique.contains(label) {
+                    unique.push(label.clone());
+                }
+            }
+            unique
+        } else {
+            Vec::new() // Don't show any conditions
+        };
+
+        // Check if this is a cycle edge (back edge)
+        let is_cycle_edge = cycle_edges.contains(&(from.clone(), to.clone()));
+
+        // Check if this is a collection edge (fan-out)
+        let is_collection_edge = collection_edges
+            .get(&(from.clone(), to.clone()))
+            .unwrap_or(&false);
+
+        let dot_edge = if !show_conditions || (labels.len() == 1 && labels[0].is_empty()) {
+            // Single edge with no label (simple transition or dashed edge)
+            if to.starts_with("unknown_")
+                || to.starts_with("spawn_")
+                || to.starts_with("dynamic_")
+                || to == "unresolved"
+            {
+                format!(
+                    "\"{}\" -> {} [style=dashed]",
+                    escape_label(from),
+                    escape_label(to)
+                )
+            } else if is_cycle_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
+                    escape_label(from),
+                    escape_label(to)
+                )
+            } else if *is_collection_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"multiple\", color=\"#4CAF50\", penwidth=2, style=bold]",
+                    escape_label(from),
+                    escape_label(to)
+                )
+            } else {
+                format!("\"{}\" -> \"{}\"", escape_label(from), escape_label(to))
+            }
+        } else if !show_conditions || non_empty_labels.is_empty() {
+            // All labels were empty - simple edge
+            if is_cycle_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
+                    escape_label(from),
+                    escape_label(to)
+                )
+            } else if *is_collection_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"multiple\", color=\"#4CAF50\", penwidth=2, style=bold]",
+                    escape_label(from),
+                    escape_label(to)
+                )
+            } else {
+                format!("\"{}\" -> \"{}\"", escape_label(from), escape_label(to))
+            }
+        } else if non_empty_labels.len() == 1 {
+            // Single unique condition
+            if is_cycle_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&non_empty_labels[0])
+                )
+            } else if *is_collection_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{} (multiple)\", color=\"#4CAF50\", penwidth=2, style=bold]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&non_empty_labels[0])
+                )
+            } else {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\"]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&non_empty_labels[0])
+                )
+            }
+        } else if non_empty_labels.len() == 1 {
+            // Single unique condition - show it
+            if is_cycle_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&non_empty_labels[0])
+                )
+            } else {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\"]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&non_empty_labels[0])
+                )
+            }
+        } else {
+            // Multiple conditions - just show the first one as example (no "alternative paths" text)
+            let sample = &non_empty_labels[0];
+            let truncated = if sample.chars().count() > 40 {
+                format!("{}...", sample.chars().take(40).collect::<String>())
+            } else {
+                sample.clone()
+            };
+            if is_cycle_edge {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&truncated)
+                )
+            } else {
+                format!(
+                    "\"{}\" -> \"{}\" [label=\"{}\"]",
+                    escape_label(from),
+                    escape_label(to),
+                    escape_label(&truncated)
+                )
+            }
+        };
+
+        result.push(((from.clone(), to.clone()), dot_edge));
+    }
+
+    for ((from, to), labels) in error_groups {
+        result.push((
+            (from.to_string(), to.to_string()),
+            error_edge(from, to, &labels.join(", ")),
+        ));
+    }
+
+    result
+}
+
+/// Extra attribute list with the source locations of the `nesteAktivitet` calls behind an edge,
+/// shown when hovering it in SVG output. DOT allows several attribute lists, so it can follow any
+/// edge statement.
+fn transition_tooltip(
+    from: &str,
+    to: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+) -> String {
+    let mut locations: Vec<String> = processor_index
+        .get(from)
+        .into_iter()
+        .flat_map(|processor| &processor.next_aktiviteter)
+        .filter(|next| next.aktivitet_name == to)
+        .map(|next| next.location.to_string())
+        .collect();
+    locations.dedup();
+    if locations.is_empty() {
+        String::new()
+    } else {
+        format!(" [tooltip=\"{}\"]", escape_label(&locations.join("\n")))
+    }
+}
+
+/// Error paths always show the caught exception, the same way fan-out edges always say "multiple"
+fn error_edge(from: &str, to: &str, label: &str) -> String {
+    format!(
+        "\"{}\" -> \"{}\" [label=\"⚡ {}\", color=\"#D32F2F\", fontcolor=\"#D32F2F\", style=dashed]",
+        escape_label(from),
+        escape_label(to),
+        escape_label(label)
+    )
+}
+
+/// Summary metrics for a single Behandling flow
+pub struct FlowSummary {
+    pub aktivitet_count: usize,
+    pub transition_count: usize,
+    pub branch_points: usize,
+    pub complexity: usize, // Cyclomatic complexity of the graph, see `flow_complexity`
+    pub degrees: Vec<NodeDegree>, // Fan-in and fan-out of every aktivitet
+    pub cycles: usize,
+    pub cycle_paths: Vec<Vec<String>>, // The aktiviteter around each elementary cycle, see `find_cycles`
+    pub max_depth: usize, // Longest shortest distance (in transitions) from the initial aktivitet
+    pub longest_paths: Vec<LongestPath>, // The most steps to each terminal node, loops left out
+    pub manual_steps: Vec<String>,
+    pub deprecated_steps: Vec<String>, // Deprecated aktiviteter that are still reached
+    pub feature_toggles: Vec<String>,
+}
+
+/// Walk the flow from the initial aktivitet and collect all edges, the same way the DOT graph does
+pub fn collect_flow_edges(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+) -> Vec<Edge> {
+    let mut visited_nodes = std::collections::HashSet::new();
+    let mut node_definitions = Vec::new();
+    let mut edges = Vec::new();
+
+    build_dot_nodes(
+        initial_aktivitet,
+        processor_index,
+        class_index,
+        &mut visited_nodes,
+        &mut node_definitions,
+        &mut edges,
+        &mut std::collections::HashSet::new(),
+    );
+
+    edges
+}
+
+/// Aktiviteter in the flow without a processor (the unknown `?` nodes of the graph), each with
+/// the aktiviteter whose processors lead to it. The initial aktivitet has none of those.
+pub fn unknown_aktiviteter(edges: &[Edge]) -> Vec<(String, Vec<String>)> {
+    let mut unknown: Vec<(String, Vec<String>)> = edges
+        .iter()
+        .filter(|edge| edge.to.starts_with("unknown_"))
+        .map(|edge| (edge.from.clone(), Vec::new()))
+        .collect();
+
+    for (aktivitet, referenced_from) in &mut unknown {
+        for edge in edges.iter().filter(|edge| edge.to == *aktivitet) {
+            if !referenced_from.contains(&edge.from) {
+                referenced_from.push(edge.from.clone());
+            }
+        }
+    }
+
+    unknown
+}
+
+/// How a flow ends at a terminal node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EndingKind {
+    /// `aktivitetFullfort()`: the behandling is done
+    Completed,
+    /// `avbrytBehandling()` or `avbryt()`
+    Aborted,
+    /// A processor without transitions or an aktivitet without processor: the flow stops there
+    /// without saying how, which usually means it could not be followed
+    DeadEnd,
+}
+
+impl FlowSummary {
+    /// Aktiviteter on the longest path to END, or 0 if the flow never completes
+    pub fn longest_to_end(&self) -> usize {
+        self.longest_paths
+            .iter()
+            .find(|path| path.terminal == "end")
+            .map_or(0, |path| path.aktiviteter.len())
+    }
+}
+
+impl EndingKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EndingKind::Completed => "completed",
+            EndingKind::Aborted => "aborted",
+            EndingKind::DeadEnd => "dead-end",
+        }
+    }
+}
+
+/// One distinct way a flow ends, e.g. completed with `Resultat.AVSLATT`, with the aktiviteter
+/// whose processors end it that way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowEnding {
+    pub kind: EndingKind,
+    pub detail: Option<String>, // The result, the reason for the abort, or why it is a dead end
+    pub aktiviteter: Vec<String>, // In the order they are reached
+}
+
+/// Every distinct ending of the flow from `initial_aktivitet`, where the graph shows only one
+/// END, ABORT and END? node: completions by result, aborts by reason, and the dead ends
+pub fn flow_endings(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    edges: &[Edge],
+) -> Vec<FlowEnding> {
+    let mut endings: Vec<FlowEnding> = Vec::new();
+    let mut add = |kind: EndingKind, detail: Option<String>, aktivitet: &str| {
+        let index = match endings
+            .iter()
+            .position(|ending| ending.kind == kind && ending.detail == detail)
+        {
+            Some(index) => index,
+            None => {
+                endings.push(FlowEnding {
+                    kind,
+                    detail,
+                    aktiviteter: Vec::new(),
+                });
+                endings.len() - 1
+            }
+        };
+        let aktiviteter = &mut endings[index].aktiviteter;
+        if !aktiviteter.iter().any(|known| known == aktivitet) {
+            aktiviteter.push(aktivitet.to_string());
+        }
+    };
+
+    for aktivitet in flow_aktiviteter(initial_aktivitet, edges) {
+        let Some(processor) = processor_index.get(&aktivitet) else {
+            add(
+                EndingKind::DeadEnd,
+                Some("no processor".to_string()),
+                &aktivitet,
+            );
+            continue;
+        };
+        for terminal in &processor.terminals {
+            let kind = match terminal.kind {
+                TerminalKind::Fullfort => EndingKind::Completed,
+                TerminalKind::Avbrutt => EndingKind::Aborted,
+            };
+            add(kind, terminal.detail.clone(), &aktivitet);
+        }
+        if edges
+            .iter()
+            .any(|edge| edge.from == aktivitet && edge.to == "unresolved")
+        {
+            add(
+                EndingKind::DeadEnd,
+                Some("no transition found".to_string()),
+                &aktivitet,
+            );
+        }
+    }
+
+    // Stable, so endings of a kind stay in the order they are first reached
+    endings.sort_by_key(|ending| ending.kind);
+    endings
+}
+
+/// The aktiviteter of a flow split by whether every completed behandling passes through them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MandatorySteps {
+    pub mandatory: Vec<String>, // In the order every completion passes them, initial aktivitet first
+    pub skippable: Vec<String>, // In the order they are first reached
+}
+
+/// The immediate dominator of each aktivitet in the flow: the last aktivitet that every route from
+/// the initial aktivitet to it passes through. The initial aktivitet has none.
+pub fn immediate_dominators(initial_aktivitet: &str, edges: &[Edge]) -> HashMap<String, String> {
+    let (nodes, successors) = aktivitet_graph(initial_aktivitet, edges, false);
+    dominator_tree(&successors)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(node, idom)| Some((nodes[node].clone(), nodes[idom?].clone())))
+        .collect()
+}
+
+/// The aktiviteter every route from the initial aktivitet to a completion (`aktivitetFullfort()`)
+/// passes through, which are the dominators of the completions, and the ones a route can skip.
+/// `None` if the flow never completes.
+pub fn mandatory_steps(initial_aktivitet: &str, edges: &[Edge]) -> Option<MandatorySteps> {
+    let (nodes, successors) = aktivitet_graph(initial_aktivitet, edges, true);
+    let exit = nodes.len() - 1;
+    let idoms = dominator_tree(&successors);
+
+    let mut mandatory = Vec::new();
+    let mut dominator = idoms[exit]?;
+    loop {
+        mandatory.push(nodes[dominator].clone());
+        match idoms[dominator] {
+            Some(next) => dominator = next,
+            None => break,
+        }
+    }
+    mandatory.reverse();
+    let skippable = nodes[..exit]
+        .iter()
+        .filter(|node| !mandatory.contains(node))
+        .cloned()
+        .collect();
+    Some(MandatorySteps {
+        mandatory,
+        skippable,
+    })
+}
+
+/// An aktivitet whose transitions split the flow, with where its branches meet again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergePoint {
+    pub branch: String,
+    pub targets: Vec<String>, // Aktiviteter and end states the branches lead to
+    pub merge: Option<String>, // The first aktivitet every branch passes; None if they only meet at the end
+}
+
+/// Where the branches of every splitting aktivitet converge again: its immediate
+/// post-dominator, the first aktivitet every route from it to an end of the flow passes. Ends
+/// are END, ABORT and the other marker nodes, so a branch that aborts keeps a split from
+/// merging; creating a manuell behandling or starting another Behandling is no branch. Splits
+/// inside a loop a sak cannot leave are left out. In the order the aktiviteter are reached.
+pub fn merge_points(initial_aktivitet: &str, edges: &[Edge]) -> Vec<MergePoint> {
+    let (nodes, successors) = aktivitet_graph(initial_aktivitet, edges, false);
+
+    let index: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.as_str(), i))
+        .collect();
+
+    // Reversed graph with a virtual exit as node 0, which every end of the flow leads to
+    let mut targets: Vec<Vec<String>> = vec![Vec::new(); nodes.len()];
+    let mut reversed = vec![Vec::new(); nodes.len() + 1];
+    for edge in edges {
+        let Some(&from) = index.get(edge.from.as_str()) else {
+            continue;
+        };
+        if edge.to == "manuell" || edge.to.starts_with("spawn_") {
+            continue;
+        }
+        if !targets[from].contains(&edge.to) {
+            targets[from].push(edge.to.clone());
+        }
+        if !index.contains_key(edge.to.as_str()) && !reversed[0].contains(&(from + 1)) {
+            reversed[0].push(from + 1);
+        }
+    }
+    for (from, nexts) in successors.iter().enumerate() {
+        if nexts.is_empty() && !reversed[0].contains(&(from + 1)) {
+            reversed[0].push(from + 1);
+        }
+        for &to in nexts {
+            reversed[to + 1].push(from + 1);
+        }
+    }
+    let post_dominators = dominator_tree(&reversed);
+
+    nodes
+        .iter()
+        .enumerate()
+        .filter(|(node, _)| targets[*node].len() > 1)
+        .filter_map(|(node, branch)| {
+            let merge = match post_dominators[node + 1]? {
+                0 => None,
+                merge => Some(nodes[merge - 1].clone()),
+            };
+            Some(MergePoint {
+                branch: branch.clone(),
+                targets: targets[node].clone(),
+                merge,
+            })
+        })
+        .collect()
+}
+
+/// Which aktiviteter of a flow can follow which: `reachable[i][j]` is true if a sak in
+/// `aktiviteter[i]` can later reach `aktiviteter[j]` in one or more transitions. An aktivitet only
+/// reaches itself through a loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachabilityMatrix {
+    pub aktiviteter: Vec<String>, // In the order they are reached, the initial aktivitet first
+    pub reachable: Vec<Vec<bool>>,
+}
+
+impl ReachabilityMatrix {
+    /// True if `from` can ever be followed by `to`; false if either is not in the flow
+    pub fn can_reach(&self, from: &str, to: &str) -> bool {
+        let position = |name: &str| self.aktiviteter.iter().position(|a| a == name);
+        match (position(from), position(to)) {
+            (Some(from), Some(to)) => self.reachable[from][to],
+            _ => false,
+        }
+    }
+}
+
+/// The reachability matrix of the aktiviteter in a flow, from a search out of every aktivitet
+pub fn reachability_matrix(initial_aktivitet: &str, edges: &[Edge]) -> ReachabilityMatrix {
+    let (aktiviteter, successors) = aktivitet_graph(initial_aktivitet, edges, false);
+    let reachable = (0..aktiviteter.len())
+        .map(|start| {
+            let mut reached = vec![false; aktiviteter.len()];
+            let mut stack = successors[start].clone();
+            while let Some(node) = stack.pop() {
+                if !reached[node] {
+                    reached[node] = true;
+                    stack.extend(&successors[node]);
+                }
+            }
+            reached
+        })
+        .collect();
+    ReachabilityMatrix {
+        aktiviteter,
+        reachable,
+    }
+}
+
+/// The step number embedded in an aktivitet name: the first digits of its simple class name, like
+/// 12 in `FleksibelApSak12VurderAktivitet` or `Steg12VurderAktivitet`
+pub fn step_number(aktivitet: &str) -> Option<u64> {
+    let name = aktivitet.rsplit('.').next().unwrap_or(aktivitet);
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let digits: String = name[start..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+/// A numbered aktivitet that the flow runs before one with a lower number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepConflict {
+    pub earlier: String, // Runs first, but has the higher number
+    pub later: String,
+}
+
+/// Numbered aktiviteter that are followed by one with a lower number, with only unnumbered
+/// aktiviteter between them. Transitions back to an aktivitet every route to them passes, the
+/// back edges of a loop, are left out, so what remains runs in topological order. In the order
+/// the aktiviteter are reached.
+pub fn step_order_conflicts(initial_aktivitet: &str, edges: &[Edge]) -> Vec<StepConflict> {
+    let (nodes, successors) = aktivitet_graph(initial_aktivitet, edges, false);
+    let idoms = dominator_tree(&successors);
+    let dominates = |dominator: usize, mut node: usize| loop {
+        if node == dominator {
+            return true;
+        }
+        match idoms[node] {
+            Some(idom) => node = idom,
+            None => return false,
+        }
+    };
+    let numbers: Vec<Option<u64>> = nodes.iter().map(|node| step_number(node)).collect();
+
+    let mut conflicts = Vec::new();
+    for (start, number) in numbers.iter().enumerate() {
+        let Some(number) = number else {
+            continue;
+        };
+        let mut visited = vec![false; nodes.len()];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for &next in &successors[node] {
+                if visited[next] || dominates(next, node) {
+                    continue;
+                }
+                visited[next] = true;
+                match numbers[next] {
+                    Some(later) if later < *number => conflicts.push(StepConflict {
+                        earlier: nodes[start].clone(),
+                        later: nodes[next].clone(),
+                    }),
+                    Some(_) => {}
+                    None => stack.push(next),
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+/// Transitions between the same two aktiviteter that the graph merges into one edge, although
+/// they differ in what it would show
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeConflict {
+    pub from: String,
+    pub to: String,
+    pub conditions: Vec<String>, // Distinct, in the order the transitions are made; the first is drawn
+    pub mixes_collection: bool,  // Some of the transitions fan out and some do not
+}
+
+/// Pairs of nodes whose transitions have different conditions, or fan out in one place but not
+/// in another, which the single edge drawn for them hides. `else`, a missing condition and the
+/// annotations under a condition are not compared; error paths are drawn apart and left out.
+/// In the order the transitions are made.
+pub fn edge_conflicts(edges: &[Edge]) -> Vec<EdgeConflict> {
+    let mut groups: Vec<(EdgeConflict, [bool; 2])> = Vec::new();
+    let mut index: HashMap<(&str, &str), usize> = HashMap::new();
+    for edge in edges.iter().filter(|edge| !edge.is_error) {
+        let group = *index
+            .entry((edge.from.as_str(), edge.to.as_str()))
+            .or_insert_with(|| {
+                groups.push((
+                    EdgeConflict {
+                        from: edge.from.clone(),
+                        to: edge.to.clone(),
+                        conditions: Vec::new(),
+                        mixes_collection: false,
+                    },
+                    [false; 2],
+                ));
+                groups.len() - 1
+            });
+        let (conflict, kinds) = &mut groups[group];
+        kinds[usize::from(edge.is_collection)] = true;
+        let condition = label_condition(&edge.label);
+        if !condition.is_empty() && condition != "else" && !conflict.conditions.contains(&condition)
+        {
+            conflict.conditions.push(condition);
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(mut conflict, kinds)| {
+            conflict.mixes_collection = kinds == [true, true];
+            (conflict.conditions.len() > 1 || conflict.mixes_collection).then_some(conflict)
+        })
+        .collect()
+}
+
+/// The condition on the first line of an edge label, with its whitespace collapsed; empty if the
+/// label only has the annotations that go under a condition
+fn label_condition(label: &str) -> String {
+    let first = label.lines().next().unwrap_or_default();
+    if first.starts_with(['↻', '«', '⏳']) {
+        return String::new();
+    }
+    first.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The aktiviteter of a flow numbered in the order they are reached, the initial aktivitet as 0,
+/// and the transitions between them. With `exit`, a last node is added that every completion of
+/// the flow leads to.
+fn aktivitet_graph(
+    initial_aktivitet: &str,
+    edges: &[Edge],
+    exit: bool,
+) -> (Vec<String>, Vec<Vec<usize>>) {
+    let mut nodes = flow_aktiviteter(initial_aktivitet, edges);
+    let index: HashMap<String, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.clone(), i))
+        .collect();
+    let exit_index = nodes.len();
+    let mut successors = vec![Vec::new(); nodes.len() + usize::from(exit)];
+    for edge in edges {
+        let Some(&from) = index.get(&edge.from) else {
+            continue;
+        };
+        let to = match index.get(&edge.to) {
+            Some(&to) => to,
+            None if exit && edge.to == "end" => exit_index,
+            None => continue,
+        };
+        if !successors[from].contains(&to) {
+            successors[from].push(to);
+        }
+    }
+    if exit {
+        nodes.push("end".to_string());
+    }
+    (nodes, successors)
+}
+
+/// The longest route without repeating a loop from the initial aktivitet to one kind of
+/// terminal node: the most steps a sak can go through before the flow ends that way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongestPath {
+    pub terminal: String,         // `end`, `abort` or `unresolved` (END?)
+    pub aktiviteter: Vec<String>, // From the initial aktivitet to the last one before the terminal
+}
+
+/// For each terminal node the flow reaches, the longest path to it once the back edges of its
+/// loops are left out, so the flow is acyclic. Sorted END, ABORT, END?.
+pub fn longest_paths(initial_aktivitet: &str, edges: &[Edge]) -> Vec<LongestPath> {
+    let (nodes, successors) = aktivitet_graph(initial_aktivitet, edges, false);
+
+    // Depth-first from the initial aktivitet: a transition to an aktivitet still on the stack
+    // closes a loop, and the reverse postorder of the rest is a topological order
+    let mut postorder = Vec::new();
+    let mut back_edges = std::collections::HashSet::new();
+    let mut state = vec![0u8; nodes.len()]; // 0 unvisited, 1 on the stack, 2 done
+    let mut stack = vec![(0, 0)];
+    state[0] = 1;
+    while let Some((node, next)) = stack.pop() {
+        if let Some(&successor) = successors[node].get(next) {
+            stack.push((node, next + 1));
+            match state[successor] {
+                0 => {
+                    state[successor] = 1;
+                    stack.push((successor, 0));
+                }
+                1 => {
+                    back_edges.insert((node, successor));
+                }
+                _ => {}
+            }
+        } else {
+            state[node] = 2;
+            postorder.push(node);
+        }
+    }
+
+    // Longest distance from the initial aktivitet, with the aktivitet it is reached from
+    let mut distance = vec![0usize; nodes.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; nodes.len()];
+    for &node in postorder.iter().rev() {
+        for &successor in &successors[node] {
+            if !back_edges.contains(&(node, successor)) && distance[node] + 1 > distance[successor]
+            {
+                distance[successor] = distance[node] + 1;
+                previous[successor] = Some(node);
+            }
+        }
+    }
+
+    ["end", "abort", "unresolved"]
+        .into_iter()
+        .filter_map(|terminal| {
+            let last = (0..nodes.len())
+                .filter(|&node| {
+                    edges
+                        .iter()
+                        .any(|edge| edge.from == nodes[node] && edge.to == terminal)
+                })
+                .max_by_key(|&node| (distance[node], std::cmp::Reverse(node)))?;
+            let mut aktiviteter = vec![nodes[last].clone()];
+            let mut current = last;
+            while let Some(node) = previous[current] {
+                aktiviteter.push(nodes[node].clone());
+                current = node;
+            }
+            aktiviteter.reverse();
+            Some(LongestPath {
+                terminal: terminal.to_string(),
+                aktiviteter,
+            })
+        })
+        .collect()
+}
+
+/// Immediate dominators of the nodes reachable from node 0, with the iterative algorithm of
+/// Cooper, Harvey and Kennedy. Node 0 and unreachable nodes have none.
+fn dominator_tree(successors: &[Vec<usize>]) -> Vec<Option<usize>> {
+    // Reverse postorder of a depth-first search from node 0
+    let mut postorder = Vec::new();
+    let mut visited = vec![false; successors.len()];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some((node, next)) = stack.pop() {
+        if let Some(&successor) = successors[node].get(next) {
+            stack.push((node, next + 1));
+            if !visited[successor] {
+                visited[successor] = true;
+                stack.push((successor, 0));
+            }
+        } else {
+            postorder.push(node);
+        }
+    }
+    let mut order = vec![usize::MAX; successors.len()]; // Position in reverse postorder
+    for (position, &node) in postorder.iter().rev().enumerate() {
+        order[node] = position;
+    }
+    let mut predecessors = vec![Vec::new(); successors.len()];
+    for (node, nexts) in successors.iter().enumerate() {
+        for &next in nexts {
+            predecessors[next].push(node);
+        }
+    }
+
+    let mut idoms: Vec<Option<usize>> = vec![None; successors.len()];
+    idoms[0] = Some(0);
+    let intersect = |idoms: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while order[a] > order[b] {
+                a = idoms[a].unwrap_or(0);
+            }
+            while order[b] > order[a] {
+                b = idoms[b].unwrap_or(0);
+            }
+        }
+        a
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in postorder.iter().rev().skip(1) {
+            let mut processed = predecessors[node]
+                .iter()
+                .copied()
+                .filter(|&predecessor| idoms[predecessor].is_some());
+            let Some(first) = processed.next() else {
+                continue;
+            };
+            let idom = processed.fold(first, |idom, predecessor| {
+                intersect(&idoms, predecessor, idom)
+            });
+            if idoms[node] != Some(idom) {
+                idoms[node] = Some(idom);
+                changed = true;
+            }
+        }
+    }
+    idoms[0] = None;
+    idoms
+}
+
+/// True for the nodes of a graph that are not aktiviteter: END, ABORT, the END? of processors
+/// without transitions, the manuell behandling that error paths lead to, unknown (`?`) nodes, the
+/// Behandlinger started from the flow, the "continues…" nodes of cut graphs, the fans out to
+/// subclasses and the collections spread into `nesteAktiviteter`
+pub fn is_marker_node(node: &str) -> bool {
+    node == "end"
+        || node == "abort"
+        || node == "unresolved"
+        || node.starts_with("spawn_")
+        || node == "manuell"
+        || node.starts_with("unknown_")
+        || node.starts_with("dynamic_")
+        || node.starts_with("continues_")
+        || node.starts_with("subtypes_")
+}
+
+/// Aktivitet nodes in the flow, in the order they are first reached
+pub fn flow_aktiviteter(initial_aktivitet: &str, edges: &[Edge]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    let mut aktiviteter = Vec::new();
+
+    let candidates = std::iter::once(initial_aktivitet)
+        .chain(edges.iter().flat_map(|e| [e.from.as_str(), e.to.as_str()]));
+    for name in candidates {
+        if is_marker_node(name) {
+            continue;
+        }
+        if seen.insert(name.to_string()) {
+            aktiviteter.push(name.to_string());
+        }
+    }
+
+    aktiviteter
+}
+
+pub fn summarize_flow(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    edges: &[Edge],
+) -> FlowSummary {
+    let aktiviteter = flow_aktiviteter(initial_aktivitet, edges);
+
+    let transitions: std::collections::HashSet<(&str, &str)> = edges
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str()))
+        .collect();
+
+    let mut branch_points = 0;
+    let mut manual_steps = Vec::new();
+    let mut feature_toggles = Vec::new();
+
+    for aktivitet in &aktiviteter {
+        if let Some(processor) = processor_index.get(aktivitet) {
+            let targets: std::collections::HashSet<&str> = processor
+                .next_aktiviteter
+                .iter()
+                .map(|n| n.aktivitet_name.as_str())
+                .collect();
+            if targets.len() > 1 {
+                branch_points += 1;
+            }
+
+            if processor.has_manuell_behandling {
+                manual_steps.push(aktivitet.clone());
+            }
+
+            for next in &processor.next_aktiviteter {
+                for toggle in next.condition.iter().flat_map(Condition::feature_toggles) {
+                    if !feature_toggles.iter().any(|known| known == toggle) {
+                        feature_toggles.push(toggle.to_string());
+                    }
+                }
+            }
+        }
+    }
+    feature_toggles.sort();
+
+    let depths = aktivitet_depths(initial_aktivitet, edges);
+
+    FlowSummary {
+        aktivitet_count: aktiviteter.len(),
+        transition_count: transitions.len(),
+        branch_points,
+        complexity: flow_complexity(edges),
+        degrees: node_degrees(initial_aktivitet, edges),
+        cycles: group_cycles(edges).len(),
+        cycle_paths: find_cycles(initial_aktivitet, processor_index, MAX_CYCLE_PATHS),
+        max_depth: depths.values().copied().max().unwrap_or(0),
+        longest_paths: longest_paths(initial_aktivitet, edges),
+        manual_steps,
+        deprecated_steps: aktiviteter
+            .iter()
+            .filter(|aktivitet| is_deprecated_aktivitet(aktivitet, class_index))
+            .cloned()
+            .collect(),
+        feature_toggles,
+    }
+}
+
+/// How many distinct transitions lead into and out of an aktivitet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeDegree {
+    pub aktivitet: String,
+    pub fan_in: usize,  // Aktiviteter with a transition to it
+    pub fan_out: usize, // Aktiviteter and end states it has a transition to
+}
+
+/// Fan-in and fan-out of every aktivitet in the flow, in the order they are reached. Each pair of
+/// aktiviteter counts once, however many transitions there are between them.
+pub fn node_degrees(initial_aktivitet: &str, edges: &[Edge]) -> Vec<NodeDegree> {
+    let transitions: std::collections::HashSet<(&str, &str)> = edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .collect();
+    flow_aktiviteter(initial_aktivitet, edges)
+        .into_iter()
+        .map(|aktivitet| NodeDegree {
+            fan_in: transitions
+                .iter()
+                .filter(|(_, to)| *to == aktivitet)
+                .count(),
+            fan_out: transitions
+                .iter()
+                .filter(|(from, _)| *from == aktivitet)
+                .count(),
+            aktivitet,
+        })
+        .collect()
+}
+
+/// Heat map colors of execution counts, from pale yellow for the rarest aktivitet to deep red for
+/// the most frequent, and of durations, from green for the quickest through yellow to red
+const COUNT_HEAT_SCALE: &[[u8; 3]] = &[[0xFF, 0xF7, 0xBC], [0xD7, 0x30, 0x1F]];
+const DURATION_HEAT_SCALE: &[[u8; 3]] =
+    &[[0x63, 0xBE, 0x7B], [0xFF, 0xEB, 0x84], [0xF8, 0x69, 0x6B]];
+
+/// Fill color at `heat` (0 to 1) on a heat map scale, blending between its neighboring colors
+fn heat_color(heat: f64, scale: &[[u8; 3]]) -> String {
+    let position = heat.clamp(0.0, 1.0) * (scale.len() - 1) as f64;
+    let index = (position.floor() as usize).min(scale.len() - 2);
+    let (cold, hot, fraction) = (scale[index], scale[index + 1], position - index as f64);
+    let channel = |channel: usize| {
+        let (cold, hot) = (cold[channel] as f64, hot[channel] as f64);
+        (cold + (hot - cold) * fraction).round() as u8
+    };
+    format!("#{:02X}{:02X}{:02X}", channel(0), channel(1), channel(2))
+}
+
+/// The part of a flow a set of changed files touches
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeImpact {
+    pub changed: Vec<String>, // Aktiviteter whose class or processor is in a changed file
+    pub changed_transitions: Vec<(String, String)>, // Transitions made in a changed file
+    pub affected: Vec<String>, // Aktiviteter downstream of a change that did not change themselves
+}
+
+impl ChangeImpact {
+    /// True if the changed files define nothing in the flow
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.changed_transitions.is_empty()
+    }
+}
+
+/// What `changed_files` define in the flow, and which aktiviteter a sak can reach from there: an
+/// aktivitet changes with its class or processor, and a transition with the file of the
+/// `nesteAktivitet` call. Files are compared as given, so pass them as in `FlowModel::files`.
+pub fn change_impact(
+    initial_aktivitet: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    edges: &[Edge],
+    changed_files: &HashSet<PathBuf>,
+) -> ChangeImpact {
+    let aktiviteter = flow_aktiviteter(initial_aktivitet, edges);
+    let changed: Vec<String> = aktiviteter
+        .iter()
+        .filter(|aktivitet| {
+            let class_changed = class_index
+                .get(aktivitet.as_str())
+                .is_some_and(|info| changed_files.contains(&info.file));
+            let processor_changed = processor_index
+                .get(aktivitet.as_str())
+                .is_some_and(|processor| changed_files.contains(&processor.file));
+            class_changed || processor_changed
+        })
+        .cloned()
+        .collect();
+
+    let mut changed_transitions: Vec<(String, String)> = Vec::new();
+    for edge in edges {
+        let made_in_changed_file = processor_index.get(&edge.from).is_some_and(|processor| {
+            processor.next_aktiviteter.iter().any(|next| {
+                next.aktivitet_name == edge.to && changed_files.contains(&next.location.file)
+            })
+        });
+        let transition = (edge.from.clone(), edge.to.clone());
+        if made_in_changed_file && !changed_transitions.contains(&transition) {
+            changed_transitions.push(transition);
+        }
+    }
+
+    // Everything a sak can reach after a changed aktivitet or transition
+    let mut affected: Vec<String> = Vec::new();
+    let mut queue: Vec<&str> = changed
+        .iter()
+        .map(String::as_str)
+        .chain(changed_transitions.iter().map(|(_, to)| to.as_str()))
+        .collect();
+    let mut seen: HashSet<&str> = queue.iter().copied().collect();
+    while let Some(aktivitet) = queue.pop() {
+        for edge in edges.iter().filter(|edge| edge.from == aktivitet) {
+            if seen.insert(&edge.to) {
+                queue.push(&edge.to);
+            }
+        }
+    }
+    for aktivitet in &aktiviteter {
+        if seen.contains(aktivitet.as_str()) && !changed.contains(aktivitet) {
+            affected.push(aktivitet.clone());
+        }
+    }
+
+    ChangeImpact {
+        changed,
+        changed_transitions,
+        affected,
+    }
+}
+
+/// Cyclomatic complexity of a flow graph: transitions − nodes + 2 · connected components, counting
+/// END, ABORT and the other marker nodes. A straight flow scores 1 and every further way through
+/// it adds one.
+pub fn flow_complexity(edges: &[Edge]) -> usize {
+    let transitions: std::collections::HashSet<(&str, &str)> = edges
+        .iter()
+        .map(|edge| (edge.from.as_str(), edge.to.as_str()))
+        .collect();
+
+    // Connected components, ignoring the direction of the transitions
+    let mut neighbors: HashMap<&str, Vec<&str>> = HashMap::new();
+    for &(from, to) in &transitions {
+        neighbors.entry(from).or_default().push(to);
+        neighbors.entry(to).or_default().push(from);
+    }
+    let mut seen = std::collections::HashSet::new();
+    let mut components = 0;
+    for &node in neighbors.keys() {
+        if !seen.insert(node) {
+            continue;
+        }
+        components += 1;
+        let mut queue = vec![node];
+        while let Some(current) = queue.pop() {
+            for &next in &neighbors[current] {
+                if seen.insert(next) {
+                    queue.push(next);
+                }
+            }
+        }
+    }
+
+    (transitions.len() + 2 * components).saturating_sub(neighbors.len())
+}
+
+/// Fewest transitions from the initial aktivitet to each aktivitet in the flow. Breadth-first,
+/// so cycles do not inflate the depth.
+fn aktivitet_depths<'e>(initial_aktivitet: &'e str, edges: &'e [Edge]) -> HashMap<&'e str, usize> {
+    let mut depths = HashMap::from([(initial_aktivitet, 0)]);
+    let mut queue = std::collections::VecDeque::from([initial_aktivitet]);
+    while let Some(current) = queue.pop_front() {
+        let depth = depths[current];
+        for edge in edges.iter().filter(|e| e.from == current) {
+            if is_marker_node(&edge.to) {
+                continue;
+            }
+            if !depths.contains_key(edge.to.as_str()) {
+                depths.insert(edge.to.as_str(), depth + 1);
+                queue.push_back(edge.to.as_str());
+            }
+        }
+    }
+    depths
+}
+
+/// Drop everything more than `max_depth` transitions from the start. Each aktivitet where the
+/// flow was cut gets a "continues…" node instead of its deeper successors.
+fn truncate_to_depth(
+    initial_aktivitet: &str,
+    max_depth: usize,
+    node_definitions: &mut Vec<(String, String)>,
+    edges: &mut Vec<Edge>,
+) {
+    let shown: std::collections::HashSet<String> = aktivitet_depths(initial_aktivitet, edges)
+        .into_iter()
+        .filter(|(_, depth)| *depth <= max_depth)
+        .map(|(aktivitet, _)| aktivitet.to_string())
+        .collect();
+
+    let mut cut_points = std::collections::BTreeSet::new();
+    edges.retain(|edge| {
+        if !shown.contains(&edge.from) {
+            return false;
+        }
+        // END and unknown markers belong to the aktivitet before them
+        if shown.contains(&edge.to) || is_marker_node(&edge.to) {
+            return true;
+        }
+        cut_points.insert(edge.from.clone());
+        false
+    });
+    node_definitions
+        .retain(|(id, _)| shown.contains(id) || edges.iter().any(|edge| &edge.to == id));
+
+    for from in cut_points {
+        let id = format!("continues_{}", from);
+        node_definitions.push((
+            id.clone(),
+            format!(
+                "\"{}\" [label=\"continues…\", shape=plaintext, fontcolor=\"#666666\"]",
+                escape_label(&id)
+            ),
+        ));
+        edges.push(Edge {
+            from,
+            to: id,
+            label: String::new(),
+            is_collection: false,
+            is_error: false,
+        });
+    }
+}
+
+/// Replace every run of two or more aktiviteter that each have exactly one predecessor and one
+/// successor with a single node showing the first and last step. The initial and focused
+/// aktiviteter are kept as they are. Returns the chain node id for each collapsed aktivitet.
+fn collapse_chains(
+    initial_aktivitet: &str,
+    focus: Option<&str>,
+    node_definitions: &mut Vec<(String, String)>,
+    edges: &mut Vec<Edge>,
+) -> HashMap<String, String> {
+    let transitions: std::collections::BTreeSet<(&str, &str)> = edges
+        .iter()
+        .map(|e| (e.from.as_str(), e.to.as_str()))
+        .collect();
+    let mut predecessors: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (from, to) in &transitions {
+        successors.entry(from).or_default().push(to);
+        predecessors.entry(to).or_default().push(from);
+    }
+
+    let chainable = |node: &str| {
+        !is_marker_node(node)
+            && node != initial_aktivitet
+            && Some(node) != focus
+            && predecessors.get(node).is_some_and(|p| p.len() == 1)
+            && successors.get(node).is_some_and(|s| s.len() == 1)
+    };
+
+    let mut collapsed = HashMap::new();
+    let mut chains = Vec::new();
+    for (id, _) in node_definitions.iter() {
+        // Start walking at the first aktivitet of each run
+        if !chainable(id) || chainable(predecessors[id.as_str()][0]) {
+            continue;
+        }
+        let mut chain = vec![id.clone()];
+        let mut current = id.as_str();
+        while let Some(&next) = successors.get(current).and_then(|s| s.first()) {
+            if !chainable(next) || chain.iter().any(|c| c == next) {
+                break;
+            }
+            chain.push(next.to_string());
+            current = next;
+        }
+        if chain.len() > 1 {
+            chains.push(chain);
+        }
+    }
+
+    for chain in chains {
+        let chain_id = format!("chain_{}", chain[0]);
+        let label = format!(
+            "{} … {}\n({} steps)",
+            shorten_aktivitet_name(&chain[0]),
+            shorten_aktivitet_name(&chain[chain.len() - 1]),
+            chain.len()
+        );
+        node_definitions.push((
+            chain_id.clone(),
+            format!(
+                "\"{}\" [label=\"{}\", style=\"filled,dashed\", fillcolor=\"#87CEEB\"]",
+                escape_label(&chain_id),
+                escape_label(&label)
+            ),
+        ));
+        for aktivitet in chain {
+            collapsed.insert(aktivitet, chain_id.clone());
+        }
+    }
+
+    node_definitions.retain(|(id, _)| !collapsed.contains_key(id));
+    edges.retain(|edge| {
+        !matches!(
+            (collapsed.get(&edge.from), collapsed.get(&edge.to)),
+            (Some(from), Some(to)) if from == to
+        )
+    });
+    for edge in edges.iter_mut() {
+        if let Some(chain_id) = collapsed.get(&edge.from) {
+            edge.from = chain_id.clone();
+        }
+        if let Some(chain_id) = collapsed.get(&edge.to) {
+            edge.to = chain_id.clone();
+        }
+    }
+
+    collapsed
+}
+
+/// True if the aktivitet class is marked `@Deprecated`
+pub fn is_deprecated_aktivitet(
+    aktivitet_name: &str,
+    class_index: &HashMap<String, ClassInfo>,
+) -> bool {
+    class_index
+        .get(aktivitet_name)
+        .is_some_and(ClassInfo::is_deprecated)
+}
+
+/// The name struck through, for deprecated aktiviteter: `V̶u̶r̶d̶e̶r̶`
+pub fn strike_through(name: &str) -> String {
+    name.chars()
+        .flat_map(|c| {
+            if c.is_whitespace() {
+                vec![c]
+            } else {
+                vec![c, '\u{0336}']
+            }
+        })
+        .collect()
+}
+
+fn is_alde_aktivitet(aktivitet_name: &str, class_index: &HashMap<String, ClassInfo>) -> bool {
+    // Check if this class extends AldeAktivitet
+    if let Some(class_info) = class_index.get(aktivitet_name) {
+        class_info
+            .supertypes
+            .iter()
+            .any(|supertype| supertype.contains("AldeAktivitet"))
+    } else {
+        false
+    }
+}
+
+pub fn shorten_aktivitet_name(name: &str) -> String {
+    // Classes that share their name with one in another package are qualified; the last
+    // package part is enough to tell them apart
+    let parts: Vec<&str> = name.rsplitn(3, '.').collect();
+    let name = match parts.as_slice() {
+        [_, _, packages] => &name[packages.len() + 1..],
+        _ => name,
+    };
+
+    // Remove common prefixes
+    let shortened = name.replace("FleksibelApSak", "").replace("Aktivitet", "");
+
+    // Extract the step number and description
+    if let Some(pos) = shortened.find(char::is_alphabetic) {
+        if pos > 0 {
+            let (num, rest) = shortened.split_at(pos);
+            // Add space between number and text for readability
+            return format!("{}\n{}", num, rest);
+        }
+    }
+
+    shortened
+}
+
+pub fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
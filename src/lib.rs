@@ -0,0 +1,475 @@
+//! Analysis of Kotlin Behandling flows: finds Behandling classes and aktivitet processors
+//! with Tree-sitter and builds flow graphs from them.
+//!
+//! ```no_run
+//! use behandling_flow::{analyze_project, DotOptions};
+//!
+//! let model = analyze_project("path/to/project")?;
+//! for (name, _) in model.behandlinger() {
+//!     let dot = model.to_dot(name, &DotOptions::default())?;
+//!     println!("{}", dot);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use extractor::{ParsedFile, ProjectIndexes};
+use tree_sitter::Parser;
+
+pub mod cache;
+pub mod config;
+pub mod diff;
+pub mod export;
+pub mod extractor;
+pub mod graph;
+pub mod lint;
+pub mod metrics;
+pub mod model;
+pub mod render;
+pub mod scanner;
+pub mod search;
+
+pub use cache::CacheUse;
+pub use config::Config;
+pub use graph::DotOptions;
+pub use metrics::{AktivitetDurations, ExecutionCounts};
+pub use model::{
+    ClassInfo, Condition, Edge, FlowModel, GradleModule, NextAktivitet, ParseError, ProcessorInfo,
+    Terminal, TerminalKind,
+};
+pub use scanner::FileFilter;
+
+/// Path that makes [`analyze_project`] read a single Kotlin source from standard input
+pub const STDIN_PATH: &str = "-";
+
+/// Scan all `.kt` files below `path` and build the class and processor indexes.
+/// `path` may also be a single `.kt` or `.kts` file, or [`STDIN_PATH`] to read the source from stdin;
+/// see [`analyze_source`] for how such a file is turned into flows.
+/// Common build directories are skipped; use [`analyze_project_with`] to change that.
+/// A [`config::CONFIG_FILE_NAME`] in the project root is read for the project's settings.
+pub fn analyze_project(path: impl AsRef<Path>) -> Result<FlowModel> {
+    analyze_project_with(path, &FileFilter::default())
+}
+
+/// Like [`analyze_project`], scanning only the files the filter lets through
+pub fn analyze_project_with(path: impl AsRef<Path>, filter: &FileFilter) -> Result<FlowModel> {
+    analyze_project_with_progress(path, filter, &mut |_, _, _| {})
+}
+
+/// Phase of a directory scan, as reported to [`analyze_project_with_progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScanPhase {
+    CollectingFiles,
+    CheckingCache,
+    ParsingFiles,
+    IndexingClasses,
+    IndexingTypeAliases,
+    IndexingRegistrations,
+    IndexingConstants,
+    IndexingFunctions,
+    IndexingProcessors,
+}
+
+/// Like [`analyze_project_with`], calling `progress` with the current phase, the work done and
+/// the total work as the scan goes on. The total is 0 while collecting files, since it is not
+/// known until the walk is done. Single files and stdin are analyzed without progress calls.
+pub fn analyze_project_with_progress(
+    path: impl AsRef<Path>,
+    filter: &FileFilter,
+    progress: &mut dyn FnMut(ScanPhase, usize, usize),
+) -> Result<FlowModel> {
+    let root_path = path.as_ref();
+    if root_path == Path::new(STDIN_PATH) {
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .context("Failed to read standard input")?;
+        return analyze_source(Path::new("<stdin>"), &source);
+    }
+    if !root_path.exists() {
+        anyhow::bail!("Path does not exist: {}", root_path.display());
+    }
+    if root_path.is_file() {
+        let source = fs::read_to_string(root_path)
+            .with_context(|| format!("Failed to read file: {}", root_path.display()))?;
+        return analyze_source(root_path, &source);
+    }
+
+    let files = collect_project_files(root_path, filter, progress)?;
+    analyze_files(root_path, files, filter, progress)
+}
+
+/// Like [`analyze_project_with_progress`], reusing the model cached in the project's
+/// [`cache::CACHE_DIR_NAME`] by a previous scan: only files whose content changed since then are
+/// parsed again. The cache is written afterwards. Single files and stdin are never cached.
+pub fn analyze_project_cached(
+    path: impl AsRef<Path>,
+    filter: &FileFilter,
+    progress: &mut dyn FnMut(ScanPhase, usize, usize),
+) -> Result<(FlowModel, CacheUse)> {
+    let root_path = path.as_ref();
+    if !root_path.is_dir() {
+        let model = analyze_project_with_progress(root_path, filter, progress)?;
+        return Ok((model, CacheUse::Rebuilt));
+    }
+
+    let files = collect_project_files(root_path, filter, progress)?;
+    let hashes = cache::hash_files(&files, &mut |done, total| {
+        progress(ScanPhase::CheckingCache, done, total)
+    })?;
+    let config = Config::load(root_path)?;
+    let (model, cache_use) = match cache::load(root_path) {
+        Some(cached) if cached.is_usable(root_path, &config, &hashes) => {
+            let changed = cached.changed_files(&hashes);
+            let mut model = cached.into_model();
+            if !changed.is_empty() {
+                reanalyze_files(&mut model, &changed)?;
+            }
+            model.modules = scanner::find_gradle_modules(root_path, filter)?;
+            let reparsed = changed.len();
+            (model, CacheUse::Reused { reparsed })
+        }
+        _ => (
+            analyze_files(root_path, files, filter, progress)?,
+            CacheUse::Rebuilt,
+        ),
+    };
+    cache::save(root_path, &hashes, &model)?;
+    Ok((model, cache_use))
+}
+
+/// The Kotlin files below the directory `root_path`; fails if there are none
+fn collect_project_files(
+    root_path: &Path,
+    filter: &FileFilter,
+    progress: &mut dyn FnMut(ScanPhase, usize, usize),
+) -> Result<Vec<PathBuf>> {
+    let files = scanner::collect_kotlin_files(root_path, filter, &mut |found| {
+        progress(ScanPhase::CollectingFiles, found, 0)
+    })?;
+    if files.is_empty() {
+        anyhow::bail!(
+            "No Kotlin files found in directory: {}",
+            root_path.display()
+        );
+    }
+    Ok(files)
+}
+
+/// Build the model of the project in `root_path` from all its Kotlin `files`
+fn analyze_files(
+    root_path: &Path,
+    files: Vec<PathBuf>,
+    filter: &FileFilter,
+    progress: &mut dyn FnMut(ScanPhase, usize, usize),
+) -> Result<FlowModel> {
+    let config = Config::load(root_path)?;
+    let mut parser = scanner::kotlin_parser()?;
+    let modules = scanner::find_gradle_modules(root_path, filter)?;
+
+    // Every file is parsed once; all indexes are built from the same trees
+    let mut parse_errors = Vec::new();
+    let parsed = extractor::parse_files(
+        &mut parser,
+        &files,
+        &mut parse_errors,
+        &mut |done, total| progress(ScanPhase::ParsingFiles, done, total),
+    )?;
+    Ok(index_files(
+        files,
+        &parsed,
+        config,
+        modules,
+        parse_errors,
+        progress,
+    ))
+}
+
+/// Build the model from every file of the project, already parsed
+fn index_files(
+    files: Vec<PathBuf>,
+    parsed: &[ParsedFile],
+    config: Config,
+    modules: Vec<GradleModule>,
+    parse_errors: Vec<ParseError>,
+    progress: &mut dyn FnMut(ScanPhase, usize, usize),
+) -> FlowModel {
+    let class_index =
+        extractor::build_class_index(parsed, &config.conventions, &mut |done, total| {
+            progress(ScanPhase::IndexingClasses, done, total)
+        });
+    let project = ProjectIndexes {
+        functions: &HashMap::new(),
+        constants: &HashMap::new(),
+        classes: &class_index,
+        registrations: &HashMap::new(),
+        config: &config,
+    };
+    let type_aliases =
+        extractor::build_type_alias_index(parsed, &class_index, &mut |done, total| {
+            progress(ScanPhase::IndexingTypeAliases, done, total)
+        });
+    let registrations = extractor::build_registration_index(parsed, project, &mut |done, total| {
+        progress(ScanPhase::IndexingRegistrations, done, total)
+    });
+    let project = ProjectIndexes {
+        registrations: &registrations,
+        ..project
+    };
+    let constant_index = extractor::build_constant_index(parsed, project, &mut |done, total| {
+        progress(ScanPhase::IndexingConstants, done, total)
+    });
+    let project = ProjectIndexes {
+        constants: &constant_index,
+        ..project
+    };
+    let function_index = extractor::build_function_index(parsed, project, &mut |done, total| {
+        progress(ScanPhase::IndexingFunctions, done, total)
+    });
+    let processor_index = extractor::build_processor_index(
+        parsed,
+        ProjectIndexes {
+            functions: &function_index,
+            ..project
+        },
+        &mut |done, total| progress(ScanPhase::IndexingProcessors, done, total),
+    );
+
+    let mut model = FlowModel {
+        files,
+        class_index,
+        processor_index,
+        function_index,
+        constant_index,
+        type_aliases,
+        registrations,
+        config,
+        modules,
+        parse_errors,
+    };
+    model.resolve_type_aliases();
+    model
+}
+
+/// Analyze a single Kotlin source in isolation; `file` is only used to label where it came from.
+/// If it declares no Behandling, every processor in it becomes the start of its own flow, named
+/// after the processor class. Aktiviteter handled outside the source end in unknown nodes.
+pub fn analyze_source(file: &Path, source: &str) -> Result<FlowModel> {
+    let mut parser = scanner::kotlin_parser()?;
+    let mut model = FlowModel {
+        files: vec![file.to_path_buf()],
+        ..FlowModel::default()
+    };
+    extractor::extract_source(&mut parser, file, source, &mut model)?;
+
+    if model.behandlinger().is_empty() {
+        let processor_flows: Vec<ClassInfo> = model
+            .processor_index
+            .iter()
+            .map(|(aktivitet, processor)| {
+                // Point at the processor class, so its flow leads back to it
+                let (line, column) = model
+                    .class_index
+                    .get(&processor.processor_class)
+                    .map_or((1, 1), |class| (class.line, class.column));
+                ClassInfo {
+                    name: processor.processor_class.clone(),
+                    package: String::new(),
+                    file: processor.file.clone(),
+                    line,
+                    column,
+                    supertypes: vec!["Behandling".to_string()],
+                    initial_aktivitet: Some(aktivitet.clone()),
+                    is_abstract: false,
+                    type_parameters: Vec::new(),
+                    supertype_arguments: HashMap::new(),
+                    annotations: Vec::new(),
+                }
+            })
+            .collect();
+        for info in processor_flows {
+            model.class_index.insert(info.name.clone(), info);
+        }
+    }
+
+    Ok(model)
+}
+
+/// Re-extract only the given changed, added or deleted files and update the model in place.
+/// Paths must have the same form as the ones in `model.files`.
+pub fn reanalyze_files(model: &mut FlowModel, changed: &[PathBuf]) -> Result<()> {
+    let changed_set: HashSet<&PathBuf> = changed.iter().collect();
+    let classes_before = model.class_index.clone();
+    extractor::remove_classes(&mut model.class_index, &changed_set);
+    let constants_before = model.constant_index.clone();
+    model
+        .constant_index
+        .retain(|_, constant| !changed_set.contains(&constant.file));
+    let functions_before = model.function_index.values().map(Vec::len).sum::<usize>();
+    for functions in model.function_index.values_mut() {
+        functions.retain(|info| !changed_set.contains(&info.file));
+    }
+    model
+        .function_index
+        .retain(|_, functions| !functions.is_empty());
+    let mut functions_changed =
+        functions_before != model.function_index.values().map(Vec::len).sum::<usize>();
+    model.files.retain(|file| !changed_set.contains(file));
+
+    let existing: Vec<PathBuf> = changed.iter().filter(|f| f.is_file()).cloned().collect();
+    let mut parser = scanner::kotlin_parser()?;
+    model
+        .parse_errors
+        .retain(|error| !changed_set.contains(&error.location.file));
+    let mut parsed = extractor::parse_files(
+        &mut parser,
+        &existing,
+        &mut model.parse_errors,
+        &mut |_, _| {},
+    )?;
+    let classes = extractor::build_class_index(&parsed, &model.config.conventions, &mut |_, _| {});
+    extractor::add_classes(&mut model.class_index, classes.into_values().collect());
+    // Everything else is resolved against the classes: a class that was added, removed, changed
+    // or keyed differently may change what any file refers to, so the whole project is indexed
+    // again
+    if !same_classes(&classes_before, &model.class_index) {
+        model.files.extend(existing);
+        let parsed = parse_all(&mut parser, &model.files, parsed)?;
+        *model = index_files(
+            std::mem::take(&mut model.files),
+            &parsed,
+            model.config.clone(),
+            std::mem::take(&mut model.modules),
+            std::mem::take(&mut model.parse_errors),
+            &mut |_, _, _| {},
+        );
+        return Ok(());
+    }
+    model
+        .type_aliases
+        .retain(|_, alias| !changed_set.contains(&alias.file));
+    model.type_aliases.extend(extractor::build_type_alias_index(
+        &parsed,
+        &model.class_index,
+        &mut |_, _| {},
+    ));
+    let registrations_before = model.registrations.clone();
+    model
+        .registrations
+        .retain(|_, registration| !changed_set.contains(&registration.location.file));
+    let project = ProjectIndexes {
+        functions: &HashMap::new(),
+        constants: &HashMap::new(),
+        classes: &model.class_index,
+        registrations: &HashMap::new(),
+        config: &model.config,
+    };
+    let registrations = extractor::build_registration_index(&parsed, project, &mut |_, _| {});
+    for (processor, registration) in registrations {
+        model.registrations.entry(processor).or_insert(registration);
+    }
+    // Processors anywhere may be bound by a registration that changed
+    let registrations_changed = model.registrations != registrations_before;
+    let project = ProjectIndexes {
+        registrations: &model.registrations,
+        ..project
+    };
+    for (name, constant) in extractor::build_constant_index(&parsed, project, &mut |_, _| {}) {
+        model.constant_index.entry(name).or_insert(constant);
+    }
+    model.files.extend(existing);
+
+    // Transitions anywhere may name a constant that changed; then everything is re-extracted
+    if model.constant_index != constants_before {
+        functions_changed = true;
+        model.function_index.clear();
+        parsed = parse_all(&mut parser, &model.files, parsed)?;
+    }
+    let project = ProjectIndexes {
+        functions: &HashMap::new(),
+        constants: &model.constant_index,
+        classes: &model.class_index,
+        registrations: &model.registrations,
+        config: &model.config,
+    };
+    for (name, functions) in extractor::build_function_index(&parsed, project, &mut |_, _| {}) {
+        functions_changed = true;
+        model
+            .function_index
+            .entry(name)
+            .or_default()
+            .extend(functions);
+    }
+
+    // Processors in unchanged files may delegate to the functions that changed
+    if functions_changed || registrations_changed {
+        parsed = parse_all(&mut parser, &model.files, parsed)?;
+    }
+    let processor_set: HashSet<&PathBuf> = parsed.iter().map(|file| &file.path).collect();
+    model
+        .processor_index
+        .retain(|_, info| !changed_set.contains(&info.file) && !processor_set.contains(&info.file));
+    let processor_index = extractor::build_processor_index(
+        &parsed,
+        ProjectIndexes {
+            functions: &model.function_index,
+            constants: &model.constant_index,
+            classes: &model.class_index,
+            registrations: &model.registrations,
+            config: &model.config,
+        },
+        &mut |_, _| {},
+    );
+    model.processor_index.extend(processor_index);
+
+    model.resolve_type_aliases();
+
+    Ok(())
+}
+
+/// True if both indexes have the same keys for the same classes, wherever they are declared
+fn same_classes(before: &HashMap<String, ClassInfo>, after: &HashMap<String, ClassInfo>) -> bool {
+    before.len() == after.len()
+        && before.iter().all(|(key, class)| {
+            after.get(key).is_some_and(|other| {
+                class.name == other.name
+                    && class.package == other.package
+                    && class.supertypes == other.supertypes
+                    && class.initial_aktivitet == other.initial_aktivitet
+                    && class.is_abstract == other.is_abstract
+                    && class.type_parameters == other.type_parameters
+                    && class.supertype_arguments == other.supertype_arguments
+                    && class.annotations == other.annotations
+            })
+        })
+}
+
+/// The parsed trees of all `files` in order, reusing the ones in `parsed` and parsing the rest
+fn parse_all(
+    parser: &mut Parser,
+    files: &[PathBuf],
+    parsed: Vec<ParsedFile>,
+) -> Result<Vec<ParsedFile>> {
+    let mut by_path: HashMap<PathBuf, ParsedFile> = parsed
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+    let missing: Vec<PathBuf> = files
+        .iter()
+        .filter(|file| !by_path.contains_key(*file))
+        .cloned()
+        .collect();
+    // The parse errors of unchanged files are already in the model
+    for file in extractor::parse_files(parser, &missing, &mut Vec::new(), &mut |_, _| {})? {
+        by_path.insert(file.path.clone(), file);
+    }
+    Ok(files
+        .iter()
+        .filter_map(|file| by_path.remove(file))
+        .collect())
+}
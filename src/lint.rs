@@ -0,0 +1,821 @@
+//! Integrity checks on the extracted flow model
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::config::FlowRule;
+use crate::graph::{
+    collect_flow_edges, edge_conflicts, flow_aktiviteter, flow_complexity, is_marker_node,
+    reachability_matrix, step_number, step_order_conflicts, EdgeConflict,
+};
+use crate::model::{ClassInfo, Edge, FlowModel, NextAktivitet, ProcessorInfo, SourceLocation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// An aktivitet in a flow has no processor
+    MissingProcessor,
+    /// A processor handles an aktivitet class that does not exist
+    OrphanProcessor,
+    /// An aktivitet is not reachable from any Behandling
+    UnreachableAktivitet,
+    /// A flow never reaches an end state
+    NoTerminalState,
+    /// A processor in a flow neither makes a transition nor completes the flow
+    UnresolvedProcessor,
+    /// A deprecated aktivitet is still reachable from a Behandling
+    DeprecatedAktivitet,
+    /// A flow is more complex than the configured maximum
+    ComplexFlow,
+    /// Two branches of a processor have the same condition but lead to different aktiviteter
+    DuplicateCondition,
+    /// A condition and its negation lead to the same aktivitet, so the branch makes no difference
+    RedundantCondition,
+    /// Every branch of a processor leads to the same aktivitet, so its conditions make no difference
+    ConvergingBranches,
+    /// Transitions between the same two aktiviteter differ in their conditions or in fanning
+    /// out, which the one edge drawn for them hides
+    ConflictingEdges,
+    /// A flow breaks one of the rules configured under `[[lint.rules]]`
+    FlowRule,
+    /// The step number in an aktivitet's name is lower than the one of an aktivitet before it
+    StepOrder,
+}
+
+impl Rule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::MissingProcessor => "missing-processor",
+            Rule::OrphanProcessor => "orphan-processor",
+            Rule::UnreachableAktivitet => "unreachable-aktivitet",
+            Rule::NoTerminalState => "no-terminal-state",
+            Rule::UnresolvedProcessor => "unresolved-processor",
+            Rule::DeprecatedAktivitet => "deprecated-aktivitet",
+            Rule::ComplexFlow => "complex-flow",
+            Rule::DuplicateCondition => "duplicate-condition",
+            Rule::RedundantCondition => "redundant-condition",
+            Rule::ConvergingBranches => "converging-branches",
+            Rule::ConflictingEdges => "conflicting-edges",
+            Rule::FlowRule => "flow-rule",
+            Rule::StepOrder => "step-order",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub rule: Rule,
+    pub subject: String, // The aktivitet, processor or Behandling the violation is about
+    pub message: String,
+    pub location: Option<SourceLocation>, // Where the subject is declared, if it is known
+}
+
+/// An aktivitet that has no processor, with the processors that may have been meant for it
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingProcessor {
+    pub aktivitet: String,
+    pub location: Option<SourceLocation>, // Where the aktivitet class is declared, if it is known
+    pub referenced_by: Vec<String>,       // Aktiviteter whose processors make a transition to it
+    pub suggestions: Vec<String>,
+}
+
+/// A processor for an aktivitet class that is not declared, with the aktiviteter it may have been
+/// meant for. It never runs, and the flow graphs leave it out.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanProcessor {
+    pub processor: String,
+    pub aktivitet: String, // The aktivitet type argument, as written
+    pub location: Option<SourceLocation>, // Where the processor class is declared, if it is known
+    pub suggestions: Vec<String>,
+}
+
+/// An aktivitet that no Behandling's flow reaches, with the processor that handles it
+#[derive(Debug, Clone, Serialize)]
+pub struct UnreachableAktivitet {
+    pub aktivitet: String,
+    pub location: Option<SourceLocation>, // Where the aktivitet class is declared, if it is known
+    pub processor: Option<String>,
+    pub processor_location: Option<SourceLocation>,
+}
+
+/// Sibling transitions of a processor whose conditions contradict each other: the same condition
+/// leading to different aktiviteter, or a condition and its negation leading to the same one.
+/// Transitions started by the same call, like `nesteAktiviteter(listOf(A(), B()))`, and the ones
+/// made in a catch block are not compared.
+fn condition_violations(
+    processor: &ProcessorInfo,
+    compared: &mut HashSet<(SourceLocation, SourceLocation)>,
+) -> Vec<Violation> {
+    let branches: Vec<&NextAktivitet> = processor
+        .next_aktiviteter
+        .iter()
+        .filter(|next| next.condition.is_some() && next.error_type.is_none() && !next.is_dynamic)
+        .collect();
+
+    let mut violations = Vec::new();
+    for (index, first) in branches.iter().enumerate() {
+        for second in &branches[index + 1..] {
+            if first.location == second.location
+                || !compared.insert((first.location.clone(), second.location.clone()))
+            {
+                continue;
+            }
+            let (Some(condition), Some(other)) = (&first.condition, &second.condition) else {
+                continue;
+            };
+            if condition == other && first.aktivitet_name != second.aktivitet_name {
+                violations.push(Violation {
+                    rule: Rule::DuplicateCondition,
+                    subject: processor.processor_class.clone(),
+                    message: format!(
+                        "{}: `{}` leads to both {} ({}) and {} ({})",
+                        processor.processor_class,
+                        condition,
+                        first.aktivitet_name,
+                        first.location,
+                        second.aktivitet_name,
+                        second.location
+                    ),
+                    location: Some(second.location.clone()),
+                });
+            } else if first.aktivitet_name == second.aktivitet_name
+                && condition.clone().negate() == *other
+            {
+                violations.push(Violation {
+                    rule: Rule::RedundantCondition,
+                    subject: processor.processor_class.clone(),
+                    message: format!(
+                        "{}: goes to {} both when `{}` ({}) and when `{}` ({}), so the condition makes no difference",
+                        processor.processor_class,
+                        first.aktivitet_name,
+                        condition,
+                        first.location,
+                        other,
+                        second.location
+                    ),
+                    location: Some(second.location.clone()),
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// A processor whose conditional transitions all start the same aktivitet, without ending the
+/// flow in another branch. A plain condition and its negation are left to `redundant-condition`.
+/// Shared functions make the same transitions in every processor that calls them; `reported`
+/// keeps them from being reported more than once.
+fn converging_branches(
+    model: &FlowModel,
+    processor: &ProcessorInfo,
+    reported: &mut HashSet<Vec<SourceLocation>>,
+) -> Option<Violation> {
+    let branches: Vec<&NextAktivitet> = processor
+        .next_aktiviteter
+        .iter()
+        .filter(|next| next.error_type.is_none() && !next.is_dynamic)
+        .collect();
+    let (first, rest) = branches.split_first()?;
+    let converges = !rest.is_empty()
+        && processor.terminals.is_empty()
+        && branches.iter().any(|next| next.condition.is_some())
+        && rest.iter().all(|next| {
+            next.aktivitet_name == first.aktivitet_name && next.location != first.location
+        });
+    if !converges {
+        return None;
+    }
+    if let [second] = rest {
+        if let (Some(condition), Some(other)) = (&first.condition, &second.condition) {
+            if condition.clone().negate() == *other {
+                return None;
+            }
+        }
+    }
+    let locations: Vec<SourceLocation> =
+        branches.iter().map(|next| next.location.clone()).collect();
+    if !reported.insert(locations.clone()) {
+        return None;
+    }
+
+    let places: Vec<String> = locations.iter().map(ToString::to_string).collect();
+    Some(Violation {
+        rule: Rule::ConvergingBranches,
+        subject: processor.processor_class.clone(),
+        message: format!(
+            "{}: all {} branches lead to {} ({}), so the conditions make no difference",
+            processor.processor_class,
+            branches.len(),
+            first.aktivitet_name,
+            places.join(", ")
+        ),
+        location: model.processor_location(processor),
+    })
+}
+
+/// Violations of a configured rule by the flow of `behandling`, each with a path that breaks it.
+/// A path ends where the flow has no further transitions; creating a manuell behandling,
+/// starting another Behandling and a dynamic target do not end it.
+fn flow_rule_violations(
+    model: &FlowModel,
+    rule: &FlowRule,
+    behandling: &str,
+    info: &ClassInfo,
+    initial_aktivitet: &str,
+    edges: &[Edge],
+) -> Vec<Violation> {
+    let violation = |message: String, location: Option<SourceLocation>| Violation {
+        rule: Rule::FlowRule,
+        subject: behandling.to_string(),
+        message: format!(
+            "{} breaks rule \"{}\": {}",
+            behandling,
+            rule.describe(),
+            message
+        ),
+        location,
+    };
+
+    let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in edges {
+        successors.entry(&edge.from).or_default().push(&edge.to);
+    }
+    let is_end = |node: &str| {
+        !successors.contains_key(node)
+            && node != "manuell"
+            && !node.starts_with("spawn_")
+            && !node.starts_with("dynamic_")
+    };
+
+    let mut violations = Vec::new();
+    if !rule.must_reach.is_empty() {
+        let targets = aktivitet_matcher(&rule.must_reach);
+        if let Some(path) = path_avoiding(initial_aktivitet, &successors, &targets, &is_end) {
+            violations.push(violation(
+                format!(
+                    "{} ends without reaching {}",
+                    format_path(&path),
+                    rule.must_reach.join(" or ")
+                ),
+                Some(info.location()),
+            ));
+        }
+    }
+    for pattern in &rule.must_pass {
+        let step = aktivitet_matcher(std::slice::from_ref(pattern));
+        let completes = |node: &str| node == "end";
+        if let Some(path) = path_avoiding(initial_aktivitet, &successors, &step, &completes) {
+            violations.push(violation(
+                format!(
+                    "{} completes without passing {}",
+                    format_path(&path),
+                    pattern
+                ),
+                Some(info.location()),
+            ));
+        }
+    }
+    if !rule.in_cycle.is_empty() {
+        let matches = aktivitet_matcher(&rule.in_cycle);
+        let reachability = reachability_matrix(initial_aktivitet, edges);
+        for aktivitet in flow_aktiviteter(initial_aktivitet, edges) {
+            if matches(&aktivitet) && !reachability.can_reach(&aktivitet, &aktivitet) {
+                violations.push(violation(
+                    format!("{} is not inside a cycle", simple_name(&aktivitet)),
+                    model.class_location(&aktivitet),
+                ));
+            }
+        }
+    }
+    violations
+}
+
+/// Matches aktiviteter by class name, with or without the package, against names and globs.
+/// Marker nodes never match.
+fn aktivitet_matcher(patterns: &[String]) -> impl Fn(&str) -> bool {
+    let globs = glob_set(patterns);
+    move |node: &str| {
+        !is_marker_node(node) && (globs.is_match(node) || globs.is_match(simple_name(node)))
+    }
+}
+
+/// Patterns that are no valid glob are left out; `Config::load` reports them
+fn glob_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for glob in patterns
+        .iter()
+        .filter_map(|pattern| Glob::new(pattern).ok())
+    {
+        builder.add(glob);
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// The shortest path from the initial aktivitet to a node `is_goal` accepts that passes no node
+/// `avoid` matches; `None` if there is no such path
+fn path_avoiding(
+    initial_aktivitet: &str,
+    successors: &HashMap<&str, Vec<&str>>,
+    avoid: &dyn Fn(&str) -> bool,
+    is_goal: &dyn Fn(&str) -> bool,
+) -> Option<Vec<String>> {
+    if avoid(initial_aktivitet) {
+        return None;
+    }
+    let mut parents: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([initial_aktivitet]);
+    while let Some(node) = queue.pop_front() {
+        if is_goal(node) {
+            let mut path = vec![node.to_string()];
+            let mut current = node;
+            while let Some(parent) = parents.get(current) {
+                path.push(parent.to_string());
+                current = parent;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &next in successors.get(node).into_iter().flatten() {
+            if next != initial_aktivitet && !parents.contains_key(next) && !avoid(next) {
+                parents.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// A path for a message, `VurderAktivitet → AvslagAktivitet → END`, without the fan-out nodes
+/// of subtypes
+fn format_path(path: &[String]) -> String {
+    path.iter()
+        .filter(|node| !node.starts_with("subtypes_"))
+        .map(|node| match node.as_str() {
+            "end" => "END".to_string(),
+            "abort" => "ABORT".to_string(),
+            "unresolved" => "END?".to_string(),
+            node if node.starts_with("unknown_") => "?".to_string(),
+            node => simple_name(node).to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// Every aktivitet that is started in a transition, is an initial aktivitet or is declared as an
+/// aktivitet class, but has no processor. Each comes with near misses: processors for an
+/// aktivitet with the same name in another package or a similar name, and processor classes
+/// named after it whose aktivitet could not be read.
+pub fn missing_processors(model: &FlowModel) -> Vec<MissingProcessor> {
+    indexed_aktiviteter(model)
+        .into_iter()
+        .filter(|aktivitet| !model.processor_index.contains_key(aktivitet))
+        .map(|aktivitet| {
+            let referenced_by = model
+                .processor_index
+                .iter()
+                .filter(|(_, processor)| {
+                    processor
+                        .next_aktiviteter
+                        .iter()
+                        .any(|next| next.aktivitet_name == aktivitet)
+                })
+                .map(|(from, _)| from.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            MissingProcessor {
+                location: model.class_location(&aktivitet),
+                referenced_by,
+                suggestions: processor_suggestions(model, &aktivitet),
+                aktivitet,
+            }
+        })
+        .collect()
+}
+
+/// Every processor whose aktivitet type argument does not match a declared class, usually after a
+/// typo or a deleted aktivitet. Each comes with the declared aktiviteter with the same name in
+/// another package or a similar name.
+pub fn orphan_processors(model: &FlowModel) -> Vec<OrphanProcessor> {
+    let mut orphans: Vec<OrphanProcessor> = model
+        .processor_index
+        .iter()
+        .filter(|(aktivitet, _)| !model.class_index.contains_key(*aktivitet))
+        .map(|(aktivitet, processor)| OrphanProcessor {
+            processor: processor.processor_class.clone(),
+            aktivitet: aktivitet.clone(),
+            location: model.processor_location(processor),
+            suggestions: aktivitet_suggestions(model, aktivitet),
+        })
+        .collect();
+    orphans.sort_by(|a, b| a.processor.cmp(&b.processor));
+    orphans
+}
+
+/// Declared aktivitet classes that a processor for the undeclared `aktivitet` may have been
+/// meant for
+fn aktivitet_suggestions(model: &FlowModel, aktivitet: &str) -> Vec<String> {
+    let name = simple_name(aktivitet);
+    let mut declared: Vec<_> = model
+        .class_index
+        .iter()
+        .filter(|(_, class)| is_aktivitet_class(model, class))
+        .collect();
+    declared.sort_by(|a, b| a.0.cmp(b.0));
+    declared
+        .into_iter()
+        .filter_map(|(key, class)| {
+            let reason = if class.name == name {
+                "same name in another package"
+            } else if base_name(&class.name) == base_name(name) {
+                "differs only in case or suffix"
+            } else if edit_distance(&class.name, name) <= 2 {
+                "similar name"
+            } else {
+                return None;
+            };
+            Some(format!("{} ({})", key, reason))
+        })
+        .collect()
+}
+
+/// Every aktivitet that is started in a transition, is an initial aktivitet, is declared as an
+/// aktivitet class or has a processor, but is not in the flow of any Behandling. These are the
+/// dead steps that never show up in a flow graph.
+pub fn unreachable_aktiviteter(model: &FlowModel) -> Vec<UnreachableAktivitet> {
+    let reachable = reachable_aktiviteter(model);
+    indexed_aktiviteter(model)
+        .into_iter()
+        .filter(|aktivitet| !reachable.contains_key(aktivitet))
+        .map(|aktivitet| {
+            let processor = model.processor_index.get(&aktivitet);
+            UnreachableAktivitet {
+                location: model.class_location(&aktivitet),
+                processor: processor.map(|processor| processor.processor_class.clone()),
+                processor_location: processor
+                    .and_then(|processor| model.processor_location(processor)),
+                aktivitet,
+            }
+        })
+        .collect()
+}
+
+/// The aktiviteter in transitions and processors, and the declared aktivitet classes
+pub(crate) fn indexed_aktiviteter(model: &FlowModel) -> BTreeSet<String> {
+    let mut aktiviteter: BTreeSet<String> = model.aktiviteter().into_iter().collect();
+    aktiviteter.extend(
+        model
+            .class_index
+            .iter()
+            .filter(|(_, class)| is_aktivitet_class(model, class))
+            .map(|(key, _)| key.clone()),
+    );
+    aktiviteter
+}
+
+/// Aktivitet -> Behandlinger whose flow contains it
+pub(crate) fn reachable_aktiviteter(model: &FlowModel) -> BTreeMap<String, Vec<String>> {
+    let mut reachable: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, info) in model.behandlinger() {
+        let Some(initial_aktivitet) = &info.initial_aktivitet else {
+            continue;
+        };
+        let edges = collect_flow_edges(
+            initial_aktivitet,
+            &model.processor_index,
+            &model.class_index,
+        );
+        for aktivitet in flow_aktiviteter(initial_aktivitet, &edges) {
+            reachable.entry(aktivitet).or_default().push(name.clone());
+        }
+    }
+    reachable
+}
+
+/// Concrete classes that are aktiviteter by name or by one of the configured annotations
+fn is_aktivitet_class(model: &FlowModel, class: &ClassInfo) -> bool {
+    let by_name = class.name.ends_with("Aktivitet") || class.name.ends_with("Activity");
+    let by_annotation = class
+        .annotations
+        .iter()
+        .any(|annotation| model.config.discovery.is_aktivitet_annotation(annotation));
+    !class.is_abstract && (by_name || by_annotation)
+}
+
+fn processor_suggestions(model: &FlowModel, aktivitet: &str) -> Vec<String> {
+    let name = simple_name(aktivitet);
+    let mut suggestions = Vec::new();
+
+    let mut handled: Vec<_> = model.processor_index.iter().collect();
+    handled.sort_by(|a, b| a.0.cmp(b.0));
+    for (other, processor) in handled {
+        let other_name = simple_name(other);
+        let reason = if other_name == name {
+            "same name in another package"
+        } else if base_name(other_name) == base_name(name) {
+            "differs only in case or suffix"
+        } else if edit_distance(other_name, name) <= 2 {
+            "similar name"
+        } else {
+            continue;
+        };
+        suggestions.push(format!(
+            "{} handles {} ({})",
+            processor.processor_class, other, reason
+        ));
+    }
+
+    // A processor class named after the aktivitet that was not recognized as a processor, e.g.
+    // because its aktivitet type argument is a type parameter that could not be resolved
+    let mut named_after: Vec<_> = model
+        .class_index
+        .values()
+        .filter(|class| {
+            class
+                .name
+                .strip_suffix("Processor")
+                .or_else(|| class.name.strip_suffix("Prosessor"))
+                .is_some_and(|stem| base_name(stem) == base_name(name))
+        })
+        .filter(|class| {
+            !model
+                .processor_index
+                .values()
+                .any(|processor| processor.processor_class == class.name)
+        })
+        .collect();
+    named_after.sort_by(|a, b| a.name.cmp(&b.name));
+    for class in named_after {
+        suggestions.push(format!(
+            "{} is named after it, but the aktivitet it handles could not be read ({})",
+            class.name,
+            class.location()
+        ));
+    }
+
+    suggestions
+}
+
+/// `VurderKravAktivitet` for `alder.VurderKravAktivitet`
+fn simple_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// The name without the aktivitet suffix, in lower case: `vurderkrav` for `VurderKravAktivitet`
+fn base_name(name: &str) -> String {
+    let name = name
+        .strip_suffix("Aktivitet")
+        .or_else(|| name.strip_suffix("Activity"))
+        .unwrap_or(name);
+    name.to_lowercase()
+}
+
+/// Number of single character insertions, deletions and substitutions between two names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Run all checks; violations are sorted by rule and subject
+pub fn lint(model: &FlowModel) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let reachable = reachable_aktiviteter(model);
+    // (earlier, later) -> Behandlinger, so a conflict in a shared part of the flows is one problem
+    let mut step_conflicts: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    let mut edge_conflicts_found: BTreeMap<(String, String), (EdgeConflict, Vec<String>)> =
+        BTreeMap::new();
+    let rule_behandlinger: Vec<GlobSet> = model
+        .config
+        .lint
+        .rules
+        .iter()
+        .map(|rule| glob_set(&rule.behandlinger))
+        .collect();
+
+    for (name, info) in model.behandlinger() {
+        let Some(initial_aktivitet) = &info.initial_aktivitet else {
+            continue;
+        };
+
+        let edges = collect_flow_edges(
+            initial_aktivitet,
+            &model.processor_index,
+            &model.class_index,
+        );
+
+        // An abort ends the flow too, just not successfully
+        if !edges
+            .iter()
+            .any(|edge| edge.to == "end" || edge.to == "abort")
+        {
+            violations.push(Violation {
+                rule: Rule::NoTerminalState,
+                subject: name.clone(),
+                message: format!("{} has no aktivitet that ends the flow", name),
+                location: Some(info.location()),
+            });
+        }
+
+        if let Some(max_complexity) = model.config.lint.max_complexity {
+            let complexity = flow_complexity(&edges);
+            if complexity > max_complexity {
+                violations.push(Violation {
+                    rule: Rule::ComplexFlow,
+                    subject: name.clone(),
+                    message: format!(
+                        "{} has complexity {}, above the maximum of {}",
+                        name, complexity, max_complexity
+                    ),
+                    location: Some(info.location()),
+                });
+            }
+        }
+
+        for conflict in step_order_conflicts(initial_aktivitet, &edges) {
+            step_conflicts
+                .entry((conflict.earlier, conflict.later))
+                .or_default()
+                .push(name.clone());
+        }
+
+        for conflict in edge_conflicts(&edges) {
+            edge_conflicts_found
+                .entry((conflict.from.clone(), conflict.to.clone()))
+                .or_insert_with(|| (conflict, Vec::new()))
+                .1
+                .push(name.clone());
+        }
+
+        for (rule, behandlinger) in model.config.lint.rules.iter().zip(&rule_behandlinger) {
+            let applies = rule.behandlinger.is_empty()
+                || behandlinger.is_match(name)
+                || behandlinger.is_match(simple_name(name));
+            if applies {
+                violations.extend(flow_rule_violations(
+                    model,
+                    rule,
+                    name,
+                    info,
+                    initial_aktivitet,
+                    &edges,
+                ));
+            }
+        }
+    }
+
+    for ((earlier, later), behandlinger) in step_conflicts {
+        let step = |aktivitet: &str| step_number(aktivitet).unwrap_or_default();
+        violations.push(Violation {
+            rule: Rule::StepOrder,
+            message: format!(
+                "{} (step {}) comes after {} (step {}) in {}",
+                later,
+                step(&later),
+                earlier,
+                step(&earlier),
+                behandlinger.join(", ")
+            ),
+            location: model.class_location(&later),
+            subject: later,
+        });
+    }
+
+    for (conflict, behandlinger) in edge_conflicts_found.into_values() {
+        let mut problems = Vec::new();
+        if let [drawn, hidden @ ..] = conflict.conditions.as_slice() {
+            if !hidden.is_empty() {
+                problems.push(format!(
+                    "is drawn with `{}` but also made under `{}`",
+                    drawn,
+                    hidden.join("`, `")
+                ));
+            }
+        }
+        if conflict.mixes_collection {
+            problems.push("both fans out and makes a single transition".to_string());
+        }
+        violations.push(Violation {
+            rule: Rule::ConflictingEdges,
+            message: format!(
+                "{} -> {} {} in {}",
+                conflict.from,
+                conflict.to,
+                problems.join(", and "),
+                behandlinger.join(", ")
+            ),
+            location: match model.processor_index.get(&conflict.from) {
+                Some(processor) => model.processor_location(processor),
+                None => model.class_location(&conflict.from),
+            },
+            subject: conflict.from,
+        });
+    }
+
+    for (aktivitet, processor) in &model.processor_index {
+        if reachable.contains_key(aktivitet) && processor.is_unresolved() {
+            violations.push(Violation {
+                rule: Rule::UnresolvedProcessor,
+                subject: processor.processor_class.clone(),
+                message: format!(
+                    "{} neither makes a transition nor ends the flow",
+                    processor.processor_class
+                ),
+                location: model.processor_location(processor),
+            });
+        }
+    }
+
+    // A shared function's transitions are part of every processor that calls it; report each
+    // pair of transitions once
+    let mut compared = HashSet::new();
+    let mut processors: Vec<&ProcessorInfo> = model.processor_index.values().collect();
+    processors.sort_by(|a, b| a.processor_class.cmp(&b.processor_class));
+    let mut reported = HashSet::new();
+    for processor in processors {
+        violations.extend(condition_violations(processor, &mut compared));
+        violations.extend(converging_branches(model, processor, &mut reported));
+    }
+
+    for (aktivitet, behandlinger) in &reachable {
+        if !model.processor_index.contains_key(aktivitet) {
+            let suggestions = processor_suggestions(model, aktivitet);
+            let hint = if suggestions.is_empty() {
+                String::new()
+            } else {
+                format!("; maybe {}", suggestions.join(", or "))
+            };
+            violations.push(Violation {
+                rule: Rule::MissingProcessor,
+                subject: aktivitet.clone(),
+                message: format!(
+                    "{} has no processor (used in {}){}",
+                    aktivitet,
+                    behandlinger.join(", "),
+                    hint
+                ),
+                location: model.class_location(aktivitet),
+            });
+        }
+    }
+
+    for (aktivitet, behandlinger) in &reachable {
+        if model
+            .class_index
+            .get(aktivitet)
+            .is_some_and(ClassInfo::is_deprecated)
+        {
+            violations.push(Violation {
+                rule: Rule::DeprecatedAktivitet,
+                subject: aktivitet.clone(),
+                message: format!(
+                    "{} is deprecated but still reached (in {})",
+                    aktivitet,
+                    behandlinger.join(", ")
+                ),
+                location: model.class_location(aktivitet),
+            });
+        }
+    }
+
+    for orphan in orphan_processors(model) {
+        let hint = if orphan.suggestions.is_empty() {
+            String::new()
+        } else {
+            format!("; maybe {}", orphan.suggestions.join(", or "))
+        };
+        violations.push(Violation {
+            rule: Rule::OrphanProcessor,
+            message: format!(
+                "{} handles {}, which is not declared anywhere, so it never runs{}",
+                orphan.processor, orphan.aktivitet, hint
+            ),
+            subject: orphan.processor,
+            location: orphan.location,
+        });
+    }
+
+    let all_aktiviteter: BTreeSet<String> = model.aktiviteter().into_iter().collect();
+    for aktivitet in all_aktiviteter {
+        if !reachable.contains_key(&aktivitet) {
+            violations.push(Violation {
+                rule: Rule::UnreachableAktivitet,
+                message: format!("{} is not reachable from any Behandling", aktivitet),
+                location: model.class_location(&aktivitet),
+                subject: aktivitet,
+            });
+        }
+    }
+
+    violations.sort_by(|a, b| (a.rule, &a.subject).cmp(&(b.rule, &b.subject)));
+    violations
+}
@@ -0,0 +1,65 @@
+//! Progress and warning output of the CLI, as console text or JSON lines
+
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io::Write;
+use std::sync::OnceLock;
+
+static FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable messages: progress on stdout, warnings and errors on stderr
+    Text,
+    /// One JSON object per message on stderr, with timestamp, level and message
+    Json,
+}
+
+struct Logger {
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Only our own messages, not the ones from dependencies
+        metadata.level() <= log::max_level() && metadata.target().starts_with("behandling_flow")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        match self.format {
+            LogFormat::Text if record.level() <= Level::Warn => eprintln!("{}", record.args()),
+            LogFormat::Text => println!("{}", record.args()),
+            LogFormat::Json => {
+                let message = record.args().to_string();
+                let entry = serde_json::json!({
+                    "timestamp": chrono::Local::now().to_rfc3339(),
+                    "level": record.level().as_str().to_lowercase(),
+                    // Drop the leading blank lines, indentation and emoji meant for the console
+                    "message": message.trim_start_matches(|c: char| !c.is_alphanumeric()),
+                });
+                eprintln!("{}", entry);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stdout().flush();
+    }
+}
+
+/// Install the logger; messages below `level` are dropped
+pub fn init(format: LogFormat, level: LevelFilter) {
+    log::set_max_level(level);
+    // Only fails if a logger is already installed
+    let _ = log::set_logger(Box::leak(Box::new(Logger { format })));
+    let _ = FORMAT.set(format);
+}
+
+/// True if info messages are printed as console text, the only case where progress bars fit in
+pub fn shows_progress() -> bool {
+    FORMAT.get() == Some(&LogFormat::Text) && log::max_level() >= LevelFilter::Info
+}
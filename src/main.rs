@@ -1,18 +1,61 @@
+mod logging;
+mod progress;
+mod serve;
+mod tui;
+mod watch;
+
 use anyhow::{Context, Result};
-use clap::Parser as ClapParser;
-use std::collections::HashMap;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use behandling_flow::diff::{
+    diff_baseline, diff_models, generate_diff_graph, Baseline, BehandlingDiff, ChangeKind,
+};
+use behandling_flow::export::{
+    generate_asciidoc, generate_confluence_page_body, generate_cypher, generate_markdown_report,
+    generate_reachability_csv, generate_reachability_json,
+};
+use behandling_flow::graph::{
+    change_impact, collect_flow_edges, drawn_flow_edges, find_cycles, find_paths, flow_aktiviteter,
+    flow_endings, format_cycle, generate_dot_graph, mandatory_steps, merge_points, path_condition,
+    shorten_aktivitet_name, summarize_flow, unknown_aktiviteter, EndingKind, PathStep,
+    MAX_CYCLE_PATHS,
+};
+use behandling_flow::lint::{
+    lint, missing_processors, orphan_processors, unreachable_aktiviteter, MissingProcessor,
+    UnreachableAktivitet,
+};
+use behandling_flow::metrics::format_duration;
+use behandling_flow::render::{
+    is_graphviz_missing, merge_pdf_documents, render_dot, render_svg_with_layout,
+};
+use behandling_flow::search::{search, Neighbour};
+use behandling_flow::{
+    analyze_project_cached, analyze_project_with, analyze_project_with_progress, reanalyze_files,
+    AktivitetDurations, CacheUse, ClassInfo, DotOptions, ExecutionCounts, FileFilter, FlowModel,
+    ProcessorInfo, ScanPhase, STDIN_PATH,
+};
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
+use globset::{Glob, GlobMatcher};
+use log::{debug, error, info, warn, LevelFilter};
+use regex::RegexBuilder;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-use tree_sitter::Parser;
-use walkdir::WalkDir;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::logging::LogFormat;
+use crate::progress::ScanProgress;
+use crate::watch::watch_kotlin_sources;
 
 /// Analyze and visualize Kotlin Behandling flow graphs
 #[derive(ClapParser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Path to the Kotlin project directory (defaults to current directory)
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
     #[arg(value_name = "PATH")]
     path: Option<String>,
 
@@ -21,15 +64,15 @@ struct Args {
     format: String,
 
     /// Edge style: curved, straight, or ortho (orthogonal)
-    #[arg(short = 'e', long, default_value = "straight")]
+    #[arg(short = 'e', long, default_value = "straight", global = true)]
     edge_style: String,
 
     /// Show condition labels on edges (default: hidden for cleaner graphs)
-    #[arg(short = 'c', long)]
+    #[arg(short = 'c', long, global = true)]
     show_conditions: bool,
 
     /// Show color legend in graph (default: hidden)
-    #[arg(short = 'l', long)]
+    #[arg(short = 'l', long, global = true)]
     show_legend: bool,
 
     /// Automatically open the generated graph
@@ -41,112 +84,967 @@ struct Args {
     keep_dot: bool,
 
     /// Output directory for generated files (defaults to current directory)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     output_dir: Option<String>,
 
     /// Verbose output
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
 
+    /// Only print warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Format of progress and warning messages; json writes one object per line to stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    log_format: LogFormat,
+
     /// Disable edge deduplication and consolidation (shows all raw edges)
     #[arg(long)]
     no_deduplicate: bool,
+
+    /// Output resolution in dots per inch for raster formats like PNG (graphviz default: 96)
+    #[arg(long, value_parser = parse_positive, global = true)]
+    dpi: Option<f64>,
+
+    /// Scale factor applied to the output resolution (e.g. 2 for high-resolution displays)
+    #[arg(long, value_parser = parse_positive, global = true)]
+    scale: Option<f64>,
+
+    /// Keep running and regenerate the flows that change when .kt files are edited
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Combine all flows into a single multi-page PDF with title, timestamp and legend on each page
+    #[arg(long)]
+    booklet: bool,
+
+    /// Also write an AsciiDoc page per flow (diagram include plus activity and condition tables)
+    #[arg(long)]
+    asciidoc: bool,
+
+    /// Only generate the Behandlinger matching this name or glob (e.g. "Alder*"); repeatable
+    #[arg(short, long, value_name = "NAME")]
+    behandling: Vec<String>,
+
+    /// Only generate the Behandlinger declared in this Gradle module (e.g. ":alder" or "alder*"); repeatable
+    #[arg(long, value_name = "MODULE")]
+    module: Vec<String>,
+
+    /// Also write one graph of all selected flows, with the aktiviteter grouped by Gradle module
+    #[arg(long)]
+    module_overview: bool,
+
+    /// Also write one graph of how the selected Behandlinger start, wait on and resume each other
+    #[arg(long)]
+    behandling_overview: bool,
+
+    /// Root the graphs at this aktivitet instead of the initial one; only flows reaching it are generated
+    #[arg(long, value_name = "AKTIVITET")]
+    start_from: Option<String>,
+
+    /// Only show the aktiviteter within --radius transitions up- and downstream of this one
+    #[arg(long, value_name = "AKTIVITET")]
+    focus: Option<String>,
+
+    /// Number of transitions shown around the --focus aktivitet
+    #[arg(long, default_value_t = 2, requires = "focus")]
+    radius: usize,
+
+    /// Mark aktiviteter with at least this many incoming transitions as hubs
+    #[arg(long, value_name = "N")]
+    hub_threshold: Option<usize>,
+
+    /// Cut the graphs after this many transitions from the start, marking where the flow continues
+    #[arg(long, value_name = "DEPTH")]
+    max_depth: Option<usize>,
+
+    /// Merge straight runs of aktiviteter (one way in, one way out) into a single summary node
+    #[arg(long)]
+    collapse_chains: bool,
+
+    /// Fan transitions to abstract or sealed aktivitet types out to their concrete subclasses
+    #[arg(long)]
+    expand_subtypes: bool,
+
+    /// Draw the longest path to END in bold: the most steps a sak goes through, loops left out
+    #[arg(long)]
+    critical_path: bool,
+
+    /// Mark the aktiviteter where the branches of a split meet again
+    #[arg(long)]
+    merge_points: bool,
+
+    /// Outline what this file defines in red and every aktivitet downstream of it in orange; repeatable
+    #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+    changed: Vec<String>,
+
+    /// Like --changed, for every file changed since this git reference (branch, tag or commit)
+    #[arg(long, value_name = "GIT_REF", conflicts_with = "watch")]
+    changed_since: Option<String>,
+
+    /// Color and size aktiviteter by how often they ran in production, from a CSV
+    /// (aktivitet,count) or JSON ({"Aktivitet": count}) export
+    #[arg(long, value_name = "FILE")]
+    counts: Option<PathBuf>,
+
+    /// Color aktiviteter from green to red by how long a sak typically stays in them, and label
+    /// transitions with it, from a CSV (aktivitet,duration) or JSON export
+    #[arg(long, value_name = "FILE")]
+    durations: Option<PathBuf>,
+
+    /// Fail if any generated graph contains aktiviteter without a processor (unknown nodes)
+    #[arg(long, conflicts_with = "watch")]
+    fail_on_unknown: bool,
+
+    /// Fail if the aktiviteter or transitions of any flow differ from this baseline JSON file
+    #[arg(long, value_name = "BASELINE", conflicts_with = "watch")]
+    assert: Option<PathBuf>,
+
+    /// Write the current flows to the --assert baseline instead of comparing with it
+    #[arg(long, requires = "assert")]
+    update_baseline: bool,
+
+    /// Fail instead of warning when an aktivitet is handled by more than one processor
+    #[arg(long)]
+    strict: bool,
+
+    /// Only scan files matching this glob, relative to PATH (e.g. "**/domain/**/*.kt"); repeatable
+    #[arg(long, value_name = "GLOB", global = true)]
+    include: Vec<String>,
+
+    /// Skip files and directories matching this glob, relative to PATH (e.g. "**/generated/**"); repeatable
+    #[arg(long, value_name = "GLOB", global = true)]
+    exclude: Vec<String>,
+
+    /// Also scan build and tool directories (build, out, target, .git, .gradle, .idea, node_modules)
+    #[arg(long, global = true)]
+    no_default_excludes: bool,
+
+    /// Also scan Kotlin scripts (.kts), except Gradle build and settings scripts
+    #[arg(long, global = true)]
+    include_scripts: bool,
+
+    /// Keep the extracted model in PATH/.flowchart-cache and only re-parse files changed since
+    #[arg(long, global = true)]
+    cache: bool,
+
+    /// Simulate the flow with a feature toggle on or off, dropping transitions it rules out
+    /// (e.g. NY_FLYT=on); repeatable
+    #[arg(long, value_name = "NAME=on|off", value_parser = parse_toggle, global = true)]
+    toggle: Vec<(String, bool)>,
+}
+
+/// Parse a `--toggle NAME=on|off` value
+fn parse_toggle(value: &str) -> Result<(String, bool), String> {
+    let (name, state) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=on|off, got '{}'", value))?;
+    let enabled = match state.to_ascii_lowercase().as_str() {
+        "on" | "true" => true,
+        "off" | "false" => false,
+        _ => return Err(format!("expected on or off for {}, got '{}'", name, state)),
+    };
+    Ok((name.trim().to_string(), enabled))
 }
 
-#[derive(Debug, Clone)]
-struct ClassInfo {
-    name: String,
-    file: PathBuf,
-    supertypes: Vec<String>,
-    initial_aktivitet: Option<String>,
+/// Parse a `--dpi` or `--scale` value: a finite number above zero
+fn parse_positive(value: &str) -> Result<f64, String> {
+    let number: f64 = value
+        .parse()
+        .map_err(|_| format!("expected a number, got '{}'", value))?;
+    if !number.is_finite() || number <= 0.0 {
+        return Err(format!("expected a finite number above 0, got '{}'", value));
+    }
+    Ok(number)
 }
 
-#[derive(Debug, Clone)]
-struct ProcessorInfo {
-    processor_class: String,
-    next_aktiviteter: Vec<NextAktivitet>,
-    has_manuell_behandling: bool,
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    /// Neo4j Cypher MERGE statements (:Aktivitet nodes, :NEXT relationships)
+    Cypher,
+    /// Which aktiviteter can follow which, as a CSV matrix per Behandling
+    ReachabilityCsv,
+    /// Which aktiviteter can follow which, as one JSON file with a matrix per Behandling
+    ReachabilityJson,
 }
 
-#[derive(Debug, Clone)]
-struct NextAktivitet {
-    aktivitet_name: String,
-    condition: Option<String>,
-    is_collection: bool, // True if this represents multiple instances (fan-out)
+impl Args {
+    /// Effective DPI from --dpi and --scale, or None to keep the graphviz default
+    fn output_dpi(&self) -> Option<f64> {
+        match (self.dpi, self.scale) {
+            (None, None) => None,
+            (dpi, scale) => Some(dpi.unwrap_or(GRAPHVIZ_DEFAULT_DPI) * scale.unwrap_or(1.0)),
+        }
+    }
+
+    /// Graph options for generated flow files
+    fn dot_options(&self) -> DotOptions<'_> {
+        DotOptions {
+            edge_style: &self.edge_style,
+            show_conditions: self.show_conditions,
+            show_legend: self.show_legend,
+            deduplicate: !self.no_deduplicate,
+            subtitle: self.toggle_subtitle(),
+            dpi: self.output_dpi(),
+            start_from: self.start_from.as_deref(),
+            focus: self.focus.as_deref(),
+            focus_radius: self.radius,
+            max_depth: self.max_depth,
+            collapse_chains: self.collapse_chains,
+            expand_subtypes: self.expand_subtypes,
+            flow_link_format: Some(&self.format),
+            highlight_path: None,
+            critical_path: self.critical_path,
+            hub_threshold: self.hub_threshold,
+            changed_files: None,
+            execution_counts: None,
+            durations: None,
+            merge_points: self.merge_points,
+        }
+    }
+
+    /// Which files to scan, from --include, --exclude, --no-default-excludes and --include-scripts
+    fn file_filter(&self) -> Result<FileFilter> {
+        Ok(
+            FileFilter::new(&self.include, &self.exclude, !self.no_default_excludes)?
+                .with_scripts(self.include_scripts),
+        )
+    }
+
+    /// How projects are scanned, from the file filter options, --cache and --toggle
+    fn scan(&self) -> Result<Scan> {
+        Ok(Scan {
+            filter: self.file_filter()?,
+            cache: self.cache,
+            toggles: self.toggles(),
+        })
+    }
+
+    /// The feature toggle values to simulate, from --toggle; the last value given wins
+    fn toggles(&self) -> HashMap<String, bool> {
+        self.toggle.iter().cloned().collect()
+    }
+
+    /// The simulated toggle values for the graph title, e.g. `🚩 NY_FLYT on, GAMMEL_FLYT off`
+    fn toggle_subtitle(&self) -> Option<String> {
+        if self.toggle.is_empty() {
+            return None;
+        }
+        let values: Vec<String> = self
+            .toggle
+            .iter()
+            .map(|(name, enabled)| format!("{} {}", name, if *enabled { "on" } else { "off" }))
+            .collect();
+        Some(format!("🚩 {}", values.join(", ")))
+    }
+
+    /// The Behandlinger selected with --behandling (all of them when none is given) whose flow
+    /// reaches --start-from and --focus. Fails if a pattern does not match any Behandling, to
+    /// catch typos.
+    fn select_behandlinger<'m>(
+        &self,
+        model: &'m FlowModel,
+    ) -> Result<Vec<(&'m String, &'m ClassInfo)>> {
+        let selected = self.filter_behandlinger(model.behandlinger())?;
+        let mut selected = self.filter_modules(model, selected)?;
+
+        for aktivitet in [&self.start_from, &self.focus].into_iter().flatten() {
+            ensure_known_aktivitet(model, aktivitet)?;
+            selected.retain(|(_, info)| {
+                info.initial_aktivitet.as_ref().is_some_and(|initial| {
+                    let edges =
+                        collect_flow_edges(initial, &model.processor_index, &model.class_index);
+                    flow_aktiviteter(initial, &edges).contains(aktivitet)
+                })
+            });
+            if selected.is_empty() {
+                anyhow::bail!("No selected Behandling flow reaches {}", aktivitet);
+            }
+        }
+
+        Ok(selected)
+    }
+
+    fn filter_behandlinger<'m>(
+        &self,
+        behandlinger: Vec<(&'m String, &'m ClassInfo)>,
+    ) -> Result<Vec<(&'m String, &'m ClassInfo)>> {
+        if self.behandling.is_empty() {
+            return Ok(behandlinger);
+        }
+
+        let mut matchers = Vec::new();
+        for pattern in &self.behandling {
+            let glob = Glob::new(pattern)
+                .with_context(|| format!("Invalid --behandling pattern: {}", pattern))?;
+            matchers.push((pattern, glob.compile_matcher()));
+        }
+
+        for (pattern, matcher) in &matchers {
+            if !behandlinger.iter().any(|(name, _)| matcher.is_match(name)) {
+                anyhow::bail!("No Behandling matches --behandling {}", pattern);
+            }
+        }
+
+        Ok(behandlinger
+            .into_iter()
+            .filter(|(name, _)| matchers.iter().any(|(_, matcher)| matcher.is_match(name)))
+            .collect())
+    }
+
+    /// Keep the Behandlinger declared in a Gradle module selected with --module. Module names
+    /// match with or without their leading colon. Fails if a pattern matches no module.
+    fn filter_modules<'m>(
+        &self,
+        model: &FlowModel,
+        behandlinger: Vec<(&'m String, &'m ClassInfo)>,
+    ) -> Result<Vec<(&'m String, &'m ClassInfo)>> {
+        if self.module.is_empty() {
+            return Ok(behandlinger);
+        }
+
+        let mut matchers = Vec::new();
+        for pattern in &self.module {
+            let glob = Glob::new(pattern)
+                .with_context(|| format!("Invalid --module pattern: {}", pattern))?;
+            matchers.push((pattern, glob.compile_matcher()));
+        }
+        let matches = |matcher: &GlobMatcher, module: &str| {
+            matcher.is_match(module) || matcher.is_match(module.trim_start_matches(':'))
+        };
+
+        for (pattern, matcher) in &matchers {
+            if !model
+                .modules
+                .iter()
+                .any(|module| matches(matcher, &module.name))
+            {
+                anyhow::bail!("No Gradle module matches --module {}", pattern);
+            }
+        }
+
+        Ok(behandlinger
+            .into_iter()
+            .filter(|(_, info)| {
+                model.module_of(&info.file).is_some_and(|module| {
+                    matchers
+                        .iter()
+                        .any(|(_, matcher)| matches(matcher, &module.name))
+                })
+            })
+            .collect())
+    }
 }
 
-#[derive(Debug, Clone)]
-struct IterationGroup {
-    trigger_node: String,        // Node that starts the iteration
-    iterated_nodes: Vec<String>, // All nodes that are part of the iteration path
+#[derive(Clone, Copy, Debug)]
+enum ListKind {
+    Behandlinger,
+    Aktiviteter,
+    Processors,
+    MissingProcessors,
+    Unreachable,
+    Endings,
+    Mandatory,
+    MergePoints,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Edge {
-    from: String,
-    to: String,
-    label: String,
-    is_collection: bool, // True if this represents multiple instances (fan-out)
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Compare the flows of two project directories, or of a project against a git reference
+    Diff {
+        /// Old and new version of the Kotlin project, or only the project with --against
+        #[arg(value_name = "PATH", num_args = 0..=2)]
+        paths: Vec<String>,
+
+        /// Compare the working tree against this git reference (branch, tag or commit)
+        #[arg(long, value_name = "GIT_REF")]
+        against: Option<String>,
+
+        /// Print the changes as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Also write a `<Behandling>_diff.<format>` graph per changed flow, with added parts in
+        /// green, removed ones in red and changed conditions in amber
+        #[arg(long)]
+        graph: bool,
+    },
+
+    /// Export all flows to a machine-readable format instead of rendering graphs
+    Export {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Export format
+        #[arg(short, long, value_enum, default_value = "cypher")]
+        format: ExportFormat,
+    },
+
+    /// Show everything known about a single aktivitet
+    Inspect {
+        /// Name of the aktivitet class
+        aktivitet: String,
+
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+    },
+
+    /// Find aktiviteter and processors whose name matches a regex, with their flow context
+    Search {
+        /// Regular expression, matched without regard to case unless it starts with (?-i)
+        pattern: String,
+
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Print the matches as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check the flows for problems; exits with 1 when violations are found
+    #[command(visible_alias = "validate")]
+    Lint {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Report flows with a higher complexity as complex-flow, overriding `[lint] max-complexity`
+        #[arg(long, value_name = "N")]
+        max_complexity: Option<usize>,
+
+        /// Print the violations as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List discovered Behandlinger, aktiviteter or processors without generating graphs
+    List {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// List main Behandling classes (default)
+        #[arg(long, group = "kind")]
+        behandlinger: bool,
+
+        /// List all aktiviteter
+        #[arg(long, group = "kind")]
+        aktiviteter: bool,
+
+        /// List processors and the aktivitet each one handles
+        #[arg(long, group = "kind")]
+        processors: bool,
+
+        /// List aktiviteter without a processor, with processors that may have been meant for them
+        #[arg(long, group = "kind")]
+        missing_processors: bool,
+
+        /// List aktiviteter and their processors that no Behandling's flow reaches
+        #[arg(long, group = "kind")]
+        unreachable: bool,
+
+        /// List the ways each Behandling's flow ends: completed, aborted or a dead end
+        #[arg(long, group = "kind")]
+        endings: bool,
+
+        /// List the aktiviteter every completed behandling passes through, and the skippable ones
+        #[arg(long, group = "kind")]
+        mandatory: bool,
+
+        /// List where the branches of every split in each Behandling's flow meet again
+        #[arg(long, group = "kind")]
+        merge_points: bool,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write one Markdown report per Behandling with a summary table and an embedded Mermaid diagram
+    Report {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+    },
+
+    /// List all routes between two aktiviteter, with the conditions along each transition
+    Paths {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Aktivitet the paths start at
+        #[arg(long)]
+        from: String,
+
+        /// Aktivitet the paths end at
+        #[arg(long)]
+        to: String,
+
+        /// Maximum number of paths to list (shortest first)
+        #[arg(long, default_value_t = 20)]
+        max_paths: usize,
+
+        /// Maximum number of transitions per path
+        #[arg(long, default_value_t = 50)]
+        max_length: usize,
+
+        /// Only the shortest path, also drawn in bold in a graph of the flow
+        #[arg(long)]
+        shortest: bool,
+
+        /// Print the paths as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print per-Behandling metrics: size, branching, depth, cycles, manual, deprecated steps and toggles
+    Stats {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Also list the fan-in and fan-out of every aktivitet
+        #[arg(long)]
+        degrees: bool,
+
+        /// Print the metrics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Start a local web UI listing all Behandlinger and rendering their flows on demand
+    Serve {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+    },
+
+    /// Explore the flows interactively in the terminal
+    Explore {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+    },
+
+    /// Publish diagrams and a summary page per Behandling to a Confluence space
+    Publish {
+        /// Path to the Kotlin project directory, a single .kt file, or - for stdin (defaults to current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<String>,
+
+        /// Confluence base URL (defaults to $CONFLUENCE_URL)
+        #[arg(long)]
+        confluence_url: Option<String>,
+
+        /// Key of the Confluence space to publish into
+        #[arg(long)]
+        space: String,
+
+        /// Id of the page that new flow pages are created under
+        #[arg(long)]
+        parent_id: Option<String>,
+
+        /// Attachment format (svg or png)
+        #[arg(short, long, default_value = "svg")]
+        format: String,
+    },
 }
 
-fn main() -> Result<()> {
+const GRAPHVIZ_DEFAULT_DPI: f64 = 96.0;
+
+fn main() {
     let args = Args::parse();
+    logging::init(
+        args.log_format,
+        if args.quiet {
+            LevelFilter::Warn
+        } else if args.verbose {
+            LevelFilter::Debug
+        } else {
+            LevelFilter::Info
+        },
+    );
 
-    // Use provided path or current directory
-    let root_folder = args.path.unwrap_or_else(|| ".".to_string());
+    // Report failures through the logger, so they are JSON too with --log-format json
+    if let Err(e) = run(&args) {
+        error!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+/// How projects are scanned: which files, whether the model is cached between runs, and the
+/// feature toggle values to simulate
+#[derive(Clone)]
+struct Scan {
+    filter: FileFilter,
+    cache: bool,
+    toggles: HashMap<String, bool>,
+}
+
+impl Scan {
+    /// Build the model of the project at `path` without reporting progress
+    fn analyze(&self, path: &str) -> Result<FlowModel> {
+        let mut model = if self.cache {
+            analyze_project_cached(path, &self.filter, &mut |_, _, _| {}).map(|(model, _)| model)
+        } else {
+            analyze_project_with(path, &self.filter)
+        }?;
+        model.apply_toggles(&self.toggles);
+        Ok(model)
+    }
+}
+
+fn run(args: &Args) -> Result<()> {
+    let scan = args.scan()?;
+
+    match &args.command {
+        Some(Commands::Diff {
+            paths,
+            against,
+            json,
+            graph,
+        }) => run_diff(paths, against.as_deref(), *json, *graph, &scan, args),
+        Some(Commands::Export { path, format }) => {
+            run_export(path.as_deref(), *format, &scan, args)
+        }
+        Some(Commands::Inspect { aktivitet, path }) => {
+            run_inspect(path.as_deref(), aktivitet, &scan)
+        }
+        Some(Commands::Search {
+            pattern,
+            path,
+            json,
+        }) => run_search(path.as_deref(), pattern, *json, &scan),
+        Some(Commands::Lint {
+            path,
+            max_complexity,
+            json,
+        }) => run_lint(path.as_deref(), *max_complexity, *json, &scan),
+        Some(Commands::List {
+            path,
+            aktiviteter,
+            processors,
+            missing_processors,
+            unreachable,
+            endings,
+            mandatory,
+            merge_points,
+            json,
+            ..
+        }) => {
+            let kind = if *aktiviteter {
+                ListKind::Aktiviteter
+            } else if *processors {
+                ListKind::Processors
+            } else if *missing_processors {
+                ListKind::MissingProcessors
+            } else if *unreachable {
+                ListKind::Unreachable
+            } else if *endings {
+                ListKind::Endings
+            } else if *mandatory {
+                ListKind::Mandatory
+            } else if *merge_points {
+                ListKind::MergePoints
+            } else {
+                ListKind::Behandlinger
+            };
+            run_list(path.as_deref(), kind, *json, &scan)
+        }
+        Some(Commands::Report { path }) => run_report(path.as_deref(), &scan, args),
+        Some(Commands::Publish {
+            path,
+            confluence_url,
+            space,
+            parent_id,
+            format,
+        }) => run_publish(
+            path.as_deref(),
+            confluence_url.as_deref(),
+            space,
+            parent_id.as_deref(),
+            format,
+            &scan,
+            args,
+        ),
+        Some(Commands::Paths {
+            path,
+            from,
+            to,
+            max_length,
+            shortest: true,
+            json,
+            ..
+        }) => run_shortest_path(path.as_deref(), from, to, *max_length, *json, &scan, args),
+        Some(Commands::Paths {
+            path,
+            from,
+            to,
+            max_paths,
+            max_length,
+            json,
+            ..
+        }) => run_paths(
+            path.as_deref(),
+            from,
+            to,
+            *max_paths,
+            *max_length,
+            *json,
+            &scan,
+        ),
+        Some(Commands::Stats {
+            path,
+            degrees,
+            json,
+        }) => run_stats(path.as_deref(), *degrees, *json, &scan),
+        Some(Commands::Serve { path, host, port }) => {
+            run_serve(path.as_deref(), host, *port, &scan, args)
+        }
+        Some(Commands::Explore { path }) => run_explore(path.as_deref(), &scan),
+        None => run_generate(&scan, args),
+    }
+}
+
+/// Scan the project and build the flow model, reporting progress
+fn load_project(root_folder: &str, scan: &Scan) -> Result<FlowModel> {
+    if root_folder == STDIN_PATH {
+        info!("🔍 Reading Kotlin source from stdin");
+    } else {
+        info!("🔍 Scanning: {}", root_folder);
+    }
+
+    let mut progress = ScanProgress::new();
+    let mut update = |phase, done, total| progress.update(phase, done, total);
+    let model = if scan.cache {
+        analyze_project_cached(root_folder, &scan.filter, &mut update)
+    } else {
+        analyze_project_with_progress(root_folder, &scan.filter, &mut update)
+            .map(|model| (model, CacheUse::Rebuilt))
+    };
+    progress.finish_phase();
+    let (mut model, cache_use) = model?;
+    if let CacheUse::Reused { reparsed } = cache_use {
+        info!(
+            "♻️  Reused cached model, re-parsed {} changed file(s){}",
+            reparsed,
+            progress.took(ScanPhase::CheckingCache)
+        );
+    }
+
+    info!(
+        "📄 Scanned {} .kt files{}{}",
+        model.files.len(),
+        progress.took(ScanPhase::CollectingFiles),
+        parse_error_summary(&model)
+    );
+    // Not shown when the model came from the cache without parsing anything
+    let parsing = progress.took(ScanPhase::ParsingFiles);
+    if !parsing.is_empty() {
+        info!("🌳 Parsed {} files{}", model.files.len(), parsing);
+    }
+    info!(
+        "📚 Indexed {} classes{}",
+        model.class_index.len(),
+        progress.took(ScanPhase::IndexingClasses)
+    );
+    info!(
+        "🔗 Indexed {} transition functions{}",
+        model.function_index.values().map(Vec::len).sum::<usize>(),
+        progress.took(ScanPhase::IndexingFunctions)
+    );
+    info!(
+        "⚙️  Found {} processors{}",
+        model.processor_index.len(),
+        progress.took(ScanPhase::IndexingProcessors)
+    );
+    if !model.registrations.is_empty() {
+        info!(
+            "🧩 Bound {} processors through registrations{}",
+            model.registrations.len(),
+            progress.took(ScanPhase::IndexingRegistrations)
+        );
+    }
+    warn_duplicate_processors(&model);
+    warn_orphan_processors(&model);
+    apply_toggles(&mut model, &scan.toggles);
+
+    Ok(model)
+}
+
+/// Simulate the --toggle values, warning about toggles no condition uses, which are most
+/// likely misspelled
+fn apply_toggles(model: &mut FlowModel, toggles: &HashMap<String, bool>) {
+    if toggles.is_empty() {
+        return;
+    }
+    let used = model.feature_toggles();
+    let mut names: Vec<&String> = toggles.keys().collect();
+    names.sort();
+    for name in names {
+        if !used.contains(&name.as_str()) {
+            warn!(
+                "⚠️  Feature toggle {} is not used in any transition condition",
+                name
+            );
+        }
+    }
+    model.apply_toggles(toggles);
+    info!("🚩 Simulating {} feature toggle(s)", toggles.len());
+}
+
+/// Note on files that did not parse cleanly for the scan summary, e.g. ` (2 with parse errors)`
+fn parse_error_summary(model: &FlowModel) -> String {
+    match model.files_with_parse_errors().len() {
+        0 => String::new(),
+        files => format!(" ({} with parse errors)", files),
+    }
+}
 
-    // Validate that the path exists
-    let root_path = PathBuf::from(&root_folder);
-    if !root_path.exists() {
-        anyhow::bail!("Path does not exist: {}", root_folder);
+/// Whatever Tree-sitter could not parse is left out of the flows, so point out where that
+/// happened. The locations of the errors are only listed with --verbose.
+fn report_parse_errors(model: &FlowModel) {
+    let files = model.files_with_parse_errors();
+    if files.is_empty() {
+        return;
+    }
+    warn!(
+        "\n⚠️  {} parse error(s) in {} file(s); flows through them may be incomplete:",
+        model.parse_errors.len(),
+        files.len()
+    );
+    for (file, count) in files {
+        warn!("     {} ({} error(s))", file.display(), count);
+    }
+    for error in &model.parse_errors {
+        debug!("     {}", error);
     }
-    if !root_path.is_dir() {
-        anyhow::bail!("Path is not a directory: {}", root_folder);
+}
+
+/// Two processors for one aktivitet are merged, which hides the mistake unless it is pointed out
+fn warn_duplicate_processors(model: &FlowModel) {
+    for (aktivitet, processor) in model.duplicate_processors() {
+        warn!(
+            "  ⚠️  {} is handled by more than one processor; their transitions are merged:",
+            aktivitet
+        );
+        let processors = std::iter::once((&processor.processor_class, &processor.file)).chain(
+            processor
+                .other_processors
+                .iter()
+                .map(|(class, file)| (class, file)),
+        );
+        for (processor_class, file) in processors {
+            warn!("     {} ({})", processor_class, file.display());
+        }
     }
+}
 
-    println!("🔍 Scanning directory: {}", root_folder);
+/// A processor for an aktivitet that is not declared is left out of every graph, so point it out
+fn warn_orphan_processors(model: &FlowModel) {
+    for orphan in orphan_processors(model) {
+        warn!(
+            "  ⚠️  {} handles {}, which is not declared anywhere; it is left out of the flows",
+            orphan.processor, orphan.aktivitet
+        );
+        if let Some(location) = &orphan.location {
+            warn!("     {}", location);
+        }
+        for suggestion in &orphan.suggestions {
+            warn!("     ? {}", suggestion);
+        }
+    }
+}
 
-    // 2. Initialize Tree-sitter Kotlin parser
-    let mut parser = Parser::new();
-    parser
-        .set_language(&tree_sitter_kotlin::language())
-        .context("Failed to set Kotlin language")?;
+/// Determine the output directory, creating it if needed
+fn resolve_output_dir(args: &Args) -> Result<PathBuf> {
+    let output_dir = args
+        .output_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::current_dir().unwrap());
 
-    // 3. Walk all subfolders and collect .kt files
-    let kt_files = collect_kotlin_files(&root_folder)?;
-    if kt_files.is_empty() {
-        anyhow::bail!("No .kt files found in directory: {}", root_folder);
+    if !output_dir.exists() {
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create output directory: {:?}", output_dir))?;
     }
-    println!("📄 Scanned {} .kt files", kt_files.len());
 
-    // 4. Build a class index
-    let class_index = build_class_index(&mut parser, &kt_files)?;
-    println!("📚 Indexed {} classes", class_index.len());
+    Ok(output_dir)
+}
 
-    // 4.5. Build processor index
-    let processor_index = build_processor_index(&mut parser, &kt_files)?;
-    println!("⚙️  Found {} processors", processor_index.len());
+fn run_generate(scan: &Scan, args: &Args) -> Result<()> {
+    // Use provided path or current directory
+    let root_folder = args.path.clone().unwrap_or_else(|| ".".to_string());
+    let model = load_project(&root_folder, scan)?;
+    let duplicates = model.duplicate_processors().len();
+    if args.strict && duplicates > 0 {
+        anyhow::bail!(
+            "{} aktivitet(er) handled by more than one processor (--strict)",
+            duplicates
+        );
+    }
 
     if args.verbose {
         println!("\n=== PROCESSOR DETAILS ===");
-        let mut processors: Vec<_> = processor_index.iter().collect();
+        let mut processors: Vec<_> = model.processor_index.iter().collect();
         processors.sort_by(|a, b| a.0.cmp(b.0));
         for (aktivitet, info) in processors {
             println!("\n  {} (handled by {})", aktivitet, info.processor_class);
+            if let Some(location) = model.processor_location(info) {
+                println!("    📍 {}", location);
+            }
             if info.has_manuell_behandling {
                 println!("    📋 Creates manuellBehandling");
             }
-            if info.next_aktiviteter.is_empty() {
-                println!("    → [END]");
-            } else {
-                for next in &info.next_aktiviteter {
-                    if let Some(condition) = &next.condition {
-                        println!("    → [{}] {}", condition, next.aktivitet_name);
-                    } else {
-                        println!("    → {}", next.aktivitet_name);
-                    }
+            for manuell in &info.manuell_behandlinger {
+                if let Some(details) = manuell.details() {
+                    println!("       {}  @ {}", details, manuell.location);
+                }
+            }
+            for next in &info.next_aktiviteter {
+                if let Some(condition) = &next.condition {
+                    println!(
+                        "    → [{}] {}  @ {}",
+                        condition,
+                        next.describe(),
+                        next.location
+                    );
+                } else {
+                    println!("    → {}  @ {}", next.describe(), next.location);
                 }
             }
+            for terminal in &info.terminals {
+                println!("    → [{}]", terminal.describe());
+            }
+            if info.is_unresolved() {
+                println!("    → [END?] (no transition found)");
+            }
+        }
+
+        let missing = missing_processors(&model);
+        if !missing.is_empty() {
+            println!("\n=== AKTIVITETER WITHOUT PROCESSOR ===");
+            print_missing_processors(&missing);
+        }
+
+        let unreachable = unreachable_aktiviteter(&model);
+        if !unreachable.is_empty() {
+            println!("\n=== AKTIVITETER NOT REACHABLE FROM ANY BEHANDLING ===");
+            print_unreachable_aktiviteter(&unreachable);
         }
     }
 
@@ -155,38 +1053,29 @@ fn main() -> Result<()> {
         println!("\n=== SUMMARY ===");
     }
 
-    // Find main Behandling classes (ones with initial aktivitet)
-    let mut main_behandling_classes: Vec<_> = class_index
-        .iter()
-        .filter(|(_, info)| {
-            info.supertypes.iter().any(|s| s.contains("Behandling"))
-                && info.initial_aktivitet.is_some()
-        })
-        .collect();
-
-    main_behandling_classes.sort_by(|a, b| a.0.cmp(b.0));
+    if model.behandlinger().is_empty() {
+        anyhow::bail!("No Behandling classes with initial aktivitet found!");
+    }
+    let main_behandling_classes = args.select_behandlinger(&model)?;
 
-    if !main_behandling_classes.is_empty() {
-        if args.verbose {
-            println!("\nMain Behandling classes with initial aktivitet:");
-            for (name, info) in &main_behandling_classes {
-                println!(
-                    "\n  {} ({})",
-                    name,
-                    info.file.file_name().unwrap().to_string_lossy()
-                );
-                if let Some(initial) = &info.initial_aktivitet {
-                    println!("    → opprettInitiellAktivitet() returns: {}", initial);
-                }
+    if args.verbose {
+        println!("\nMain Behandling classes with initial aktivitet:");
+        for (name, info) in &main_behandling_classes {
+            println!(
+                "\n  {} ({})",
+                name,
+                info.file.file_name().unwrap().to_string_lossy()
+            );
+            if let Some(initial) = &info.initial_aktivitet {
+                println!("    → opprettInitiellAktivitet() returns: {}", initial);
             }
         }
-    } else {
-        anyhow::bail!("No Behandling classes with initial aktivitet found!");
     }
 
     if args.verbose {
         println!("\n\n=== ALL BEHANDLING CLASSES ===");
-        let mut all_behandling: Vec<_> = class_index
+        let mut all_behandling: Vec<_> = model
+            .class_index
             .iter()
             .filter(|(_, info)| info.supertypes.iter().any(|s| s.contains("Behandling")))
             .collect();
@@ -210,26 +1099,18 @@ fn main() -> Result<()> {
                 println!("  Starting with: {}", initial_aktivitet);
 
                 let mut visited = std::collections::HashSet::new();
-                traverse_aktivitet_flow(initial_aktivitet, &processor_index, &mut visited, 1);
+                traverse_aktivitet_flow(initial_aktivitet, &model.processor_index, &mut visited, 1);
 
-                // Detect and report cycles for this flow
-                let cycles = detect_cycles(initial_aktivitet, &processor_index);
+                // Report every way around each loop of this flow
+                let cycles =
+                    find_cycles(initial_aktivitet, &model.processor_index, MAX_CYCLE_PATHS);
                 if !cycles.is_empty() {
                     println!("\n  🔄 Detected {} cycle(s) in this flow:", cycles.len());
-                    let mut cycle_pairs: std::collections::HashSet<String> =
-                        std::collections::HashSet::new();
-                    for (from, to) in &cycles {
-                        let pair_desc = format!(
-                            "    {} ↩ {}",
-                            shorten_aktivitet_name(from),
-                            shorten_aktivitet_name(to)
-                        );
-                        cycle_pairs.insert(pair_desc);
+                    for cycle in &cycles {
+                        println!("    {}", format_cycle(cycle));
                     }
-                    let mut pairs: Vec<_> = cycle_pairs.into_iter().collect();
-                    pairs.sort();
-                    for pair in pairs {
-                        println!("{}", pair);
+                    if cycles.len() == MAX_CYCLE_PATHS {
+                        println!("    (showing the first {})", MAX_CYCLE_PATHS);
                     }
                 }
             }
@@ -237,2111 +1118,2119 @@ fn main() -> Result<()> {
     }
 
     // 7. Generate DOT graph and convert to requested format
-    println!("\n📊 Generating graphs...");
-
-    // Determine output directory
-    let output_dir = args
-        .output_dir
-        .as_ref()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| env::current_dir().unwrap());
+    info!("\n📊 Generating graphs...");
+
+    let output_dir = resolve_output_dir(args)?;
+    let changed_files = changed_files(&model, &root_folder, args)?;
+    let execution_counts = load_execution_counts(&model, args)?;
+    let durations = load_durations(&model, args)?;
+    let dot_options = DotOptions {
+        changed_files: changed_files.as_ref(),
+        execution_counts: execution_counts.as_ref(),
+        durations: durations.as_ref(),
+        ..args.dot_options()
+    };
 
-    if !output_dir.exists() {
-        fs::create_dir_all(&output_dir)
-            .with_context(|| format!("Failed to create output directory: {:?}", output_dir))?;
+    if let Some(changed_files) = &changed_files {
+        for (name, info) in &main_behandling_classes {
+            if let Some(initial_aktivitet) = &info.initial_aktivitet {
+                report_change_impact(name, initial_aktivitet, &model, changed_files);
+            }
+        }
     }
 
     let mut generated_files = Vec::new();
 
-    for (name, info) in &main_behandling_classes {
-        if let Some(initial_aktivitet) = &info.initial_aktivitet {
-            let dot_content = generate_dot_graph(
-                name,
-                initial_aktivitet,
-                &processor_index,
-                &class_index,
-                &args.edge_style,
-                args.show_conditions,
-                args.show_legend,
-                !args.no_deduplicate,
-            )?;
-
-            let dot_filename = output_dir.join(format!("{}_flow.dot", name));
-            fs::write(&dot_filename, dot_content)
-                .with_context(|| format!("Failed to write DOT file: {:?}", dot_filename))?;
-
-            if args.verbose {
-                println!("  ✓ Generated DOT: {}", dot_filename.display());
+    if args.booklet {
+        let booklet_filename = generate_booklet(
+            &main_behandling_classes,
+            &model.processor_index,
+            &model.class_index,
+            &dot_options,
+            &output_dir,
+            args,
+        )?;
+        info!("  ✅ Generated: {}", booklet_filename.display());
+        generated_files.push(booklet_filename);
+    } else {
+        for (name, _) in &main_behandling_classes {
+            let dot_content = model.to_dot(name, &dot_options)?;
+            if let Some(output_filename) =
+                generate_flow_files(name, dot_content, &model, &dot_options, &output_dir, args)?
+            {
+                generated_files.push(output_filename);
             }
+        }
+    }
 
-            // Convert to requested format using graphviz
-            let output_filename = output_dir.join(format!("{}_flow.{}", name, args.format));
+    if args.module_overview {
+        let names: Vec<&str> = main_behandling_classes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let dot_content = model.to_module_overview_dot(&names, &args.dot_options())?;
+        if let Some(output_filename) =
+            write_overview("module_overview", &dot_content, &output_dir, args)?
+        {
+            generated_files.push(output_filename);
+        }
+    }
 
-            let status = Command::new("dot")
-                .arg(format!("-T{}", args.format))
-                .arg(&dot_filename)
-                .arg("-o")
-                .arg(&output_filename)
-                .status();
+    if args.behandling_overview {
+        let names: Vec<&str> = main_behandling_classes
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let dot_content = model.to_behandling_overview_dot(&names, &args.dot_options())?;
+        if let Some(output_filename) =
+            write_overview("behandling_overview", &dot_content, &output_dir, args)?
+        {
+            generated_files.push(output_filename);
+        }
+    }
 
-            match status {
-                Ok(s) if s.success() => {
-                    println!("  ✅ Generated: {}", output_filename.display());
-                    generated_files.push(output_filename.clone());
+    if args.fail_on_unknown {
+        check_unknown_aktiviteter(&model, &main_behandling_classes, args)?;
+    }
 
-                    // Delete the .dot file unless --keep-dot is specified
-                    if !args.keep_dot {
-                        let _ = fs::remove_file(&dot_filename);
-                    }
-                }
-                Ok(s) => {
-                    eprintln!(
-                        "  ⚠️  Warning: graphviz 'dot' command failed with status: {}",
-                        s
-                    );
-                    eprintln!("     DOT file saved at: {}", dot_filename.display());
-                    eprintln!(
-                        "     You can manually convert it with: dot -T{} {} -o {}",
-                        args.format,
-                        dot_filename.display(),
-                        output_filename.display()
-                    );
-                }
-                Err(e) => {
-                    eprintln!("  ⚠️  Warning: Could not run graphviz 'dot' command: {}", e);
-                    eprintln!("     Make sure graphviz is installed (brew install graphviz / apt install graphviz)");
-                    eprintln!("     DOT file saved at: {}", dot_filename.display());
-                }
-            }
-        }
+    if let Some(baseline) = &args.assert {
+        check_baseline(&model, baseline, args.update_baseline)?;
     }
 
     // Open all generated files (if --open is specified)
     if args.open && !generated_files.is_empty() {
-        println!("\n🚀 Opening {} file(s)...", generated_files.len());
+        info!("\n🚀 Opening {} file(s)...", generated_files.len());
 
         for file in &generated_files {
-            if args.verbose {
-                println!("  Opening {}...", file.display());
-            }
+            debug!("  Opening {}...", file.display());
 
             match opener::open(file) {
-                Ok(_) => {
-                    if args.verbose {
-                        println!("    ✓ Opened successfully");
-                    }
-                }
+                Ok(_) => debug!("    ✓ Opened successfully"),
                 Err(e) => {
-                    eprintln!(
+                    warn!(
                         "  ⚠️  Could not automatically open {}: {}",
                         file.display(),
                         e
                     );
-                    eprintln!("     Please open manually: {}", file.display());
+                    warn!("     Please open manually: {}", file.display());
                 }
             }
         }
     }
 
-    println!("\n✨ Done!");
-    Ok(())
-}
+    report_parse_errors(&model);
 
-fn traverse_aktivitet_flow(
-    aktivitet_name: &str,
-    processor_index: &HashMap<String, ProcessorInfo>,
-    visited: &mut std::collections::HashSet<String>,
-    depth: usize,
-) {
-    if visited.contains(aktivitet_name) {
-        println!(
-            "{}  [CYCLE DETECTED: {}]",
-            "  ".repeat(depth),
-            aktivitet_name
-        );
-        return;
+    if args.watch {
+        return watch_and_regenerate(&root_folder, model, &dot_options, &output_dir, scan, args);
     }
 
-    visited.insert(aktivitet_name.to_string());
-
-    if let Some(processor) = processor_index.get(aktivitet_name) {
-        if processor.next_aktiviteter.is_empty() {
-            println!("{}  → [END]", "  ".repeat(depth));
-        } else if processor.next_aktiviteter.len() == 1 {
-            let next = &processor.next_aktiviteter[0];
-            println!("{}  → {}", "  ".repeat(depth), next.aktivitet_name);
-            traverse_aktivitet_flow(&next.aktivitet_name, processor_index, visited, depth + 1);
-        } else {
-            // Multiple branches
-            for next in &processor.next_aktiviteter {
-                if let Some(condition) = &next.condition {
-                    println!(
-                        "{}  → [IF {}] {}",
-                        "  ".repeat(depth),
-                        condition,
-                        next.aktivitet_name
-                    );
-                } else {
-                    println!("{}  → [ELSE] {}", "  ".repeat(depth), next.aktivitet_name);
-                }
-                let mut branch_visited = visited.clone();
-                traverse_aktivitet_flow(
-                    &next.aktivitet_name,
-                    processor_index,
-                    &mut branch_visited,
-                    depth + 1,
-                );
-            }
-        }
-    } else {
-        println!("{}  → [PROCESSOR NOT FOUND]", "  ".repeat(depth));
-    }
+    info!("\n✨ Done!");
+    Ok(())
 }
 
-fn collect_kotlin_files(root: &str) -> Result<Vec<PathBuf>> {
-    let mut kt_files = Vec::new();
-
-    for entry in WalkDir::new(root)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().is_file())
-    {
-        if let Some(ext) = entry.path().extension() {
-            if ext == "kt" {
-                kt_files.push(entry.path().to_path_buf());
-            }
-        }
+/// The scanned files named by --changed and --changed-since, as in `FlowModel::files`, or None if
+/// neither option is given. Deleted files and files outside the scan define nothing and are left out.
+fn changed_files(
+    model: &FlowModel,
+    root_folder: &str,
+    args: &Args,
+) -> Result<Option<HashSet<PathBuf>>> {
+    if args.changed.is_empty() && args.changed_since.is_none() {
+        return Ok(None);
+    }
+    let mut named: Vec<PathBuf> = args.changed.iter().map(PathBuf::from).collect();
+    if let Some(git_ref) = &args.changed_since {
+        named.extend(git_changed_files(root_folder, git_ref)?);
     }
 
-    Ok(kt_files)
+    let named: HashSet<PathBuf> = named
+        .iter()
+        .filter_map(|file| file.canonicalize().ok())
+        .collect();
+    let changed: HashSet<PathBuf> = model
+        .files
+        .iter()
+        .filter(|file| file.canonicalize().is_ok_and(|file| named.contains(&file)))
+        .cloned()
+        .collect();
+    if changed.is_empty() {
+        warn!("⚠️  None of the changed files is a scanned Kotlin file");
+    } else {
+        info!("🎯 {} changed file(s) in the scan", changed.len());
+    }
+    Ok(Some(changed))
 }
 
-fn build_class_index(parser: &mut Parser, files: &[PathBuf]) -> Result<HashMap<String, ClassInfo>> {
-    let mut index = HashMap::new();
-
-    for file in files {
-        let source_code = fs::read_to_string(file)
-            .with_context(|| format!("Failed to read file: {}", file.display()))?;
-
-        let tree = parser
-            .parse(&source_code, None)
-            .context("Failed to parse file")?;
-
-        let root_node = tree.root_node();
+/// The execution counts given with --counts, warning about aktiviteter in the export that the
+/// project does not have
+fn load_execution_counts(model: &FlowModel, args: &Args) -> Result<Option<ExecutionCounts>> {
+    let Some(path) = &args.counts else {
+        return Ok(None);
+    };
+    let counts = ExecutionCounts::load(path)?;
+    warn_unknown_metric_names(model, &counts.names(), path);
+    info!(
+        "📈 Read execution counts of {} aktivitet(er), at most {}",
+        counts.names().len(),
+        counts.max()
+    );
+    Ok(Some(counts))
+}
 
-        // Extract all class declarations
-        extract_classes(&source_code, root_node, file, &mut index);
+/// The durations given with --durations, warning about aktiviteter the project does not have
+fn load_durations(model: &FlowModel, args: &Args) -> Result<Option<AktivitetDurations>> {
+    let Some(path) = &args.durations else {
+        return Ok(None);
+    };
+    let durations = AktivitetDurations::load(path)?;
+    warn_unknown_metric_names(model, &durations.names(), path);
+    if let Some((aktivitet, longest)) = durations.max_entry() {
+        info!(
+            "⏱  Read durations of {} aktivitet(er), longest {} ({})",
+            durations.names().len(),
+            aktivitet,
+            format_duration(longest)
+        );
     }
+    Ok(Some(durations))
+}
 
-    // Second pass: extract opprettInitiellAktivitet for Behandling classes
-    for file in files {
-        let source_code = fs::read_to_string(file)
-            .with_context(|| format!("Failed to read file: {}", file.display()))?;
-
-        let tree = parser
-            .parse(&source_code, None)
-            .context("Failed to parse file")?;
+/// Warn about the aktiviteter in a metrics export that the project does not have, which were most
+/// likely renamed or removed
+fn warn_unknown_metric_names(model: &FlowModel, names: &[&str], path: &Path) {
+    let known = ExecutionCounts::new(
+        model
+            .aktiviteter()
+            .into_iter()
+            .map(|aktivitet| (aktivitet, 0))
+            .collect(),
+    );
+    let unknown: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| known.get(name).is_none())
+        .collect();
+    if !unknown.is_empty() {
+        warn!(
+            "⚠️  {} aktivitet(er) in {} not found in the project: {}",
+            unknown.len(),
+            path.display(),
+            unknown.join(", ")
+        );
+    }
+}
 
-        let root_node = tree.root_node();
+/// The files below `path` that differ from `git_ref`, staged or not, and the new files git does
+/// not track yet (unless ignored)
+fn git_changed_files(path: &str, git_ref: &str) -> Result<Vec<PathBuf>> {
+    // `--end-of-options` keeps a ref starting with `-` from being read as an option, and `--`
+    // keeps it from being read as a path
+    let mut files = git_files(
+        path,
+        git_ref,
+        &[
+            "diff",
+            "--name-only",
+            "--relative",
+            "-z",
+            "--end-of-options",
+            git_ref,
+            "--",
+        ],
+    )?;
+    files.extend(git_files(
+        path,
+        git_ref,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )?);
+    Ok(files)
+}
 
-        extract_initial_aktivitet(&source_code, root_node, &mut index);
+/// The files a git command run in `path` lists with `-z`, NUL-separated and relative to `path`,
+/// so that names are not quoted
+fn git_files(path: &str, git_ref: &str, args: &[&str]) -> Result<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(args)
+        .output()
+        .context("Could not run 'git' (is git installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Could not compare {} with {}: {}",
+            path,
+            git_ref,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
-
-    Ok(index)
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(|file| Path::new(path).join(file))
+        .collect())
 }
 
-fn extract_classes(
-    source: &str,
-    node: tree_sitter::Node,
-    file: &PathBuf,
-    index: &mut HashMap<String, ClassInfo>,
+/// Print what the changed files define in a flow and how many aktiviteter are downstream of it
+fn report_change_impact(
+    name: &str,
+    initial_aktivitet: &str,
+    model: &FlowModel,
+    changed_files: &HashSet<PathBuf>,
 ) {
-    let mut cursor = node.walk();
-
-    // Recursively traverse the tree
-    fn visit_node(
-        cursor: &mut tree_sitter::TreeCursor,
-        source: &str,
-        file: &PathBuf,
-        index: &mut HashMap<String, ClassInfo>,
-    ) {
-        let node = cursor.node();
-
-        if node.kind() == "class_declaration" {
-            // Extract class name and supertypes
-            if let Some(class_info) = extract_class_info(node, source, file) {
-                index.insert(class_info.name.clone(), class_info);
-            }
-        }
-
-        // Recurse into children
-        if cursor.goto_first_child() {
-            loop {
-                visit_node(cursor, source, file, index);
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
-        }
+    let edges = collect_flow_edges(
+        initial_aktivitet,
+        &model.processor_index,
+        &model.class_index,
+    );
+    let impact = change_impact(
+        initial_aktivitet,
+        &model.processor_index,
+        &model.class_index,
+        &edges,
+        changed_files,
+    );
+    if impact.is_empty() {
+        debug!("  {}: not touched by the changed files", name);
+        return;
     }
-
-    visit_node(&mut cursor, source, file, index);
+    info!(
+        "  🎯 {}: {} aktivitet(er) and {} transition(s) changed, {} aktivitet(er) downstream",
+        name,
+        impact.changed.len(),
+        impact.changed_transitions.len(),
+        impact.affected.len()
+    );
 }
 
-fn extract_class_info(
-    class_node: tree_sitter::Node,
-    source: &str,
-    file: &PathBuf,
-) -> Option<ClassInfo> {
-    let mut class_name = None;
-    let mut supertypes = Vec::new();
-
-    let mut cursor = class_node.walk();
-
-    // Look for simple_identifier (class name) and delegation_specifier (supertypes)
-    for child in class_node.children(&mut cursor) {
-        match child.kind() {
-            "simple_identifier" | "type_identifier" => {
-                if class_name.is_none() {
-                    let name = child.utf8_text(source.as_bytes()).ok()?.to_string();
-                    class_name = Some(name);
-                }
-            }
-            "delegation_specifier" => {
-                if let Some(supertype) = extract_single_supertype(child, source) {
-                    supertypes.push(supertype);
+/// Report every aktivitet without a processor in the generated graphs, once with the flows it is
+/// in and the files that refer to it, and fail if there are any
+fn check_unknown_aktiviteter(
+    model: &FlowModel,
+    behandlinger: &[(&String, &ClassInfo)],
+    args: &Args,
+) -> Result<()> {
+    let options = args.dot_options();
+    // By aktivitet: the flows it is in, and the files that refer to it
+    let mut unknown: BTreeMap<String, (Vec<&str>, BTreeSet<String>)> = BTreeMap::new();
+
+    for (name, info) in behandlinger {
+        let Some(start) = args.start_from.as_ref().or(info.initial_aktivitet.as_ref()) else {
+            continue;
+        };
+        let edges = drawn_flow_edges(start, &model.processor_index, &model.class_index, &options);
+
+        for (aktivitet, referenced_from) in unknown_aktiviteter(&edges) {
+            let (flows, files) = unknown.entry(aktivitet).or_default();
+            flows.push(name.as_str());
+            if referenced_from.is_empty() {
+                files.insert(info.file.display().to_string());
+            }
+            for from in referenced_from {
+                if let Some(processor) = model.processor_index.get(&from) {
+                    files.insert(format!(
+                        "{} ({})",
+                        processor.file.display(),
+                        processor.processor_class
+                    ));
                 }
             }
-            _ => {}
         }
     }
 
-    class_name.map(|name| ClassInfo {
-        name,
-        file: file.clone(),
-        supertypes,
-        initial_aktivitet: None,
-    })
-}
-
-fn extract_single_supertype(delegation_node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = delegation_node.walk();
-
-    for child in delegation_node.children(&mut cursor) {
-        if child.kind() == "user_type"
-            || child.kind() == "type_identifier"
-            || child.kind() == "constructor_invocation"
-        {
-            return Some(extract_type_name(child, source));
+    for (aktivitet, (flows, files)) in &unknown {
+        error!(
+            "  ❓ {} in {} has no processor",
+            aktivitet,
+            flows.join(", ")
+        );
+        for file in files {
+            error!("     referenced from {}", file);
         }
     }
 
-    None
-}
-
-fn extract_type_name(node: tree_sitter::Node, source: &str) -> String {
-    match node.kind() {
-        "user_type" => {
-            // For user_type, concatenate all type_identifier children
-            let mut cursor = node.walk();
-            let mut parts = Vec::new();
-
-            for child in node.children(&mut cursor) {
-                if child.kind() == "type_identifier" || child.kind() == "simple_identifier" {
-                    if let Ok(text) = child.utf8_text(source.as_bytes()) {
-                        parts.push(text.to_string());
-                    }
-                }
-            }
-
-            if !parts.is_empty() {
-                parts.join(".")
-            } else {
-                node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
-            }
-        }
-        "constructor_invocation" => {
-            // For constructor invocations like "Behandling()", extract the type
-            let mut cursor = node.walk();
-            for child in node.children(&mut cursor) {
-                if child.kind() == "user_type"
-                    || child.kind() == "type_identifier"
-                    || child.kind() == "simple_identifier"
-                {
-                    return extract_type_name(child, source);
-                }
-            }
-            node.utf8_text(source.as_bytes()).unwrap_or("").to_string()
-        }
-        _ => node.utf8_text(source.as_bytes()).unwrap_or("").to_string(),
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "{} aktivitet(s) without a processor in the generated graphs (--fail-on-unknown)",
+            unknown.len()
+        );
     }
+    Ok(())
 }
 
-fn extract_initial_aktivitet(
-    source: &str,
-    node: tree_sitter::Node,
-    index: &mut HashMap<String, ClassInfo>,
-) {
-    let mut cursor = node.walk();
-
-    fn visit_node(
-        cursor: &mut tree_sitter::TreeCursor,
-        source: &str,
-        index: &mut HashMap<String, ClassInfo>,
-        current_class: &mut Option<String>,
-    ) {
-        let node = cursor.node();
-
-        match node.kind() {
-            "class_declaration" => {
-                // Track which class we're in
-                let mut class_cursor = node.walk();
-                for child in node.children(&mut class_cursor) {
-                    if child.kind() == "type_identifier" || child.kind() == "simple_identifier" {
-                        if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                            *current_class = Some(name.to_string());
-                            break;
-                        }
-                    }
-                }
-            }
-            "function_declaration" => {
-                // Check if this is opprettInitiellAktivitet
-                if let Some(class_name) = current_class {
-                    if is_opprett_initiell_aktivitet(node, source) {
-                        if let Some(aktivitet_name) =
-                            extract_return_type_from_function(node, source)
-                        {
-                            if let Some(class_info) = index.get_mut(class_name) {
-                                class_info.initial_aktivitet = Some(aktivitet_name);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
+/// Compare the flows of every Behandling with the baseline and fail on any difference, or write
+/// them to the baseline with `update`
+fn check_baseline(model: &FlowModel, path: &Path, update: bool) -> Result<()> {
+    let current = Baseline::from_model(model);
+    if update {
+        current.save(path)?;
+        info!(
+            "  ✅ Updated baseline with {} flow(s): {}",
+            current.behandlinger.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+    if !path.exists() {
+        anyhow::bail!(
+            "Baseline {} does not exist; create it with --update-baseline",
+            path.display()
+        );
+    }
 
-        // Recurse into children
-        if cursor.goto_first_child() {
-            loop {
-                visit_node(cursor, source, index, current_class);
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
+    let diffs = diff_baseline(&Baseline::load(path)?, model);
+    if diffs.is_empty() {
+        info!("  ✅ Flows match the baseline {}", path.display());
+        return Ok(());
+    }
+    for diff in &diffs {
+        for line in diff_lines(diff) {
+            error!("{}", line);
         }
     }
-
-    let mut current_class = None;
-    visit_node(&mut cursor, source, index, &mut current_class);
+    anyhow::bail!(
+        "{} flow(s) differ from the baseline {} (--assert); run with --update-baseline if the change is intended",
+        diffs.len(),
+        path.display()
+    );
 }
 
-fn is_opprett_initiell_aktivitet(func_node: tree_sitter::Node, source: &str) -> bool {
-    let mut cursor = func_node.walk();
-    for child in func_node.children(&mut cursor) {
-        if child.kind() == "simple_identifier" {
-            if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                return name == "opprettInitiellAktivitet";
-            }
+/// Regenerate the flows whose graph changed every time a .kt file changes, until interrupted
+fn watch_and_regenerate(
+    root_folder: &str,
+    mut model: FlowModel,
+    dot_options: &DotOptions,
+    output_dir: &Path,
+    scan: &Scan,
+    args: &Args,
+) -> Result<()> {
+    let mut previous_dots = HashMap::new();
+    for (name, _) in args.select_behandlinger(&model)? {
+        previous_dots.insert(name.clone(), model.to_dot(name, dot_options)?);
+    }
+
+    info!(
+        "\n👀 Watching {} for changes (Ctrl+C to stop)...",
+        root_folder
+    );
+
+    watch_kotlin_sources(Path::new(root_folder), &scan.filter, |changed| {
+        info!("\n🔄 {} file(s) changed", changed.len());
+        if let Err(e) = regenerate_changed_flows(
+            &mut model,
+            &changed,
+            &mut previous_dots,
+            dot_options,
+            output_dir,
+            args,
+        ) {
+            warn!("  ⚠️  Regeneration failed: {:#}", e);
         }
-    }
-    false
+    })
 }
 
-fn extract_return_type_from_function(func_node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = func_node.walk();
-
-    // First, try to find a call_expression in the function body
-    for child in func_node.children(&mut cursor) {
-        if child.kind() == "function_body" {
-            if let Some(call_type) = find_constructor_call(child, source) {
-                return Some(call_type);
-            }
+/// Re-analyze the changed files and rewrite only the flows whose DOT graph is different
+fn regenerate_changed_flows(
+    model: &mut FlowModel,
+    changed: &[PathBuf],
+    previous_dots: &mut HashMap<String, String>,
+    dot_options: &DotOptions,
+    output_dir: &Path,
+    args: &Args,
+) -> Result<()> {
+    reanalyze_files(model, changed)?;
+    model.apply_toggles(&args.toggles());
+    // A half-typed edit often does not parse; say so rather than draw a flow with a gap
+    for error in &model.parse_errors {
+        if changed.contains(&error.location.file) {
+            warn!("  ⚠️  Parse error at {}", error);
+        }
+    }
+
+    let selected = args.select_behandlinger(model)?;
+    let mut changed_flows = Vec::new();
+    for &(name, _) in &selected {
+        let dot_content = model.to_dot(name, dot_options)?;
+        if previous_dots.get(name) != Some(&dot_content) {
+            previous_dots.insert(name.clone(), dot_content.clone());
+            changed_flows.push((name.clone(), dot_content));
+        }
+    }
+
+    if changed_flows.is_empty() {
+        info!("  No flow changes");
+    } else if args.booklet {
+        let booklet_filename = generate_booklet(
+            &selected,
+            &model.processor_index,
+            &model.class_index,
+            dot_options,
+            output_dir,
+            args,
+        )?;
+        info!("  ✅ Generated: {}", booklet_filename.display());
+    } else {
+        for (name, dot_content) in changed_flows {
+            generate_flow_files(&name, dot_content, model, dot_options, output_dir, args)?;
         }
     }
 
-    None
+    Ok(())
 }
 
-fn build_processor_index(
-    parser: &mut Parser,
-    files: &[PathBuf],
-) -> Result<HashMap<String, ProcessorInfo>> {
-    let mut index = HashMap::new();
-
-    for file in files {
-        let source_code = fs::read_to_string(file)
-            .with_context(|| format!("Failed to read file: {}", file.display()))?;
-
-        let tree = parser
-            .parse(&source_code, None)
-            .context("Failed to parse file")?;
-
-        let root_node = tree.root_node();
-
-        extract_processors(&source_code, root_node, &mut index);
-    }
+/// Write the DOT file, the rendered graph and the optional AsciiDoc page for one flow.
+/// Returns the rendered file, or None if rendering failed and only the DOT file was kept.
+fn generate_flow_files(
+    name: &str,
+    dot_content: String,
+    model: &FlowModel,
+    dot_options: &DotOptions,
+    output_dir: &Path,
+    args: &Args,
+) -> Result<Option<PathBuf>> {
+    let mut generated = None;
+
+    let dot_filename = output_dir.join(format!("{}_flow.dot", name));
+    fs::write(&dot_filename, &dot_content)
+        .with_context(|| format!("Failed to write DOT file: {:?}", dot_filename))?;
+
+    debug!("  ✓ Generated DOT: {}", dot_filename.display());
+
+    // Convert to requested format using graphviz
+    let output_filename = output_dir.join(format!("{}_flow.{}", name, args.format));
+
+    match render_dot(&dot_content, &args.format) {
+        Ok(output) => {
+            fs::write(&output_filename, output)
+                .with_context(|| format!("Failed to write output file: {:?}", output_filename))?;
+            info!("  ✅ Generated: {}", output_filename.display());
+            generated = Some(output_filename.clone());
+
+            // Delete the .dot file unless --keep-dot is specified
+            if !args.keep_dot {
+                let _ = fs::remove_file(&dot_filename);
+            }
+        }
+        Err(e) if is_graphviz_missing(&e) && args.format == "svg" => {
+            // No graphviz installed - fall back to the built-in layout engine
+            warn!("  ⚠️  Graphviz 'dot' not found, using built-in renderer (simpler layout)");
+            let fallback_dot = if args.show_legend {
+                // The HTML legend table is not supported by the built-in renderer
+                model.to_dot(
+                    name,
+                    &DotOptions {
+                        show_legend: false,
+                        subtitle: None,
+                        ..*dot_options
+                    },
+                )?
+            } else {
+                dot_content
+            };
 
-    Ok(index)
-}
+            match render_svg_with_layout(&fallback_dot) {
+                Ok(svg) => {
+                    fs::write(&output_filename, svg).with_context(|| {
+                        format!("Failed to write SVG file: {:?}", output_filename)
+                    })?;
+                    info!("  ✅ Generated: {}", output_filename.display());
+                    generated = Some(output_filename.clone());
 
-fn extract_processors(
-    source: &str,
-    node: tree_sitter::Node,
-    index: &mut HashMap<String, ProcessorInfo>,
-) {
-    let mut cursor = node.walk();
-
-    fn visit_node(
-        cursor: &mut tree_sitter::TreeCursor,
-        source: &str,
-        index: &mut HashMap<String, ProcessorInfo>,
-        current_class: &mut Option<String>,
-        current_aktivitet_class: &mut Option<String>,
-    ) {
-        let node = cursor.node();
-
-        match node.kind() {
-            "class_declaration" => {
-                // Extract class name
-                let mut class_cursor = node.walk();
-                for child in node.children(&mut class_cursor) {
-                    if child.kind() == "type_identifier" || child.kind() == "simple_identifier" {
-                        if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                            *current_class = Some(name.to_string());
-
-                            // Check if this is a processor (ends with Processor)
-                            if name.ends_with("Processor") {
-                                // Try to extract the aktivitet class from the supertype
-                                if let Some(aktivitet) =
-                                    extract_aktivitet_from_processor(node, source)
-                                {
-                                    *current_aktivitet_class = Some(aktivitet);
-                                }
-                            }
-                            break;
-                        }
+                    if !args.keep_dot {
+                        let _ = fs::remove_file(&dot_filename);
                     }
                 }
-            }
-            "function_declaration" => {
-                // Check if this is doProcess or onFinished
-                if let Some(processor_class) = current_class {
-                    if let Some(aktivitet_class) = current_aktivitet_class {
-                        if is_do_process_function(node, source)
-                            || is_on_finished_function(node, source)
-                        {
-                            let next_aktiviteter = extract_neste_aktivitet_calls(node, source);
-                            let has_manuell = has_manuell_behandling_call(node, source);
-                            // Always add to index, even with empty next_aktiviteter (end state)
-                            // Check if we already have an entry for this aktivitet
-                            if let Some(existing) = index.get_mut(aktivitet_class) {
-                                // Merge the next aktiviteter
-                                for next in next_aktiviteter {
-                                    if !existing
-                                        .next_aktiviteter
-                                        .iter()
-                                        .any(|n| n.aktivitet_name == next.aktivitet_name)
-                                    {
-                                        existing.next_aktiviteter.push(next);
-                                    }
-                                }
-                                // Update manuell flag if found
-                                if has_manuell {
-                                    existing.has_manuell_behandling = true;
-                                }
-                            } else {
-                                // Create new entry
-                                index.insert(
-                                    aktivitet_class.clone(),
-                                    ProcessorInfo {
-                                        processor_class: processor_class.clone(),
-                                        next_aktiviteter,
-                                        has_manuell_behandling: has_manuell,
-                                    },
-                                );
-                            }
-                        }
-                    }
+                Err(err) => {
+                    warn!("  ⚠️  Warning: Built-in renderer failed: {}", err);
+                    warn!("     Install graphviz for full rendering (brew install graphviz / apt install graphviz)");
+                    warn!("     DOT file saved at: {}", dot_filename.display());
                 }
             }
-            _ => {}
         }
-
-        // Recurse into children
-        if cursor.goto_first_child() {
-            loop {
-                visit_node(
-                    cursor,
-                    source,
-                    index,
-                    current_class,
-                    current_aktivitet_class,
-                );
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
-            cursor.goto_parent();
+        Err(e) if is_graphviz_missing(&e) => {
+            warn!(
+                "  ⚠️  Warning: Could not run graphviz 'dot' command: {:#}",
+                e
+            );
+            warn!("     Make sure graphviz is installed (brew install graphviz / apt install graphviz)");
+            warn!("     DOT file saved at: {}", dot_filename.display());
+        }
+        Err(e) => {
+            warn!("  ⚠️  Warning: {:#}", e);
+            warn!("     DOT file saved at: {}", dot_filename.display());
+            warn!(
+                "     You can manually convert it with: dot -T{} {} -o {}",
+                args.format,
+                dot_filename.display(),
+                output_filename.display()
+            );
         }
     }
 
-    let mut current_class = None;
-    let mut current_aktivitet_class = None;
-    visit_node(
-        &mut cursor,
-        source,
-        index,
-        &mut current_class,
-        &mut current_aktivitet_class,
-    );
-}
-
-fn extract_aktivitet_from_processor(class_node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = class_node.walk();
+    if args.asciidoc {
+        let initial_aktivitet = model
+            .class_index
+            .get(name)
+            .and_then(|info| info.initial_aktivitet.as_ref())
+            .with_context(|| format!("No initial aktivitet for {}", name))?;
+        let image_name = format!("{}_flow.{}", name, args.format);
+        let adoc_content = generate_asciidoc(
+            name,
+            initial_aktivitet,
+            &model.processor_index,
+            &model.class_index,
+            &image_name,
+        );
 
-    for child in class_node.children(&mut cursor) {
-        if child.kind() == "delegation_specifier" {
-            // Look for the type parameter in the supertype
-            if let Some(type_param) = extract_type_parameter(child, source) {
-                return Some(type_param);
-            }
-        }
+        let adoc_filename = output_dir.join(format!("{}_flow.adoc", name));
+        fs::write(&adoc_filename, adoc_content)
+            .with_context(|| format!("Failed to write AsciiDoc file: {:?}", adoc_filename))?;
+        info!("  ✅ Generated: {}", adoc_filename.display());
     }
 
-    None
+    Ok(generated)
 }
 
-fn extract_type_parameter(node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = node.walk();
-
-    for child in node.children(&mut cursor) {
-        if child.kind() == "constructor_invocation" {
-            // The type_arguments are inside the user_type
-            let mut user_type_cursor = child.walk();
-            for ut_child in child.children(&mut user_type_cursor) {
-                if ut_child.kind() == "user_type" {
-                    // The type_arguments are inside the user_type
-                    let mut type_args_cursor = ut_child.walk();
-                    for arg in ut_child.children(&mut type_args_cursor) {
-                        if arg.kind() == "type_arguments" {
-                            let mut args_cursor = arg.walk();
-                            let mut type_projections = Vec::new();
-
-                            // Collect all type projections
-                            for type_arg in arg.children(&mut args_cursor) {
-                                if type_arg.kind() == "type_projection" {
-                                    let mut proj_cursor = type_arg.walk();
-                                    for type_node in type_arg.children(&mut proj_cursor) {
-                                        if type_node.kind() == "user_type"
-                                            || type_node.kind() == "type_identifier"
-                                        {
-                                            type_projections
-                                                .push(extract_type_name(type_node, source));
-                                        }
-                                    }
-                                }
-                            }
-
-                            // Return the second type parameter (aktivitet class)
-                            // For AktivitetProcessor<Behandling, Aktivitet>, we want the second one (index 1)
-                            // For AldeAktivitetProcessor<Behandling, Aktivitet, G, V, S, F>, we also want the second one (index 1)
-                            if type_projections.len() >= 2 {
-                                return Some(type_projections[1].clone());
-                            } else if type_projections.len() == 1 {
-                                return Some(type_projections[0].clone());
-                            }
-                        }
-                    }
-                }
-            }
+/// Write a graph of all selected flows, like `module_overview`. Returns the rendered file, or
+/// None if rendering failed and only the DOT file was kept.
+fn write_overview(
+    name: &str,
+    dot_content: &str,
+    output_dir: &Path,
+    args: &Args,
+) -> Result<Option<PathBuf>> {
+    let dot_filename = output_dir.join(format!("{}.dot", name));
+    fs::write(&dot_filename, dot_content)
+        .with_context(|| format!("Failed to write DOT file: {:?}", dot_filename))?;
+    let output_filename = output_dir.join(format!("{}.{}", name, args.format));
+
+    match render_dot(dot_content, &args.format) {
+        Ok(output) => {
+            fs::write(&output_filename, output)
+                .with_context(|| format!("Failed to write output file: {:?}", output_filename))?;
+            info!("  ✅ Generated: {}", output_filename.display());
+            if !args.keep_dot {
+                let _ = fs::remove_file(&dot_filename);
+            }
+            Ok(Some(output_filename))
+        }
+        Err(e) => {
+            warn!(
+                "  ⚠️  Warning: Could not render the {}: {:#}",
+                name.replace('_', " "),
+                e
+            );
+            warn!("     DOT file saved at: {}", dot_filename.display());
+            Ok(None)
         }
     }
-
-    None
 }
 
-fn is_do_process_function(node: tree_sitter::Node, source: &str) -> bool {
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if child.kind() == "simple_identifier" {
-            if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                return name == "doProcess";
-            }
+/// Write the graph of a path. Returns the rendered file, or None if rendering failed and only
+/// the DOT file was kept.
+fn write_path_graph(
+    name: &str,
+    dot_content: &str,
+    output_dir: &Path,
+    args: &Args,
+) -> Result<Option<PathBuf>> {
+    let dot_filename = output_dir.join(format!("{}.dot", name));
+    fs::write(&dot_filename, dot_content)
+        .with_context(|| format!("Failed to write DOT file: {:?}", dot_filename))?;
+    let output_filename = output_dir.join(format!("{}.{}", name, args.format));
+
+    match render_dot(dot_content, &args.format) {
+        Ok(output) => {
+            fs::write(&output_filename, output)
+                .with_context(|| format!("Failed to write output file: {:?}", output_filename))?;
+            info!("  ✅ Generated: {}", output_filename.display());
+            if !args.keep_dot {
+                let _ = fs::remove_file(&dot_filename);
+            }
+            Ok(Some(output_filename))
+        }
+        Err(e) => {
+            warn!("  ⚠️  Warning: Could not render the path: {:#}", e);
+            warn!("     DOT file saved at: {}", dot_filename.display());
+            Ok(None)
         }
     }
-    false
 }
 
-fn is_on_finished_function(func_node: tree_sitter::Node, source: &str) -> bool {
-    let mut cursor = func_node.walk();
-    for child in func_node.children(&mut cursor) {
-        if child.kind() == "simple_identifier" {
-            if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                return name == "onFinished";
-            }
+fn run_diff(
+    paths: &[String],
+    against: Option<&str>,
+    json: bool,
+    graph: bool,
+    scan: &Scan,
+    args: &Args,
+) -> Result<()> {
+    let (old_model, new_model) = match (against, paths) {
+        (Some(git_ref), [] | [_]) => {
+            let path = paths.first().map(String::as_str).unwrap_or(".");
+            let checkout = checkout_git_ref(path, git_ref)?;
+            let old_model = analyze_project_with(checkout.path(), &scan.filter)
+                .with_context(|| format!("Failed to analyze {} at {}", path, git_ref))?;
+            let new_model = analyze_project_with(path, &scan.filter)
+                .with_context(|| format!("Failed to analyze {}", path))?;
+            (old_model, new_model)
+        }
+        (None, [old_path, new_path]) => {
+            let old_model = analyze_project_with(old_path, &scan.filter)
+                .with_context(|| format!("Failed to analyze {}", old_path))?;
+            let new_model = analyze_project_with(new_path, &scan.filter)
+                .with_context(|| format!("Failed to analyze {}", new_path))?;
+            (old_model, new_model)
+        }
+        (Some(_), _) => anyhow::bail!("Give a single project path together with --against"),
+        (None, _) => {
+            anyhow::bail!("Give an old and a new project path, or use --against <GIT_REF>")
         }
+    };
+
+    let diffs = diff_models(&old_model, &new_model);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diffs)?);
+    } else {
+        print_diff(&diffs);
     }
-    false
-}
 
-fn has_manuell_behandling_call(func_node: tree_sitter::Node, source: &str) -> bool {
-    fn search_node(node: tree_sitter::Node, source: &str) -> bool {
-        // Check if this is an assignment with manuellBehandling
-        if node.kind() == "assignment" {
-            // Check the entire assignment text for the pattern
-            if let Ok(text) = node.utf8_text(source.as_bytes()) {
-                if text.contains("manuellBehandling") && text.contains("ManuellBehandling") {
-                    return true;
-                }
-            }
+    if graph && !diffs.is_empty() {
+        let output_dir = resolve_output_dir(args)?;
+        let dot_options = args.dot_options();
+        for diff in &diffs {
+            let dot_content =
+                generate_diff_graph(&old_model, &new_model, &diff.behandling, &dot_options);
+            write_diff_graph(&diff.behandling, &dot_content, &output_dir, args)?;
         }
+    }
+    Ok(())
+}
 
-        // Recursively search children
-        let mut cursor = node.walk();
-        if cursor.goto_first_child() {
-            loop {
-                if search_node(cursor.node(), source) {
-                    return true;
-                }
-                if !cursor.goto_next_sibling() {
-                    break;
-                }
-            }
+/// Write the diff graph of a Behandling. Returns the rendered file, or None if rendering failed
+/// and only the DOT file was kept.
+fn write_diff_graph(
+    name: &str,
+    dot_content: &str,
+    output_dir: &Path,
+    args: &Args,
+) -> Result<Option<PathBuf>> {
+    let dot_filename = output_dir.join(format!("{}_diff.dot", name));
+    fs::write(&dot_filename, dot_content)
+        .with_context(|| format!("Failed to write DOT file: {:?}", dot_filename))?;
+    let output_filename = output_dir.join(format!("{}_diff.{}", name, args.format));
+
+    match render_dot(dot_content, &args.format) {
+        Ok(output) => {
+            fs::write(&output_filename, output)
+                .with_context(|| format!("Failed to write output file: {:?}", output_filename))?;
+            info!("  ✅ Generated: {}", output_filename.display());
+            if !args.keep_dot {
+                let _ = fs::remove_file(&dot_filename);
+            }
+            Ok(Some(output_filename))
+        }
+        Err(e) => {
+            warn!(
+                "  ⚠️  Warning: Could not render the diff of {}: {:#}",
+                name, e
+            );
+            warn!("     DOT file saved at: {}", dot_filename.display());
+            Ok(None)
         }
+    }
+}
 
-        false
+/// Extract the sources below `path` as they were at `git_ref` into a temporary directory
+fn checkout_git_ref(path: &str, git_ref: &str) -> Result<tempfile::TempDir> {
+    // Run from inside the project so the archive only holds that directory. `--end-of-options`
+    // keeps a ref starting with `-` from being read as an option.
+    let archive = Command::new("git")
+        .args([
+            "-C",
+            path,
+            "archive",
+            "--format=tar",
+            "--end-of-options",
+            git_ref,
+        ])
+        .output()
+        .context("Could not run 'git' (is git installed?)")?;
+    if !archive.status.success() {
+        anyhow::bail!(
+            "Could not read {} at {}: {}",
+            path,
+            git_ref,
+            String::from_utf8_lossy(&archive.stderr).trim()
+        );
     }
 
-    search_node(func_node, source)
+    let checkout = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let mut tar = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(checkout.path())
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Could not run 'tar'")?;
+    tar.stdin
+        .take()
+        .context("Failed to open stdin of tar")?
+        .write_all(&archive.stdout)
+        .context("Failed to extract git archive")?;
+    if !tar.wait().context("Failed to wait for tar")?.success() {
+        anyhow::bail!("Failed to extract {} at {}", path, git_ref);
+    }
+
+    Ok(checkout)
 }
 
-fn extract_neste_aktivitet_calls(func_node: tree_sitter::Node, source: &str) -> Vec<NextAktivitet> {
-    let mut aktiviteter = Vec::new();
-    let mut cursor = func_node.walk();
+fn print_diff(diffs: &[BehandlingDiff]) {
+    if diffs.is_empty() {
+        println!("No flow changes");
+        return;
+    }
 
-    // Look for the function body
-    for child in func_node.children(&mut cursor) {
-        if child.kind() == "function_body" {
-            find_neste_aktivitet_in_node(child, source, &mut aktiviteter, None);
+    for diff in diffs {
+        println!();
+        for line in diff_lines(diff) {
+            println!("{}", line);
         }
     }
-
-    // If no nesteAktivitet calls found, check if it's an end state (aktivitetFullfort)
-    // Empty list means end state
-    aktiviteter
 }
 
-fn find_neste_aktivitet_in_node(
-    node: tree_sitter::Node,
-    source: &str,
-    aktiviteter: &mut Vec<NextAktivitet>,
-    condition: Option<String>,
-) {
-    let mut cursor = node.walk();
-
-    match node.kind() {
-        "call_expression" => {
-            // Check if this is a nesteAktivitet call
-            if is_neste_aktivitet_call(node, source) {
-                if let Some(aktivitet_name) = extract_aktivitet_from_call(node, source) {
-                    aktiviteter.push(NextAktivitet {
-                        aktivitet_name,
-                        condition: condition.clone(),
-                        is_collection: false,
-                    });
-                }
-            }
-            // Check if this is a collection operation that creates multiple aktiviteter
-            else if is_collection_operation(node, source) {
-                if let Some(aktivitet_name) = extract_aktivitet_from_collection_call(node, source) {
-                    aktiviteter.push(NextAktivitet {
-                        aktivitet_name,
-                        condition: condition.clone(),
-                        is_collection: true,
-                    });
-                }
-            }
-            // Check if this is a nesteAktiviteter() call with a collection pattern
-            else if is_neste_aktiviteter_call(node, source) {
-                if let Some(aktivitet_names) =
-                    extract_aktiviteter_from_collection_pattern(node, source)
-                {
-                    for aktivitet_name in aktivitet_names {
-                        aktiviteter.push(NextAktivitet {
-                            aktivitet_name,
-                            condition: condition.clone(),
-                            is_collection: true,
-                        });
-                    }
-                }
-            }
-            // Note: aktivitetFullfort() calls are ignored - they indicate end state
-            // which is represented by empty next_aktiviteter list
-        }
-        "if_expression" => {
-            // Extract the condition
-            let mut if_cursor = node.walk();
-            let mut condition_text = None;
-
-            for child in node.children(&mut if_cursor) {
-                if child.kind() == "(" {
-                    // Next sibling should be the condition
-                    continue;
-                } else if condition_text.is_none()
-                    && child.kind() != "if"
-                    && child.kind() != "control_structure_body"
-                {
-                    if let Ok(text) = child.utf8_text(source.as_bytes()) {
-                        condition_text = Some(text.to_string());
-                    }
-                }
-            }
+/// The changes to one flow for text output: the Behandling, then one line per change
+fn diff_lines(diff: &BehandlingDiff) -> Vec<String> {
+    let kind = match diff.kind {
+        ChangeKind::Added => "added",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Changed => "changed",
+    };
+    let mut lines = vec![format!("{} ({})", diff.behandling, kind)];
 
-            // Process if and else branches
-            let mut if_cursor = node.walk();
-            let mut branch_count = 0;
-            for child in node.children(&mut if_cursor) {
-                if child.kind() == "control_structure_body" || child.kind() == "call_expression" {
-                    branch_count += 1;
-                    let branch_condition = if branch_count == 1 {
-                        condition_text.clone()
-                    } else {
-                        condition_text.as_ref().map(|c| format!("NOT ({})", c))
-                    };
-                    find_neste_aktivitet_in_node(child, source, aktiviteter, branch_condition);
-                }
-            }
-        }
-        "return_expression" => {
-            // Look for nesteAktivitet in return statement
-            if cursor.goto_first_child() {
-                loop {
-                    find_neste_aktivitet_in_node(
-                        cursor.node(),
-                        source,
-                        aktiviteter,
-                        condition.clone(),
-                    );
-                    if !cursor.goto_next_sibling() {
-                        break;
-                    }
-                }
-                cursor.goto_parent();
-            }
-        }
-        _ => {
-            // For other node types, recursively search children without duplicate processing
-        }
+    for aktivitet in &diff.added_aktiviteter {
+        lines.push(format!("  + {}", aktivitet));
     }
-
-    // Recursively search all children, but avoid duplicate processing
-    if cursor.goto_first_child() {
-        loop {
-            find_neste_aktivitet_in_node(cursor.node(), source, aktiviteter, condition.clone());
-            if !cursor.goto_next_sibling() {
-                break;
-            }
-        }
+    for aktivitet in &diff.removed_aktiviteter {
+        lines.push(format!("  - {}", aktivitet));
+    }
+    for transition in &diff.added_transitions {
+        lines.push(format!(
+            "  + {} → {}{}",
+            transition.from,
+            transition.to,
+            format_diff_condition(&transition.condition)
+        ));
+    }
+    for transition in &diff.removed_transitions {
+        lines.push(format!(
+            "  - {} → {}{}",
+            transition.from,
+            transition.to,
+            format_diff_condition(&transition.condition)
+        ));
     }
+    for change in &diff.changed_conditions {
+        lines.push(format!(
+            "  ~ {} → {}: {} ⇒ {}",
+            change.from,
+            change.to,
+            change.old.as_deref().unwrap_or("(none)"),
+            change.new.as_deref().unwrap_or("(none)")
+        ));
+    }
+    lines
 }
 
-/// Check if a call expression is a collection operation that might create multiple aktiviteter
-fn is_collection_operation(node: tree_sitter::Node, source: &str) -> bool {
-    let mut cursor = node.walk();
-
-    // Look for patterns like: someCollection.map { ... } or someCollection.forEach { ... }
-    for child in node.children(&mut cursor) {
-        if child.kind() == "navigation_expression" {
-            // Get the full navigation text and check if it ends with collection method
-            if let Ok(nav_text) = child.utf8_text(source.as_bytes()) {
-                if nav_text.ends_with(".map")
-                    || nav_text.ends_with(".forEach")
-                    || nav_text.ends_with(".flatMap")
-                {
-                    return true;
-                }
-            }
-        }
-    }
-    false
+fn format_diff_condition(condition: &Option<String>) -> String {
+    condition
+        .as_ref()
+        .map(|c| format!(" [{}]", c))
+        .unwrap_or_default()
 }
 
-/// Extract aktivitet name from a collection operation using pure AST traversal
-fn extract_aktivitet_from_collection_call(node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if child.kind() == "call_suffix" {
-            if let Some(name) = extract_from_lambda_in_suffix(child, source) {
-                return Some(name);
-            }
-        }
+fn run_export(path: Option<&str>, format: ExportFormat, scan: &Scan, args: &Args) -> Result<()> {
+    let root_folder = path.unwrap_or(".");
+    let model = load_project(root_folder, scan)?;
+
+    let main_behandling_classes = model.behandlinger();
+    if main_behandling_classes.is_empty() {
+        anyhow::bail!("No Behandling classes with initial aktivitet found!");
     }
-    None
-}
 
-/// Extract activity name from lambda within call suffix using pure AST traversal
-fn extract_from_lambda_in_suffix(node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut activities = Vec::new();
-    extract_activities_from_ast_node(node, source, &mut activities);
-    activities.into_iter().next()
-}
+    info!("\n📦 Exporting flows...");
 
-/// Check if a call expression is a nesteAktiviteter() call
-fn is_neste_aktiviteter_call(node: tree_sitter::Node, source: &str) -> bool {
-    let mut cursor = node.walk();
+    let output_dir = resolve_output_dir(args)?;
+    let exports = match format {
+        ExportFormat::Cypher => vec![(
+            generate_cypher(
+                &main_behandling_classes,
+                &model.processor_index,
+                &model.class_index,
+            ),
+            "behandling_flows.cypher".to_string(),
+        )],
+        ExportFormat::ReachabilityCsv => main_behandling_classes
+            .iter()
+            .filter_map(|(name, info)| {
+                let initial_aktivitet = info.initial_aktivitet.as_ref()?;
+                let edges = collect_flow_edges(
+                    initial_aktivitet,
+                    &model.processor_index,
+                    &model.class_index,
+                );
+                Some((
+                    generate_reachability_csv(initial_aktivitet, &edges),
+                    format!("{}_reachability.csv", name),
+                ))
+            })
+            .collect(),
+        ExportFormat::ReachabilityJson => vec![(
+            generate_reachability_json(
+                &main_behandling_classes,
+                &model.processor_index,
+                &model.class_index,
+            ),
+            "reachability.json".to_string(),
+        )],
+    };
 
-    for child in node.children(&mut cursor) {
-        if child.kind() == "simple_identifier" {
-            if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                if name == "nesteAktiviteter" {
-                    return true;
-                }
-            }
-        }
+    for (content, filename) in exports {
+        let export_filename = output_dir.join(filename);
+        fs::write(&export_filename, content)
+            .with_context(|| format!("Failed to write export: {:?}", export_filename))?;
+        info!("  ✅ Generated: {}", export_filename.display());
     }
-    false
+
+    info!("\n✨ Done!");
+    Ok(())
 }
 
-/// Extract aktivitet names from nesteAktiviteter() call with collection patterns
-fn extract_aktiviteter_from_collection_pattern(
-    node: tree_sitter::Node,
-    source: &str,
-) -> Option<Vec<String>> {
-    let mut aktivitet_names = Vec::new();
-    let mut cursor = node.walk();
+/// Fail with suggestions for similarly named aktiviteter if `aktivitet` is not in the model
+fn ensure_known_aktivitet(model: &FlowModel, aktivitet: &str) -> Result<()> {
+    let aktiviteter = model.aktiviteter();
+    if aktiviteter.iter().any(|a| a == aktivitet) {
+        return Ok(());
+    }
 
-    // Walk through all children to find value_arguments
-    for child in node.children(&mut cursor) {
-        if child.kind() == "call_suffix" {
-            extract_from_call_suffix(child, source, &mut aktivitet_names);
-        }
+    let similar: Vec<_> = aktiviteter
+        .iter()
+        .filter(|a| a.to_lowercase().contains(&aktivitet.to_lowercase()))
+        .map(String::as_str)
+        .collect();
+    if similar.is_empty() {
+        anyhow::bail!("Unknown aktivitet: {}", aktivitet);
     }
+    anyhow::bail!(
+        "Unknown aktivitet: {} (did you mean: {}?)",
+        aktivitet,
+        similar.join(", ")
+    );
+}
 
-    if aktivitet_names.is_empty() {
-        None
+fn run_paths(
+    path: Option<&str>,
+    from: &str,
+    to: &str,
+    max_paths: usize,
+    max_length: usize,
+    json: bool,
+    scan: &Scan,
+) -> Result<()> {
+    let root_folder = path.unwrap_or(".");
+    let model = if json {
+        scan.analyze(root_folder)?
     } else {
-        Some(aktivitet_names)
+        load_project(root_folder, scan)?
+    };
+
+    ensure_known_aktivitet(&model, from)?;
+    ensure_known_aktivitet(&model, to)?;
+
+    let paths = find_paths(from, to, &model.processor_index, max_paths, max_length);
+    if json {
+        let entries: Vec<_> = paths.iter().map(|steps| path_json(steps)).collect();
+        let output = serde_json::json!({
+            "from": from,
+            "to": to,
+            "paths": entries,
+            "truncated": paths.len() == max_paths,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
     }
-}
 
-/// Extract from call suffix using pure AST traversal
-fn extract_from_call_suffix(
-    node: tree_sitter::Node,
-    source: &str,
-    aktivitet_names: &mut Vec<String>,
-) {
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if child.kind() == "value_arguments" {
-            extract_from_value_arguments(child, source, aktivitet_names);
-        }
+    if paths.is_empty() {
+        println!(
+            "\nNo path from {} to {} (within {} transitions)",
+            from, to, max_length
+        );
+        return Ok(());
     }
-}
 
-/// Extract from value arguments using pure AST traversal
-fn extract_from_value_arguments(
-    node: tree_sitter::Node,
-    source: &str,
-    aktivitet_names: &mut Vec<String>,
-) {
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        extract_activities_from_ast_node(child, source, aktivitet_names);
+    println!("\n🧭 Paths from {} to {}:", from, to);
+    for (i, steps) in paths.iter().enumerate() {
+        println!("\n  Path {} ({} transitions)", i + 1, steps.len() - 1);
+        print_path(steps);
     }
-}
 
-/// Extract activities from any AST node recursively
-fn extract_activities_from_ast_node(
-    node: tree_sitter::Node,
-    source: &str,
-    aktivitet_names: &mut Vec<String>,
-) {
-    match node.kind() {
-        "call_expression" => {
-            // Check if this is a direct activity constructor call
-            if let Some(activity_name) = extract_constructor_name(node, source) {
-                if is_likely_aktivitet_class(&activity_name) {
-                    aktivitet_names.push(activity_name);
-                }
-            } else {
-                // Not a constructor, recursively search children
-                let mut cursor = node.walk();
-                for child in node.children(&mut cursor) {
-                    extract_activities_from_ast_node(child, source, aktivitet_names);
-                }
-            }
-        }
-        "lambda_literal" | "function_literal" => {
-            // Search inside lambda expressions for activity constructors
-            let mut cursor = node.walk();
-            for child in node.children(&mut cursor) {
-                extract_activities_from_ast_node(child, source, aktivitet_names);
-            }
-        }
-        _ => {
-            // For all other node types, recursively search children
-            let mut cursor = node.walk();
-            for child in node.children(&mut cursor) {
-                extract_activities_from_ast_node(child, source, aktivitet_names);
-            }
-        }
+    if paths.len() == max_paths {
+        println!(
+            "\n(showing the first {} paths, use --max-paths to see more)",
+            max_paths
+        );
     }
+    Ok(())
 }
 
-/// Extract aktivitet names from binary expressions (like it.map {...} + SomeActivity())
-fn extract_aktiviteter_from_binary_expression(
-    node: tree_sitter::Node,
-    source: &str,
-    aktivitet_names: &mut Vec<String>,
-) {
-    // Use pure AST traversal for binary expressions
-    extract_activities_from_ast_node(node, source, aktivitet_names);
-}
+/// Find the shortest path, print it and write a graph of the flow it is in with the path in bold
+fn run_shortest_path(
+    path: Option<&str>,
+    from: &str,
+    to: &str,
+    max_length: usize,
+    json: bool,
+    scan: &Scan,
+    args: &Args,
+) -> Result<()> {
+    let root_folder = path.unwrap_or(".");
+    let model = if json {
+        scan.analyze(root_folder)?
+    } else {
+        load_project(root_folder, scan)?
+    };
 
-/// Find nesteAktivitet calls within lambda expressions using pure AST traversal
-fn find_nested_aktivitet_in_lambda(node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut activities = Vec::new();
-    extract_activities_from_ast_node(node, source, &mut activities);
-    activities.into_iter().next()
-}
+    ensure_known_aktivitet(&model, from)?;
+    ensure_known_aktivitet(&model, to)?;
 
-/// Search deeply for nesteAktiviteter function calls within complex expressions
-fn search_for_nested_neste_aktiviteter(
-    node: tree_sitter::Node,
-    source: &str,
-    aktiviteter: &mut Vec<NextAktivitet>,
-    condition: Option<String>,
-) {
-    let mut cursor = node.walk();
-
-    // Check if this node is itself a nesteAktiviteter call
-    if node.kind() == "call_expression" && is_neste_aktiviteter_call(node, source) {
-        if let Some(aktivitet_names) = extract_aktiviteter_from_collection_pattern(node, source) {
-            for aktivitet_name in aktivitet_names {
-                aktiviteter.push(NextAktivitet {
-                    aktivitet_name,
-                    condition: condition.clone(),
-                    is_collection: true,
-                });
-            }
+    let Some(steps) = find_paths(from, to, &model.processor_index, 1, max_length).pop() else {
+        if json {
+            println!("null");
+        } else {
+            println!(
+                "\nNo path from {} to {} (within {} transitions)",
+                from, to, max_length
+            );
         }
-        return;
-    }
+        return Ok(());
+    };
 
-    // Recursively search all children
-    for child in node.children(&mut cursor) {
-        if let Ok(child_text) = child.utf8_text(source.as_bytes()) {
-            if child_text.contains("nesteAktiviteter(") {
-                search_for_nested_neste_aktiviteter(child, source, aktiviteter, condition.clone());
-            }
-        }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&path_json(&steps))?);
+    } else {
+        println!(
+            "\n🧭 Shortest path from {} to {} ({} transitions):",
+            from,
+            to,
+            steps.len() - 1
+        );
+        print_path(&steps);
     }
-}
 
-/// Extract aktiviteter from generic nesteAktiviteter patterns like:
-/// nesteAktiviteter(it.map { ... } + SomeActivity())
-/// nesteAktiviteter(listOf(Activity1(), Activity2()))
-fn extract_aktiviteter_from_generic_nesteAktiviteter_pattern(
-    node: tree_sitter::Node,
-    source: &str,
-    aktiviteter: &mut Vec<NextAktivitet>,
-    condition: Option<String>,
-) {
-    if let Ok(text) = node.utf8_text(source.as_bytes()) {
-        // Only process if this contains nesteAktiviteter and hasn't been processed by other methods
-        if text.contains("nesteAktiviteter(") && !text.contains("nesteAktivitet(") {
-            extract_all_activity_constructors(text, aktiviteter, condition);
-        }
-    }
+    // Draw the path in the flow of a Behandling that reaches it, or on its own if none does
+    let behandling = model.behandlinger().into_iter().find_map(|(name, info)| {
+        let initial_aktivitet = info.initial_aktivitet.as_ref()?;
+        let edges = collect_flow_edges(
+            initial_aktivitet,
+            &model.processor_index,
+            &model.class_index,
+        );
+        flow_aktiviteter(initial_aktivitet, &edges)
+            .iter()
+            .any(|aktivitet| aktivitet == from)
+            .then_some((name.clone(), initial_aktivitet.clone()))
+    });
+    let (title, initial_aktivitet) =
+        behandling.unwrap_or_else(|| (shorten_aktivitet_name(from), from.to_string()));
+    let aktiviteter: Vec<String> = steps.iter().map(|step| step.aktivitet.clone()).collect();
+    let dot_content = generate_dot_graph(
+        &title,
+        &initial_aktivitet,
+        &model.processor_index,
+        &model.class_index,
+        &DotOptions {
+            start_from: (initial_aktivitet != from).then_some(from),
+            focus: None,
+            highlight_path: Some(&aktiviteter),
+            flow_link_format: None,
+            ..args.dot_options()
+        },
+    )?;
+    let output_dir = resolve_output_dir(args)?;
+    let name = format!(
+        "{}_to_{}_path",
+        shorten_aktivitet_name(from),
+        shorten_aktivitet_name(to)
+    )
+    .replace(['\n', ' '], "");
+    write_path_graph(&name, &dot_content, &output_dir, args)?;
+    Ok(())
 }
 
-/// Extract all activity constructor calls from nesteAktiviteter text
-fn extract_all_activity_constructors(
-    text: &str,
-    aktiviteter: &mut Vec<NextAktivitet>,
-    condition: Option<String>,
-) {
-    // Find all constructor patterns: ClassName() or ClassName(params)
-    let mut pos = 0;
-    let mut found_activities = std::collections::HashSet::new();
-
-    while pos < text.len() {
-        if let Some(constructor_match) = find_next_constructor(&text[pos..]) {
-            let full_pos = pos + constructor_match.start;
-            let class_name = constructor_match.name;
-
-            // Check if this looks like an Aktivitet class and we haven't seen it before
-            if is_likely_aktivitet_class(&class_name) && !found_activities.contains(&class_name) {
-                found_activities.insert(class_name.clone());
-
-                // Determine if this is part of a collection operation (it.map, forEach, etc.)
-                let is_collection = text.contains("it.map")
-                    || text.contains(".forEach")
-                    || text.contains(".flatMap");
-
-                aktiviteter.push(NextAktivitet {
-                    aktivitet_name: class_name,
-                    condition: condition.clone(),
-                    is_collection,
-                });
-            }
-
-            pos = full_pos + constructor_match.length;
-        } else {
-            break;
+/// The steps of a path, each transition with its condition, followed by what the whole path needs
+fn print_path(steps: &[PathStep]) {
+    println!("    {}", steps[0].aktivitet);
+    for step in &steps[1..] {
+        match &step.condition {
+            Some(condition) => println!("      → [{}] {}", condition, step.aktivitet),
+            None => println!("      → {}", step.aktivitet),
         }
     }
+    if let Some(condition) = path_condition(steps) {
+        println!("    ⇒ when {}", condition);
+    }
 }
 
-struct ConstructorMatch {
-    start: usize,
-    length: usize,
-    name: String,
+fn path_json(steps: &[PathStep]) -> serde_json::Value {
+    serde_json::json!({
+        "aktiviteter": steps.iter().map(|step| &step.aktivitet).collect::<Vec<_>>(),
+        "transitions": steps[1..]
+            .iter()
+            .map(|step| {
+                serde_json::json!({
+                    "aktivitet": step.aktivitet,
+                    "condition": step.condition.as_ref().map(ToString::to_string),
+                })
+            })
+            .collect::<Vec<_>>(),
+        "condition": path_condition(steps).as_ref().map(ToString::to_string),
+    })
 }
 
-/// Find the next constructor call pattern in the text
-fn find_next_constructor(text: &str) -> Option<ConstructorMatch> {
-    // Look for pattern: UpperCaseIdentifier(
-    let mut pos = 0;
-    let chars: Vec<char> = text.chars().collect();
-
-    while pos < chars.len() {
-        // Look for uppercase letter (start of class name)
-        if chars[pos].is_ascii_uppercase() {
-            let start_pos = pos;
+fn run_stats(path: Option<&str>, degrees: bool, json: bool, scan: &Scan) -> Result<()> {
+    let model = scan.analyze(path.unwrap_or("."))?;
 
-            // Collect the class name (alphanumeric + underscore)
-            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
-                pos += 1;
-            }
-
-            // Check if followed by opening parenthesis
-            if pos < chars.len() && chars[pos] == '(' {
-                let class_name: String = chars[start_pos..pos].iter().collect();
-                return Some(ConstructorMatch {
-                    start: start_pos,
-                    length: pos - start_pos + 1,
-                    name: class_name,
-                });
-            }
+    let mut rows = Vec::new();
+    for (name, info) in model.behandlinger() {
+        if let Some(initial_aktivitet) = &info.initial_aktivitet {
+            let edges = collect_flow_edges(
+                initial_aktivitet,
+                &model.processor_index,
+                &model.class_index,
+            );
+            let summary = summarize_flow(
+                initial_aktivitet,
+                &model.processor_index,
+                &model.class_index,
+                &edges,
+            );
+            rows.push((name, summary));
         }
-        pos += 1;
     }
 
-    None
-}
-
-/// Check if the current position is inside a collection operation like it.map
-fn is_inside_collection_operation(preceding_text: &str) -> bool {
-    // Look for collection operations in the preceding text
-    let collection_patterns = ["it.map", ".map", ".forEach", ".flatMap"];
-
-    for pattern in &collection_patterns {
-        if let Some(last_occurrence) = preceding_text.rfind(pattern) {
-            // Check if there's a closing } after the pattern but before our position
-            let after_pattern = &preceding_text[last_occurrence + pattern.len()..];
-            if !after_pattern.contains('}') {
-                return true;
-            }
-        }
+    if json {
+        let entries: Vec<_> = rows
+            .iter()
+            .map(|(name, summary)| {
+                serde_json::json!({
+                    "behandling": name,
+                    "nodes": summary.aktivitet_count,
+                    "edges": summary.transition_count,
+                    "branchPoints": summary.branch_points,
+                    "complexity": summary.complexity,
+                    "maxDepth": summary.max_depth,
+                    "longestPaths": summary
+                        .longest_paths
+                        .iter()
+                        .map(|path| {
+                            serde_json::json!({
+                                "terminal": path.terminal,
+                                "aktiviteter": path.aktiviteter,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                    "cycles": summary.cycles,
+                    "cyclePaths": summary.cycle_paths,
+                    "manualSteps": summary.manual_steps.len(),
+                    "deprecatedSteps": summary.deprecated_steps,
+                    "featureToggles": summary.feature_toggles.len(),
+                    "degrees": summary
+                        .degrees
+                        .iter()
+                        .map(|degree| {
+                            serde_json::json!({
+                                "aktivitet": degree.aktivitet,
+                                "fanIn": degree.fan_in,
+                                "fanOut": degree.fan_out,
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
     }
 
-    false
-}
-
-/// Heuristic to determine if a class name looks like an Aktivitet
-fn is_likely_aktivitet_class(class_name: &str) -> bool {
-    // Must be a valid identifier (alphanumeric + underscore)
-    if !class_name.chars().all(|c| c.is_alphanumeric() || c == '_') {
-        return false;
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max("Behandling".len());
+    println!(
+        "{:<width$}  {:>5}  {:>5}  {:>8}  {:>10}  {:>5}  {:>7}  {:>6}  {:>6}  {:>10}  {:>7}",
+        "Behandling",
+        "Nodes",
+        "Edges",
+        "Branches",
+        "Complexity",
+        "Depth",
+        "Longest",
+        "Cycles",
+        "Manual",
+        "Deprecated",
+        "Toggles",
+        width = name_width
+    );
+    for (name, summary) in &rows {
+        println!(
+            "{:<width$}  {:>5}  {:>5}  {:>8}  {:>10}  {:>5}  {:>7}  {:>6}  {:>6}  {:>10}  {:>7}",
+            name,
+            summary.aktivitet_count,
+            summary.transition_count,
+            summary.branch_points,
+            summary.complexity,
+            summary.max_depth,
+            summary.longest_to_end(),
+            summary.cycles,
+            summary.manual_steps.len(),
+            summary.deprecated_steps.len(),
+            summary.feature_toggles.len(),
+            width = name_width
+        );
     }
 
-    // Must start with uppercase letter (class naming convention)
-    if !class_name
-        .chars()
-        .next()
-        .unwrap_or('a')
-        .is_ascii_uppercase()
-    {
-        return false;
+    // Legacy steps that the flows still lead to
+    for (name, summary) in &rows {
+        if !summary.deprecated_steps.is_empty() {
+            println!(
+                "\n⊘ {} still reaches deprecated: {}",
+                name,
+                summary.deprecated_steps.join(", ")
+            );
+        }
     }
 
-    // Must be reasonable length for a class name
-    if class_name.len() < 3 || class_name.len() > 100 {
-        return false;
+    // Fan-in and fan-out per aktivitet, the most converged-on first
+    if degrees {
+        for (name, summary) in &rows {
+            println!("\n{}", name);
+            let mut sorted: Vec<_> = summary.degrees.iter().collect();
+            sorted.sort_by(|a, b| b.fan_in.cmp(&a.fan_in).then(b.fan_out.cmp(&a.fan_out)));
+            let aktivitet_width = sorted
+                .iter()
+                .map(|degree| degree.aktivitet.len())
+                .max()
+                .unwrap_or(0)
+                .max("Aktivitet".len());
+            println!(
+                "    {:<width$}  {:>6}  {:>7}",
+                "Aktivitet",
+                "Fan-in",
+                "Fan-out",
+                width = aktivitet_width
+            );
+            for degree in sorted {
+                println!(
+                    "    {:<width$}  {:>6}  {:>7}",
+                    degree.aktivitet,
+                    degree.fan_in,
+                    degree.fan_out,
+                    width = aktivitet_width
+                );
+            }
+        }
     }
 
-    // Check for aktivitet patterns
-    class_name.ends_with("Aktivitet")
-        || class_name.ends_with("Activity")
-        || class_name.contains("Aktivitet")
+    Ok(())
 }
 
-fn is_neste_aktivitet_call(call_node: tree_sitter::Node, source: &str) -> bool {
-    let mut cursor = call_node.walk();
+fn run_inspect(path: Option<&str>, aktivitet: &str, scan: &Scan) -> Result<()> {
+    let root_folder = path.unwrap_or(".");
+    let model = load_project(root_folder, scan)?;
 
-    for child in call_node.children(&mut cursor) {
-        if child.kind() == "simple_identifier" {
-            if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                return name == "nesteAktivitet" || name == "nesteAktiviteter";
-            }
-        }
+    ensure_known_aktivitet(&model, aktivitet)?;
+
+    println!("\n🔎 {}", aktivitet);
+    if let Some(location) = model.class_location(aktivitet) {
+        println!("  Declared: {}", location);
     }
-    false
-}
 
-fn extract_aktivitet_from_call(call_node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = call_node.walk();
-
-    for child in call_node.children(&mut cursor) {
-        if child.kind() == "call_suffix" {
-            // Look for value_arguments inside call_suffix
-            let mut suffix_cursor = child.walk();
-            for suffix_child in child.children(&mut suffix_cursor) {
-                if suffix_child.kind() == "value_arguments" {
-                    let mut args_cursor = suffix_child.walk();
-                    for arg in suffix_child.children(&mut args_cursor) {
-                        if arg.kind() == "value_argument" {
-                            // Check for both positional and named arguments
-                            if let Some(name) = extract_aktivitet_from_value_argument(arg, source) {
-                                return Some(name);
-                            }
-                        }
-                    }
+    let processor = model.processor_index.get(aktivitet);
+    match processor {
+        Some(info) => {
+            let location = model
+                .processor_location(info)
+                .map_or_else(|| info.file.display().to_string(), |l| l.to_string());
+            println!("  Processor: {} ({})", info.processor_class, location);
+            println!(
+                "  Creates manuell behandling: {}",
+                if info.has_manuell_behandling {
+                    "yes"
+                } else {
+                    "no"
                 }
+            );
+            for manuell in &info.manuell_behandlinger {
+                let details = manuell
+                    .details()
+                    .unwrap_or_else(|| "no details".to_string());
+                println!("    📋 {} @ {}", details, manuell.location);
             }
         }
+        None => println!("  Processor: not found"),
     }
 
-    None
-}
-
-fn extract_aktivitet_from_value_argument(
-    arg_node: tree_sitter::Node,
-    source: &str,
-) -> Option<String> {
-    let mut cursor = arg_node.walk();
-
-    for child in arg_node.children(&mut cursor) {
-        match child.kind() {
-            "call_expression" => {
-                // Direct constructor call: nesteAktivitet(ActivityName())
-                return extract_constructor_name(child, source);
+    println!("\n  Incoming:");
+    let mut incoming: Vec<_> = model
+        .processor_index
+        .iter()
+        .flat_map(|(from, info)| {
+            info.next_aktiviteter
+                .iter()
+                .filter(|next| next.aktivitet_name == aktivitet)
+                .map(move |next| (from, next))
+        })
+        .collect();
+    incoming.sort_by(|a, b| a.0.cmp(b.0));
+    if incoming.is_empty() {
+        println!("    (none)");
+    }
+    for (from, next) in incoming {
+        match &next.condition {
+            Some(condition) => println!("    ← {} [{}]  @ {}", from, condition, next.location),
+            None => println!("    ← {}  @ {}", from, next.location),
+        }
+    }
+
+    println!("\n  Outgoing:");
+    match processor {
+        Some(info) => {
+            for until in &info.waits_until {
+                println!("    ⏳ waits until {}", until);
+            }
+            for next in &info.next_aktiviteter {
+                let suffix = match &next.error_type {
+                    Some(error_type) => format!(" (on {})", error_type),
+                    None if next.is_collection => match &next.loop_context {
+                        Some(context) => format!(" (for each, {})", context),
+                        None => " (for each)".to_string(),
+                    },
+                    None => String::new(),
+                };
+                let suffix = match &next.begrunnelse {
+                    Some(begrunnelse) => format!("{} \"{}\"", suffix, begrunnelse),
+                    None => suffix,
+                };
+                let suffix = match &next.wait_until {
+                    Some(until) => format!("{} (waits until {})", suffix, until),
+                    None => suffix,
+                };
+                match &next.condition {
+                    Some(condition) => println!(
+                        "    → {}{} [{}]  @ {}",
+                        next.aktivitet_name, suffix, condition, next.location
+                    ),
+                    None => println!(
+                        "    → {}{}  @ {}",
+                        next.aktivitet_name, suffix, next.location
+                    ),
+                }
             }
-            "simple_identifier" => {
-                // This might be a named parameter like "aktivitet ="
-                // Continue to next sibling to find the value
-                continue;
+            for terminal in &info.terminals {
+                println!("    → [{}]", terminal.describe());
             }
-            _ => {
-                // Recursively check this node for call expressions
-                if let Some(name) = find_constructor_in_node(child, source) {
-                    return Some(name);
-                }
+            if info.is_unresolved() {
+                println!("    → [END?] (no transition found)");
+            }
+            for behandling in &info.spawns {
+                println!("    ▷ starts {}", behandling);
+            }
+            for behandling in &info.waits_for {
+                println!("    ⏸ waits on {}", behandling);
+            }
+            for behandling in &info.resumes {
+                println!("    ⏵ resumes {}", behandling);
+            }
+            for error_type in &info.manuell_on_error {
+                println!("    → [MANUELL BEHANDLING] (on {})", error_type);
             }
         }
+        None => println!("    (unknown)"),
     }
 
-    None
-}
-
-fn extract_constructor_name(call_node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = call_node.walk();
-    for child in call_node.children(&mut cursor) {
-        if child.kind() == "simple_identifier" || child.kind() == "type_identifier" {
-            if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                // Only return if this looks like a class constructor (starts with uppercase)
-                if name.chars().next().unwrap_or('a').is_ascii_uppercase() {
-                    return Some(name.to_string());
+    println!("\n  Flows:");
+    let mut found_in_flow = false;
+    for (name, info) in model.behandlinger() {
+        if let Some(initial_aktivitet) = &info.initial_aktivitet {
+            let edges = collect_flow_edges(
+                initial_aktivitet,
+                &model.processor_index,
+                &model.class_index,
+            );
+            if flow_aktiviteter(initial_aktivitet, &edges)
+                .iter()
+                .any(|a| a == aktivitet)
+            {
+                if initial_aktivitet == aktivitet {
+                    println!("    {} (initial aktivitet)", name);
+                } else {
+                    println!("    {}", name);
                 }
+                found_in_flow = true;
             }
         }
     }
-    None
-}
-
-fn find_constructor_in_node(node: tree_sitter::Node, source: &str) -> Option<String> {
-    if node.kind() == "call_expression" {
-        return extract_constructor_name(node, source);
-    }
-
-    let mut cursor = node.walk();
-    if cursor.goto_first_child() {
-        loop {
-            if let Some(name) = find_constructor_in_node(cursor.node(), source) {
-                return Some(name);
-            }
-            if !cursor.goto_next_sibling() {
-                break;
-            }
-        }
+    if !found_in_flow {
+        println!("    (not reachable from any Behandling)");
     }
 
-    None
+    Ok(())
 }
 
-fn detect_cycles(
-    start: &str,
-    processor_index: &HashMap<String, ProcessorInfo>,
-) -> Vec<(String, String)> {
-    let mut cycles = Vec::new();
-    let mut visited = std::collections::HashSet::new();
-    let mut rec_stack = std::collections::HashSet::new();
-    let mut parent_map: HashMap<String, Vec<String>> = HashMap::new();
-
-    fn dfs(
-        node: &str,
-        processor_index: &HashMap<String, ProcessorInfo>,
-        visited: &mut std::collections::HashSet<String>,
-        rec_stack: &mut std::collections::HashSet<String>,
-        parent_map: &mut HashMap<String, Vec<String>>,
-        cycles: &mut Vec<(String, String)>,
-    ) {
-        visited.insert(node.to_string());
-        rec_stack.insert(node.to_string());
-
-        if let Some(processor) = processor_index.get(node) {
-            for next in &processor.next_aktiviteter {
-                let next_name = &next.aktivitet_name;
-
-                // Track parent relationships
-                parent_map
-                    .entry(next_name.clone())
-                    .or_insert_with(Vec::new)
-                    .push(node.to_string());
-
-                if rec_stack.contains(next_name) {
-                    // Back edge found - this is a cycle
-                    cycles.push((node.to_string(), next_name.clone()));
-                } else if !visited.contains(next_name) {
-                    dfs(
-                        next_name,
-                        processor_index,
-                        visited,
-                        rec_stack,
-                        parent_map,
-                        cycles,
-                    );
-                }
+/// Print every aktivitet whose name or processor matches the pattern, with the transitions to and
+/// from it and the flows it is part of
+fn run_search(path: Option<&str>, pattern: &str, json: bool, scan: &Scan) -> Result<()> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .with_context(|| format!("Invalid search pattern: {}", pattern))?;
+    let model = scan.analyze(path.unwrap_or("."))?;
+    let hits = search(&model, &regex);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+    if hits.is_empty() {
+        println!("No aktivitet or processor matches {}", pattern);
+        return Ok(());
+    }
+
+    let describe = |neighbour: &Neighbour| {
+        let error = neighbour
+            .error_type
+            .as_ref()
+            .map(|error_type| format!(" (on {})", error_type))
+            .unwrap_or_default();
+        let condition = neighbour
+            .condition
+            .as_ref()
+            .map(|condition| format!(" [{}]", condition))
+            .unwrap_or_default();
+        format!(
+            "{}{}{}  @ {}",
+            neighbour.aktivitet, error, condition, neighbour.location
+        )
+    };
+    println!("🔎 {} match(es) for {}", hits.len(), pattern);
+    for hit in &hits {
+        println!("\n{}", hit.aktivitet);
+        if let Some(location) = &hit.location {
+            println!("  Declared: {}", location);
+        }
+        match (&hit.processor, &hit.processor_location) {
+            (Some(processor), Some(location)) => {
+                println!("  Processor: {} ({})", processor, location)
             }
+            (Some(processor), None) => println!("  Processor: {}", processor),
+            (None, _) => println!("  Processor: not found"),
+        }
+        if hit.behandlinger.is_empty() {
+            println!("  Flows: (not reachable from any Behandling)");
+        } else {
+            println!("  Flows: {}", hit.behandlinger.join(", "));
+        }
+        for predecessor in &hit.predecessors {
+            println!("  ← {}", describe(predecessor));
+        }
+        for successor in &hit.successors {
+            println!("  → {}", describe(successor));
+        }
+        for ending in &hit.endings {
+            println!("  → [{}]", ending);
         }
-
-        rec_stack.remove(node);
     }
-
-    dfs(
-        start,
-        processor_index,
-        &mut visited,
-        &mut rec_stack,
-        &mut parent_map,
-        &mut cycles,
-    );
-
-    cycles
+    Ok(())
 }
 
-fn group_cycles(cycles: &[(String, String)], edges: &[Edge]) -> Vec<Vec<String>> {
-    if cycles.is_empty() {
-        return Vec::new();
+/// Exit code when the lint finds violations; analysis errors exit with 2
+const LINT_VIOLATIONS_EXIT_CODE: i32 = 1;
+const LINT_ERROR_EXIT_CODE: i32 = 2;
+
+fn run_lint(
+    path: Option<&str>,
+    max_complexity: Option<usize>,
+    json: bool,
+    scan: &Scan,
+) -> Result<()> {
+    let mut model = match scan.analyze(path.unwrap_or(".")) {
+        Ok(model) => model,
+        Err(e) => {
+            error!("Error: {:#}", e);
+            std::process::exit(LINT_ERROR_EXIT_CODE);
+        }
+    };
+    if max_complexity.is_some() {
+        model.config.lint.max_complexity = max_complexity;
     }
 
-    // Build adjacency map from edges
-    let mut adj_map: HashMap<String, Vec<String>> = HashMap::new();
-    for edge in edges {
-        adj_map
-            .entry(edge.from.clone())
-            .or_insert_with(Vec::new)
-            .push(edge.to.clone());
+    let violations = lint(&model);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&violations)?);
+    } else {
+        for violation in &violations {
+            match &violation.location {
+                Some(location) => println!(
+                    "❌ {}: {}  @ {}",
+                    violation.rule.name(),
+                    violation.message,
+                    location
+                ),
+                None => println!("❌ {}: {}", violation.rule.name(), violation.message),
+            }
+        }
+        if violations.is_empty() {
+            println!("✅ No problems found");
+        } else {
+            println!("\n{} problem(s) found", violations.len());
+        }
     }
 
-    // Find all nodes involved in cycles
-    let mut cycle_nodes: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for (from, to) in cycles {
-        cycle_nodes.insert(from.clone());
-        cycle_nodes.insert(to.clone());
+    if !violations.is_empty() {
+        std::process::exit(LINT_VIOLATIONS_EXIT_CODE);
     }
+    Ok(())
+}
 
-    // Use DFS to find strongly connected components among cycle nodes
-    let mut groups: Vec<Vec<String>> = Vec::new();
-    let mut visited: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-    for node in &cycle_nodes {
-        if !visited.contains(node) {
-            let mut component = Vec::new();
-            let mut stack = vec![node.clone()];
-            let mut local_visited = std::collections::HashSet::new();
-
-            while let Some(current) = stack.pop() {
-                if local_visited.contains(&current) {
-                    continue;
-                }
-                local_visited.insert(current.clone());
-
-                if cycle_nodes.contains(&current) {
-                    component.push(current.clone());
-                    visited.insert(current.clone());
-
-                    // Add neighbors that are in cycle_nodes
-                    if let Some(neighbors) = adj_map.get(&current) {
-                        for neighbor in neighbors {
-                            if cycle_nodes.contains(neighbor) && !local_visited.contains(neighbor) {
-                                stack.push(neighbor.clone());
-                            }
-                        }
-                    }
+/// Print what was discovered, one name per line or as JSON, without any progress output
+fn run_list(path: Option<&str>, kind: ListKind, json: bool, scan: &Scan) -> Result<()> {
+    let model = scan.analyze(path.unwrap_or("."))?;
 
-                    // Also check reverse edges (nodes that point to current)
-                    for (from, to) in cycles {
-                        if to == &current && !local_visited.contains(from) {
-                            stack.push(from.clone());
-                        }
-                    }
+    match kind {
+        ListKind::Behandlinger => {
+            let behandlinger = model.behandlinger();
+            if json {
+                let entries: Vec<_> = behandlinger
+                    .iter()
+                    .map(|(name, info)| {
+                        serde_json::json!({
+                            "name": name,
+                            "file": info.file,
+                            "location": info.location(),
+                            "module": model.module_of(&info.file).map(|module| &module.name),
+                            "initialAktivitet": info.initial_aktivitet,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for (name, _) in behandlinger {
+                    println!("{}", name);
                 }
             }
-
-            if !component.is_empty() {
-                groups.push(component);
+        }
+        ListKind::Aktiviteter => {
+            let aktiviteter = model.aktiviteter();
+            if json {
+                let entries: Vec<_> = aktiviteter
+                    .iter()
+                    .map(|aktivitet| {
+                        serde_json::json!({
+                            "name": aktivitet,
+                            "location": model.class_location(aktivitet),
+                            "processor": model
+                                .processor_index
+                                .get(aktivitet)
+                                .map(|p| &p.processor_class),
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for aktivitet in aktiviteter {
+                    println!("{}", aktivitet);
+                }
             }
         }
-    }
-
-    groups
-}
-
-fn generate_dot_graph(
-    behandling_name: &str,
-    initial_aktivitet: &str,
-    processor_index: &HashMap<String, ProcessorInfo>,
-    class_index: &HashMap<String, ClassInfo>,
-    edge_style: &str,
-    show_conditions: bool,
-    show_legend: bool,
-    deduplicate: bool,
-) -> Result<String> {
-    let mut dot = String::new();
-    dot.push_str("digraph BehandlingFlow {\n");
-    dot.push_str("  rankdir=TB;\n");
-
-    // Set splines based on edge style preference
-    match edge_style {
-        "straight" | "polyline" => dot.push_str("  splines=polyline;\n"),
-        "ortho" | "orthogonal" => dot.push_str("  splines=ortho;\n"),
-        "curved" | "spline" => dot.push_str("  splines=spline;\n"),
-        _ => dot.push_str("  splines=polyline;\n"), // default to straight
-    }
-
-    dot.push_str("  node [shape=box, style=rounded, fontname=\"Arial\"];\n");
-    dot.push_str("  edge [fontname=\"Arial\", fontsize=10];\n\n");
-
-    // Add title
-    dot.push_str(&format!(
-        "  labelloc=\"t\";\n  label=\"{} Flow\";\n  fontsize=16;\n\n",
-        behandling_name
-    ));
-
-    // Track all nodes and edges to avoid duplicates
-    let mut visited_nodes = std::collections::HashSet::new();
-    let mut node_definitions = Vec::new();
-    let mut edges: Vec<Edge> = Vec::new();
-
-    // Start node
-    dot.push_str(&format!(
-        "  start [label=\"START\", shape=circle, style=filled, fillcolor=\"#90EE90\"];\n"
-    ));
-    dot.push_str(&format!(
-        "  start -> \"{}\";\n\n",
-        escape_label(initial_aktivitet)
-    ));
-
-    // Build graph recursively
-    build_dot_nodes(
-        initial_aktivitet,
-        processor_index,
-        class_index,
-        &mut visited_nodes,
-        &mut node_definitions,
-        &mut edges,
-        &mut std::collections::HashSet::new(),
-    );
-
-    // Detect iteration groups
-    let iteration_groups = detect_iteration_groups(processor_index, &edges);
-
-    // Detect cycles
-    let cycles = detect_cycles(initial_aktivitet, processor_index);
-
-    // Group cycles into strongly connected components
-    let cycle_groups = group_cycles(&cycles, &edges);
-
-    // Create a set of all nodes in cycles for easy lookup
-    let mut nodes_in_cycles = std::collections::HashSet::new();
-    for group in &cycle_groups {
-        for node in group {
-            nodes_in_cycles.insert(node.clone());
-        }
-    }
-
-    // Create a set of cycle edges (back edges)
-    let cycle_edges: std::collections::HashSet<(String, String)> = cycles.iter().cloned().collect();
-
-    // Add iteration clusters
-    for (idx, iteration_group) in iteration_groups.iter().enumerate() {
-        if iteration_group.iterated_nodes.len() > 1 {
-            dot.push_str(&format!("  subgraph cluster_iteration_{} {{\n", idx));
-            dot.push_str("    style=\"rounded,dashed\";\n");
-            dot.push_str("    color=\"#4CAF50\";\n");
-            dot.push_str("    penwidth=2.5;\n");
-            dot.push_str("    bgcolor=\"#F0FFF0\";\n");
-            dot.push_str(&format!(
-                "    label=\"Loop (triggered by {})\";\n",
-                iteration_group.trigger_node
-            ));
-            dot.push_str("    fontcolor=\"#2E7D32\";\n");
-            dot.push_str("    fontsize=12;\n");
-
-            // Add all nodes in the iteration path to the cluster
-            for node in &iteration_group.iterated_nodes {
-                // Only add if the node has a definition (avoid duplicates and unknown nodes)
-                if node_definitions
+        ListKind::Processors => {
+            let mut processors: Vec<_> = model.processor_index.iter().collect();
+            processors.sort_by(|a, b| a.1.processor_class.cmp(&b.1.processor_class));
+            if json {
+                let entries: Vec<_> = processors
                     .iter()
-                    .any(|def| def.contains(&format!("\"{}\"", escape_label(node))))
-                {
-                    dot.push_str(&format!("    \"{}\";\n", escape_label(node)));
+                    .map(|(aktivitet, info)| {
+                        serde_json::json!({
+                            "name": info.processor_class,
+                            "aktivitet": aktivitet,
+                            "location": model.processor_location(info),
+                            "nextAktiviteter": info
+                                .next_aktiviteter
+                                .iter()
+                                .map(|next| &next.aktivitet_name)
+                                .collect::<Vec<_>>(),
+                            "transitions": info
+                                .next_aktiviteter
+                                .iter()
+                                .map(|next| {
+                                    serde_json::json!({
+                                        "aktivitet": next.aktivitet_name,
+                                        "condition": next.condition.as_ref().map(ToString::to_string),
+                                        "location": next.location,
+                                    })
+                                })
+                                .collect::<Vec<_>>(),
+                            "manuellBehandling": info.has_manuell_behandling,
+                            "manuelleBehandlinger": info.manuell_behandlinger,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for (aktivitet, info) in processors {
+                    println!("{}\t{}", info.processor_class, aktivitet);
                 }
             }
-
-            dot.push_str("  }\n\n");
         }
-    }
-
-    // Add cycle clusters
-    for (idx, cycle_nodes) in cycle_groups.iter().enumerate() {
-        if cycle_nodes.len() > 1 {
-            dot.push_str(&format!("\n  subgraph cluster_{} {{\n", idx));
-            dot.push_str("    style=\"rounded,dashed\";\n");
-            dot.push_str("    color=\"#FF6B6B\";\n");
-            dot.push_str("    penwidth=2.5;\n");
-            dot.push_str("    bgcolor=\"#FFF5F5\";\n");
-            dot.push_str("    label=\"🔄 Waiting/Retry Loop\";\n");
-            dot.push_str("    fontcolor=\"#FF6B6B\";\n");
-            dot.push_str("    fontsize=12;\n");
-            dot.push_str("    fontname=\"Arial Bold\";\n");
-
-            // Add nodes in this cycle to the cluster
-            for node in cycle_nodes {
-                dot.push_str(&format!("    \"{}\";\n", escape_label(node)));
+        ListKind::MissingProcessors => {
+            let missing = missing_processors(&model);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&missing)?);
+            } else {
+                print_missing_processors(&missing);
             }
-
-            dot.push_str("  }\n");
         }
-    }
-
-    // Add node definitions
-    for node_def in node_definitions {
-        dot.push_str(&format!("  {};\n", node_def));
-    }
-
-    // Consolidate and add edges (if deduplication enabled)
-    if deduplicate {
-        let consolidated = consolidate_edges(&edges, &cycle_edges, show_conditions);
-        for edge in consolidated {
-            dot.push_str(&format!("  {};\n", edge));
+        ListKind::Unreachable => {
+            let unreachable = unreachable_aktiviteter(&model);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&unreachable)?);
+            } else {
+                print_unreachable_aktiviteter(&unreachable);
+            }
         }
-    } else {
-        // Add edges without consolidation
-        for edge in &edges {
-            let dot_edge = if edge.to.starts_with("unknown_") {
-                format!(
-                    "\"{}\" -> {} [style=dashed]",
-                    escape_label(&edge.from),
-                    escape_label(&edge.to)
-                )
-            } else if cycle_edges.contains(&(edge.from.clone(), edge.to.clone())) {
-                format!(
-                    "\"{}\" -> \"{}\" [color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false{}]",
-                    escape_label(&edge.from),
-                    escape_label(&edge.to),
-                    if show_conditions && !edge.label.is_empty() {
-                        format!(", label=\"{}\"", escape_label(&edge.label))
-                    } else {
-                        String::new()
-                    }
-                )
-            } else if edge.is_collection {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#4CAF50\", penwidth=2, style=bold]",
-                    escape_label(&edge.from),
-                    escape_label(&edge.to),
-                    if show_conditions && !edge.label.is_empty() {
-                        format!("{} (multiple)", escape_label(&edge.label))
-                    } else {
-                        "multiple".to_string()
+        ListKind::Endings => {
+            let mut entries = Vec::new();
+            for (name, info) in model.behandlinger() {
+                let Some(initial_aktivitet) = &info.initial_aktivitet else {
+                    continue;
+                };
+                let edges = collect_flow_edges(
+                    initial_aktivitet,
+                    &model.processor_index,
+                    &model.class_index,
+                );
+                let endings = flow_endings(initial_aktivitet, &model.processor_index, &edges);
+                if json {
+                    entries.push(serde_json::json!({
+                        "behandling": name,
+                        "endings": endings
+                            .iter()
+                            .map(|ending| {
+                                serde_json::json!({
+                                    "kind": ending.kind.name(),
+                                    "detail": ending.detail,
+                                    "aktiviteter": ending.aktiviteter,
+                                })
+                            })
+                            .collect::<Vec<_>>(),
+                    }));
+                } else {
+                    println!("{}", name);
+                    for ending in &endings {
+                        let marker = match ending.kind {
+                            EndingKind::Completed => "✅ END",
+                            EndingKind::Aborted => "🛑 ABORT",
+                            EndingKind::DeadEnd => "❓ END?",
+                        };
+                        match &ending.detail {
+                            Some(detail) => print!("    {} ({})", marker, detail),
+                            None => print!("    {}", marker),
+                        }
+                        println!("  ← {}", ending.aktiviteter.join(", "));
                     }
-                )
-            } else if show_conditions && !edge.label.is_empty() {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\"]",
-                    escape_label(&edge.from),
-                    escape_label(&edge.to),
-                    escape_label(&edge.label)
-                )
-            } else {
-                format!(
-                    "\"{}\" -> \"{}\"",
-                    escape_label(&edge.from),
-                    escape_label(&edge.to)
-                )
-            };
-            dot.push_str(&format!("  {};\n", dot_edge));
+                }
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
         }
-    }
-
-    // Add legend as HTML table (if enabled)
-    if show_legend {
-        dot.push_str("\n  // Legend\n");
-        dot.push_str("  {rank=sink;\n");
-        dot.push_str("    Legend [shape=none, margin=0, label=<\n");
-        dot.push_str(
-            "      <TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\" CELLPADDING=\"4\">\n",
-        );
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD COLSPAN=\"2\" BGCOLOR=\"#E8E8E8\"><B>Legend</B></TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#90EE90\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">START</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#9370DB\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">AldeAktivitet</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#FFA500\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">📋 Creates Oppgave</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#87CEEB\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">Regular</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#FFD700\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">Waiting</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#FF6B6B\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">Manual</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#FF4444\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">Abort</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#4CAF50\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">Decision</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#FFB6C1\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">END</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("        <TR>\n");
-        dot.push_str("          <TD BGCOLOR=\"#CCCCCC\">  </TD>\n");
-        dot.push_str("          <TD ALIGN=\"LEFT\">Unknown</TD>\n");
-        dot.push_str("        </TR>\n");
-        dot.push_str("      </TABLE>\n");
-        dot.push_str("    >];\n");
-        dot.push_str("  }\n");
-    }
-
-    dot.push_str("}\n");
-    Ok(dot)
-}
-
-/// Detect iteration groups where one aktivitet creates multiple instances of subsequent aktiviteter
-fn detect_iteration_groups(
-    processor_index: &HashMap<String, ProcessorInfo>,
-    edges: &[Edge],
-) -> Vec<IterationGroup> {
-    let mut iteration_groups = Vec::new();
-
-    // Find all collection edges (fan-out edges)
-    let collection_edges: Vec<&Edge> = edges.iter().filter(|e| e.is_collection).collect();
-
-    for collection_edge in collection_edges {
-        let trigger_node = collection_edge.from.clone();
-        let first_iterated_node = collection_edge.to.clone();
-
-        // Trace the path from the first iterated node to find all nodes in the iteration
-        let mut iterated_nodes = vec![first_iterated_node.clone()];
-        let mut current_nodes = vec![first_iterated_node];
-        let mut visited = std::collections::HashSet::new();
-
-        // Follow the path until we reach an end or cycle back to a known node
-        while !current_nodes.is_empty() {
-            let mut next_nodes = Vec::new();
-
-            for current_node in &current_nodes {
-                if visited.contains(current_node) {
+        ListKind::Mandatory => {
+            let mut entries = Vec::new();
+            for (name, info) in model.behandlinger() {
+                let Some(initial_aktivitet) = &info.initial_aktivitet else {
+                    continue;
+                };
+                let edges = collect_flow_edges(
+                    initial_aktivitet,
+                    &model.processor_index,
+                    &model.class_index,
+                );
+                let steps = mandatory_steps(initial_aktivitet, &edges);
+                if json {
+                    entries.push(serde_json::json!({
+                        "behandling": name,
+                        "completes": steps.is_some(),
+                        "mandatory": steps.as_ref().map(|steps| &steps.mandatory),
+                        "skippable": steps.as_ref().map(|steps| &steps.skippable),
+                    }));
                     continue;
                 }
-                visited.insert(current_node.clone());
-
-                if let Some(processor) = processor_index.get(current_node) {
-                    for next_aktivitet in &processor.next_aktiviteter {
-                        // Only include in iteration if it's a direct single path (not conditional)
-                        if processor.next_aktiviteter.len() == 1 {
-                            next_nodes.push(next_aktivitet.aktivitet_name.clone());
-                            iterated_nodes.push(next_aktivitet.aktivitet_name.clone());
+                println!("{}", name);
+                match steps {
+                    Some(steps) => {
+                        println!("    ✔ {}", steps.mandatory.join(" → "));
+                        if !steps.skippable.is_empty() {
+                            println!("    ↷ {}", steps.skippable.join(", "));
                         }
                     }
+                    None => println!("    (the flow never completes)"),
                 }
             }
-
-            current_nodes = next_nodes;
-
-            // Prevent infinite loops
-            if visited.len() > 20 {
-                break;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
             }
         }
-
-        // Only create a group if we have multiple nodes in the iteration path
-        if iterated_nodes.len() > 1 {
-            iteration_groups.push(IterationGroup {
-                trigger_node,
-                iterated_nodes,
-            });
+        ListKind::MergePoints => {
+            let mut entries = Vec::new();
+            for (name, info) in model.behandlinger() {
+                let Some(initial_aktivitet) = &info.initial_aktivitet else {
+                    continue;
+                };
+                let edges = collect_flow_edges(
+                    initial_aktivitet,
+                    &model.processor_index,
+                    &model.class_index,
+                );
+                let points = merge_points(initial_aktivitet, &edges);
+                if json {
+                    entries.push(serde_json::json!({
+                        "behandling": name,
+                        "mergePoints": points
+                            .iter()
+                            .map(|point| serde_json::json!({
+                                "branch": point.branch,
+                                "targets": point.targets,
+                                "merge": point.merge,
+                            }))
+                            .collect::<Vec<_>>(),
+                    }));
+                    continue;
+                }
+                println!("{}", name);
+                if points.is_empty() {
+                    println!("    (the flow does not split)");
+                }
+                for point in &points {
+                    match &point.merge {
+                        Some(merge) => println!("    ⑂ {}  ⋈ {}", point.branch, merge),
+                        None => println!("    ⑂ {}  (never meet again)", point.branch),
+                    }
+                    println!("        → {}", point.targets.join(", "));
+                }
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            }
         }
     }
 
-    iteration_groups
+    Ok(())
 }
 
-fn build_dot_nodes(
-    aktivitet_name: &str,
-    processor_index: &HashMap<String, ProcessorInfo>,
-    class_index: &HashMap<String, ClassInfo>,
-    visited_nodes: &mut std::collections::HashSet<String>,
-    node_definitions: &mut Vec<String>,
-    edges: &mut Vec<Edge>,
-    visiting: &mut std::collections::HashSet<String>,
-) {
-    if visited_nodes.contains(aktivitet_name) {
-        return;
+/// One aktivitet per line with where it is declared, followed by its processor
+fn print_unreachable_aktiviteter(unreachable: &[UnreachableAktivitet]) {
+    for entry in unreachable {
+        match &entry.location {
+            Some(location) => println!("{}  @ {}", entry.aktivitet, location),
+            None => println!("{}", entry.aktivitet),
+        }
+        match (&entry.processor, &entry.processor_location) {
+            (Some(processor), Some(location)) => println!("    ⚙ {}  @ {}", processor, location),
+            (Some(processor), None) => println!("    ⚙ {}", processor),
+            _ => {}
+        }
     }
+}
 
-    if visiting.contains(aktivitet_name) {
-        // Cycle detected
-        return;
+/// One aktivitet per line, with where it is declared, what leads to it and the near misses
+fn print_missing_processors(missing: &[MissingProcessor]) {
+    for entry in missing {
+        match &entry.location {
+            Some(location) => println!("{}  @ {}", entry.aktivitet, location),
+            None => println!("{}", entry.aktivitet),
+        }
+        if !entry.referenced_by.is_empty() {
+            println!("    ← {}", entry.referenced_by.join(", "));
+        }
+        for suggestion in &entry.suggestions {
+            println!("    ? {}", suggestion);
+        }
     }
+}
 
-    visiting.insert(aktivitet_name.to_string());
-    visited_nodes.insert(aktivitet_name.to_string());
-
-    // Shorten the name for display
-    let display_name = shorten_aktivitet_name(aktivitet_name);
-
-    // Check if this aktivitet creates a manuell behandling
-    let creates_oppgave = processor_index
-        .get(aktivitet_name)
-        .map(|p| p.has_manuell_behandling)
-        .unwrap_or(false);
-
-    // Determine node color based on name patterns and type
-    let color = if is_alde_aktivitet(aktivitet_name, class_index) {
-        "#9370DB" // Medium purple for AldeAktivitet (important)
-    } else if creates_oppgave {
-        "#FFA500" // Orange for activities that create manual tasks
-    } else if aktivitet_name.contains("Vent") || aktivitet_name.contains("Wait") {
-        "#FFD700" // Gold for waiting activities
-    } else if aktivitet_name.contains("Manuell") || aktivitet_name.contains("Oppgave") {
-        "#FF6B6B" // Red for manual activities
-    } else if aktivitet_name.contains("Avbryt") || aktivitet_name.contains("Avslag") {
-        "#FF4444" // Dark red for abort/rejection
-    } else if aktivitet_name.contains("Iverksett") || aktivitet_name.contains("Vedtak") {
-        "#4CAF50" // Green for decision/execution
-    } else {
-        "#87CEEB" // Sky blue for regular activities
-    };
+fn run_report(path: Option<&str>, scan: &Scan, args: &Args) -> Result<()> {
+    let root_folder = path.unwrap_or(".");
+    let model = load_project(root_folder, scan)?;
 
-    // Add node definition with oppgave indicator if applicable
-    let label = if creates_oppgave {
-        format!("📋 {}", display_name)
-    } else {
-        display_name
-    };
+    let main_behandling_classes = model.behandlinger();
+    if main_behandling_classes.is_empty() {
+        anyhow::bail!("No Behandling classes with initial aktivitet found!");
+    }
 
-    node_definitions.push(format!(
-        "\"{}\" [label=\"{}\", style=filled, fillcolor=\"{}\"]",
-        escape_label(aktivitet_name),
-        escape_label(&label),
-        color
-    ));
+    info!("\n📝 Generating reports...");
 
-    if let Some(processor) = processor_index.get(aktivitet_name) {
-        if processor.next_aktiviteter.is_empty() {
-            // End node
-            node_definitions.push(
-                "end [label=\"END\", shape=circle, style=filled, fillcolor=\"#FFB6C1\"]"
-                    .to_string(),
-            );
-            edges.push(Edge {
-                from: aktivitet_name.to_string(),
-                to: "end".to_string(),
-                label: "".to_string(),
-                is_collection: false,
-            });
-        } else if processor.next_aktiviteter.len() == 1 {
-            let next = &processor.next_aktiviteter[0];
-            let label = if let Some(condition) = &next.condition {
-                format_condition_label(condition)
-            } else {
-                "".to_string()
-            };
-            edges.push(Edge {
-                from: aktivitet_name.to_string(),
-                to: next.aktivitet_name.clone(),
-                label,
-                is_collection: next.is_collection,
-            });
-            build_dot_nodes(
-                &next.aktivitet_name,
-                processor_index,
-                class_index,
-                visited_nodes,
-                node_definitions,
-                edges,
-                visiting,
+    let output_dir = resolve_output_dir(args)?;
+
+    for (name, info) in &main_behandling_classes {
+        if let Some(initial_aktivitet) = &info.initial_aktivitet {
+            let report = generate_markdown_report(
+                name,
+                info,
+                initial_aktivitet,
+                &model.processor_index,
+                &model.class_index,
+                args.show_conditions,
             );
-        } else {
-            // Multiple branches - conditional
-            for next in processor.next_aktiviteter.iter() {
-                let label = if let Some(condition) = &next.condition {
-                    format_condition_label(condition)
-                } else {
-                    "else".to_string()
-                };
 
-                edges.push(Edge {
-                    from: aktivitet_name.to_string(),
-                    to: next.aktivitet_name.clone(),
-                    label,
-                    is_collection: next.is_collection,
-                });
+            let report_filename = output_dir.join(format!("{}_flow.md", name));
+            fs::write(&report_filename, report)
+                .with_context(|| format!("Failed to write report: {:?}", report_filename))?;
 
-                build_dot_nodes(
-                    &next.aktivitet_name,
-                    processor_index,
-                    class_index,
-                    visited_nodes,
-                    node_definitions,
-                    edges,
-                    visiting,
-                );
-            }
+            info!("  ✅ Generated: {}", report_filename.display());
         }
-    } else {
-        // No processor found - mark as unknown
-        let unknown_id = format!("unknown_{}", aktivitet_name);
-        node_definitions.push(format!(
-            "{} [label=\"?\", shape=diamond, style=filled, fillcolor=\"#CCCCCC\"]",
-            escape_label(&unknown_id)
-        ));
-        edges.push(Edge {
-            from: aktivitet_name.to_string(),
-            to: unknown_id,
-            label: "".to_string(),
-            is_collection: false,
-        });
     }
 
-    visiting.remove(aktivitet_name);
+    info!("\n✨ Done!");
+    Ok(())
 }
 
-fn consolidate_edges(
-    edges: &[Edge],
-    cycle_edges: &std::collections::HashSet<(String, String)>,
-    show_conditions: bool,
-) -> Vec<String> {
-    // Group edges by (from, to) pair
-    let mut edge_groups: HashMap<(String, String), Vec<String>> = HashMap::new();
-    let mut collection_edges: HashMap<(String, String), bool> = HashMap::new();
-
-    for edge in edges {
-        let key = (edge.from.clone(), edge.to.clone());
-        edge_groups
-            .entry(key.clone())
-            .or_insert_with(Vec::new)
-            .push(edge.label.clone());
+fn run_serve(path: Option<&str>, host: &str, port: u16, scan: &Scan, args: &Args) -> Result<()> {
+    let root_folder = path.unwrap_or(".");
+    let model = load_project(root_folder, scan)?;
 
-        // Track if any edge in this group is a collection edge
-        if edge.is_collection {
-            collection_edges.insert(key, true);
-        }
+    if model.behandlinger().is_empty() {
+        anyhow::bail!("No Behandling classes with initial aktivitet found!");
     }
 
-    let mut result = Vec::new();
-
-    for ((from, to), labels) in edge_groups.iter() {
-        // Filter out empty labels and "else" labels, and get unique ones
-        let non_empty_labels: Vec<String> = if show_conditions {
-            labels
-                .iter()
-                .filter(|l| !l.is_empty() && *l != "else")
-                .cloned()
-                .collect::<std::collections::HashSet<_>>()
-                .into_iter()
-                .collect()
-        } else {
-            Vec::new() // Don't show any conditions
-        };
+    // Pages are served under /flow/, not as generated files
+    let dot_options = DotOptions {
+        dpi: None,
+        flow_link_format: None,
+        ..args.dot_options()
+    };
+    serve::serve(root_folder, model, host, port, scan, &dot_options)
+}
 
-        // Check if this is a cycle edge (back edge)
-        let is_cycle_edge = cycle_edges.contains(&(from.clone(), to.clone()));
-
-        // Check if this is a collection edge (fan-out)
-        let is_collection_edge = collection_edges
-            .get(&(from.clone(), to.clone()))
-            .unwrap_or(&false);
-
-        let dot_edge = if !show_conditions || (labels.len() == 1 && labels[0].is_empty()) {
-            // Single edge with no label (simple transition or dashed edge)
-            if to.starts_with("unknown_") {
-                format!(
-                    "\"{}\" -> {} [style=dashed]",
-                    escape_label(from),
-                    escape_label(to)
-                )
-            } else if is_cycle_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
-                    escape_label(from),
-                    escape_label(to)
-                )
-            } else if *is_collection_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"multiple\", color=\"#4CAF50\", penwidth=2, style=bold]",
-                    escape_label(from),
-                    escape_label(to)
-                )
-            } else {
-                format!("\"{}\" -> \"{}\"", escape_label(from), escape_label(to))
-            }
-        } else if !show_conditions || non_empty_labels.is_empty() {
-            // All labels were empty - simple edge
-            if is_cycle_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
-                    escape_label(from),
-                    escape_label(to)
-                )
-            } else if *is_collection_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"multiple\", color=\"#4CAF50\", penwidth=2, style=bold]",
-                    escape_label(from),
-                    escape_label(to)
-                )
-            } else {
-                format!("\"{}\" -> \"{}\"", escape_label(from), escape_label(to))
-            }
-        } else if non_empty_labels.len() == 1 {
-            // Single unique condition
-            if is_cycle_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&non_empty_labels[0])
-                )
-            } else if *is_collection_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{} (multiple)\", color=\"#4CAF50\", penwidth=2, style=bold]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&non_empty_labels[0])
-                )
-            } else {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\"]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&non_empty_labels[0])
-                )
-            }
-        } else if non_empty_labels.len() == 1 {
-            // Single unique condition - show it
-            if is_cycle_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&non_empty_labels[0])
-                )
-            } else {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\"]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&non_empty_labels[0])
-                )
-            }
-        } else {
-            // Multiple conditions - just show the first one as example (no "alternative paths" text)
-            let sample = &non_empty_labels[0];
-            let truncated = if sample.len() > 40 {
-                format!("{}...", &sample[..40])
-            } else {
-                sample.clone()
-            };
-            if is_cycle_edge {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\", color=\"#FF6B6B\", penwidth=2, style=bold, constraint=false]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&truncated)
-                )
-            } else {
-                format!(
-                    "\"{}\" -> \"{}\" [label=\"{}\"]",
-                    escape_label(from),
-                    escape_label(to),
-                    escape_label(&truncated)
-                )
-            }
-        };
+fn run_explore(path: Option<&str>, scan: &Scan) -> Result<()> {
+    let model = scan.analyze(path.unwrap_or("."))?;
 
-        result.push(dot_edge);
+    if model.behandlinger().is_empty() {
+        anyhow::bail!("No Behandling classes with initial aktivitet found!");
     }
 
-    result
+    tui::explore(&model)
 }
 
-fn is_alde_aktivitet(aktivitet_name: &str, class_index: &HashMap<String, ClassInfo>) -> bool {
-    // Check if this class extends AldeAktivitet
-    if let Some(class_info) = class_index.get(aktivitet_name) {
-        class_info
-            .supertypes
-            .iter()
-            .any(|supertype| supertype.contains("AldeAktivitet"))
-    } else {
-        false
+fn run_publish(
+    path: Option<&str>,
+    confluence_url: Option<&str>,
+    space: &str,
+    parent_id: Option<&str>,
+    format: &str,
+    scan: &Scan,
+    args: &Args,
+) -> Result<()> {
+    let base_url = confluence_url
+        .map(str::to_string)
+        .or_else(|| env::var("CONFLUENCE_URL").ok())
+        .context("No Confluence URL given (use --confluence-url or set CONFLUENCE_URL)")?;
+    let token = env::var("CONFLUENCE_TOKEN")
+        .context("CONFLUENCE_TOKEN must be set to publish to Confluence")?;
+    let client = ConfluenceClient::new(&base_url, &token, env::var("CONFLUENCE_USER").ok());
+
+    let root_folder = path.unwrap_or(".");
+    let model = load_project(root_folder, scan)?;
+
+    let main_behandling_classes = model.behandlinger();
+    if main_behandling_classes.is_empty() {
+        anyhow::bail!("No Behandling classes with initial aktivitet found!");
     }
-}
 
-fn shorten_aktivitet_name(name: &str) -> String {
-    // Remove common prefixes
-    let shortened = name.replace("FleksibelApSak", "").replace("Aktivitet", "");
+    info!("\n📤 Publishing to Confluence space {}...", space);
+
+    for (name, info) in &main_behandling_classes {
+        if let Some(initial_aktivitet) = &info.initial_aktivitet {
+            let dot_content = generate_dot_graph(
+                name,
+                initial_aktivitet,
+                &model.processor_index,
+                &model.class_index,
+                &DotOptions {
+                    show_legend: false,
+                    ..args.dot_options()
+                },
+            )?;
+            let image = render_dot(&dot_content, format)?;
+
+            let title = format!("{} Flow", name);
+            let attachment_name = format!("{}_flow.{}", name, format);
+            let body = generate_confluence_page_body(
+                initial_aktivitet,
+                &model.processor_index,
+                &model.class_index,
+                &attachment_name,
+            );
+
+            let page_id = client.upsert_page(space, parent_id, &title, &body)?;
+            client.upload_attachment(&page_id, &attachment_name, image)?;
 
-    // Extract the step number and description
-    if let Some(pos) = shortened.find(char::is_alphabetic) {
-        if pos > 0 {
-            let (num, rest) = shortened.split_at(pos);
-            // Add space between number and text for readability
-            return format!("{}\n{}", num, rest);
+            info!("  ✅ Published: {} (page {})", title, page_id);
         }
     }
 
-    shortened
+    info!("\n✨ Done!");
+    Ok(())
 }
 
-fn format_condition_label(condition: &str) -> String {
-    let mut formatted = condition.to_string();
+/// Render every flow as a PDF page and merge them into a single booklet
+fn generate_booklet(
+    main_behandling_classes: &[(&String, &ClassInfo)],
+    processor_index: &HashMap<String, ProcessorInfo>,
+    class_index: &HashMap<String, ClassInfo>,
+    dot_options: &DotOptions,
+    output_dir: &Path,
+    args: &Args,
+) -> Result<PathBuf> {
+    let generated_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+    let dot_options = DotOptions {
+        show_legend: true,
+        subtitle: Some(match args.toggle_subtitle() {
+            Some(toggles) => format!("Generated {} · {}", generated_at, toggles),
+            None => format!("Generated {}", generated_at),
+        }),
+        flow_link_format: None,
+        ..*dot_options
+    };
 
-    // Detect feature toggle patterns
-    if formatted.contains("unleashNextService.isEnabled") || formatted.contains("unleashNext") {
-        // Extract feature name - look for the first parameter which is the feature flag
-        if let Some(start) = formatted.find("isEnabled(") {
-            let after_enabled = &formatted[start + 10..];
+    let mut pages = Vec::new();
+    for (name, info) in main_behandling_classes {
+        if let Some(initial_aktivitet) = &info.initial_aktivitet {
+            let dot_content = generate_dot_graph(
+                name,
+                initial_aktivitet,
+                processor_index,
+                class_index,
+                &dot_options,
+            )?;
+            let pdf = render_dot(&dot_content, "pdf")
+                .with_context(|| format!("Failed to render {} as PDF", name))?;
+            pages.push(pdf);
 
-            // Find the feature flag name (first parameter)
-            let feature_part = if let Some(comma_pos) = after_enabled.find(',') {
-                &after_enabled[..comma_pos]
-            } else if let Some(paren_pos) = after_enabled.find(')') {
-                &after_enabled[..paren_pos]
-            } else {
-                after_enabled
-            };
+            debug!("  ✓ Rendered page: {}", name);
+        }
+    }
 
-            // Clean up the feature name
-            let feature_name = feature_part
-                .trim()
-                .replace("PenFeature.", "")
-                .replace("\"", "");
-
-            // Check if there are additional conditions after the isEnabled call
-            let rest_of_condition = if let Some(close_paren) = after_enabled.find(')') {
-                let after_close = &after_enabled[close_paren + 1..].trim();
-                if after_close.starts_with("&&") {
-                    let extra = after_close[2..]
-                        .trim()
-                        .replace("behandling.", "")
-                        .replace("krav.", "");
-                    if !extra.is_empty() {
-                        format!(" && {}", extra)
-                    } else {
-                        String::new()
-                    }
-                } else {
-                    String::new()
-                }
-            } else {
-                String::new()
-            };
+    let booklet_filename = output_dir.join("behandling_flows.pdf");
+    merge_pdf_documents(&pages, &booklet_filename)?;
+    Ok(booklet_filename)
+}
 
-            return format!("🚩 FEATURE: {}{}", feature_name.trim(), rest_of_condition);
-        }
-        // Fallback if we can't extract the name
-        formatted = format!("🚩 FEATURE TOGGLE: {}", formatted);
+fn traverse_aktivitet_flow(
+    aktivitet_name: &str,
+    processor_index: &HashMap<String, ProcessorInfo>,
+    visited: &mut std::collections::HashSet<String>,
+    depth: usize,
+) {
+    if visited.contains(aktivitet_name) {
+        println!(
+            "{}  [CYCLE DETECTED: {}]",
+            "  ".repeat(depth),
+            aktivitet_name
+        );
+        return;
     }
 
-    // Simplify common patterns
-    formatted = formatted.replace("behandling.", "");
-    formatted = formatted.replace("krav.", "");
+    visited.insert(aktivitet_name.to_string());
 
-    // Truncate very long conditions
-    if formatted.len() > 80 {
-        format!("{}...", &formatted[..77])
+    if let Some(processor) = processor_index.get(aktivitet_name) {
+        if processor.is_unresolved() {
+            println!("{}  → [END?]", "  ".repeat(depth));
+        } else if processor.next_aktiviteter.len() == 1
+            && processor.next_aktiviteter[0].error_type.is_none()
+        {
+            let next = &processor.next_aktiviteter[0];
+            println!("{}  → {}", "  ".repeat(depth), next.aktivitet_name);
+            traverse_aktivitet_flow(&next.aktivitet_name, processor_index, visited, depth + 1);
+        } else {
+            // Multiple branches
+            for next in &processor.next_aktiviteter {
+                if let Some(error_type) = &next.error_type {
+                    println!(
+                        "{}  → [CATCH {}] {}",
+                        "  ".repeat(depth),
+                        error_type,
+                        next.aktivitet_name
+                    );
+                } else if let Some(condition) = &next.condition {
+                    println!(
+                        "{}  → [IF {}] {}",
+                        "  ".repeat(depth),
+                        condition,
+                        next.aktivitet_name
+                    );
+                } else {
+                    println!("{}  → [ELSE] {}", "  ".repeat(depth), next.aktivitet_name);
+                }
+                let mut branch_visited = visited.clone();
+                traverse_aktivitet_flow(
+                    &next.aktivitet_name,
+                    processor_index,
+                    &mut branch_visited,
+                    depth + 1,
+                );
+            }
+        }
+        for terminal in &processor.terminals {
+            println!("{}  → [{}]", "  ".repeat(depth), terminal.describe());
+        }
     } else {
-        formatted
+        println!("{}  → [PROCESSOR NOT FOUND]", "  ".repeat(depth));
     }
 }
 
-fn escape_label(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
+const MULTIPART_BOUNDARY: &str = "----behandling-flow-attachment-boundary";
+
+/// Minimal client for the Confluence REST API (content and attachments)
+struct ConfluenceClient {
+    base_url: String,
+    authorization: String,
 }
 
-fn find_constructor_call(node: tree_sitter::Node, source: &str) -> Option<String> {
-    let mut cursor = node.walk();
+impl ConfluenceClient {
+    /// Uses bearer auth with a personal access token, or basic auth when a user is given (Confluence Cloud)
+    fn new(base_url: &str, token: &str, user: Option<String>) -> Self {
+        let authorization = match user {
+            Some(user) => format!("Basic {}", BASE64.encode(format!("{}:{}", user, token))),
+            None => format!("Bearer {}", token),
+        };
 
-    if node.kind() == "call_expression" {
-        // This is a constructor call
-        for child in node.children(&mut cursor) {
-            if child.kind() == "simple_identifier" || child.kind() == "type_identifier" {
-                if let Ok(name) = child.utf8_text(source.as_bytes()) {
-                    return Some(name.to_string());
-                }
-            }
-        }
-    }
+        ConfluenceClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            authorization,
+        }
+    }
+
+    /// Look up a page by title, returning its id and current version number
+    fn find_page(&self, space: &str, title: &str) -> Result<Option<(String, u64)>> {
+        let mut response = ureq::get(&format!("{}/rest/api/content", self.base_url))
+            .header("Authorization", &self.authorization)
+            .query("spaceKey", space)
+            .query("title", title)
+            .query("expand", "version")
+            .call()
+            .with_context(|| format!("Failed to look up Confluence page '{}'", title))?;
+
+        let json: serde_json::Value = response
+            .body_mut()
+            .read_json()
+            .context("Invalid response from Confluence")?;
+
+        Ok(json["results"].get(0).and_then(|page| {
+            Some((
+                page["id"].as_str()?.to_string(),
+                page["version"]["number"].as_u64()?,
+            ))
+        }))
+    }
+
+    /// Create the page, or update its body if a page with the same title exists. Returns the page id.
+    fn upsert_page(
+        &self,
+        space: &str,
+        parent_id: Option<&str>,
+        title: &str,
+        body: &str,
+    ) -> Result<String> {
+        let storage = serde_json::json!({
+            "storage": { "value": body, "representation": "storage" }
+        });
 
-    // Recurse into children
-    if cursor.goto_first_child() {
-        loop {
-            if let Some(result) = find_constructor_call(cursor.node(), source) {
-                return Some(result);
-            }
-            if !cursor.goto_next_sibling() {
-                break;
-            }
-        }
-        cursor.goto_parent();
-    }
+        if let Some((page_id, version)) = self.find_page(space, title)? {
+            ureq::put(&format!("{}/rest/api/content/{}", self.base_url, page_id))
+                .header("Authorization", &self.authorization)
+                .send_json(serde_json::json!({
+                    "id": page_id,
+                    "type": "page",
+                    "title": title,
+                    "space": { "key": space },
+                    "body": storage,
+                    "version": { "number": version + 1 },
+                }))
+                .with_context(|| format!("Failed to update Confluence page '{}'", title))?;
+            return Ok(page_id);
+        }
+
+        let mut page = serde_json::json!({
+            "type": "page",
+            "title": title,
+            "space": { "key": space },
+            "body": storage,
+        });
+        if let Some(parent_id) = parent_id {
+            page["ancestors"] = serde_json::json!([{ "id": parent_id }]);
+        }
+
+        let mut response = ureq::post(&format!("{}/rest/api/content", self.base_url))
+            .header("Authorization", &self.authorization)
+            .send_json(page)
+            .with_context(|| format!("Failed to create Confluence page '{}'", title))?;
+
+        let json: serde_json::Value = response
+            .body_mut()
+            .read_json()
+            .context("Invalid response from Confluence")?;
+        json["id"]
+            .as_str()
+            .map(str::to_string)
+            .context("Confluence did not return an id for the created page")
+    }
+
+    /// Upload an attachment to a page, replacing any existing attachment with the same name
+    fn upload_attachment(&self, page_id: &str, filename: &str, data: Vec<u8>) -> Result<()> {
+        let content_type = if filename.ends_with(".svg") {
+            "image/svg+xml"
+        } else if filename.ends_with(".png") {
+            "image/png"
+        } else {
+            "application/octet-stream"
+        };
 
-    None
+        let mut body = Vec::new();
+        body.extend_from_slice(format!("--{}\r\n", MULTIPART_BOUNDARY).as_bytes());
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n",
+                filename
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(format!("Content-Type: {}\r\n\r\n", content_type).as_bytes());
+        body.extend_from_slice(&data);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", MULTIPART_BOUNDARY).as_bytes());
+
+        ureq::put(&format!(
+            "{}/rest/api/content/{}/child/attachment",
+            self.base_url, page_id
+        ))
+        .header("Authorization", &self.authorization)
+        .header("X-Atlassian-Token", "no-check")
+        .header(
+            "Content-Type",
+            &format!("multipart/form-data; boundary={}", MULTIPART_BOUNDARY),
+        )
+        .send(&body[..])
+        .with_context(|| format!("Failed to upload attachment '{}'", filename))?;
+
+        Ok(())
+    }
 }
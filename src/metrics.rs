@@ -0,0 +1,244 @@
+//! Production metrics of aktiviteter, like execution counts and durations, read from a CSV or
+//! JSON export
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// A value per aktivitet, keyed by class name as in the export
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AktivitetMetric<T> {
+    values: HashMap<String, T>,
+    by_simple_name: HashMap<String, T>, // For exports without packages, or with them
+}
+
+/// How often each aktivitet has run
+pub type ExecutionCounts = AktivitetMetric<u64>;
+
+/// How long a sak typically stays in each aktivitet before it moves on
+pub type AktivitetDurations = AktivitetMetric<Duration>;
+
+impl<T: Copy + Ord> AktivitetMetric<T> {
+    /// Values keyed by aktivitet class name, with or without the package
+    pub fn new(values: HashMap<String, T>) -> Self {
+        let mut by_simple_name = HashMap::new();
+        for (name, value) in &values {
+            by_simple_name
+                .entry(simple_name(name).to_string())
+                .or_insert(*value);
+        }
+        AktivitetMetric {
+            values,
+            by_simple_name,
+        }
+    }
+
+    /// The value of the aktivitet, matching a qualified class index key like
+    /// `alder.VurderAktivitet` to a plain `VurderAktivitet` in the export and the other way
+    /// around; `None` if the export does not mention it
+    pub fn get(&self, aktivitet: &str) -> Option<T> {
+        self.values
+            .get(aktivitet)
+            .or_else(|| self.by_simple_name.get(simple_name(aktivitet)))
+            .copied()
+    }
+
+    /// The highest value in the export, with its aktivitet
+    pub fn max_entry(&self) -> Option<(&str, T)> {
+        self.values
+            .iter()
+            .max_by_key(|(name, value)| (**value, std::cmp::Reverse(name.as_str())))
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    /// The aktiviteter in the export, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.values.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl ExecutionCounts {
+    /// Read a `.json` file with an object of counts, `{"VurderSoknadAktivitet": 1200}`, or an
+    /// array of `{"aktivitet": ..., "count": ...}` objects. Any other file is read as CSV: one
+    /// `aktivitet,count` per line, separated by comma or semicolon, with an optional header.
+    /// Counts given more than once are added up.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut counts = HashMap::new();
+        for (name, value) in read_rows(path, "count")? {
+            let count = match &value {
+                Value::String(text) => text.parse().ok(),
+                value => value.as_u64(),
+            }
+            .with_context(|| format!("Count of {} is not a whole number: {}", name, value))
+            .with_context(|| format!("Invalid execution counts: {}", path.display()))?;
+            let total: &mut u64 = counts.entry(name.clone()).or_insert(0);
+            *total = total
+                .checked_add(count)
+                .with_context(|| format!("Count of {} is too large", name))
+                .with_context(|| format!("Invalid execution counts: {}", path.display()))?;
+        }
+        Ok(ExecutionCounts::new(counts))
+    }
+
+    /// The highest count in the export
+    pub fn max(&self) -> u64 {
+        self.max_entry().map_or(0, |(_, count)| count)
+    }
+}
+
+impl AktivitetDurations {
+    /// Read durations laid out as for [`ExecutionCounts::load`], with a `duration` instead of a
+    /// `count`. A duration is a number of seconds, a number with a unit like `90s`, `45m`, `4h`
+    /// or `2d`, or an ISO 8601 duration like `PT4H` or `P2DT3H`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut durations = HashMap::new();
+        for (name, value) in read_rows(path, "duration")? {
+            let duration = match &value {
+                Value::String(text) => parse_duration(text),
+                value => value
+                    .as_f64()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()),
+            }
+            .with_context(|| format!("Duration of {} is not understood: {}", name, value))
+            .with_context(|| format!("Invalid durations: {}", path.display()))?;
+            durations.insert(name, duration);
+        }
+        Ok(AktivitetDurations::new(durations))
+    }
+}
+
+/// A duration for a graph label, with its two largest units: `2d 4h`, `45m`, `30s`
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let units = [(86_400, "d"), (3_600, "h"), (60, "m"), (1, "s")];
+    let parts: Vec<String> = units
+        .iter()
+        .scan(seconds, |rest, &(size, unit)| {
+            let amount = *rest / size;
+            *rest %= size;
+            Some((amount, unit))
+        })
+        .skip_while(|(amount, _)| *amount == 0)
+        .take(2)
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{}{}", amount, unit))
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+fn simple_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// The (aktivitet, value) rows of an export; `value_field` names the value in JSON arrays. CSV
+/// values are given as strings.
+fn read_rows(path: &Path, value_field: &str) -> Result<Vec<(String, Value)>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read metrics: {}", path.display()))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    if is_json {
+        parse_json(&text, value_field)
+    } else {
+        parse_csv(&text)
+    }
+    .with_context(|| format!("Invalid metrics file: {}", path.display()))
+}
+
+fn parse_json(text: &str, value_field: &str) -> Result<Vec<(String, Value)>> {
+    match serde_json::from_str(text)? {
+        Value::Object(entries) => Ok(entries.into_iter().collect()),
+        Value::Array(rows) => rows
+            .into_iter()
+            .map(|row| {
+                let name = row
+                    .get("aktivitet")
+                    .and_then(Value::as_str)
+                    .with_context(|| format!("Row without an \"aktivitet\": {}", row))?;
+                let value = row
+                    .get(value_field)
+                    .with_context(|| format!("Row without a \"{}\": {}", value_field, row))?;
+                Ok((name.to_string(), value.clone()))
+            })
+            .collect(),
+        _ => anyhow::bail!("Expected an object or an array of rows"),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<(String, Value)>> {
+    let mut rows = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let unquote = |field: &str| field.trim().trim_matches('"').to_string();
+        let Some((name, value)) = line.split_once([',', ';']) else {
+            anyhow::bail!("Line {}: expected aktivitet,value", index + 1);
+        };
+        // A header like `aktivitet,count` has no digits in its value column
+        if index == 0 && !value.chars().any(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        rows.push((unquote(name), Value::String(unquote(value))));
+    }
+    Ok(rows)
+}
+
+/// Seconds, a number with a unit (`s`, `m`, `h`, `d`, `w`), or an ISO 8601 duration
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let seconds = if let Some(iso) = text.strip_prefix(['P', 'p']) {
+        let (date, time) = iso.split_once(['T', 't']).unwrap_or((iso, ""));
+        iso_seconds(date, &[('W', 604_800.0), ('D', 86_400.0)])?
+            + iso_seconds(time, &[('H', 3_600.0), ('M', 60.0), ('S', 1.0)])?
+    } else {
+        let split = text
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(text.len());
+        let (number, unit) = text.split_at(split);
+        let unit = match unit.trim() {
+            "" | "s" | "sec" => 1.0,
+            "m" | "min" => 60.0,
+            "h" => 3_600.0,
+            "d" => 86_400.0,
+            "w" => 604_800.0,
+            _ => return None,
+        };
+        number.parse::<f64>().ok()? * unit
+    };
+    // None when negative, not finite or too large for a `Duration`
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// The seconds of one part of an ISO 8601 duration, like `2DT` or `4H30M`
+fn iso_seconds(part: &str, units: &[(char, f64)]) -> Option<f64> {
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in part.chars() {
+        if c.is_ascii_digit() || c == '.' || c == ',' {
+            number.push(if c == ',' { '.' } else { c });
+        } else {
+            let (_, size) = units
+                .iter()
+                .find(|(unit, _)| unit.eq_ignore_ascii_case(&c))?;
+            seconds += number.parse::<f64>().ok()? * size;
+            number.clear();
+        }
+    }
+    number.is_empty().then_some(seconds)
+}
@@ -0,0 +1,747 @@
+//! Data model for discovered classes, processors and flow edges
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::graph::{
+    generate_behandling_overview, generate_dot_graph, generate_module_overview, DotOptions,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassInfo {
+    pub name: String,
+    pub package: String, // Empty for the default package
+    pub file: PathBuf,
+    pub line: usize,   // Line of the declaration, counted from 1
+    pub column: usize, // Column of the declaration, counted from 1
+    pub supertypes: Vec<String>,
+    pub initial_aktivitet: Option<String>,
+    pub is_abstract: bool,            // Abstract or sealed class, or interface
+    pub type_parameters: Vec<String>, // Generic parameters, e.g. [B, A] for `Base<B, A : Aktivitet>`
+    pub supertype_arguments: HashMap<String, Vec<String>>, // Type arguments passed to each supertype
+    pub annotations: Vec<String>, // Simple names of the class's annotations, e.g. [Aktivitet]
+}
+
+impl ClassInfo {
+    /// True for Behandling classes that start a flow
+    pub fn is_behandling(&self) -> bool {
+        self.supertypes.iter().any(|s| s.contains("Behandling")) && self.initial_aktivitet.is_some()
+    }
+
+    /// True for classes marked `@Deprecated`: legacy steps that should no longer be reached
+    pub fn is_deprecated(&self) -> bool {
+        self.annotations
+            .iter()
+            .any(|annotation| annotation == "Deprecated")
+    }
+
+    /// Where the class is declared
+    pub fn location(&self) -> SourceLocation {
+        SourceLocation {
+            file: self.file.clone(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// The name including the package, e.g. `no.nav.pensjon.VurderVilkarAktivitet`
+    pub fn qualified_name(&self) -> String {
+        if self.package.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.package, self.name)
+        }
+    }
+}
+
+/// A position in a Kotlin source, shown as `path/Fil.kt:12:5` so editors and terminals can open it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: usize,   // Counted from 1
+    pub column: usize, // Counted from 1
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+/// A part of a source Tree-sitter could not parse. Classes and transitions inside it are lost,
+/// so a flow may be incomplete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub location: SourceLocation,
+    pub message: String, // e.g. "missing `)`" or "unexpected `val x =`"
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessorInfo {
+    pub processor_class: String,
+    pub file: PathBuf,
+    pub next_aktiviteter: Vec<NextAktivitet>,
+    pub has_manuell_behandling: bool,
+    pub manuell_behandlinger: Vec<ManuellBehandling>, // The manuelle behandlinger it creates, where they could be told apart
+    pub manuell_on_error: Vec<String>, // Exception types whose catch block creates a manuell behandling
+    pub terminals: Vec<Terminal>,      // Ways the processor ends the flow, e.g. aktivitetFullfort()
+    pub waits_until: Vec<String>, // What the processor waits for before it runs again: `utsettTil(virkningsdato)`
+    pub spawns: Vec<String>, // Behandlinger the processor starts: `opprettBehandling(AnnenBehandling())`
+    pub waits_for: Vec<String>, // Behandlinger the processor waits on: `ventPaaBehandling(AnnenBehandling::class)`
+    pub resumes: Vec<String>, // Behandlinger the processor resumes: `gjenopptaBehandling(AnnenBehandling::class)`
+    pub other_processors: Vec<(String, PathBuf)>, // Further processor classes and files found for the same aktivitet
+}
+
+impl ProcessorInfo {
+    /// True if neither a transition nor an end of the flow was found, which usually means the
+    /// processor's logic could not be followed rather than that the flow ends there
+    pub fn is_unresolved(&self) -> bool {
+        self.next_aktiviteter.is_empty() && self.terminals.is_empty()
+    }
+}
+
+/// A manuell behandling a processor creates, with the oppgave details read from its arguments:
+/// `ManuellBehandling(kategori = "INNTEKT_AVVIK", frist = LocalDate.now().plusDays(14))`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ManuellBehandling {
+    pub kategori: Option<String>, // Oppgave type or kategori
+    pub frist: Option<String>,    // Deadline of the oppgave, as written
+    pub location: SourceLocation, // The constructor, factory call or assignment
+}
+
+impl ManuellBehandling {
+    /// The details for a tooltip or report, e.g. `kategori INNTEKT_AVVIK, frist 14 dager`, or
+    /// `None` if no details could be read
+    pub fn details(&self) -> Option<String> {
+        let details: Vec<String> = [("kategori", &self.kategori), ("frist", &self.frist)]
+            .into_iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| format!("{} {}", name, value)))
+            .collect();
+        (!details.is_empty()).then(|| details.join(", "))
+    }
+}
+
+/// How a processor ends the flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TerminalKind {
+    /// `aktivitetFullfort()`: the behandling is done
+    Fullfort,
+    /// `avbrytBehandling()` or `avbryt()`: the behandling is stopped before it is done
+    Avbrutt,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Terminal {
+    pub kind: TerminalKind,
+    pub detail: Option<String>, // The result of aktivitetFullfort or the reason for an abort
+}
+
+impl Terminal {
+    /// Short description for text output, e.g. `END (Resultat.INNVILGET)`
+    pub fn describe(&self) -> String {
+        let name = match self.kind {
+            TerminalKind::Fullfort => "END",
+            TerminalKind::Avbrutt => "ABORT",
+        };
+        match &self.detail {
+            Some(detail) => format!("{} ({})", name, detail),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// The condition under which a transition is made, as far as it can be read from the code
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Condition {
+    /// Any other expression, as written: `behandling.harUttak`, `erGyldig(krav)`
+    Expr(String),
+    /// A comparison or test: `alder >= 67`, `krav != null`, `krav is Revurdering`, `alder in 62..67`
+    Compare {
+        left: String,
+        op: String,
+        right: String,
+    },
+    /// A `when (krav.type)` branch matching an enum entry, e.g. `KravType.ALDER ->`
+    Entry {
+        subject: String,
+        entry: String,
+    },
+    /// A feature toggle that is on: `unleashNextService.isEnabled(PenFeature.NY_FLYT)`
+    Toggle(String),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+}
+
+impl Condition {
+    /// `outer && inner`, for a branch nested in another one
+    pub fn and(outer: Option<Condition>, inner: Condition) -> Condition {
+        let Some(outer) = outer else {
+            return inner;
+        };
+        let mut operands = Vec::new();
+        for condition in [outer, inner] {
+            match condition {
+                Condition::And(nested) => operands.extend(nested),
+                condition => operands.push(condition),
+            }
+        }
+        Condition::And(operands)
+    }
+
+    /// `a || b`, flattening nested alternatives
+    pub fn or(alternatives: Vec<Condition>) -> Condition {
+        let mut operands = Vec::new();
+        for condition in alternatives {
+            match condition {
+                Condition::Or(nested) => operands.extend(nested),
+                condition => operands.push(condition),
+            }
+        }
+        match <[Condition; 1]>::try_from(operands) {
+            Ok([single]) => single,
+            Err(operands) => Condition::Or(operands),
+        }
+    }
+
+    /// The opposite condition. Comparisons are turned around, so the else branch of
+    /// `if (krav == null)` reads `krav != null`.
+    pub fn negate(self) -> Condition {
+        match self {
+            Condition::Not(inner) => *inner,
+            Condition::Compare { left, op, right } => match negated_operator(&op) {
+                Some(negated) => Condition::Compare {
+                    left,
+                    op: negated.to_string(),
+                    right,
+                },
+                None => Condition::Not(Box::new(Condition::Compare { left, op, right })),
+            },
+            condition => Condition::Not(Box::new(condition)),
+        }
+    }
+
+    /// Names of the feature toggles the condition depends on, whether they must be on or off
+    pub fn feature_toggles(&self) -> Vec<&str> {
+        match self {
+            Condition::Toggle(name) => vec![name.as_str()],
+            Condition::Not(inner) => inner.feature_toggles(),
+            Condition::And(operands) | Condition::Or(operands) => operands
+                .iter()
+                .flat_map(|operand| operand.feature_toggles())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The value of the condition if it follows from the given feature toggle values alone:
+    /// `NY_FLYT && alder >= 67` is false with `NY_FLYT` off, and unknown with it on
+    pub fn evaluate_toggles(&self, toggles: &HashMap<String, bool>) -> Option<bool> {
+        match self {
+            Condition::Toggle(name) => toggles.get(name).copied(),
+            Condition::Not(inner) => inner.evaluate_toggles(toggles).map(|value| !value),
+            Condition::And(operands) => {
+                let values: Vec<_> = operands
+                    .iter()
+                    .map(|o| o.evaluate_toggles(toggles))
+                    .collect();
+                if values.contains(&Some(false)) {
+                    Some(false)
+                } else {
+                    values.iter().all(Option::is_some).then_some(true)
+                }
+            }
+            Condition::Or(operands) => {
+                let values: Vec<_> = operands
+                    .iter()
+                    .map(|o| o.evaluate_toggles(toggles))
+                    .collect();
+                if values.contains(&Some(true)) {
+                    Some(true)
+                } else {
+                    values.iter().all(Option::is_some).then_some(false)
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The condition without the operands the toggle values settle, so `NY_FLYT && alder >= 67`
+    /// reads `alder >= 67` with `NY_FLYT` on. Only meaningful if
+    /// [`evaluate_toggles`](Self::evaluate_toggles) leaves the condition unknown.
+    pub fn assume_toggles(self, toggles: &HashMap<String, bool>) -> Condition {
+        let remaining = |operands: Vec<Condition>, settled: bool| {
+            operands
+                .into_iter()
+                .filter(|operand| operand.evaluate_toggles(toggles) != Some(settled))
+                .map(|operand| operand.assume_toggles(toggles))
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Condition::Not(inner) => Condition::Not(Box::new(inner.assume_toggles(toggles))),
+            Condition::And(operands) => match <[Condition; 1]>::try_from(remaining(operands, true))
+            {
+                Ok([single]) => single,
+                Err(operands) => Condition::And(operands),
+            },
+            Condition::Or(operands) => Condition::or(remaining(operands, false)),
+            condition => condition,
+        }
+    }
+
+    /// Short edge label: `behandling.` and `krav.` are left out of the operands, toggles are
+    /// flagged, and long conditions are cut at 80 characters
+    pub fn label(&self) -> String {
+        let label = self.render(true);
+        if label.chars().count() > 80 {
+            format!("{}...", label.chars().take(77).collect::<String>())
+        } else {
+            label
+        }
+    }
+
+    fn render(&self, short: bool) -> String {
+        let operand = |text: &str| {
+            if short {
+                text.replace("behandling.", "").replace("krav.", "")
+            } else {
+                text.to_string()
+            }
+        };
+        match self {
+            Condition::Expr(text) => operand(text),
+            Condition::Compare { left, op, right } => {
+                format!("{} {} {}", operand(left), op, operand(right))
+            }
+            Condition::Entry { entry, .. } => entry.clone(),
+            Condition::Toggle(name) if short => format!("🚩 FEATURE: {}", name),
+            Condition::Toggle(name) => format!("isEnabled({})", name),
+            Condition::Not(inner) => format!("NOT ({})", inner.render(short)),
+            Condition::And(operands) => operands
+                .iter()
+                .map(|condition| match condition {
+                    Condition::Or(_) => format!("({})", condition.render(short)),
+                    _ => condition.render(short),
+                })
+                .collect::<Vec<_>>()
+                .join(" && "),
+            Condition::Or(operands) => operands
+                .iter()
+                .map(|condition| condition.render(short))
+                .collect::<Vec<_>>()
+                .join(" || "),
+        }
+    }
+}
+
+/// The condition as it reads in the code, with toggles as `isEnabled(NY_FLYT)`
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(false))
+    }
+}
+
+fn negated_operator(op: &str) -> Option<&'static str> {
+    Some(match op {
+        "==" => "!=",
+        "!=" => "==",
+        "===" => "!==",
+        "!==" => "===",
+        "<" => ">=",
+        ">=" => "<",
+        ">" => "<=",
+        "<=" => ">",
+        "is" => "!is",
+        "!is" => "is",
+        "in" => "!in",
+        "!in" => "in",
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NextAktivitet {
+    pub aktivitet_name: String,
+    pub condition: Option<Condition>,
+    pub is_collection: bool, // True if this represents multiple instances (fan-out)
+    pub error_type: Option<String>, // Exception type if the transition is made in a catch block
+    pub begrunnelse: Option<String>, // Reason given as `nesteAktivitet(..., begrunnelse = "...")`
+    pub wait_until: Option<String>, // What the next aktivitet waits for: `nesteAktivitet(...).utsettTil(dato)`
+    pub location: SourceLocation,   // The nesteAktivitet call that makes the transition
+    pub loop_context: Option<String>, // The loop a fan-out is made in: `for (krav in kravListe)`
+    pub is_dynamic: bool, // Spread into `nesteAktiviteter(*steg)`; the name is the spread collection
+}
+
+impl NextAktivitet {
+    /// The aktivitet for text output, or `*steg` for a spread collection
+    pub fn describe(&self) -> String {
+        if self.is_dynamic {
+            format!("*{}", self.aktivitet_name)
+        } else {
+            self.aktivitet_name.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub label: String,
+    pub is_collection: bool, // True if this represents multiple instances (fan-out)
+    pub is_error: bool,      // Made in a catch block; the label names the exception
+}
+
+/// A processor bound to its aktivitet outside the processor class, by a registry call like
+/// `registrer(VurderAktivitet::class, VurderProcessor())` or a map entry like
+/// `VurderAktivitet::class to VurderProcessor()`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessorRegistration {
+    pub aktivitet_class: String,  // As a class index key
+    pub location: SourceLocation, // The registration
+}
+
+/// A function outside the processors that makes transitions, e.g. a shared
+/// `OvergangService.nesteSteg(behandling)` that processors delegate to, or the `doProcess` and
+/// `onFinished` of a base processor class that concrete processors inherit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    pub class_name: String, // Empty for top-level functions
+    pub function_name: String,
+    pub file: PathBuf,
+    pub next_aktiviteter: Vec<NextAktivitet>,
+    pub has_manuell_behandling: bool,
+    pub manuell_behandlinger: Vec<ManuellBehandling>,
+    pub manuell_on_error: Vec<String>,
+    pub terminals: Vec<Terminal>,
+    pub waits_until: Vec<String>,
+    pub spawns: Vec<String>,
+    pub waits_for: Vec<String>,
+    pub resumes: Vec<String>,
+}
+
+/// A constant or enum entry that stands for an aktivitet, like `val NESTE_STEG = VurderAktivitet()`
+/// or `enum class Steg { VURDER({ VurderAktivitet() }) }`. Companion and object factory functions
+/// like `VurderAktivitet.forKrav(krav)` are indexed the same way.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AktivitetConstant {
+    pub aktivitet_name: String,
+    pub file: PathBuf,
+}
+
+/// A `typealias Steg2 = VurderSoknadAktivitet`; references to the alias stand for the target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeAlias {
+    pub target: String, // Class index key of the aliased class
+    pub file: PathBuf,
+}
+
+/// A module of a multi-module Gradle build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GradleModule {
+    pub name: String, // Gradle project path like `:alder:domain`, or the root project name
+    pub dir: PathBuf,
+}
+
+/// Everything discovered in a Kotlin project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowModel {
+    pub files: Vec<PathBuf>,
+    pub class_index: HashMap<String, ClassInfo>,
+    pub processor_index: HashMap<String, ProcessorInfo>, // Keyed by the aktivitet the processor handles
+    pub function_index: HashMap<String, Vec<FunctionInfo>>, // Keyed by function name
+    pub constant_index: HashMap<String, AktivitetConstant>, // Keyed by NAME and Owner.NAME, factories by Owner.function
+    pub type_aliases: HashMap<String, TypeAlias>,           // Keyed by alias name
+    pub registrations: HashMap<String, ProcessorRegistration>, // Keyed by processor class
+    pub config: Config, // Settings of the project, kept for re-extracting changed files
+    pub modules: Vec<GradleModule>, // Empty unless the project is a Gradle build
+    pub parse_errors: Vec<ParseError>, // In file order
+}
+
+impl FlowModel {
+    /// The files with parse errors, with the number of errors in each
+    pub fn files_with_parse_errors(&self) -> Vec<(&Path, usize)> {
+        let mut files: Vec<(&Path, usize)> = Vec::new();
+        for error in &self.parse_errors {
+            match files.last_mut() {
+                Some((file, count)) if *file == error.location.file => *count += 1,
+                _ => files.push((&error.location.file, 1)),
+            }
+        }
+        files
+    }
+
+    /// Replace every reference to a type alias by the class it stands for, so an aktivitet
+    /// referenced both ways is one node. Processors keyed by an alias move to the class, unless
+    /// the class has a processor of its own.
+    pub fn resolve_type_aliases(&mut self) {
+        if self.type_aliases.is_empty() {
+            return;
+        }
+        let aliases = &self.type_aliases;
+        // Aliases may point to other aliases; stop after as many steps as there are aliases
+        let resolve = |name: &mut String| {
+            for _ in 0..aliases.len() {
+                match aliases.get(name.as_str()) {
+                    Some(alias) if alias.target != *name => *name = alias.target.clone(),
+                    _ => break,
+                }
+            }
+        };
+
+        for info in self.class_index.values_mut() {
+            if let Some(initial_aktivitet) = &mut info.initial_aktivitet {
+                resolve(initial_aktivitet);
+            }
+        }
+        let next_aktiviteter = self
+            .processor_index
+            .values_mut()
+            .flat_map(|processor| &mut processor.next_aktiviteter)
+            .chain(
+                self.function_index
+                    .values_mut()
+                    .flatten()
+                    .flat_map(|function| &mut function.next_aktiviteter),
+            );
+        for next in next_aktiviteter {
+            resolve(&mut next.aktivitet_name);
+        }
+        for constant in self.constant_index.values_mut() {
+            resolve(&mut constant.aktivitet_name);
+        }
+
+        let aliased: Vec<String> = self
+            .processor_index
+            .keys()
+            .filter(|aktivitet| aliases.contains_key(aktivitet.as_str()))
+            .cloned()
+            .collect();
+        for alias in aliased {
+            if let Some(processor) = self.processor_index.remove(&alias) {
+                let mut aktivitet = alias;
+                resolve(&mut aktivitet);
+                match self.processor_index.entry(aktivitet) {
+                    Entry::Occupied(mut kept) => {
+                        let other = (processor.processor_class, processor.file);
+                        if !kept.get().other_processors.contains(&other) {
+                            kept.get_mut().other_processors.push(other);
+                        }
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(processor);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Simulate the flow with the given feature toggle values: transitions whose condition the
+    /// toggles make false are dropped, and settled toggles are left out of the remaining
+    /// conditions. Aktiviteter only reached through a dropped transition drop out of the graph.
+    pub fn apply_toggles(&mut self, toggles: &HashMap<String, bool>) {
+        if toggles.is_empty() {
+            return;
+        }
+        let transitions = self
+            .processor_index
+            .values_mut()
+            .map(|processor| &mut processor.next_aktiviteter)
+            .chain(
+                self.function_index
+                    .values_mut()
+                    .flatten()
+                    .map(|function| &mut function.next_aktiviteter),
+            );
+        for next_aktiviteter in transitions {
+            next_aktiviteter.retain_mut(|next| {
+                let Some(condition) = next.condition.take() else {
+                    return true;
+                };
+                match condition.evaluate_toggles(toggles) {
+                    Some(false) => false,
+                    Some(true) => true,
+                    None => {
+                        next.condition = Some(condition.assume_toggles(toggles));
+                        true
+                    }
+                }
+            });
+        }
+    }
+
+    /// Names of the feature toggles used in any transition condition, sorted
+    pub fn feature_toggles(&self) -> Vec<&str> {
+        let mut toggles: Vec<&str> = self
+            .processor_index
+            .values()
+            .flat_map(|processor| &processor.next_aktiviteter)
+            .chain(
+                self.function_index
+                    .values()
+                    .flatten()
+                    .flat_map(|function| &function.next_aktiviteter),
+            )
+            .flat_map(|next| next.condition.iter().flat_map(Condition::feature_toggles))
+            .collect();
+        toggles.sort_unstable();
+        toggles.dedup();
+        toggles
+    }
+
+    /// The Gradle module a source file belongs to: the innermost module directory containing it
+    pub fn module_of(&self, file: &Path) -> Option<&GradleModule> {
+        self.modules
+            .iter()
+            .filter(|module| file.starts_with(&module.dir))
+            .max_by_key(|module| module.dir.components().count())
+    }
+
+    /// The Gradle module that declares a class in the class index
+    pub fn class_module(&self, class: &str) -> Option<&GradleModule> {
+        self.module_of(&self.class_index.get(class)?.file)
+    }
+
+    /// Where a class in the class index is declared
+    pub fn class_location(&self, class: &str) -> Option<SourceLocation> {
+        self.class_index.get(class).map(ClassInfo::location)
+    }
+
+    /// Where the class of a processor is declared
+    pub fn processor_location(&self, processor: &ProcessorInfo) -> Option<SourceLocation> {
+        self.class_index
+            .values()
+            .find(|class| class.name == processor.processor_class && class.file == processor.file)
+            .map(ClassInfo::location)
+    }
+
+    /// Aktiviteter handled by more than one processor class, sorted by aktivitet
+    pub fn duplicate_processors(&self) -> Vec<(&String, &ProcessorInfo)> {
+        let mut duplicates: Vec<_> = self
+            .processor_index
+            .iter()
+            .filter(|(_, processor)| !processor.other_processors.is_empty())
+            .collect();
+        duplicates.sort_by(|a, b| a.0.cmp(b.0));
+        duplicates
+    }
+
+    /// Main Behandling classes (ones with initial aktivitet), sorted by name
+    pub fn behandlinger(&self) -> Vec<(&String, &ClassInfo)> {
+        let mut main_behandling_classes: Vec<_> = self
+            .class_index
+            .iter()
+            .filter(|(_, info)| info.is_behandling())
+            .collect();
+
+        main_behandling_classes.sort_by(|a, b| a.0.cmp(b.0));
+        main_behandling_classes
+    }
+
+    /// All aktivitet names that are an initial aktivitet, have a processor or are a next aktivitet, sorted
+    pub fn aktiviteter(&self) -> Vec<String> {
+        let mut aktiviteter = BTreeSet::new();
+        for (_, info) in self.behandlinger() {
+            aktiviteter.extend(info.initial_aktivitet.clone());
+        }
+        for (aktivitet, processor) in &self.processor_index {
+            aktiviteter.insert(aktivitet.clone());
+            aktiviteter.extend(
+                processor
+                    .next_aktiviteter
+                    .iter()
+                    .map(|next| next.aktivitet_name.clone()),
+            );
+        }
+        aktiviteter.into_iter().collect()
+    }
+
+    /// Generate the DOT graph for one Behandling flow
+    pub fn to_dot(&self, behandling: &str, options: &DotOptions) -> Result<String> {
+        let initial_aktivitet = self
+            .class_index
+            .get(behandling)
+            .and_then(|info| info.initial_aktivitet.as_ref())
+            .with_context(|| {
+                format!("No Behandling with initial aktivitet named {}", behandling)
+            })?;
+
+        generate_dot_graph(
+            behandling,
+            initial_aktivitet,
+            &self.processor_index,
+            &self.class_index,
+            options,
+        )
+    }
+
+    /// Generate one DOT graph of how the given Behandlinger start, wait on and resume each other
+    pub fn to_behandling_overview_dot(
+        &self,
+        behandlinger: &[&str],
+        options: &DotOptions,
+    ) -> Result<String> {
+        let flows = self.initial_aktiviteter(behandlinger)?;
+        Ok(generate_behandling_overview(
+            &flows,
+            &self.processor_index,
+            &self.class_index,
+            options,
+        ))
+    }
+
+    /// (Behandling, initial aktivitet) of each of the Behandlinger
+    fn initial_aktiviteter<'a>(
+        &'a self,
+        behandlinger: &[&'a str],
+    ) -> Result<Vec<(&'a str, &'a str)>> {
+        let mut flows = Vec::new();
+        for behandling in behandlinger {
+            let initial_aktivitet = self
+                .class_index
+                .get(*behandling)
+                .and_then(|info| info.initial_aktivitet.as_ref())
+                .with_context(|| {
+                    format!("No Behandling with initial aktivitet named {}", behandling)
+                })?;
+            flows.push((*behandling, initial_aktivitet.as_str()));
+        }
+        Ok(flows)
+    }
+
+    /// Generate one DOT graph of the given Behandling flows, with their aktiviteter grouped in a
+    /// cluster per Gradle module
+    pub fn to_module_overview_dot(
+        &self,
+        behandlinger: &[&str],
+        options: &DotOptions,
+    ) -> Result<String> {
+        let flows = self.initial_aktiviteter(behandlinger)?;
+        let modules = self
+            .class_index
+            .iter()
+            .filter_map(|(name, info)| {
+                Some((name.clone(), self.module_of(&info.file)?.name.clone()))
+            })
+            .collect();
+
+        generate_module_overview(
+            &flows,
+            &self.processor_index,
+            &self.class_index,
+            &modules,
+            options,
+        )
+    }
+}
@@ -0,0 +1,92 @@
+//! Progress bars and per-phase timings for directory scans
+
+use behandling_flow::ScanPhase;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::{Duration, Instant};
+
+use crate::logging;
+
+/// Follows the progress reported by `analyze_project_with_progress`, drawing a bar on stderr for
+/// the current phase and recording how long each phase took
+pub struct ScanProgress {
+    visible: bool,
+    current: Option<(ScanPhase, Instant, ProgressBar)>,
+    timings: Vec<(ScanPhase, Duration)>,
+}
+
+impl ScanProgress {
+    pub fn new() -> Self {
+        ScanProgress {
+            // indicatif hides the bar by itself when stderr is not a terminal
+            visible: logging::shows_progress(),
+            current: None,
+            timings: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, phase: ScanPhase, done: usize, total: usize) {
+        if self.current.as_ref().map(|(current, _, _)| *current) != Some(phase) {
+            self.finish_phase();
+            let bar = if self.visible {
+                phase_bar(phase, total)
+            } else {
+                ProgressBar::hidden()
+            };
+            self.current = Some((phase, Instant::now(), bar));
+        }
+
+        if let Some((_, _, bar)) = &self.current {
+            bar.set_position(done as u64);
+        }
+    }
+
+    /// Stop the bar of the running phase and record its time
+    pub fn finish_phase(&mut self) {
+        if let Some((phase, started, bar)) = self.current.take() {
+            bar.finish_and_clear();
+            self.timings.push((phase, started.elapsed()));
+        }
+    }
+
+    /// How long a finished phase took, e.g. " in 1.2s"; empty if the phase did not run
+    pub fn took(&self, phase: ScanPhase) -> String {
+        self.timings
+            .iter()
+            .find(|(finished, _)| *finished == phase)
+            .map(|(_, duration)| format!(" in {:.1?}", duration))
+            .unwrap_or_default()
+    }
+}
+
+fn phase_bar(phase: ScanPhase, total: usize) -> ProgressBar {
+    let message = match phase {
+        ScanPhase::CollectingFiles => "Collecting .kt files",
+        ScanPhase::CheckingCache => "Checking cache",
+        ScanPhase::ParsingFiles => "Parsing files",
+        ScanPhase::IndexingClasses => "Indexing classes",
+        ScanPhase::IndexingTypeAliases => "Indexing type aliases",
+        ScanPhase::IndexingRegistrations => "Indexing processor registrations",
+        ScanPhase::IndexingConstants => "Indexing constants",
+        ScanPhase::IndexingFunctions => "Indexing functions",
+        ScanPhase::IndexingProcessors => "Indexing processors",
+    };
+
+    // The number of files is not known while collecting them
+    let bar = if total == 0 {
+        let bar = ProgressBar::new_spinner().with_style(
+            ProgressStyle::with_template("{spinner} {msg}: {pos} [{elapsed}]")
+                .expect("valid progress template"),
+        );
+        bar.enable_steady_tick(Duration::from_millis(100));
+        bar
+    } else {
+        ProgressBar::new(total as u64).with_style(
+            ProgressStyle::with_template(
+                "{msg:20} [{bar:40}] {percent:>3}% [{elapsed}, ETA {eta}]",
+            )
+            .expect("valid progress template")
+            .progress_chars("=> "),
+        )
+    };
+    bar.with_message(message)
+}
@@ -0,0 +1,247 @@
+//! Rendering DOT graphs to images and documents
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Merge single-page PDFs into one document, keeping the page order
+pub fn merge_pdf_documents(documents: &[Vec<u8>], output: &Path) -> Result<()> {
+    let mut max_id = 1;
+    let mut document_pages = BTreeMap::new();
+    let mut document_objects = BTreeMap::new();
+
+    for bytes in documents {
+        let mut doc =
+            lopdf::Document::load_mem(bytes).context("Failed to read PDF produced by graphviz")?;
+        doc.renumber_objects_with(max_id);
+        max_id = doc.max_id + 1;
+
+        for object_id in doc.get_pages().into_values() {
+            let page = doc
+                .get_object(object_id)
+                .context("PDF page object missing")?
+                .to_owned();
+            document_pages.insert(object_id, page);
+        }
+        document_objects.extend(doc.objects);
+    }
+
+    // Catalog and Pages are mandatory; keep the first of each and point everything at them
+    let mut merged = lopdf::Document::with_version("1.5");
+    let mut catalog_object: Option<(lopdf::ObjectId, lopdf::Object)> = None;
+    let mut pages_object: Option<(lopdf::ObjectId, lopdf::Object)> = None;
+
+    for (object_id, object) in document_objects {
+        match object.type_name().unwrap_or(b"") {
+            b"Catalog" => {
+                let id = catalog_object.map(|(id, _)| id).unwrap_or(object_id);
+                catalog_object = Some((id, object));
+            }
+            b"Pages" => {
+                if let Ok(dictionary) = object.as_dict() {
+                    let mut dictionary = dictionary.clone();
+                    if let Some((_, ref old)) = pages_object {
+                        if let Ok(old_dictionary) = old.as_dict() {
+                            dictionary.extend(old_dictionary);
+                        }
+                    }
+                    let id = pages_object.map(|(id, _)| id).unwrap_or(object_id);
+                    pages_object = Some((id, lopdf::Object::Dictionary(dictionary)));
+                }
+            }
+            b"Page" | b"Outlines" | b"Outline" => {}
+            _ => {
+                merged.objects.insert(object_id, object);
+            }
+        }
+    }
+
+    let (pages_id, pages_object) = pages_object.context("No pages found in rendered PDFs")?;
+    let (catalog_id, catalog_object) =
+        catalog_object.context("No catalog found in rendered PDFs")?;
+
+    for (object_id, object) in &document_pages {
+        if let Ok(dictionary) = object.as_dict() {
+            let mut dictionary = dictionary.clone();
+            dictionary.set("Parent", pages_id);
+            merged
+                .objects
+                .insert(*object_id, lopdf::Object::Dictionary(dictionary));
+        }
+    }
+
+    if let Ok(dictionary) = pages_object.as_dict() {
+        let mut dictionary = dictionary.clone();
+        dictionary.set("Count", document_pages.len() as u32);
+        dictionary.set(
+            "Kids",
+            document_pages
+                .keys()
+                .map(|object_id| lopdf::Object::Reference(*object_id))
+                .collect::<Vec<_>>(),
+        );
+        merged
+            .objects
+            .insert(pages_id, lopdf::Object::Dictionary(dictionary));
+    }
+
+    if let Ok(dictionary) = catalog_object.as_dict() {
+        let mut dictionary = dictionary.clone();
+        dictionary.set("Pages", pages_id);
+        dictionary.remove(b"Outlines");
+        merged
+            .objects
+            .insert(catalog_id, lopdf::Object::Dictionary(dictionary));
+    }
+
+    merged.trailer.set("Root", catalog_id);
+    merged.max_id = merged.objects.len() as u32;
+    merged.renumber_objects();
+    merged.compress();
+
+    merged
+        .save(output)
+        .with_context(|| format!("Failed to write PDF booklet: {:?}", output))?;
+    Ok(())
+}
+
+/// Lay out and render DOT content as SVG with the built-in layout-rs engine (no graphviz needed)
+pub fn render_svg_with_layout(dot_content: &str) -> Result<String> {
+    let mut parser = layout::gv::DotParser::new(dot_content);
+    let graph = parser
+        .process()
+        .map_err(|e| anyhow::anyhow!("Could not parse DOT: {}", e))?;
+
+    let mut builder = layout::gv::GraphBuilder::new();
+    builder.visit_graph(&graph);
+    let mut visual_graph = builder.get();
+
+    let mut svg = layout::backends::svg::SVGWriter::new();
+    visual_graph.do_it(false, false, false, &mut svg);
+    Ok(svg.finalize())
+}
+
+/// Render DOT content to the requested format with the configured graphviz backend
+pub fn render_dot(dot_content: &str, format: &str) -> Result<Vec<u8>> {
+    #[cfg(feature = "graphviz-lib")]
+    {
+        graphviz_lib::render(dot_content, format)
+    }
+    #[cfg(not(feature = "graphviz-lib"))]
+    {
+        render_with_graphviz(dot_content, format)
+    }
+}
+
+/// True if rendering failed because the graphviz `dot` executable is not installed
+pub fn is_graphviz_missing(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .map(|e| e.kind() == std::io::ErrorKind::NotFound)
+        .unwrap_or(false)
+}
+
+/// Render DOT content by piping it through the graphviz `dot` executable
+#[cfg_attr(feature = "graphviz-lib", allow(dead_code))]
+fn render_with_graphviz(dot_content: &str, format: &str) -> Result<Vec<u8>> {
+    let mut child = Command::new("dot")
+        .arg(format!("-T{}", format))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Could not run graphviz 'dot' command (is graphviz installed?)")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open stdin of graphviz 'dot'")?
+        .write_all(dot_content.as_bytes())
+        .context("Failed to write DOT content to graphviz")?;
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for graphviz 'dot'")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "graphviz 'dot' command failed with status {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(output.stdout)
+}
+
+/// Renders DOT in-process through the Graphviz C library (libgvc) instead of running `dot`. The
+/// libraries are found and linked by `build.rs`, which refuses a Graphviz older than 3.0: these
+/// declarations follow its ABI, where `gvRenderData` gives the length as a `size_t`.
+#[cfg(feature = "graphviz-lib")]
+mod graphviz_lib {
+    use anyhow::{Context, Result};
+    use std::ffi::{c_char, c_int, c_void, CString};
+
+    extern "C" {
+        fn gvContext() -> *mut c_void;
+        fn gvFreeContext(gvc: *mut c_void) -> c_int;
+        fn agmemread(source: *const c_char) -> *mut c_void;
+        fn agclose(graph: *mut c_void) -> c_int;
+        fn gvLayout(gvc: *mut c_void, graph: *mut c_void, engine: *const c_char) -> c_int;
+        fn gvFreeLayout(gvc: *mut c_void, graph: *mut c_void) -> c_int;
+        fn gvRenderData(
+            gvc: *mut c_void,
+            graph: *mut c_void,
+            format: *const c_char,
+            result: *mut *mut c_char,
+            length: *mut usize,
+        ) -> c_int;
+        fn gvFreeRenderData(data: *mut c_char);
+    }
+
+    pub fn render(dot_content: &str, format: &str) -> Result<Vec<u8>> {
+        let source = CString::new(dot_content).context("DOT content contains a NUL byte")?;
+        let format_name = CString::new(format).context("Invalid output format")?;
+        let engine = CString::new("dot").unwrap();
+
+        // SAFETY: every handle is checked for null before use and released exactly once below
+        unsafe {
+            let gvc = gvContext();
+            if gvc.is_null() {
+                anyhow::bail!("Failed to create graphviz context");
+            }
+
+            let graph = agmemread(source.as_ptr());
+            if graph.is_null() {
+                gvFreeContext(gvc);
+                anyhow::bail!("Graphviz could not parse the generated DOT graph");
+            }
+
+            let result = if gvLayout(gvc, graph, engine.as_ptr()) != 0 {
+                Err(anyhow::anyhow!("Graphviz layout failed"))
+            } else {
+                let mut data: *mut c_char = std::ptr::null_mut();
+                let mut length: usize = 0;
+                let status = gvRenderData(gvc, graph, format_name.as_ptr(), &mut data, &mut length);
+                let rendered = if status != 0 || data.is_null() {
+                    Err(anyhow::anyhow!(
+                        "Graphviz could not render format '{}' (is the plugin installed?)",
+                        format
+                    ))
+                } else {
+                    Ok(std::slice::from_raw_parts(data as *const u8, length).to_vec())
+                };
+                if !data.is_null() {
+                    gvFreeRenderData(data);
+                }
+                gvFreeLayout(gvc, graph);
+                rendered
+            };
+
+            agclose(graph);
+            gvFreeContext(gvc);
+            result
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Finding Kotlin source files and setting up the Tree-sitter parser
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::{Path, PathBuf};
+use tree_sitter::Parser;
+use walkdir::WalkDir;
+
+use crate::model::GradleModule;
+
+/// Build and tool directories that are skipped unless default exclusions are turned off
+pub const DEFAULT_EXCLUDED_DIRS: &[&str] = &[
+    ".git",
+    ".gradle",
+    ".idea",
+    "build",
+    "node_modules",
+    "out",
+    "target",
+];
+
+/// Decides which files and directories below the project root are scanned.
+/// Globs are matched against paths relative to the root, e.g. `**/generated/**`.
+#[derive(Debug, Clone)]
+pub struct FileFilter {
+    include: Option<GlobSet>, // None scans every file that is not excluded
+    exclude: GlobSet,
+    default_excludes: bool,
+    scripts: bool, // Also scan `.kts` scripts
+}
+
+impl Default for FileFilter {
+    fn default() -> Self {
+        FileFilter {
+            include: None,
+            exclude: GlobSet::empty(),
+            default_excludes: true,
+            scripts: false,
+        }
+    }
+}
+
+impl FileFilter {
+    pub fn new(include: &[String], exclude: &[String], default_excludes: bool) -> Result<Self> {
+        Ok(FileFilter {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build_glob_set(include, "include")?)
+            },
+            exclude: build_glob_set(exclude, "exclude")?,
+            default_excludes,
+            scripts: false,
+        })
+    }
+
+    /// Also scan Kotlin scripts (`.kts`). Gradle build and settings scripts are left out, since
+    /// they never declare a flow.
+    pub fn with_scripts(mut self, scripts: bool) -> Self {
+        self.scripts = scripts;
+        self
+    }
+
+    /// True if `path` is a Kotlin source this filter scans, judged by its file name only
+    pub fn is_kotlin_source(&self, path: &Path) -> bool {
+        match path.extension() {
+            Some(ext) if ext == "kt" => true,
+            Some(ext) if ext == "kts" && self.scripts => !path.file_name().is_some_and(|name| {
+                GRADLE_BUILD_FILES
+                    .iter()
+                    .chain(GRADLE_SETTINGS_FILES)
+                    .any(|gradle_file| name == *gradle_file)
+            }),
+            _ => false,
+        }
+    }
+
+    /// True if the file at `relative` (relative to the root) should be scanned
+    pub fn is_match(&self, relative: &Path) -> bool {
+        !self.is_excluded(relative)
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(relative))
+    }
+
+    /// True if the file or directory at `relative` (relative to the root) should be skipped.
+    /// Include globs are not considered, since they usually only match files.
+    pub fn is_excluded(&self, relative: &Path) -> bool {
+        if self.default_excludes
+            && relative.components().any(|component| {
+                DEFAULT_EXCLUDED_DIRS
+                    .iter()
+                    .any(|dir| component.as_os_str() == *dir)
+            })
+        {
+            return true;
+        }
+        self.exclude.is_match(relative)
+    }
+}
+
+fn build_glob_set(patterns: &[String], kind: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(
+            Glob::new(pattern).with_context(|| format!("Invalid {} pattern: {}", kind, pattern))?,
+        );
+    }
+    builder
+        .build()
+        .with_context(|| format!("Failed to build {} patterns", kind))
+}
+
+/// Create a Tree-sitter parser for Kotlin
+pub fn kotlin_parser() -> Result<Parser> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_kotlin::language())
+        .context("Failed to set Kotlin language")?;
+    Ok(parser)
+}
+
+/// Find the `.kt` files (and `.kts` scripts, if enabled) below `root` that the filter lets
+/// through, calling `progress` with the
+/// number found so far every time one is added
+pub fn collect_kotlin_files(
+    root: &Path,
+    filter: &FileFilter,
+    progress: &mut dyn FnMut(usize),
+) -> Result<Vec<PathBuf>> {
+    let mut kt_files = Vec::new();
+
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| {
+            // Skipping excluded directories here avoids walking them at all
+            let relative = e.path().strip_prefix(root).unwrap_or(e.path());
+            relative.as_os_str().is_empty() || !filter.is_excluded(relative)
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+        if filter.is_kotlin_source(entry.path()) && filter.is_match(relative) {
+            kt_files.push(entry.path().to_path_buf());
+            progress(kt_files.len());
+        }
+    }
+
+    Ok(kt_files)
+}
+
+/// Build files that make the directory they are in a Gradle module
+const GRADLE_BUILD_FILES: &[&str] = &["build.gradle.kts", "build.gradle"];
+
+/// Settings files that list the modules of a multi-module Gradle build
+const GRADLE_SETTINGS_FILES: &[&str] = &["settings.gradle.kts", "settings.gradle"];
+
+/// Find the Gradle modules below `root`: every directory with a build file, and every project
+/// `include`d in the settings file. Modules are named by their Gradle project path, like
+/// `:alder:domain`; the root module by `rootProject.name`. Empty if `root` is no Gradle build.
+pub fn find_gradle_modules(root: &Path, filter: &FileFilter) -> Result<Vec<GradleModule>> {
+    let settings = GRADLE_SETTINGS_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+        .map(|path| {
+            std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut dirs: Vec<PathBuf> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| {
+            let relative = e.path().strip_prefix(root).unwrap_or(e.path());
+            relative.as_os_str().is_empty() || !filter.is_excluded(relative)
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_file()
+                && GRADLE_BUILD_FILES
+                    .iter()
+                    .any(|name| e.file_name() == std::ffi::OsStr::new(name))
+        })
+        .filter_map(|e| e.path().parent().map(Path::to_path_buf))
+        .collect();
+    // Projects without a build file of their own, e.g. `include(":alder:domain")`
+    for project in settings_includes(&settings) {
+        let dir = project
+            .split(':')
+            .filter(|part| !part.is_empty())
+            .fold(root.to_path_buf(), |dir, part| dir.join(part));
+        if dir.is_dir() {
+            dirs.push(dir);
+        }
+    }
+    dirs.sort();
+    dirs.dedup();
+
+    let root_name = settings_root_name(&settings).unwrap_or_else(|| {
+        root.canonicalize()
+            .ok()
+            .and_then(|root| {
+                root.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| ":".to_string())
+    });
+    Ok(dirs
+        .into_iter()
+        .map(|dir| {
+            let relative = dir.strip_prefix(root).unwrap_or(&dir);
+            let name = if relative.as_os_str().is_empty() {
+                root_name.clone()
+            } else {
+                relative
+                    .components()
+                    .map(|part| format!(":{}", part.as_os_str().to_string_lossy()))
+                    .collect()
+            };
+            GradleModule { name, dir }
+        })
+        .collect())
+}
+
+/// The project paths in the `include` statements of a settings file, like `:alder:domain` in
+/// `include(":alder:domain")` or `include ':alder:domain'`
+fn settings_includes(settings: &str) -> Vec<String> {
+    settings
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("include"))
+        .flat_map(|line| {
+            line.split(['"', '\''])
+                .skip(1)
+                .step_by(2)
+                .map(|project| project.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// The `rootProject.name = "..."` of a settings file
+fn settings_root_name(settings: &str) -> Option<String> {
+    settings.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("rootProject.name")?;
+        let value = value.trim().strip_prefix('=')?.trim();
+        let name = value.trim_matches(|c| c == '"' || c == '\'');
+        (!name.is_empty()).then(|| name.to_string())
+    })
+}
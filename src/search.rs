@@ -0,0 +1,110 @@
+//! Finding aktiviteter and processors by name, with where they sit in the flows
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::lint::{indexed_aktiviteter, reachable_aktiviteter};
+use crate::model::{FlowModel, NextAktivitet, SourceLocation};
+
+/// What a search pattern matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMatch {
+    Aktivitet,
+    Processor,
+}
+
+/// An aktivitet whose name or processor matched, with its flow context
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub aktivitet: String,
+    pub matches: Vec<SearchMatch>,
+    pub location: Option<SourceLocation>, // Where the aktivitet class is declared, if it is known
+    pub processor: Option<String>,
+    pub processor_location: Option<SourceLocation>,
+    pub behandlinger: Vec<String>, // Behandlinger whose flow contains the aktivitet
+    pub predecessors: Vec<Neighbour>,
+    pub successors: Vec<Neighbour>,
+    pub endings: Vec<String>, // How the processor ends the flow, e.g. `END (Resultat.INNVILGET)`
+}
+
+/// A transition to or from a found aktivitet
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Neighbour {
+    pub aktivitet: String,
+    pub condition: Option<String>,
+    pub error_type: Option<String>, // Exception type if the transition is made in a catch block
+    pub location: SourceLocation,   // The nesteAktivitet call that makes the transition
+}
+
+impl Neighbour {
+    fn new(aktivitet: &str, next: &NextAktivitet) -> Self {
+        Neighbour {
+            aktivitet: aktivitet.to_string(),
+            condition: next.condition.as_ref().map(ToString::to_string),
+            error_type: next.error_type.clone(),
+            location: next.location.clone(),
+        }
+    }
+}
+
+/// Every aktivitet whose name, or the name of its processor class, matches the pattern, sorted by
+/// name. Names are matched with their package, so `^alder\.` finds a package.
+pub fn search(model: &FlowModel, pattern: &Regex) -> Vec<SearchHit> {
+    let reachable = reachable_aktiviteter(model);
+    let mut hits = Vec::new();
+
+    for aktivitet in indexed_aktiviteter(model) {
+        let processor = model.processor_index.get(&aktivitet);
+        let mut matches = Vec::new();
+        if pattern.is_match(&aktivitet) {
+            matches.push(SearchMatch::Aktivitet);
+        }
+        if processor.is_some_and(|processor| pattern.is_match(&processor.processor_class)) {
+            matches.push(SearchMatch::Processor);
+        }
+        if matches.is_empty() {
+            continue;
+        }
+
+        let mut predecessors: Vec<Neighbour> = model
+            .processor_index
+            .iter()
+            .flat_map(|(from, info)| {
+                info.next_aktiviteter
+                    .iter()
+                    .filter(|next| next.aktivitet_name == aktivitet)
+                    .map(move |next| Neighbour::new(from, next))
+            })
+            .collect();
+        predecessors.sort_by_key(|neighbour| {
+            let location = &neighbour.location;
+            (neighbour.aktivitet.clone(), location.line, location.column)
+        });
+        let successors = processor
+            .into_iter()
+            .flat_map(|info| &info.next_aktiviteter)
+            .map(|next| Neighbour::new(&next.aktivitet_name, next))
+            .collect();
+        let endings = processor
+            .into_iter()
+            .flat_map(|info| &info.terminals)
+            .map(|terminal| terminal.describe())
+            .collect();
+
+        hits.push(SearchHit {
+            matches,
+            location: model.class_location(&aktivitet),
+            processor: processor.map(|info| info.processor_class.clone()),
+            processor_location: processor.and_then(|info| model.processor_location(info)),
+            behandlinger: reachable.get(&aktivitet).cloned().unwrap_or_default(),
+            predecessors,
+            successors,
+            endings,
+            aktivitet,
+        });
+    }
+    hits
+}
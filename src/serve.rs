@@ -0,0 +1,254 @@
+//! Local web UI for browsing the flows (`serve` subcommand)
+
+use anyhow::{Context, Result};
+use behandling_flow::render::{is_graphviz_missing, render_dot, render_svg_with_layout};
+use behandling_flow::{DotOptions, FlowModel};
+use log::{info, warn};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use tiny_http::{Header, ReadWrite, Request, Response, Server};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use crate::watch::watch_kotlin_sources;
+use crate::Scan;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+type Clients = Arc<Mutex<Vec<WebSocket<Box<dyn ReadWrite + Send>>>>>;
+
+/// Serve an index of all Behandlinger and render their SVGs on demand, until interrupted.
+/// The Kotlin sources are watched, and connected browsers reload when a flow changes.
+pub fn serve(
+    root_folder: &str,
+    model: FlowModel,
+    host: &str,
+    port: u16,
+    scan: &Scan,
+    dot_options: &DotOptions,
+) -> Result<()> {
+    let server = Server::http((host, port))
+        .map_err(|e| anyhow::anyhow!("Could not start server on {}:{}: {}", host, port, e))?;
+
+    let model = Arc::new(RwLock::new(model));
+    let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+    spawn_reloader(
+        root_folder.to_string(),
+        scan.clone(),
+        model.clone(),
+        clients.clone(),
+    );
+
+    info!(
+        "\n🌐 Serving flows on http://{}:{} (Ctrl+C to stop)",
+        host, port
+    );
+
+    for request in server.incoming_requests() {
+        let url = request.url().to_string();
+        let path = url.split('?').next().unwrap_or_default();
+
+        if path == "/ws" {
+            if let Some(socket) = accept_websocket(request) {
+                clients.lock().unwrap().push(socket);
+            }
+            continue;
+        }
+
+        let response = handle_request(&model.read().unwrap(), path, dot_options);
+        if let Err(e) = request.respond(response) {
+            warn!("  ⚠️  Failed to answer {}: {}", path, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Re-analyze the project whenever a .kt file changes and tell all browsers to reload. The
+/// project is scanned as at startup, with the same cache and simulated feature toggles.
+fn spawn_reloader(
+    root_folder: String,
+    scan: Scan,
+    model: Arc<RwLock<FlowModel>>,
+    clients: Clients,
+) {
+    thread::spawn(move || {
+        let root_path = PathBuf::from(&root_folder);
+        let result = watch_kotlin_sources(&root_path, &scan.filter, |changed| {
+            info!("🔄 {} file(s) changed, re-analyzing...", changed.len());
+            match scan.analyze(&root_folder) {
+                Ok(updated) => {
+                    *model.write().unwrap() = updated;
+                    clients
+                        .lock()
+                        .unwrap()
+                        .retain_mut(|socket| socket.send(Message::text("reload")).is_ok());
+                }
+                Err(e) => warn!("  ⚠️  Analysis failed: {:#}", e),
+            }
+        });
+        if let Err(e) = result {
+            warn!("  ⚠️  Live reload disabled: {:#}", e);
+        }
+    });
+}
+
+/// Complete the WebSocket handshake; the socket is only used to push reload messages
+fn accept_websocket(request: Request) -> Option<WebSocket<Box<dyn ReadWrite + Send>>> {
+    let key = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+        .map(|header| header.value.as_str().to_string());
+
+    let Some(key) = key else {
+        let _ = request.respond(
+            Response::from_data(b"Expected a WebSocket upgrade".to_vec()).with_status_code(400),
+        );
+        return None;
+    };
+
+    let accept = tungstenite::handshake::derive_accept_key(key.as_bytes());
+    let response = Response::empty(101)
+        .with_header(Header::from_bytes(&b"Sec-WebSocket-Accept"[..], accept.as_bytes()).unwrap());
+    let stream = request.upgrade("websocket", response);
+    Some(WebSocket::from_raw_socket(stream, Role::Server, None))
+}
+
+fn handle_request(model: &FlowModel, path: &str, dot_options: &DotOptions) -> HttpResponse {
+    // Browsers send the names of Behandlinger outside ASCII percent-encoded
+    let Ok(path) = percent_decode_str(path).decode_utf8() else {
+        return not_found();
+    };
+    let path: &str = &path;
+    if path == "/" {
+        return html_response(&index_page(model));
+    }
+
+    let Some(page) = path.strip_prefix("/flow/") else {
+        return not_found();
+    };
+    let svg_name = page.strip_suffix(".svg");
+    let name = svg_name.unwrap_or(page);
+    if !model
+        .behandlinger()
+        .iter()
+        .any(|(behandling, _)| *behandling == name)
+    {
+        return not_found();
+    }
+    if svg_name.is_none() {
+        return html_response(&flow_page(name));
+    }
+
+    match render_flow_svg(model, name, dot_options) {
+        Ok(svg) => Response::from_data(svg).with_header(content_type("image/svg+xml")),
+        Err(e) => {
+            warn!("  ⚠️  Could not render {}: {:#}", name, e);
+            Response::from_data(format!("Could not render {}: {:#}", name, e).into_bytes())
+                .with_status_code(500)
+                .with_header(content_type("text/plain; charset=utf-8"))
+        }
+    }
+}
+
+/// Render with graphviz, or with the built-in renderer (without legend) when graphviz is missing
+fn render_flow_svg(model: &FlowModel, name: &str, dot_options: &DotOptions) -> Result<Vec<u8>> {
+    let dot_content = model.to_dot(name, dot_options)?;
+    match render_dot(&dot_content, "svg") {
+        Err(e) if is_graphviz_missing(&e) => {
+            let fallback_dot = model.to_dot(
+                name,
+                &DotOptions {
+                    show_legend: false,
+                    subtitle: None,
+                    ..*dot_options
+                },
+            )?;
+            Ok(render_svg_with_layout(&fallback_dot)?.into_bytes())
+        }
+        result => result,
+    }
+}
+
+fn index_page(model: &FlowModel) -> String {
+    let mut items = String::new();
+    for (name, info) in model.behandlinger() {
+        items.push_str(&format!(
+            "      <li><a href=\"/flow/{0}\">{1}</a> <small>starts with {2}</small></li>\n",
+            utf8_percent_encode(name, NON_ALPHANUMERIC),
+            name,
+            info.initial_aktivitet.as_deref().unwrap_or_default()
+        ));
+    }
+
+    page(
+        "Behandling flows",
+        &format!(
+            "    <h1>Behandling flows</h1>\n    <ul>\n{}    </ul>\n",
+            items
+        ),
+    )
+}
+
+fn flow_page(name: &str) -> String {
+    page(
+        &format!("{} Flow", name),
+        &format!(
+            "    <p><a href=\"/\">← All flows</a></p>\n    <h1>{0} Flow</h1>\n    <img id=\"flow\" src=\"/flow/{1}.svg\" alt=\"{0} flow\">\n",
+            name,
+            utf8_percent_encode(name, NON_ALPHANUMERIC)
+        ),
+    )
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <title>{}</title>
+    <style>
+      body {{ font-family: Arial, sans-serif; margin: 2em; }}
+      li {{ margin: 0.3em 0; }}
+      small {{ color: #666; }}
+      img {{ max-width: 100%; }}
+    </style>
+  </head>
+  <body>
+{}    <script>
+      // Live reload: refresh the diagram (or the list) when the Kotlin sources change
+      const socket = new WebSocket(`ws://${{location.host}}/ws`);
+      socket.onmessage = () => {{
+        const flow = document.getElementById("flow");
+        if (flow) {{
+          flow.src = flow.src.split("?")[0] + "?t=" + Date.now();
+        }} else {{
+          location.reload();
+        }}
+      }};
+    </script>
+  </body>
+</html>
+"#,
+        title, body
+    )
+}
+
+fn html_response(html: &str) -> HttpResponse {
+    Response::from_data(html.as_bytes().to_vec())
+        .with_header(content_type("text/html; charset=utf-8"))
+}
+
+fn not_found() -> HttpResponse {
+    Response::from_data(b"Not found".to_vec())
+        .with_status_code(404)
+        .with_header(content_type("text/plain; charset=utf-8"))
+}
+
+fn content_type(value: &str) -> Header {
+    Header::from_bytes(&b"Content-Type"[..], value.as_bytes()).unwrap()
+}
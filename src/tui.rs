@@ -0,0 +1,388 @@
+//! Interactive terminal flow explorer (`explore` subcommand)
+
+use anyhow::{Context, Result};
+use behandling_flow::FlowModel;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+/// A visible line in the flow tree
+struct TreeRow {
+    path: Vec<String>, // Aktiviteter from the initial aktivitet down to this one
+    condition: Option<String>,
+    is_collection: bool,
+    error_type: Option<String>, // Set for transitions made in a catch block
+    is_cycle: bool,             // The aktivitet already appears higher up on this branch
+}
+
+impl TreeRow {
+    fn aktivitet(&self) -> &str {
+        self.path.last().map(String::as_str).unwrap_or_default()
+    }
+}
+
+enum Screen {
+    Behandlinger,
+    Flow {
+        behandling: String,
+        expanded: HashSet<Vec<String>>,
+        selected: ListState,
+    },
+}
+
+struct App<'a> {
+    model: &'a FlowModel,
+    behandlinger: Vec<String>,
+    behandling_list: ListState,
+    screen: Screen,
+}
+
+/// Run the explorer until the user quits
+pub fn explore(model: &FlowModel) -> Result<()> {
+    let behandlinger: Vec<String> = model
+        .behandlinger()
+        .into_iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    let mut app = App {
+        model,
+        behandlinger,
+        behandling_list: ListState::default().with_selected(Some(0)),
+        screen: Screen::Behandlinger,
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match &mut self.screen {
+                Screen::Behandlinger => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Down | KeyCode::Char('j') => self.behandling_list.select_next(),
+                    KeyCode::Up | KeyCode::Char('k') => self.behandling_list.select_previous(),
+                    KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                        if let Some(index) = self.behandling_list.selected() {
+                            let behandling =
+                                self.behandlinger[index.min(self.behandlinger.len() - 1)].clone();
+                            self.screen = Screen::Flow {
+                                behandling,
+                                expanded: HashSet::new(),
+                                selected: ListState::default().with_selected(Some(0)),
+                            };
+                        }
+                    }
+                    _ => {}
+                },
+                Screen::Flow {
+                    behandling,
+                    expanded,
+                    selected,
+                } => {
+                    let rows = flow_rows(self.model, behandling, expanded);
+                    let current = selected
+                        .selected()
+                        .and_then(|index| rows.get(index.min(rows.len().saturating_sub(1))));
+
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => self.screen = Screen::Behandlinger,
+                        KeyCode::Down | KeyCode::Char('j') => selected.select_next(),
+                        KeyCode::Up | KeyCode::Char('k') => selected.select_previous(),
+                        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                            if let Some(row) = current.filter(|row| !row.is_cycle) {
+                                // Enter toggles, right arrow only expands
+                                if !expanded.insert(row.path.clone()) && key.code == KeyCode::Enter
+                                {
+                                    expanded.remove(&row.path);
+                                }
+                            }
+                        }
+                        KeyCode::Left | KeyCode::Char('h') => {
+                            if let Some(row) = current {
+                                if !expanded.remove(&row.path) && row.path.len() > 1 {
+                                    // Already collapsed: jump to the parent
+                                    let parent = &row.path[..row.path.len() - 1];
+                                    if let Some(index) = rows.iter().position(|r| r.path == parent)
+                                    {
+                                        selected.select(Some(index));
+                                    }
+                                }
+                            }
+                        }
+                        KeyCode::Char('e') => {
+                            if let Some(processor) = current
+                                .and_then(|row| self.model.processor_index.get(row.aktivitet()))
+                            {
+                                open_in_editor(terminal, &processor.file)?;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main_area, help_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        match &mut self.screen {
+            Screen::Behandlinger => {
+                let items: Vec<ListItem> = self
+                    .behandlinger
+                    .iter()
+                    .map(|name| ListItem::new(name.as_str()))
+                    .collect();
+                let list = List::new(items)
+                    .block(Block::bordered().title(" Behandlinger "))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                frame.render_stateful_widget(list, main_area, &mut self.behandling_list);
+                frame.render_widget(
+                    Paragraph::new(" ↑/↓ select   Enter open   q quit"),
+                    help_area,
+                );
+            }
+            Screen::Flow {
+                behandling,
+                expanded,
+                selected,
+            } => {
+                let [tree_area, detail_area] =
+                    Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .areas(main_area);
+
+                let rows = flow_rows(self.model, behandling, expanded);
+                let items: Vec<ListItem> = rows
+                    .iter()
+                    .map(|row| tree_item(self.model, row, expanded))
+                    .collect();
+                let list = List::new(items)
+                    .block(Block::bordered().title(format!(" {} Flow ", behandling)))
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                frame.render_stateful_widget(list, tree_area, selected);
+
+                let current = selected
+                    .selected()
+                    .and_then(|index| rows.get(index.min(rows.len().saturating_sub(1))));
+                let details = Paragraph::new(detail_lines(self.model, current))
+                    .block(Block::bordered().title(" Details "))
+                    .wrap(Wrap { trim: false });
+                frame.render_widget(details, detail_area);
+
+                frame.render_widget(
+                    Paragraph::new(
+                        " ↑/↓ move   →/Enter expand   ← collapse   e open file   q back",
+                    ),
+                    help_area,
+                );
+            }
+        }
+    }
+}
+
+/// Flatten the expanded part of the flow tree into visible rows
+fn flow_rows(model: &FlowModel, behandling: &str, expanded: &HashSet<Vec<String>>) -> Vec<TreeRow> {
+    fn add_children(
+        model: &FlowModel,
+        parent_path: &[String],
+        expanded: &HashSet<Vec<String>>,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        if !expanded.contains(parent_path) {
+            return;
+        }
+        let Some(processor) = parent_path
+            .last()
+            .and_then(|aktivitet| model.processor_index.get(aktivitet))
+        else {
+            return;
+        };
+
+        for next in &processor.next_aktiviteter {
+            let mut path = parent_path.to_vec();
+            path.push(next.aktivitet_name.clone());
+            let is_cycle = parent_path.contains(&next.aktivitet_name);
+            rows.push(TreeRow {
+                path: path.clone(),
+                condition: next.condition.as_ref().map(ToString::to_string),
+                is_collection: next.is_collection,
+                error_type: next.error_type.clone(),
+                is_cycle,
+            });
+            if !is_cycle {
+                add_children(model, &path, expanded, rows);
+            }
+        }
+    }
+
+    let Some(initial_aktivitet) = model
+        .class_index
+        .get(behandling)
+        .and_then(|info| info.initial_aktivitet.clone())
+    else {
+        return Vec::new();
+    };
+
+    let root_path = vec![initial_aktivitet];
+    let mut rows = vec![TreeRow {
+        path: root_path.clone(),
+        condition: None,
+        is_collection: false,
+        error_type: None,
+        is_cycle: false,
+    }];
+    add_children(model, &root_path, expanded, &mut rows);
+    rows
+}
+
+fn tree_item<'a>(
+    model: &FlowModel,
+    row: &'a TreeRow,
+    expanded: &HashSet<Vec<String>>,
+) -> ListItem<'a> {
+    let processor = model.processor_index.get(row.aktivitet());
+    let marker = match processor {
+        _ if row.is_cycle => "↩ ",
+        None => "? ",
+        Some(info) if info.next_aktiviteter.is_empty() => "■ ",
+        Some(_) if expanded.contains(&row.path) => "▼ ",
+        Some(_) => "▶ ",
+    };
+
+    let mut spans = vec![
+        Span::raw("  ".repeat(row.path.len() - 1)),
+        Span::raw(marker),
+        Span::styled(
+            row.aktivitet(),
+            match processor {
+                None => Style::default().fg(Color::DarkGray),
+                Some(info) if info.has_manuell_behandling => Style::default().fg(Color::Red),
+                Some(_) => Style::default(),
+            },
+        ),
+    ];
+    if row.is_collection {
+        spans.push(Span::styled(
+            " (for each)",
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+    if let Some(error_type) = &row.error_type {
+        spans.push(Span::styled(
+            format!(" (on {})", error_type),
+            Style::default().fg(Color::Red),
+        ));
+    }
+    if let Some(condition) = &row.condition {
+        spans.push(Span::styled(
+            format!("  [{}]", condition),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+fn detail_lines<'a>(model: &'a FlowModel, row: Option<&'a TreeRow>) -> Vec<Line<'a>> {
+    let Some(row) = row else {
+        return Vec::new();
+    };
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![Line::styled(row.aktivitet(), bold), Line::raw("")];
+    if let Some(error_type) = &row.error_type {
+        lines.push(Line::styled(
+            format!("Reached when {} is caught", error_type),
+            Style::default().fg(Color::Red),
+        ));
+        lines.push(Line::raw(""));
+    }
+    if let Some(condition) = &row.condition {
+        lines.push(Line::raw(format!("Reached when: {}", condition)));
+        lines.push(Line::raw(""));
+    }
+
+    match model.processor_index.get(row.aktivitet()) {
+        Some(processor) => {
+            lines.push(Line::raw(format!(
+                "Processor: {}",
+                processor.processor_class
+            )));
+            lines.push(Line::raw(format!("File: {}", processor.file.display())));
+            if processor.has_manuell_behandling {
+                lines.push(Line::styled(
+                    "Creates manuell behandling",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            lines.push(Line::raw(""));
+            lines.push(Line::styled("Next:", bold));
+            for terminal in &processor.terminals {
+                lines.push(Line::raw(format!("  [{}]", terminal.describe())));
+            }
+            if processor.is_unresolved() {
+                lines.push(Line::raw("  [END?] no transition found"));
+            }
+            for next in &processor.next_aktiviteter {
+                lines.push(Line::raw(format!("  → {}", next.describe())));
+                if let Some(error_type) = &next.error_type {
+                    lines.push(Line::styled(
+                        format!("    on {}", error_type),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                if let Some(condition) = &next.condition {
+                    lines.push(Line::styled(
+                        format!("    if {}", condition),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+            }
+            for error_type in &processor.manuell_on_error {
+                lines.push(Line::raw("  → 📋 Manuell behandling"));
+                lines.push(Line::styled(
+                    format!("    on {}", error_type),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+        }
+        None => lines.push(Line::raw("No processor found")),
+    }
+
+    lines
+}
+
+/// Leave the TUI, open the file in $EDITOR (or the system default), and come back
+fn open_in_editor(terminal: &mut DefaultTerminal, file: &Path) -> Result<()> {
+    ratatui::restore();
+    let result = match std::env::var("EDITOR") {
+        Ok(editor) => Command::new(editor)
+            .arg(file)
+            .status()
+            .map(|_| ())
+            .with_context(|| format!("Failed to open {} in $EDITOR", file.display())),
+        Err(_) => opener::open(file).with_context(|| format!("Failed to open {}", file.display())),
+    };
+    *terminal = ratatui::init();
+    result
+}
@@ -0,0 +1,76 @@
+//! Watching Kotlin sources for changes
+
+use anyhow::{Context, Result};
+use behandling_flow::FileFilter;
+use log::warn;
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Editors often save a file in several steps; events arriving this close together are merged
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Block and call `on_change` with the changed Kotlin sources every time sources below `root` change.
+/// The paths are given below `root` as passed in, the same way the scanner lists them, and
+/// files the filter excludes are ignored.
+pub fn watch_kotlin_sources(
+    root: &Path,
+    filter: &FileFilter,
+    mut on_change: impl FnMut(Vec<PathBuf>),
+) -> Result<()> {
+    let canonical_root = root
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", root.display()))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to create file watcher")?;
+    watcher
+        .watch(&canonical_root, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", root.display()))?;
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        collect_kotlin_changes(event, &canonical_root, filter, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_kotlin_changes(event, &canonical_root, filter, &mut changed);
+        }
+
+        let changed: Vec<PathBuf> = changed
+            .into_iter()
+            .filter_map(|path| match path.strip_prefix(&canonical_root) {
+                // Watching a single file
+                Ok(relative) if relative.as_os_str().is_empty() => Some(root.to_path_buf()),
+                Ok(relative) if !filter.is_match(relative) => None,
+                Ok(relative) => Some(root.join(relative)),
+                Err(_) => Some(path),
+            })
+            .collect();
+        if !changed.is_empty() {
+            on_change(changed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the Kotlin sources in `event` to `changed`. A watched single file always counts, whatever
+/// its extension.
+fn collect_kotlin_changes(
+    event: notify::Result<notify::Event>,
+    canonical_root: &Path,
+    filter: &FileFilter,
+    changed: &mut BTreeSet<PathBuf>,
+) {
+    match event {
+        Ok(event) if !event.kind.is_access() => changed.extend(
+            event
+                .paths
+                .into_iter()
+                .filter(|path| path == canonical_root || filter.is_kotlin_source(path)),
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("  ⚠️  File watcher error: {}", e),
+    }
+}
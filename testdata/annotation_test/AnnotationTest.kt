@@ -0,0 +1,53 @@
+package no.nav.pensjon.annotation
+
+annotation class Steg
+annotation class Prosessor(val aktivitet: KClass<*>)
+annotation class StegHandler
+
+class AnnotationTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaSoknad {
+        return MottaSoknad()
+    }
+}
+
+@Steg
+class MottaSoknad : Aktivitet()
+
+@Steg
+class VurderVilkar : Aktivitet()
+
+@Steg
+class SendBrev : Aktivitet()
+
+@Steg
+class Avslutt : Aktivitet()
+
+// Named like a plain service, found through the annotation's class literal
+@Prosessor(MottaSoknad::class)
+class MottakHandler {
+    fun doProcess(aktivitet: MottaSoknad): AktivitetResponse {
+        return nesteAktivitet(VurderVilkar())
+    }
+}
+
+// No class literal; the aktivitet comes from the supertype
+@StegHandler
+class VilkarsvurderingService : AktivitetProcessor<AnnotationTestBehandling, VurderVilkar>() {
+    fun doProcess(aktivitet: VurderVilkar): AktivitetResponse {
+        return nesteAktiviteter(listOf("bruker", "verge").map { SendBrev() })
+    }
+}
+
+@Prosessor(aktivitet = SendBrev::class)
+class BrevHandler {
+    fun doProcess(aktivitet: SendBrev): AktivitetResponse {
+        return nesteAktivitet(Avslutt())
+    }
+}
+
+@Prosessor(Avslutt::class)
+class AvsluttHandler {
+    fun doProcess(aktivitet: Avslutt): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
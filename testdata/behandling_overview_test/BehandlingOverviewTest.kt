@@ -0,0 +1,59 @@
+// `--behandling-overview` draws SoknadBehandling starting EtteroppgjorBehandling and waiting on
+// UforetrygdBehandling, which resumes it once it is done
+class SoknadBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderSoknadAktivitet {
+        return VurderSoknadAktivitet()
+    }
+}
+
+class UforetrygdBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderUforegradAktivitet {
+        return VurderUforegradAktivitet()
+    }
+}
+
+class EtteroppgjorBehandling(val soknad: Soknad) : Behandling() {
+    fun opprettInitiellAktivitet(): BeregnEtteroppgjorAktivitet {
+        return BeregnEtteroppgjorAktivitet()
+    }
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class VentPaaUforetrygdAktivitet : Aktivitet()
+class VurderUforegradAktivitet : Aktivitet()
+class BeregnEtteroppgjorAktivitet : Aktivitet()
+
+class VurderSoknadAktivitetProcessor : AktivitetProcessor<SoknadBehandling, VurderSoknadAktivitet>() {
+    fun doProcess(aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        if (aktivitet.soknad.krevetEtteroppgjor) {
+            behandlingService.opprettBehandling(EtteroppgjorBehandling(aktivitet.soknad))
+        }
+        return nesteAktivitet(VentPaaUforetrygdAktivitet())
+    }
+}
+
+class VentPaaUforetrygdAktivitetProcessor : AktivitetProcessor<SoknadBehandling, VentPaaUforetrygdAktivitet>() {
+    fun doProcess(aktivitet: VentPaaUforetrygdAktivitet): AktivitetResponse {
+        // By class literal
+        behandlingService.ventPaaBehandling(UforetrygdBehandling::class)
+        return aktivitetFullfort()
+    }
+}
+
+class VurderUforegradAktivitetProcessor : AktivitetProcessor<UforetrygdBehandling, VurderUforegradAktivitet>() {
+    fun doProcess(aktivitet: VurderUforegradAktivitet): AktivitetResponse {
+        gjenoppta(aktivitet.sakId)
+        return aktivitetFullfort()
+    }
+
+    // Found through the helper, by type argument
+    private fun gjenoppta(sakId: Long) {
+        behandlingService.gjenopptaBehandling<SoknadBehandling>(sakId)
+    }
+}
+
+class BeregnEtteroppgjorAktivitetProcessor : AktivitetProcessor<EtteroppgjorBehandling, BeregnEtteroppgjorAktivitet>() {
+    fun doProcess(aktivitet: BeregnEtteroppgjorAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,30 @@
+package no.nav.pensjon.alder
+
+// A cached rerun must give the same model as a full scan. Run `behandling-flow --cache` on
+// testdata/cache_test, then copy pending/UforeBehandling.kt.pending to ufore/UforeBehandling.kt
+// and run it again: VurderVilkarAktivitet is now declared in two packages, so both are keyed by
+// their qualified name, and the flow of AlderBehandling must still reach IverksettVedtakAktivitet
+// instead of ending in `?`. Deleting ufore/UforeBehandling.kt again must give the first model
+// back, keyed by the simple name.
+class AlderBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderVilkarAktivitet {
+        return VurderVilkarAktivitet()
+    }
+}
+
+class VurderVilkarAktivitet : Aktivitet()
+class IverksettVedtakAktivitet : Aktivitet()
+
+class VurderVilkarAktivitetProcessor :
+    AktivitetProcessor<AlderBehandling, VurderVilkarAktivitet>() {
+    fun doProcess(aktivitet: VurderVilkarAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
+
+class IverksettVedtakAktivitetProcessor :
+    AktivitetProcessor<AlderBehandling, IverksettVedtakAktivitet>() {
+    fun doProcess(aktivitet: IverksettVedtakAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
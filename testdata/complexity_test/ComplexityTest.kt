@@ -0,0 +1,43 @@
+package no.nav.test.complexity
+
+// 8 transitions between 6 nodes (START is not counted) in one component: complexity 8 − 6 + 2 = 4,
+// above the max-complexity of 3 in .behandling-flow.toml, so lint reports complex-flow
+class ComplexityTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderAktivitet()
+}
+
+class VurderAktivitet : Aktivitet()
+class BeregnAktivitet : Aktivitet()
+class UtlandAktivitet : Aktivitet()
+class VedtakAktivitet : Aktivitet()
+
+class VurderProcessor : AktivitetProcessor<ComplexityTestBehandling, VurderAktivitet>() {
+    override fun doProcess(behandling: ComplexityTestBehandling, aktivitet: VurderAktivitet): AktivitetResponse {
+        return when {
+            behandling.erTrukket -> avbrytBehandling("Trukket")
+            behandling.erUtland -> nesteAktivitet(UtlandAktivitet())
+            else -> nesteAktivitet(BeregnAktivitet())
+        }
+    }
+}
+
+class UtlandProcessor : AktivitetProcessor<ComplexityTestBehandling, UtlandAktivitet>() {
+    override fun doProcess(behandling: ComplexityTestBehandling, aktivitet: UtlandAktivitet): AktivitetResponse {
+        return if (behandling.harAvtale) nesteAktivitet(BeregnAktivitet()) else nesteAktivitet(VedtakAktivitet())
+    }
+}
+
+class BeregnProcessor : AktivitetProcessor<ComplexityTestBehandling, BeregnAktivitet>() {
+    override fun doProcess(behandling: ComplexityTestBehandling, aktivitet: BeregnAktivitet): AktivitetResponse {
+        if (behandling.manglerGrunnlag) {
+            return avbrytBehandling("Mangler grunnlag")
+        }
+        return nesteAktivitet(VedtakAktivitet())
+    }
+}
+
+class VedtakProcessor : AktivitetProcessor<ComplexityTestBehandling, VedtakAktivitet>() {
+    override fun doProcess(behandling: ComplexityTestBehandling, aktivitet: VedtakAktivitet): AktivitetResponse {
+        return if (behandling.innvilget) aktivitetFullfort(Resultat.INNVILGET) else aktivitetFullfort(Resultat.AVSLATT)
+    }
+}
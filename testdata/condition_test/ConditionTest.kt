@@ -0,0 +1,47 @@
+package no.nav.test.condition
+
+// `lint` reports a duplicate-condition in VurderSoknadProcessor, where `alder >= 67` was copied
+// to a second branch, and a redundant-condition in BeregnProcessor, whose if and else both go to
+// IverksettAktivitet
+class ConditionTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class BeregnAktivitet : Aktivitet()
+class AvslaAktivitet : Aktivitet()
+class IverksettAktivitet : Aktivitet()
+
+class VurderSoknadProcessor : AktivitetProcessor<ConditionTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: ConditionTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        if (behandling.alder >= 67) {
+            return nesteAktivitet(BeregnAktivitet())
+        }
+        if (behandling.alder >= 67) {
+            return nesteAktivitet(AvslaAktivitet())
+        }
+        return nesteAktivitet(AvslaAktivitet())
+    }
+}
+
+class BeregnProcessor : AktivitetProcessor<ConditionTestBehandling, BeregnAktivitet>() {
+    override fun doProcess(behandling: ConditionTestBehandling, aktivitet: BeregnAktivitet): AktivitetResponse {
+        return if (behandling.harUttak) {
+            nesteAktivitet(IverksettAktivitet())
+        } else {
+            nesteAktivitet(IverksettAktivitet())
+        }
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<ConditionTestBehandling, AvslaAktivitet>() {
+    override fun doProcess(behandling: ConditionTestBehandling, aktivitet: AvslaAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.AVSLATT)
+    }
+}
+
+class IverksettProcessor : AktivitetProcessor<ConditionTestBehandling, IverksettAktivitet>() {
+    override fun doProcess(behandling: ConditionTestBehandling, aktivitet: IverksettAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.INNVILGET)
+    }
+}
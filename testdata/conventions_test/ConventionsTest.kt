@@ -0,0 +1,42 @@
+class ConventionsTestBehandling : Behandling() {
+    fun createInitialActivity(): VurderKravAktivitet {
+        return VurderKravAktivitet()
+    }
+}
+
+class VurderKravAktivitet : Aktivitet()
+class InnvilgKravAktivitet : Aktivitet()
+class SendBrevAktivitet : Aktivitet()
+class ArkiverAktivitet : Aktivitet()
+
+class VurderKravAktivitetProcessor : AktivitetProcessor<ConventionsTestBehandling, VurderKravAktivitet>() {
+    fun process(aktivitet: VurderKravAktivitet): AktivitetResponse {
+        if (aktivitet.krav == null) {
+            manualProcessing = ManualProcessing("Krav mangler")
+        }
+        return nextActivity(InnvilgKravAktivitet())
+    }
+}
+
+class InnvilgKravAktivitetProcessor : AktivitetProcessor<ConventionsTestBehandling, InnvilgKravAktivitet>() {
+    fun process(aktivitet: InnvilgKravAktivitet): AktivitetResponse {
+        return nextActivities(listOf(SendBrevAktivitet(), ArkiverAktivitet()))
+    }
+}
+
+class SendBrevAktivitetProcessor : AktivitetProcessor<ConventionsTestBehandling, SendBrevAktivitet>() {
+    fun process(aktivitet: SendBrevAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+
+    // Runs once the aktivitet is done, like onFinished in the original framework
+    fun finished(aktivitet: SendBrevAktivitet): AktivitetResponse {
+        return nextActivity(ArkiverAktivitet())
+    }
+}
+
+class ArkiverAktivitetProcessor : AktivitetProcessor<ConventionsTestBehandling, ArkiverAktivitet>() {
+    fun process(aktivitet: ArkiverAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
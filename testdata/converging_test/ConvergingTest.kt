@@ -0,0 +1,37 @@
+package no.nav.test.converging
+
+// `lint` reports converging-branches in VurderSoknadProcessor, whose three branches all go to
+// BeregnAktivitet. BeregnProcessor is left alone: its if and else are already redundant-condition.
+class ConvergingTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class BeregnAktivitet : Aktivitet()
+class IverksettAktivitet : Aktivitet()
+
+class VurderSoknadProcessor : AktivitetProcessor<ConvergingTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: ConvergingTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return when (behandling.sakstype) {
+            Sakstype.ALDER -> nesteAktivitet(BeregnAktivitet())
+            Sakstype.UFORE -> nesteAktivitet(BeregnAktivitet())
+            else -> nesteAktivitet(BeregnAktivitet())
+        }
+    }
+}
+
+class BeregnProcessor : AktivitetProcessor<ConvergingTestBehandling, BeregnAktivitet>() {
+    override fun doProcess(behandling: ConvergingTestBehandling, aktivitet: BeregnAktivitet): AktivitetResponse {
+        return if (behandling.harUttak) {
+            nesteAktivitet(IverksettAktivitet())
+        } else {
+            nesteAktivitet(IverksettAktivitet())
+        }
+    }
+}
+
+class IverksettProcessor : AktivitetProcessor<ConvergingTestBehandling, IverksettAktivitet>() {
+    override fun doProcess(behandling: ConvergingTestBehandling, aktivitet: IverksettAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.INNVILGET)
+    }
+}
@@ -0,0 +1,42 @@
+package no.nav.test.counts
+
+// `--counts testdata/counts_test/execution_counts.csv` draws VurderSoknadAktivitet and
+// InnvilgAktivitet hot, AvslaAktivitet cooler and ManuellKontrollAktivitet as never run.
+// `--durations testdata/counts_test/durations.csv` draws ManuellKontrollAktivitet red and
+// InnvilgAktivitet green.
+class CountsTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class InnvilgAktivitet : Aktivitet()
+class AvslaAktivitet : Aktivitet()
+class ManuellKontrollAktivitet : Aktivitet()
+
+class VurderSoknadProcessor : AktivitetProcessor<CountsTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: CountsTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return when {
+            behandling.harUtenlandsopphold -> nesteAktivitet(ManuellKontrollAktivitet())
+            behandling.oppfyllerVilkar -> nesteAktivitet(InnvilgAktivitet())
+            else -> nesteAktivitet(AvslaAktivitet())
+        }
+    }
+}
+
+class InnvilgProcessor : AktivitetProcessor<CountsTestBehandling, InnvilgAktivitet>() {
+    override fun doProcess(behandling: CountsTestBehandling, aktivitet: InnvilgAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.INNVILGET)
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<CountsTestBehandling, AvslaAktivitet>() {
+    override fun doProcess(behandling: CountsTestBehandling, aktivitet: AvslaAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.AVSLATT)
+    }
+}
+
+class ManuellKontrollProcessor : AktivitetProcessor<CountsTestBehandling, ManuellKontrollAktivitet>() {
+    override fun doProcess(behandling: CountsTestBehandling, aktivitet: ManuellKontrollAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,43 @@
+package no.nav.test
+
+// Two loops through the same aktiviteter: back edges alone show only where they close, while
+// the cycle paths are VentPaSvar → SjekkSvar → VentPaSvar and
+// VentPaSvar → SjekkSvar → PurrSvar → VentPaSvar
+class InterleavedCycleBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VentPaSvarAktivitet {
+        return VentPaSvarAktivitet()
+    }
+}
+
+class VentPaSvarAktivitet : Aktivitet()
+class SjekkSvarAktivitet : Aktivitet()
+class PurrSvarAktivitet : Aktivitet()
+class RegistrerSvarAktivitet : Aktivitet()
+
+class VentPaSvarProcessor : AktivitetProcessor<VentPaSvarAktivitet>() {
+    fun doProcess(aktivitet: VentPaSvarAktivitet): AktivitetResponse {
+        return nesteAktivitet(SjekkSvarAktivitet())
+    }
+}
+
+class SjekkSvarProcessor : AktivitetProcessor<SjekkSvarAktivitet>() {
+    fun doProcess(aktivitet: SjekkSvarAktivitet): AktivitetResponse {
+        return when {
+            aktivitet.harSvar -> nesteAktivitet(RegistrerSvarAktivitet())
+            aktivitet.fristPassert -> nesteAktivitet(PurrSvarAktivitet())
+            else -> nesteAktivitet(VentPaSvarAktivitet())
+        }
+    }
+}
+
+class PurrSvarProcessor : AktivitetProcessor<PurrSvarAktivitet>() {
+    fun doProcess(aktivitet: PurrSvarAktivitet): AktivitetResponse {
+        return nesteAktivitet(VentPaSvarAktivitet())
+    }
+}
+
+class RegistrerSvarProcessor : AktivitetProcessor<RegistrerSvarAktivitet>() {
+    fun doProcess(aktivitet: RegistrerSvarAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,45 @@
+package no.nav.test.deprecated
+
+class DeprecatedTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = StartAktivitet()
+}
+
+class StartAktivitet : Aktivitet()
+
+// Still reached while the new flow is rolled out: drawn struck through and flagged by lint
+@Deprecated("Erstattes av NyVurderingAktivitet")
+class GammelVurderingAktivitet : Aktivitet()
+
+class NyVurderingAktivitet : Aktivitet()
+
+// No longer reached from any Behandling, so only unreachable-aktivitet applies
+@kotlin.Deprecated("Ikke i bruk")
+class UtgattAktivitet : Aktivitet()
+
+class StartProcessor : AktivitetProcessor<DeprecatedTestBehandling, StartAktivitet>() {
+    override fun doProcess(behandling: DeprecatedTestBehandling, aktivitet: StartAktivitet): AktivitetResponse {
+        return if (behandling.nyFlyt) {
+            nesteAktivitet(NyVurderingAktivitet())
+        } else {
+            nesteAktivitet(GammelVurderingAktivitet())
+        }
+    }
+}
+
+class GammelVurderingProcessor : AktivitetProcessor<DeprecatedTestBehandling, GammelVurderingAktivitet>() {
+    override fun doProcess(behandling: DeprecatedTestBehandling, aktivitet: GammelVurderingAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class NyVurderingProcessor : AktivitetProcessor<DeprecatedTestBehandling, NyVurderingAktivitet>() {
+    override fun doProcess(behandling: DeprecatedTestBehandling, aktivitet: NyVurderingAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class UtgattProcessor : AktivitetProcessor<DeprecatedTestBehandling, UtgattAktivitet>() {
+    override fun doProcess(behandling: DeprecatedTestBehandling, aktivitet: UtgattAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
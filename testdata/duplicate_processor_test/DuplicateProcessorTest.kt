@@ -0,0 +1,35 @@
+class DuplicateProcessorTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderKravAktivitet {
+        return VurderKravAktivitet()
+    }
+}
+
+class VurderKravAktivitet : Aktivitet()
+class InnvilgKravAktivitet : Aktivitet()
+class AvslaKravAktivitet : Aktivitet()
+
+class VurderKravAktivitetProcessor : AktivitetProcessor<DuplicateProcessorTestBehandling, VurderKravAktivitet>() {
+    fun doProcess(aktivitet: VurderKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(InnvilgKravAktivitet())
+    }
+}
+
+// A copy left behind after a refactoring: it handles the same aktivitet, so its transitions are
+// merged into the first processor's and a warning names both
+class GammelVurderKravAktivitetProcessor : AktivitetProcessor<DuplicateProcessorTestBehandling, VurderKravAktivitet>() {
+    fun doProcess(aktivitet: VurderKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(AvslaKravAktivitet())
+    }
+}
+
+class InnvilgKravAktivitetProcessor : AktivitetProcessor<DuplicateProcessorTestBehandling, InnvilgKravAktivitet>() {
+    fun doProcess(aktivitet: InnvilgKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class AvslaKravAktivitetProcessor : AktivitetProcessor<DuplicateProcessorTestBehandling, AvslaKravAktivitet>() {
+    fun doProcess(aktivitet: AvslaKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
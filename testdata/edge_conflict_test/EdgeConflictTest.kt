@@ -0,0 +1,62 @@
+package no.nav.test.edgeconflict
+
+// `lint` reports conflicting-edges three times: VurderSoknadProcessor goes to BeregnAktivitet
+// under two different conditions, of which the graph only shows the first, FordelKravProcessor
+// starts OpprettLinjeAktivitet both per krav linje and once on its own, and BeregnProcessor goes
+// to FordelKravAktivitet from doProcess and from onFinished, each under its own condition
+class EdgeConflictTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class BeregnAktivitet : Aktivitet()
+class AvslaAktivitet : Aktivitet()
+class FordelKravAktivitet : Aktivitet()
+class OpprettLinjeAktivitet : Aktivitet()
+
+class VurderSoknadProcessor : AktivitetProcessor<EdgeConflictTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: EdgeConflictTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return when {
+            behandling.alder >= 67 -> nesteAktivitet(BeregnAktivitet())
+            behandling.harAfp -> nesteAktivitet(BeregnAktivitet())
+            else -> nesteAktivitet(AvslaAktivitet())
+        }
+    }
+}
+
+class BeregnProcessor : AktivitetProcessor<EdgeConflictTestBehandling, BeregnAktivitet>() {
+    override fun doProcess(behandling: EdgeConflictTestBehandling, aktivitet: BeregnAktivitet): AktivitetResponse {
+        if (behandling.harUttak) {
+            return nesteAktivitet(FordelKravAktivitet())
+        }
+        return aktivitetFullfort()
+    }
+
+    override fun onFinished(behandling: EdgeConflictTestBehandling, aktivitet: BeregnAktivitet): AktivitetResponse {
+        if (behandling.erEtteroppgjor) {
+            return nesteAktivitet(FordelKravAktivitet())
+        }
+        return aktivitetFullfort()
+    }
+}
+
+class FordelKravProcessor : AktivitetProcessor<EdgeConflictTestBehandling, FordelKravAktivitet>() {
+    override fun doProcess(behandling: EdgeConflictTestBehandling, aktivitet: FordelKravAktivitet): AktivitetResponse {
+        behandling.kravLinjer.forEach { linje ->
+            nesteAktivitet(OpprettLinjeAktivitet(linje))
+        }
+        return nesteAktivitet(OpprettLinjeAktivitet())
+    }
+}
+
+class OpprettLinjeProcessor : AktivitetProcessor<EdgeConflictTestBehandling, OpprettLinjeAktivitet>() {
+    override fun doProcess(behandling: EdgeConflictTestBehandling, aktivitet: OpprettLinjeAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<EdgeConflictTestBehandling, AvslaAktivitet>() {
+    override fun doProcess(behandling: EdgeConflictTestBehandling, aktivitet: AvslaAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.AVSLATT)
+    }
+}
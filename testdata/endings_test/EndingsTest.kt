@@ -0,0 +1,46 @@
+package no.nav.test.endings
+
+// `list --endings` shows four endings for this flow where the graph has one END, ABORT and END?
+class EndingsTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class InnvilgAktivitet : Aktivitet()
+class AvslaAktivitet : Aktivitet()
+class VentPaDokumentasjonAktivitet : Aktivitet()
+class SendTilUtlandAktivitet : Aktivitet()
+
+class VurderSoknadProcessor : AktivitetProcessor<EndingsTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: EndingsTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return when {
+            behandling.soknadTrukket -> avbrytBehandling("Søknaden er trukket")
+            behandling.manglerDokumentasjon -> nesteAktivitet(VentPaDokumentasjonAktivitet())
+            behandling.bosattIUtlandet -> nesteAktivitet(SendTilUtlandAktivitet())
+            behandling.oppfyllerVilkar -> nesteAktivitet(InnvilgAktivitet())
+            else -> nesteAktivitet(AvslaAktivitet())
+        }
+    }
+}
+
+// Completed, once for each result
+class InnvilgProcessor : AktivitetProcessor<EndingsTestBehandling, InnvilgAktivitet>() {
+    override fun doProcess(behandling: EndingsTestBehandling, aktivitet: InnvilgAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.INNVILGET)
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<EndingsTestBehandling, AvslaAktivitet>() {
+    override fun doProcess(behandling: EndingsTestBehandling, aktivitet: AvslaAktivitet): AktivitetResponse {
+        return aktivitetFullfort(Resultat.AVSLATT)
+    }
+}
+
+// Dead end: no transition found
+class VentPaDokumentasjonProcessor : AktivitetProcessor<EndingsTestBehandling, VentPaDokumentasjonAktivitet>() {
+    override fun doProcess(behandling: EndingsTestBehandling, aktivitet: VentPaDokumentasjonAktivitet): AktivitetResponse {
+        return dokumentasjonService.purr(behandling)
+    }
+}
+
+// SendTilUtlandAktivitet has no processor: a dead end too
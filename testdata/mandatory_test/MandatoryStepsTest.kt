@@ -0,0 +1,55 @@
+package no.nav.test.mandatory
+
+// Every completion passes VurderVilkar and FattVedtak: `list --mandatory` prints
+// MottaSoknad → VurderVilkar → FattVedtak, and InnhentOpplysninger and Purring as skippable.
+// The abort does not count, since only completed behandlinger are considered.
+class MandatoryTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = MottaSoknadAktivitet()
+}
+
+class MottaSoknadAktivitet : Aktivitet()
+class InnhentOpplysningerAktivitet : Aktivitet()
+class PurringAktivitet : Aktivitet()
+class VurderVilkarAktivitet : Aktivitet()
+class FattVedtakAktivitet : Aktivitet()
+
+class MottaSoknadProcessor : AktivitetProcessor<MandatoryTestBehandling, MottaSoknadAktivitet>() {
+    override fun doProcess(behandling: MandatoryTestBehandling, aktivitet: MottaSoknadAktivitet): AktivitetResponse {
+        if (behandling.erTrukket) {
+            return avbrytBehandling("Trukket")
+        }
+        return if (behandling.manglerOpplysninger) {
+            nesteAktivitet(InnhentOpplysningerAktivitet())
+        } else {
+            nesteAktivitet(VurderVilkarAktivitet())
+        }
+    }
+}
+
+class InnhentOpplysningerProcessor : AktivitetProcessor<MandatoryTestBehandling, InnhentOpplysningerAktivitet>() {
+    override fun doProcess(behandling: MandatoryTestBehandling, aktivitet: InnhentOpplysningerAktivitet): AktivitetResponse {
+        return if (behandling.harSvar) {
+            nesteAktivitet(VurderVilkarAktivitet())
+        } else {
+            nesteAktivitet(PurringAktivitet())
+        }
+    }
+}
+
+class PurringProcessor : AktivitetProcessor<MandatoryTestBehandling, PurringAktivitet>() {
+    override fun doProcess(behandling: MandatoryTestBehandling, aktivitet: PurringAktivitet): AktivitetResponse {
+        return nesteAktivitet(InnhentOpplysningerAktivitet())
+    }
+}
+
+class VurderVilkarProcessor : AktivitetProcessor<MandatoryTestBehandling, VurderVilkarAktivitet>() {
+    override fun doProcess(behandling: MandatoryTestBehandling, aktivitet: VurderVilkarAktivitet): AktivitetResponse {
+        return nesteAktivitet(FattVedtakAktivitet())
+    }
+}
+
+class FattVedtakProcessor : AktivitetProcessor<MandatoryTestBehandling, FattVedtakAktivitet>() {
+    override fun doProcess(behandling: MandatoryTestBehandling, aktivitet: FattVedtakAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,49 @@
+package no.nav.test
+
+data class ManuellBehandling(val kategori: String, val beskrivelse: String)
+
+class ManuellFactoryTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = KontrollerInntektAktivitet()
+}
+
+class KontrollerInntektAktivitet : Aktivitet()
+class VurderAvvikAktivitet : Aktivitet()
+class JusterInntektAktivitet : Aktivitet()
+class FerdigstillAktivitet : Aktivitet()
+
+class KontrollerInntektAktivitetProcessor : AktivitetProcessor<ManuellFactoryTestBehandling, KontrollerInntektAktivitet>() {
+    override fun doProcess(aktivitet: KontrollerInntektAktivitet): AktivitetResponse {
+        // Created by a factory function, with no assignment in sight: marked with 📋
+        if (aktivitet.inntekt.harAvvik) {
+            opprettManuellBehandling(aktivitet, kategori = "INNTEKT_AVVIK", frist = LocalDate.now().plusDays(14))
+        }
+        return nesteAktivitet(VurderAvvikAktivitet())
+    }
+}
+
+class VurderAvvikAktivitetProcessor : AktivitetProcessor<ManuellFactoryTestBehandling, VurderAvvikAktivitet>() {
+    override fun doProcess(aktivitet: VurderAvvikAktivitet): AktivitetResponse {
+        // Only mentioned: manuellBehandling = ManuellBehandling(...) is not needed here. Not marked
+        logger.info("Ingen manuellBehandling, ManuellBehandling er allerede opprettet")
+        return nesteAktivitet(JusterInntektAktivitet())
+    }
+}
+
+class JusterInntektAktivitetProcessor : AktivitetProcessor<ManuellFactoryTestBehandling, JusterInntektAktivitet>() {
+    override fun doProcess(aktivitet: JusterInntektAktivitet): AktivitetResponse {
+        // Assigned from a helper: marked, whatever the helper is called
+        aktivitet.manuellBehandling = lagOppgave(aktivitet)
+        return nesteAktivitet(FerdigstillAktivitet())
+    }
+
+    private fun lagOppgave(aktivitet: JusterInntektAktivitet) =
+        ManuellBehandling(kategori = "INNTEKT", beskrivelse = "Juster inntekten")
+}
+
+class FerdigstillAktivitetProcessor : AktivitetProcessor<ManuellFactoryTestBehandling, FerdigstillAktivitet>() {
+    override fun doProcess(aktivitet: FerdigstillAktivitet): AktivitetResponse {
+        // Clearing the property creates nothing: not marked
+        aktivitet.manuellBehandling = null
+        return aktivitetFullfort()
+    }
+}
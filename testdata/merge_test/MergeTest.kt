@@ -0,0 +1,50 @@
+package no.nav.test.merge
+
+// `list --merge-points` shows that the split in VurderSoknadProcessor meets again at
+// IverksettAktivitet, while the one in KontrollerProcessor never does, because one branch aborts
+class MergeTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class InnvilgAktivitet : Aktivitet()
+class AvslaAktivitet : Aktivitet()
+class IverksettAktivitet : Aktivitet()
+class KontrollerAktivitet : Aktivitet()
+
+class VurderSoknadProcessor : AktivitetProcessor<MergeTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: MergeTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return if (behandling.oppfyllerVilkar) {
+            nesteAktivitet(InnvilgAktivitet())
+        } else {
+            nesteAktivitet(AvslaAktivitet())
+        }
+    }
+}
+
+class InnvilgProcessor : AktivitetProcessor<MergeTestBehandling, InnvilgAktivitet>() {
+    override fun doProcess(behandling: MergeTestBehandling, aktivitet: InnvilgAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettAktivitet())
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<MergeTestBehandling, AvslaAktivitet>() {
+    override fun doProcess(behandling: MergeTestBehandling, aktivitet: AvslaAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettAktivitet())
+    }
+}
+
+class IverksettProcessor : AktivitetProcessor<MergeTestBehandling, IverksettAktivitet>() {
+    override fun doProcess(behandling: MergeTestBehandling, aktivitet: IverksettAktivitet): AktivitetResponse {
+        return nesteAktivitet(KontrollerAktivitet())
+    }
+}
+
+class KontrollerProcessor : AktivitetProcessor<MergeTestBehandling, KontrollerAktivitet>() {
+    override fun doProcess(behandling: MergeTestBehandling, aktivitet: KontrollerAktivitet): AktivitetResponse {
+        if (behandling.harAvvik) {
+            return avbrytBehandling("Avvik i utbetaling")
+        }
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,33 @@
+class MissingProcessorTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaKravAktivitet {
+        return MottaKravAktivitet()
+    }
+}
+
+class MottaKravAktivitet : Aktivitet()
+class VurderKravAktivitet : Aktivitet()
+class VurderKravAktivtet : Aktivitet()
+class IverksettKravAktivitet : Aktivitet()
+
+// Never started from any transition, but declared, so it is reported too
+class ArkiverKravAktivitet : Aktivitet()
+
+class MottaKravAktivitetProcessor : AktivitetProcessor<MissingProcessorTestBehandling, MottaKravAktivitet>() {
+    fun doProcess(aktivitet: MottaKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(VurderKravAktivitet())
+    }
+}
+
+// Written for a misspelled copy of the aktivitet: suggested for VurderKravAktivitet
+class VurderKravAktivtetProcessor : AktivitetProcessor<MissingProcessorTestBehandling, VurderKravAktivtet>() {
+    fun doProcess(aktivitet: VurderKravAktivtet): AktivitetResponse {
+        return nesteAktivitet(IverksettKravAktivitet())
+    }
+}
+
+// Extends an unknown base class, so it is not recognized as the processor of IverksettKravAktivitet
+class IverksettKravAktivitetProcessor : EgenProsessor() {
+    fun doProcess(aktivitet: IverksettKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
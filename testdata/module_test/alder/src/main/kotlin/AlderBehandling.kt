@@ -0,0 +1,17 @@
+package no.nav.pensjon.alder
+
+import no.nav.pensjon.felles.JournalforAktivitet
+
+class AlderModulBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderAlderAktivitet {
+        return VurderAlderAktivitet()
+    }
+}
+
+class VurderAlderAktivitet : Aktivitet()
+
+class VurderAlderAktivitetProcessor : AktivitetProcessor<AlderModulBehandling, VurderAlderAktivitet>() {
+    fun doProcess(aktivitet: VurderAlderAktivitet): AktivitetResponse {
+        return nesteAktivitet(JournalforAktivitet())
+    }
+}
@@ -0,0 +1,16 @@
+package no.nav.pensjon.felles
+
+class JournalforAktivitet : Aktivitet()
+class SendVedtaksbrevAktivitet : Aktivitet()
+
+class JournalforAktivitetProcessor : AktivitetProcessor<Behandling, JournalforAktivitet>() {
+    fun doProcess(aktivitet: JournalforAktivitet): AktivitetResponse {
+        return nesteAktivitet(SendVedtaksbrevAktivitet())
+    }
+}
+
+class SendVedtaksbrevAktivitetProcessor : AktivitetProcessor<Behandling, SendVedtaksbrevAktivitet>() {
+    fun doProcess(aktivitet: SendVedtaksbrevAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
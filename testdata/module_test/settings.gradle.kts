@@ -0,0 +1,4 @@
+rootProject.name = "behandling-moduler"
+
+include(":alder", ":ufore")
+include(":felles:domain")
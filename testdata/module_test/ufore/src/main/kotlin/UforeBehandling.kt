@@ -0,0 +1,17 @@
+package no.nav.pensjon.ufore
+
+import no.nav.pensjon.felles.JournalforAktivitet
+
+class UforeModulBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderUforegradAktivitet {
+        return VurderUforegradAktivitet()
+    }
+}
+
+class VurderUforegradAktivitet : Aktivitet()
+
+class VurderUforegradAktivitetProcessor : AktivitetProcessor<UforeModulBehandling, VurderUforegradAktivitet>() {
+    fun doProcess(aktivitet: VurderUforegradAktivitet): AktivitetResponse {
+        return nesteAktivitet(JournalforAktivitet())
+    }
+}
@@ -0,0 +1,28 @@
+package no.nav.test.orphan
+
+class OrphanTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderKravAktivitet()
+}
+
+class VurderKravAktivitet : Aktivitet()
+class BeregnYtelseAktivitet : Aktivitet()
+
+class VurderKravProcessor : AktivitetProcessor<OrphanTestBehandling, VurderKravAktivitet>() {
+    override fun doProcess(behandling: OrphanTestBehandling, aktivitet: VurderKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(BeregnYtelseAktivitet())
+    }
+}
+
+// Typo in the aktivitet: orphan-processor suggests BeregnYtelseAktivitet
+class BeregnYtelseProcessor : AktivitetProcessor<OrphanTestBehandling, BeregnYtelsAktivitet>() {
+    override fun doProcess(behandling: OrphanTestBehandling, aktivitet: BeregnYtelsAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+// The aktivitet was deleted: orphan-processor without suggestions
+class SendBrevProcessor : AktivitetProcessor<OrphanTestBehandling, SendBrevAktivitet>() {
+    override fun doProcess(behandling: OrphanTestBehandling, aktivitet: SendBrevAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,19 @@
+package no.nav.pensjon.alder
+
+import no.nav.pensjon.felles.IverksettVedtakAktivitet
+
+class AlderBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderVilkarAktivitet {
+        return VurderVilkarAktivitet()
+    }
+}
+
+// Same simple name as the one in the ufore package
+class VurderVilkarAktivitet : Aktivitet()
+
+class VurderVilkarAktivitetProcessor :
+    AktivitetProcessor<AlderBehandling, VurderVilkarAktivitet>() {
+    fun doProcess(aktivitet: VurderVilkarAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
@@ -0,0 +1,9 @@
+package no.nav.pensjon.felles
+
+class IverksettVedtakAktivitet : Aktivitet()
+
+class IverksettVedtakAktivitetProcessor : AktivitetProcessor<Behandling, IverksettVedtakAktivitet>() {
+    fun doProcess(aktivitet: IverksettVedtakAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,27 @@
+package no.nav.pensjon.ufore
+
+import no.nav.pensjon.felles.*
+
+class UforeBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderVilkarAktivitet {
+        return VurderVilkarAktivitet()
+    }
+}
+
+// Same simple name as the one in the alder package
+class VurderVilkarAktivitet : Aktivitet()
+class VurderUforegradAktivitet : Aktivitet()
+
+class VurderVilkarAktivitetProcessor :
+    AktivitetProcessor<UforeBehandling, VurderVilkarAktivitet>() {
+    fun doProcess(aktivitet: VurderVilkarAktivitet): AktivitetResponse {
+        return nesteAktivitet(VurderUforegradAktivitet())
+    }
+}
+
+class VurderUforegradAktivitetProcessor :
+    AktivitetProcessor<UforeBehandling, VurderUforegradAktivitet>() {
+    fun doProcess(aktivitet: VurderUforegradAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
@@ -0,0 +1,17 @@
+class ParseErrorTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): StartAktivitet {
+        return StartAktivitet()
+    }
+}
+
+class StartAktivitet : Aktivitet()
+class FerdigAktivitet : Aktivitet()
+
+class StartAktivitetProcessor : AktivitetProcessor<ParseErrorTestBehandling, StartAktivitet>() {
+    fun doProcess(aktivitet: StartAktivitet): AktivitetResponse {
+        // The unbalanced parenthesis is a syntax error: the transition below may be lost,
+        // and the file is reported with the location of the error
+        val belop = beregn(aktivitet.krav
+        return nesteAktivitet(FerdigAktivitet())
+    }
+}
@@ -0,0 +1,61 @@
+package no.nav.test.registry
+
+import org.springframework.context.annotation.Bean
+import org.springframework.context.annotation.Configuration
+import org.springframework.stereotype.Component
+
+class RegistryTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = MottaEtteroppgjorAktivitet()
+}
+
+class MottaEtteroppgjorAktivitet : Aktivitet()
+class BeregnEtteroppgjorAktivitet : Aktivitet()
+class FattEtteroppgjorAktivitet : Aktivitet()
+class SendEtteroppgjorAktivitet : Aktivitet()
+
+// None of the handlers below is named like a processor or extends AktivitetProcessor; they are
+// bound to their aktiviteter here and by their bean names
+@Configuration
+class EtteroppgjorKonfigurasjon(private val registry: AktivitetRegistry) {
+    init {
+        registry.registrer(MottaEtteroppgjorAktivitet::class, MottakHandler())
+    }
+
+    @Bean
+    fun handlere() = mapOf(
+        BeregnEtteroppgjorAktivitet::class to BeregningHandler(),
+        // Not a registration: the value is no handler
+        FattEtteroppgjorAktivitet::class to "Fatt vedtak",
+    )
+}
+
+class MottakHandler {
+    fun doProcess(aktivitet: MottaEtteroppgjorAktivitet): AktivitetResponse {
+        return nesteAktivitet(BeregnEtteroppgjorAktivitet())
+    }
+}
+
+class BeregningHandler {
+    fun doProcess(aktivitet: BeregnEtteroppgjorAktivitet): AktivitetResponse {
+        return nesteAktivitet(FattEtteroppgjorAktivitet())
+    }
+}
+
+@Component("FattEtteroppgjorAktivitet")
+class VedtakHandler {
+    fun doProcess(aktivitet: FattEtteroppgjorAktivitet): AktivitetResponse {
+        return nesteAktivitet(SendEtteroppgjorAktivitet())
+    }
+}
+
+// A bean name that is no aktivitet makes no processor
+@Service("brevtjeneste")
+class BrevHandler {
+    fun doProcess(aktivitet: SendEtteroppgjorAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class SendEtteroppgjorAktivitetProcessor : AktivitetProcessor<RegistryTestBehandling, SendEtteroppgjorAktivitet>() {
+    override fun doProcess(aktivitet: SendEtteroppgjorAktivitet) = aktivitetFullfort()
+}
@@ -0,0 +1,71 @@
+package no.nav.test.rules
+
+// `lint` reports three `flow-rule` violations with the .behandling-flow.toml next to this file:
+// - AvslaAktivitet completes the flow without IverksettVedtakAktivitet or AvbrytAktivitet
+// - the forenklet route goes straight to IverksettVedtakAktivitet, skipping VurderVilkarAktivitet
+// - VentPaaUtbetalingAktivitet is waited in only once, while VentPaaDokumentasjonAktivitet is
+//   retried through VurderVilkarAktivitet
+class RulesTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = RegistrerKravAktivitet()
+}
+
+class RegistrerKravAktivitet : Aktivitet()
+class VurderVilkarAktivitet : Aktivitet()
+class VentPaaDokumentasjonAktivitet : Aktivitet()
+class AvslaAktivitet : Aktivitet()
+class IverksettVedtakAktivitet : Aktivitet()
+class VentPaaUtbetalingAktivitet : Aktivitet()
+class AvbrytAktivitet : Aktivitet()
+
+class RegistrerKravProcessor : AktivitetProcessor<RulesTestBehandling, RegistrerKravAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: RegistrerKravAktivitet): AktivitetResponse {
+        if (behandling.erTrukket) {
+            return nesteAktivitet(AvbrytAktivitet())
+        }
+        return if (behandling.erForenklet) {
+            nesteAktivitet(IverksettVedtakAktivitet())
+        } else {
+            nesteAktivitet(VurderVilkarAktivitet())
+        }
+    }
+}
+
+class VurderVilkarProcessor : AktivitetProcessor<RulesTestBehandling, VurderVilkarAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: VurderVilkarAktivitet): AktivitetResponse {
+        return when {
+            behandling.manglerDokumentasjon -> nesteAktivitet(VentPaaDokumentasjonAktivitet())
+            behandling.oppfyllerVilkar -> nesteAktivitet(IverksettVedtakAktivitet())
+            else -> nesteAktivitet(AvslaAktivitet())
+        }
+    }
+}
+
+class VentPaaDokumentasjonProcessor : AktivitetProcessor<RulesTestBehandling, VentPaaDokumentasjonAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: VentPaaDokumentasjonAktivitet): AktivitetResponse {
+        return nesteAktivitet(VurderVilkarAktivitet())
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<RulesTestBehandling, AvslaAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: AvslaAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class IverksettVedtakProcessor : AktivitetProcessor<RulesTestBehandling, IverksettVedtakAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: IverksettVedtakAktivitet): AktivitetResponse {
+        return nesteAktivitet(VentPaaUtbetalingAktivitet())
+    }
+}
+
+class VentPaaUtbetalingProcessor : AktivitetProcessor<RulesTestBehandling, VentPaaUtbetalingAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: VentPaaUtbetalingAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class AvbrytProcessor : AktivitetProcessor<RulesTestBehandling, AvbrytAktivitet>() {
+    override fun doProcess(behandling: RulesTestBehandling, aktivitet: AvbrytAktivitet): AktivitetResponse {
+        return avbrytBehandling("Kravet er trukket")
+    }
+}
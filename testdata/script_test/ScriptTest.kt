@@ -0,0 +1,14 @@
+class ScriptTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): StartAktivitet {
+        return StartAktivitet()
+    }
+}
+
+class StartAktivitet : Aktivitet()
+class SkriptetAktivitet : Aktivitet()
+
+class StartAktivitetProcessor : AktivitetProcessor<ScriptTestBehandling, StartAktivitet>() {
+    fun doProcess(aktivitet: StartAktivitet): AktivitetResponse {
+        return nesteAktivitet(SkriptetAktivitet())
+    }
+}
@@ -0,0 +1,4 @@
+// Gradle build scripts are never scanned, even with --include-scripts
+plugins {
+    kotlin("jvm")
+}
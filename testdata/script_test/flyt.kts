@@ -0,0 +1,7 @@
+// The processor for SkriptetAktivitet lives in a script: it is only found with --include-scripts,
+// otherwise SkriptetAktivitet shows up as unknown
+class SkriptetAktivitetProcessor : AktivitetProcessor<ScriptTestBehandling, SkriptetAktivitet>() {
+    fun doProcess(aktivitet: SkriptetAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
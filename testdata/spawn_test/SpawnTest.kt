@@ -0,0 +1,35 @@
+class SoknadBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): BehandleSoknadAktivitet {
+        return BehandleSoknadAktivitet()
+    }
+}
+
+// Started from the SoknadBehandling flow, drawn in its own graph
+class EtteroppgjorBehandling(val soknad: Soknad) : Behandling() {
+    fun opprettInitiellAktivitet(): BeregnEtteroppgjorAktivitet {
+        return BeregnEtteroppgjorAktivitet()
+    }
+}
+
+class BehandleSoknadAktivitet : Aktivitet()
+class BeregnEtteroppgjorAktivitet : Aktivitet()
+
+class BehandleSoknadAktivitetProcessor : AktivitetProcessor<SoknadBehandling, BehandleSoknadAktivitet>() {
+    fun doProcess(aktivitet: BehandleSoknadAktivitet): AktivitetResponse {
+        if (aktivitet.soknad.krevetEtteroppgjor) {
+            startEtteroppgjor(aktivitet.soknad)
+        }
+        return aktivitetFullfort()
+    }
+
+    // Found through the helper as well
+    private fun startEtteroppgjor(soknad: Soknad) {
+        behandlingService.opprettBehandling(EtteroppgjorBehandling(soknad))
+    }
+}
+
+class BeregnEtteroppgjorAktivitetProcessor : AktivitetProcessor<EtteroppgjorBehandling, BeregnEtteroppgjorAktivitet>() {
+    fun doProcess(aktivitet: BeregnEtteroppgjorAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
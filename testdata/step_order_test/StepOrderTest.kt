@@ -0,0 +1,65 @@
+package no.nav.test.steporder
+
+// `lint` reports one `step-order` problem: Steg020BeregnAktivitet comes after
+// Steg030VurderVilkarAktivitet. The retry back to Steg030VurderVilkarAktivitet is a loop and no
+// problem, and neither are the parallel branches Steg040 and Steg045.
+class StepOrderTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = Steg010RegistrerAktivitet()
+}
+
+class Steg010RegistrerAktivitet : Aktivitet()
+class Steg030VurderVilkarAktivitet : Aktivitet()
+class Steg020BeregnAktivitet : Aktivitet()
+class Steg040InnvilgAktivitet : Aktivitet()
+class Steg045AvslaAktivitet : Aktivitet()
+class VentPaaDokumentasjonAktivitet : Aktivitet()
+class Steg050IverksettAktivitet : Aktivitet()
+
+class RegistrerProcessor : AktivitetProcessor<StepOrderTestBehandling, Steg010RegistrerAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: Steg010RegistrerAktivitet): AktivitetResponse {
+        return nesteAktivitet(Steg030VurderVilkarAktivitet())
+    }
+}
+
+class VurderVilkarProcessor : AktivitetProcessor<StepOrderTestBehandling, Steg030VurderVilkarAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: Steg030VurderVilkarAktivitet): AktivitetResponse {
+        if (behandling.manglerDokumentasjon) {
+            return nesteAktivitet(VentPaaDokumentasjonAktivitet())
+        }
+        return nesteAktivitet(Steg020BeregnAktivitet())
+    }
+}
+
+class VentPaaDokumentasjonProcessor : AktivitetProcessor<StepOrderTestBehandling, VentPaaDokumentasjonAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: VentPaaDokumentasjonAktivitet): AktivitetResponse {
+        return nesteAktivitet(Steg030VurderVilkarAktivitet())
+    }
+}
+
+class BeregnProcessor : AktivitetProcessor<StepOrderTestBehandling, Steg020BeregnAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: Steg020BeregnAktivitet): AktivitetResponse {
+        return if (behandling.oppfyllerVilkar) {
+            nesteAktivitet(Steg040InnvilgAktivitet())
+        } else {
+            nesteAktivitet(Steg045AvslaAktivitet())
+        }
+    }
+}
+
+class InnvilgProcessor : AktivitetProcessor<StepOrderTestBehandling, Steg040InnvilgAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: Steg040InnvilgAktivitet): AktivitetResponse {
+        return nesteAktivitet(Steg050IverksettAktivitet())
+    }
+}
+
+class AvslaProcessor : AktivitetProcessor<StepOrderTestBehandling, Steg045AvslaAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: Steg045AvslaAktivitet): AktivitetResponse {
+        return nesteAktivitet(Steg050IverksettAktivitet())
+    }
+}
+
+class IverksettProcessor : AktivitetProcessor<StepOrderTestBehandling, Steg050IverksettAktivitet>() {
+    override fun doProcess(behandling: StepOrderTestBehandling, aktivitet: Steg050IverksettAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
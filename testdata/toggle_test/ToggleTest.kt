@@ -0,0 +1,55 @@
+package no.nav.test.toggle
+
+// `--toggle NY_BEREGNING=off` drops NyBeregningAktivitet and KontrollerBeregningAktivitet;
+// `--toggle NY_BEREGNING=on` drops GammelBeregningAktivitet and leaves `alder >= 67` on the edge
+// to KontrollerBeregningAktivitet
+class ToggleTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = VurderSoknadAktivitet()
+}
+
+class VurderSoknadAktivitet : Aktivitet()
+class NyBeregningAktivitet : Aktivitet()
+class GammelBeregningAktivitet : Aktivitet()
+class KontrollerBeregningAktivitet : Aktivitet()
+class IverksettVedtakAktivitet : Aktivitet()
+
+class VurderSoknadProcessor(
+    private val unleashNextService: UnleashNextService,
+) : AktivitetProcessor<ToggleTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(behandling: ToggleTestBehandling, aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return if (unleashNextService.isEnabled(PenFeature.NY_BEREGNING)) {
+            nesteAktivitet(NyBeregningAktivitet())
+        } else {
+            nesteAktivitet(GammelBeregningAktivitet())
+        }
+    }
+}
+
+class NyBeregningProcessor(
+    private val unleashNextService: UnleashNextService,
+) : AktivitetProcessor<ToggleTestBehandling, NyBeregningAktivitet>() {
+    override fun doProcess(behandling: ToggleTestBehandling, aktivitet: NyBeregningAktivitet): AktivitetResponse {
+        if (unleashNextService.isEnabled(PenFeature.NY_BEREGNING) && behandling.alder >= 67) {
+            return nesteAktivitet(KontrollerBeregningAktivitet())
+        }
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
+
+class GammelBeregningProcessor : AktivitetProcessor<ToggleTestBehandling, GammelBeregningAktivitet>() {
+    override fun doProcess(behandling: ToggleTestBehandling, aktivitet: GammelBeregningAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
+
+class KontrollerBeregningProcessor : AktivitetProcessor<ToggleTestBehandling, KontrollerBeregningAktivitet>() {
+    override fun doProcess(behandling: ToggleTestBehandling, aktivitet: KontrollerBeregningAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
+
+class IverksettVedtakProcessor : AktivitetProcessor<ToggleTestBehandling, IverksettVedtakAktivitet>() {
+    override fun doProcess(behandling: ToggleTestBehandling, aktivitet: IverksettVedtakAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
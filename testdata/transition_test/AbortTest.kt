@@ -0,0 +1,27 @@
+class AbortTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): KontrollerBrukerAktivitet {
+        return KontrollerBrukerAktivitet()
+    }
+}
+
+class KontrollerBrukerAktivitet : Aktivitet()
+class BeregnYtelseAktivitet : Aktivitet()
+
+class KontrollerBrukerAktivitetProcessor : AktivitetProcessor<AbortTestBehandling, KontrollerBrukerAktivitet>() {
+    fun doProcess(aktivitet: KontrollerBrukerAktivitet): AktivitetResponse {
+        if (aktivitet.bruker.erDod) {
+            // The reason is the first argument that is not a plain variable
+            return behandlingService.avbryt(aktivitet, AvbruttArsak.DOD)
+        }
+        if (!aktivitet.bruker.harBosted) {
+            return avbrytBehandling("Bruker er ikke bosatt i Norge")
+        }
+        return nesteAktivitet(BeregnYtelseAktivitet())
+    }
+}
+
+class BeregnYtelseAktivitetProcessor : AktivitetProcessor<AbortTestBehandling, BeregnYtelseAktivitet>() {
+    fun doProcess(aktivitet: BeregnYtelseAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
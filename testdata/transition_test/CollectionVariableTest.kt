@@ -0,0 +1,48 @@
+class CollectionVariableTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = FordelSakAktivitet()
+}
+
+class FordelSakAktivitet : Aktivitet()
+class BeregnYtelseAktivitet : Aktivitet()
+class VurderBarnetilleggAktivitet : Aktivitet()
+class VurderInstitusjonsoppholdAktivitet : Aktivitet()
+class VarsleVergeAktivitet(val verge: Verge) : Aktivitet()
+class SendBrevAktivitet : Aktivitet()
+
+class FordelSakAktivitetProcessor : AktivitetProcessor<CollectionVariableTestBehandling, FordelSakAktivitet>() {
+    override fun doProcess(aktivitet: FordelSakAktivitet): AktivitetResponse {
+        // Always started, and the rest only under the condition they are added in
+        val steg = mutableListOf<Aktivitet>(BeregnYtelseAktivitet())
+        if (aktivitet.sak.harBarn) {
+            steg.add(VurderBarnetilleggAktivitet())
+        }
+        when {
+            aktivitet.sak.erPaInstitusjon -> steg += VurderInstitusjonsoppholdAktivitet()
+        }
+        aktivitet.sak.verger.forEach { verge -> steg.add(VarsleVergeAktivitet(verge)) }
+
+        // A list built from another one includes everything put in that one
+        val alle = steg + SendBrevAktivitet()
+        return nesteAktiviteter(alle)
+    }
+}
+
+class BeregnYtelseAktivitetProcessor : AktivitetProcessor<CollectionVariableTestBehandling, BeregnYtelseAktivitet>() {
+    override fun doProcess(aktivitet: BeregnYtelseAktivitet) = aktivitetFullfort()
+}
+
+class VurderBarnetilleggAktivitetProcessor : AktivitetProcessor<CollectionVariableTestBehandling, VurderBarnetilleggAktivitet>() {
+    override fun doProcess(aktivitet: VurderBarnetilleggAktivitet) = aktivitetFullfort()
+}
+
+class VurderInstitusjonsoppholdAktivitetProcessor : AktivitetProcessor<CollectionVariableTestBehandling, VurderInstitusjonsoppholdAktivitet>() {
+    override fun doProcess(aktivitet: VurderInstitusjonsoppholdAktivitet) = aktivitetFullfort()
+}
+
+class VarsleVergeAktivitetProcessor : AktivitetProcessor<CollectionVariableTestBehandling, VarsleVergeAktivitet>() {
+    override fun doProcess(aktivitet: VarsleVergeAktivitet) = aktivitetFullfort()
+}
+
+class SendBrevAktivitetProcessor : AktivitetProcessor<CollectionVariableTestBehandling, SendBrevAktivitet>() {
+    override fun doProcess(aktivitet: SendBrevAktivitet) = aktivitetFullfort()
+}
@@ -0,0 +1,36 @@
+class ExpressionBodyTestBehandling : Behandling() {
+    // Expression bodies need no return statement
+    override fun opprettInitiellAktivitet() = MottaSoknadAktivitet()
+}
+
+class MottaSoknadAktivitet : Aktivitet()
+class VurderSoknadAktivitet : Aktivitet()
+class InnvilgSoknadAktivitet : Aktivitet()
+class AvslaSoknadAktivitet : Aktivitet()
+class SendVedtakAktivitet : Aktivitet()
+
+class MottaSoknadAktivitetProcessor : AktivitetProcessor<ExpressionBodyTestBehandling, MottaSoknadAktivitet>() {
+    override fun doProcess(aktivitet: MottaSoknadAktivitet): AktivitetResponse = nesteAktivitet(VurderSoknadAktivitet())
+}
+
+class VurderSoknadAktivitetProcessor : AktivitetProcessor<ExpressionBodyTestBehandling, VurderSoknadAktivitet>() {
+    override fun doProcess(aktivitet: VurderSoknadAktivitet) =
+        if (aktivitet.soknad.erGyldig) nesteAktivitet(InnvilgSoknadAktivitet()) else nesteAktivitet(avslag())
+
+    // Helpers with an expression body are followed as well
+    private fun avslag() = AvslaSoknadAktivitet()
+}
+
+class InnvilgSoknadAktivitetProcessor : AktivitetProcessor<ExpressionBodyTestBehandling, InnvilgSoknadAktivitet>() {
+    override fun doProcess(aktivitet: InnvilgSoknadAktivitet) = aktivitetFullfort()
+
+    override fun onFinished(aktivitet: InnvilgSoknadAktivitet) = nesteAktivitet(SendVedtakAktivitet())
+}
+
+class AvslaSoknadAktivitetProcessor : AktivitetProcessor<ExpressionBodyTestBehandling, AvslaSoknadAktivitet>() {
+    override fun doProcess(aktivitet: AvslaSoknadAktivitet) = nesteAktivitet(SendVedtakAktivitet())
+}
+
+class SendVedtakAktivitetProcessor : AktivitetProcessor<ExpressionBodyTestBehandling, SendVedtakAktivitet>() {
+    override fun doProcess(aktivitet: SendVedtakAktivitet) = aktivitetFullfort()
+}
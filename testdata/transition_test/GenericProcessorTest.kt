@@ -0,0 +1,36 @@
+class GenericProcessorTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): FattVedtakAktivitet {
+        return FattVedtakAktivitet()
+    }
+}
+
+class FattVedtakAktivitet : Aktivitet()
+class AttesterVedtakAktivitet : Aktivitet()
+class IverksettVedtakAktivitet : Aktivitet()
+
+// The aktivitet comes first here, unlike in AktivitetProcessor<Behandling, Aktivitet>
+abstract class VedtakProcessor<A : Aktivitet, B : Behandling> : AktivitetProcessor<B, A>()
+
+// Fixes the aktivitet, leaving only the Behandling open
+abstract class IverksettingProcessor<B : Behandling> : VedtakProcessor<IverksettVedtakAktivitet, B>()
+
+class FattVedtakAktivitetProcessor :
+    VedtakProcessor<FattVedtakAktivitet, GenericProcessorTestBehandling>() {
+    fun doProcess(aktivitet: FattVedtakAktivitet): AktivitetResponse {
+        return nesteAktivitet(AttesterVedtakAktivitet())
+    }
+}
+
+class AttesterVedtakAktivitetProcessor :
+    VedtakProcessor<AttesterVedtakAktivitet, GenericProcessorTestBehandling>() {
+    fun doProcess(aktivitet: AttesterVedtakAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettVedtakAktivitet())
+    }
+}
+
+class IverksettVedtakAktivitetProcessor :
+    IverksettingProcessor<GenericProcessorTestBehandling>() {
+    fun doProcess(aktivitet: IverksettVedtakAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
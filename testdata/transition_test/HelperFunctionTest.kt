@@ -0,0 +1,53 @@
+class HelperFunctionTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderVilkaarAktivitet {
+        return VurderVilkaarAktivitet()
+    }
+}
+
+class VurderVilkaarAktivitet : Aktivitet()
+class AvslagAktivitet : Aktivitet()
+class InnvilgelseAktivitet : Aktivitet()
+class VurderTrygdetidAktivitet : Aktivitet()
+
+class VurderVilkaarAktivitetProcessor :
+    AktivitetProcessor<HelperFunctionTestBehandling, VurderVilkaarAktivitet>() {
+    fun doProcess(aktivitet: VurderVilkaarAktivitet): AktivitetResponse {
+        val resultat = vurder(behandling)
+        // The transitions are made in a private helper, which calls another helper
+        return bestemNesteAktivitet(resultat)
+    }
+
+    private fun bestemNesteAktivitet(resultat: Vilkaarsresultat): AktivitetResponse {
+        if (!resultat.oppfylt) {
+            return nesteAktivitet(AvslagAktivitet())
+        }
+        return vurderTrygdetid(resultat)
+    }
+
+    private fun vurderTrygdetid(resultat: Vilkaarsresultat): AktivitetResponse =
+        if (resultat.manglerTrygdetid) {
+            nesteAktivitet(VurderTrygdetidAktivitet())
+        } else {
+            nesteAktivitet(InnvilgelseAktivitet())
+        }
+}
+
+class VurderTrygdetidAktivitetProcessor :
+    AktivitetProcessor<HelperFunctionTestBehandling, VurderTrygdetidAktivitet>() {
+    fun doProcess(aktivitet: VurderTrygdetidAktivitet): AktivitetResponse {
+        return nesteAktivitet(InnvilgelseAktivitet())
+    }
+}
+
+class AvslagAktivitetProcessor : AktivitetProcessor<HelperFunctionTestBehandling, AvslagAktivitet>() {
+    fun doProcess(aktivitet: AvslagAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class InnvilgelseAktivitetProcessor :
+    AktivitetProcessor<HelperFunctionTestBehandling, InnvilgelseAktivitet>() {
+    fun doProcess(aktivitet: InnvilgelseAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
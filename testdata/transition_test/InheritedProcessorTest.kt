@@ -0,0 +1,39 @@
+class InheritedProcessorTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): HentOpplysningerAktivitet {
+        return HentOpplysningerAktivitet()
+    }
+}
+
+class HentOpplysningerAktivitet : Aktivitet()
+class VurderOpplysningerAktivitet : Aktivitet()
+class SendBrevAktivitet : Aktivitet()
+
+// Every subclass continues with SendBrevAktivitet when it is done
+abstract class FellesAktivitetProcessor<A : Aktivitet> :
+    AktivitetProcessor<InheritedProcessorTestBehandling, A>() {
+    fun onFinished(aktivitet: A): AktivitetResponse {
+        return nesteAktivitet(SendBrevAktivitet())
+    }
+}
+
+// Only inherits the transition
+class HentOpplysningerAktivitetProcessor : FellesAktivitetProcessor<HentOpplysningerAktivitet>() {
+    fun hent(aktivitet: HentOpplysningerAktivitet) {
+        register.hentOpplysninger(aktivitet)
+    }
+}
+
+// Adds its own transition to the inherited one
+class VurderOpplysningerAktivitetProcessor :
+    FellesAktivitetProcessor<VurderOpplysningerAktivitet>() {
+    fun doProcess(aktivitet: VurderOpplysningerAktivitet): AktivitetResponse {
+        return nesteAktivitet(HentOpplysningerAktivitet())
+    }
+}
+
+class SendBrevAktivitetProcessor :
+    AktivitetProcessor<InheritedProcessorTestBehandling, SendBrevAktivitet>() {
+    fun doProcess(aktivitet: SendBrevAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
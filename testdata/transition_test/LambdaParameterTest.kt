@@ -0,0 +1,35 @@
+class LambdaParameterTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): FordelLinjerAktivitet {
+        return FordelLinjerAktivitet()
+    }
+}
+
+class FordelLinjerAktivitet : Aktivitet()
+class OpprettLinjeAktivitet : Aktivitet()
+class VarsleMottakerAktivitet : Aktivitet()
+class AvslaKravAktivitet : Aktivitet()
+class BeregnPeriodeAktivitet : Aktivitet()
+
+class FordelLinjerAktivitetProcessor : AktivitetProcessor<LambdaParameterTestBehandling, FordelLinjerAktivitet>() {
+    fun doProcess(aktivitet: FordelLinjerAktivitet): AktivitetResponse {
+        // Named lambda parameters are fan-outs just like `it`
+        behandling.kravLinjer.forEach { linje ->
+            nesteAktivitet(OpprettLinjeAktivitet(linje))
+        }
+        behandling.mottakere?.forEachIndexed { indeks, mottaker ->
+            nesteAktivitet(VarsleMottakerAktivitet(mottaker, indeks))
+        }
+        // A return from the lambda leaves doProcess, so the transition is made once
+        behandling.kravLinjer.forEach { linje ->
+            if (linje.avslatt) return nesteAktivitet(AvslaKravAktivitet())
+        }
+        return aktivitetFullfort()
+    }
+}
+
+class OpprettLinjeAktivitetProcessor : AktivitetProcessor<LambdaParameterTestBehandling, OpprettLinjeAktivitet>() {
+    fun doProcess(aktivitet: OpprettLinjeAktivitet): AktivitetResponse {
+        // The lambda creates the aktiviteter that are started, with a named parameter
+        return nesteAktiviteter(aktivitet.perioder.map { periode -> BeregnPeriodeAktivitet(periode) })
+    }
+}
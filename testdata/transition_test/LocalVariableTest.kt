@@ -0,0 +1,58 @@
+class LocalVariableTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = MottaKlageAktivitet()
+}
+
+class MottaKlageAktivitet : Aktivitet()
+class VurderKlageAktivitet : Aktivitet()
+class OmgjorVedtakAktivitet : Aktivitet()
+class StadfestVedtakAktivitet : Aktivitet()
+class OversendKlageinstansAktivitet : Aktivitet()
+class AvsluttKlageAktivitet : Aktivitet()
+
+class MottaKlageAktivitetProcessor : AktivitetProcessor<LocalVariableTestBehandling, MottaKlageAktivitet>() {
+    override fun doProcess(aktivitet: MottaKlageAktivitet): AktivitetResponse {
+        // The variable holds one of two aktiviteter, each under its own condition
+        val neste = if (aktivitet.klage.erFristOverholdt) VurderKlageAktivitet() else AvsluttKlageAktivitet()
+        return nesteAktivitet(neste)
+    }
+}
+
+class VurderKlageAktivitetProcessor : AktivitetProcessor<LocalVariableTestBehandling, VurderKlageAktivitet>() {
+    override fun doProcess(aktivitet: VurderKlageAktivitet): AktivitetResponse {
+        val neste = when (aktivitet.klage.utfall) {
+            Utfall.MEDHOLD -> OmgjorVedtakAktivitet()
+            Utfall.AVSLAG -> StadfestVedtakAktivitet()
+            else -> {
+                loggUkjentUtfall(aktivitet.klage)
+                OversendKlageinstansAktivitet()
+            }
+        }
+        return nesteAktivitet(neste, begrunnelse = "Klagen er vurdert")
+    }
+}
+
+class StadfestVedtakAktivitetProcessor : AktivitetProcessor<LocalVariableTestBehandling, StadfestVedtakAktivitet>() {
+    override fun doProcess(aktivitet: StadfestVedtakAktivitet): AktivitetResponse {
+        // Reassigned after the declaration: both values are possible
+        var neste: Aktivitet = AvsluttKlageAktivitet()
+        if (aktivitet.klage.skalOversendes) {
+            neste = OversendKlageinstansAktivitet()
+        }
+        return nesteAktivitet(neste)
+    }
+}
+
+class OmgjorVedtakAktivitetProcessor : AktivitetProcessor<LocalVariableTestBehandling, OmgjorVedtakAktivitet>() {
+    override fun doProcess(aktivitet: OmgjorVedtakAktivitet): AktivitetResponse {
+        val neste = AvsluttKlageAktivitet()
+        return nesteAktivitet(neste)
+    }
+}
+
+class OversendKlageinstansAktivitetProcessor : AktivitetProcessor<LocalVariableTestBehandling, OversendKlageinstansAktivitet>() {
+    override fun doProcess(aktivitet: OversendKlageinstansAktivitet) = nesteAktivitet(AvsluttKlageAktivitet())
+}
+
+class AvsluttKlageAktivitetProcessor : AktivitetProcessor<LocalVariableTestBehandling, AvsluttKlageAktivitet>() {
+    override fun doProcess(aktivitet: AvsluttKlageAktivitet) = aktivitetFullfort()
+}
@@ -0,0 +1,49 @@
+class LoopTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): FordelKravAktivitet {
+        return FordelKravAktivitet()
+    }
+}
+
+class FordelKravAktivitet : Aktivitet()
+class VurderKravAktivitet : Aktivitet()
+class VarsleMottakerAktivitet : Aktivitet()
+class AvslaKravAktivitet : Aktivitet()
+class HentNesteDokumentAktivitet : Aktivitet()
+class IverksettAktivitet : Aktivitet()
+
+class FordelKravAktivitetProcessor : AktivitetProcessor<LoopTestBehandling, FordelKravAktivitet>() {
+    fun doProcess(aktivitet: FordelKravAktivitet): AktivitetResponse {
+        // One VurderKravAktivitet for each krav: a fan-out over behandling.kravListe
+        for (krav in behandling.kravListe) {
+            nesteAktivitet(VurderKravAktivitet(krav))
+        }
+        return aktivitetFullfort()
+    }
+}
+
+class VurderKravAktivitetProcessor : AktivitetProcessor<LoopTestBehandling, VurderKravAktivitet>() {
+    fun doProcess(aktivitet: VurderKravAktivitet): AktivitetResponse {
+        // Returning from the loop makes the transition once, so it is not a fan-out
+        for (vilkar in aktivitet.krav.vilkar) {
+            if (!vilkar.oppfylt) {
+                return nesteAktivitet(AvslaKravAktivitet())
+            }
+        }
+        val mottakere = aktivitet.krav.mottakere.iterator()
+        while (mottakere.hasNext()) {
+            nesteAktivitet(VarsleMottakerAktivitet(mottakere.next()))
+        }
+        return nesteAktivitet(IverksettAktivitet())
+    }
+}
+
+class VarsleMottakerAktivitetProcessor : AktivitetProcessor<LoopTestBehandling, VarsleMottakerAktivitet>() {
+    fun doProcess(aktivitet: VarsleMottakerAktivitet): AktivitetResponse {
+        var side = 0
+        do {
+            nesteAktivitet(HentNesteDokumentAktivitet(side))
+            side++
+        } while (side < aktivitet.antallSider)
+        return aktivitetFullfort()
+    }
+}
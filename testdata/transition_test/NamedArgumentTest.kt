@@ -0,0 +1,42 @@
+class NamedArgumentTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaKlageAktivitet {
+        return MottaKlageAktivitet()
+    }
+}
+
+class MottaKlageAktivitet : Aktivitet()
+class VurderKlageAktivitet : Aktivitet()
+class OversendKlageAktivitet : Aktivitet()
+class AvvisKlageAktivitet : Aktivitet()
+
+class MottaKlageAktivitetProcessor : AktivitetProcessor<NamedArgumentTestBehandling, MottaKlageAktivitet>() {
+    fun doProcess(aktivitet: MottaKlageAktivitet): AktivitetResponse {
+        // The begrunnelse comes first and constructs a Melding, the aktivitet is named
+        return nesteAktivitet(
+            begrunnelse = Melding("Klage mottatt").tekst,
+            aktivitet = VurderKlageAktivitet(aktivitet.klage),
+        )
+    }
+}
+
+class VurderKlageAktivitetProcessor : AktivitetProcessor<NamedArgumentTestBehandling, VurderKlageAktivitet>() {
+    fun doProcess(aktivitet: VurderKlageAktivitet): AktivitetResponse {
+        return if (aktivitet.klage.erRettidig) {
+            nesteAktivitet(aktivitet = OversendKlageAktivitet(), begrunnelse = "Klagen er rettidig")
+        } else {
+            nesteAktivitet(AvvisKlageAktivitet(), begrunnelse = "Klagefristen er ute")
+        }
+    }
+}
+
+class OversendKlageAktivitetProcessor : AktivitetProcessor<NamedArgumentTestBehandling, OversendKlageAktivitet>() {
+    fun doProcess(aktivitet: OversendKlageAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class AvvisKlageAktivitetProcessor : AktivitetProcessor<NamedArgumentTestBehandling, AvvisKlageAktivitet>() {
+    fun doProcess(aktivitet: AvvisKlageAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
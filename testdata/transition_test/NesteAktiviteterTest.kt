@@ -0,0 +1,40 @@
+class NesteAktiviteterTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): FordelKravAktivitet {
+        return FordelKravAktivitet()
+    }
+}
+
+class FordelKravAktivitet : Aktivitet()
+class BeregnKravAktivitet(val krav: Krav) : Aktivitet()
+class SendVedtakAktivitet(val melding: Melding) : Aktivitet()
+class ArkiverAktivitet : Aktivitet()
+
+class FordelKravAktivitetProcessor : AktivitetProcessor<NesteAktiviteterTestBehandling, FordelKravAktivitet>() {
+    fun doProcess(aktivitet: FordelKravAktivitet): AktivitetResponse {
+        // One BeregnKravAktivitet per krav, a single SendVedtakAktivitet. The text below is not a
+        // constructor: GammelAktivitet()
+        return nesteAktiviteter(
+            aktivitet.krav.map { krav -> BeregnKravAktivitet(krav) } +
+                SendVedtakAktivitet(Melding("Se ArkiverAktivitet() for arkivering")),
+            begrunnelse = "Kravene beregnes hver for seg",
+        )
+    }
+}
+
+class BeregnKravAktivitetProcessor : AktivitetProcessor<NesteAktiviteterTestBehandling, BeregnKravAktivitet>() {
+    fun doProcess(aktivitet: BeregnKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class SendVedtakAktivitetProcessor : AktivitetProcessor<NesteAktiviteterTestBehandling, SendVedtakAktivitet>() {
+    fun doProcess(aktivitet: SendVedtakAktivitet): AktivitetResponse {
+        return nesteAktiviteter(listOf(ArkiverAktivitet()))
+    }
+}
+
+class ArkiverAktivitetProcessor : AktivitetProcessor<NesteAktiviteterTestBehandling, ArkiverAktivitet>() {
+    fun doProcess(aktivitet: ArkiverAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,50 @@
+class NestedClassTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaSoknadAktivitet {
+        return MottaSoknadAktivitet()
+    }
+
+    // Builds test data only, it is not part of the flow
+    class TestBuilder {
+        fun opprettInitiellAktivitet(): AvvisSoknadAktivitet {
+            return AvvisSoknadAktivitet()
+        }
+    }
+}
+
+class MottaSoknadAktivitet : Aktivitet()
+class VurderSoknadAktivitet : Aktivitet()
+class InnvilgSoknadAktivitet : Aktivitet()
+class AvvisSoknadAktivitet : Aktivitet()
+
+class MottaSoknadAktivitetProcessor : AktivitetProcessor<NestedClassTestBehandling, MottaSoknadAktivitet>() {
+    // A nested helper must not take over the processor's transitions
+    class Validator {
+        fun doProcess(aktivitet: MottaSoknadAktivitet): AktivitetResponse {
+            return nesteAktivitet(AvvisSoknadAktivitet())
+        }
+    }
+
+    inner class Logger {
+        fun log(melding: String) {}
+    }
+
+    // Declared after the nested classes, still belongs to the processor
+    fun doProcess(aktivitet: MottaSoknadAktivitet): AktivitetResponse {
+        return nesteAktivitet(VurderSoknadAktivitet())
+    }
+}
+
+class VurderSoknadAktivitetProcessor : AktivitetProcessor<NestedClassTestBehandling, VurderSoknadAktivitet>() {
+    fun doProcess(aktivitet: VurderSoknadAktivitet): AktivitetResponse {
+        return nesteAktivitet(InnvilgSoknadAktivitet())
+    }
+
+    // Processors nested in another class are still found, with their own transitions
+    class InnvilgSoknadAktivitetProcessor : AktivitetProcessor<NestedClassTestBehandling, InnvilgSoknadAktivitet>() {
+        fun doProcess(aktivitet: InnvilgSoknadAktivitet): AktivitetResponse {
+            return aktivitetFullfort()
+        }
+    }
+
+    fun onFinished(aktivitet: VurderSoknadAktivitet) {}
+}
@@ -0,0 +1,32 @@
+class ObjectProcessorTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): RegistrerKravAktivitet {
+        return RegistrerKravAktivitet()
+    }
+}
+
+class RegistrerKravAktivitet : Aktivitet()
+class KontrollerKravAktivitet : Aktivitet()
+class LukkKravAktivitet : Aktivitet()
+
+// Stateless processors declared as singletons
+object RegistrerKravAktivitetProcessor : AktivitetProcessor<ObjectProcessorTestBehandling, RegistrerKravAktivitet>() {
+    fun doProcess(aktivitet: RegistrerKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(KontrollerKravAktivitet())
+    }
+}
+
+object KontrollerKravAktivitetProcessor : AktivitetProcessor<ObjectProcessorTestBehandling, KontrollerKravAktivitet>() {
+    fun doProcess(aktivitet: KontrollerKravAktivitet): AktivitetResponse {
+        return if (aktivitet.godkjent) {
+            nesteAktivitet(LukkKravAktivitet())
+        } else {
+            nesteAktivitet(RegistrerKravAktivitet())
+        }
+    }
+}
+
+object LukkKravAktivitetProcessor : AktivitetProcessor<ObjectProcessorTestBehandling, LukkKravAktivitet>() {
+    fun doProcess(aktivitet: LukkKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,65 @@
+class ScopeFunctionTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): LesSoknadAktivitet {
+        return LesSoknadAktivitet()
+    }
+}
+
+class LesSoknadAktivitet : Aktivitet()
+class VurderInntektAktivitet : Aktivitet()
+class VurderFormueAktivitet : Aktivitet()
+class BeregnPensjonAktivitet : Aktivitet()
+class SendVedtaksbrevAktivitet : Aktivitet()
+class ArkiverAktivitet : Aktivitet()
+
+class LesSoknadAktivitetProcessor : AktivitetProcessor<ScopeFunctionTestBehandling, LesSoknadAktivitet>() {
+    fun doProcess(aktivitet: LesSoknadAktivitet): AktivitetResponse {
+        // Plain let: the transition is made unconditionally
+        return behandling.let {
+            it.soknadLest = true
+            nesteAktivitet(VurderInntektAktivitet())
+        }
+    }
+}
+
+class VurderInntektAktivitetProcessor : AktivitetProcessor<ScopeFunctionTestBehandling, VurderInntektAktivitet>() {
+    fun doProcess(aktivitet: VurderInntektAktivitet): AktivitetResponse {
+        // Conditions inside the lambda still label the branches
+        return with(behandling.inntekt) {
+            if (overGrense) {
+                nesteAktivitet(VurderFormueAktivitet())
+            } else {
+                nesteAktivitet(BeregnPensjonAktivitet())
+            }
+        }
+    }
+}
+
+class VurderFormueAktivitetProcessor : AktivitetProcessor<ScopeFunctionTestBehandling, VurderFormueAktivitet>() {
+    fun doProcess(aktivitet: VurderFormueAktivitet): AktivitetResponse {
+        return run {
+            behandling.formueVurdert = true
+            nesteAktivitet(BeregnPensjonAktivitet())
+        }
+    }
+}
+
+class BeregnPensjonAktivitetProcessor : AktivitetProcessor<ScopeFunctionTestBehandling, BeregnPensjonAktivitet>() {
+    fun doProcess(aktivitet: BeregnPensjonAktivitet): AktivitetResponse {
+        // The aktivitet is created as the receiver and passed on as `it`
+        return SendVedtaksbrevAktivitet().apply {
+            mottaker = behandling.bruker
+        }.let { nesteAktivitet(it) }
+    }
+}
+
+class SendVedtaksbrevAktivitetProcessor : AktivitetProcessor<ScopeFunctionTestBehandling, SendVedtaksbrevAktivitet>() {
+    fun doProcess(aktivitet: SendVedtaksbrevAktivitet): AktivitetResponse {
+        return ArkiverAktivitet().let { arkivering -> nesteAktivitet(arkivering) }
+    }
+}
+
+class ArkiverAktivitetProcessor : AktivitetProcessor<ScopeFunctionTestBehandling, ArkiverAktivitet>() {
+    fun doProcess(aktivitet: ArkiverAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
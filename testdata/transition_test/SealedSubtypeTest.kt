@@ -0,0 +1,38 @@
+class SealedSubtypeTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaSoknadAktivitet {
+        return MottaSoknadAktivitet()
+    }
+}
+
+class MottaSoknadAktivitet : Aktivitet()
+
+enum class Steg(val aktivitet: KClass<out Aktivitet>) {
+    VURDER(VurderSoknadAktivitet::class),
+}
+
+// The transition targets the sealed base type; use --expand-subtypes to fan it out
+sealed class VurderSoknadAktivitet : Aktivitet()
+class VurderAlderspensjonAktivitet : VurderSoknadAktivitet()
+class VurderUforetrygdAktivitet : VurderSoknadAktivitet()
+
+class MottaSoknadAktivitetProcessor :
+    AktivitetProcessor<SealedSubtypeTestBehandling, MottaSoknadAktivitet>() {
+    fun doProcess(aktivitet: MottaSoknadAktivitet): AktivitetResponse {
+        // Which VurderSoknadAktivitet is created is only known at runtime
+        return nesteAktivitet(Steg.VURDER.opprett(behandling))
+    }
+}
+
+class VurderAlderspensjonAktivitetProcessor :
+    AktivitetProcessor<SealedSubtypeTestBehandling, VurderAlderspensjonAktivitet>() {
+    fun doProcess(aktivitet: VurderAlderspensjonAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class VurderUforetrygdAktivitetProcessor :
+    AktivitetProcessor<SealedSubtypeTestBehandling, VurderUforetrygdAktivitet>() {
+    fun doProcess(aktivitet: VurderUforetrygdAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,35 @@
+class SpreadArgumentTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = StartOppgjorAktivitet()
+}
+
+class StartOppgjorAktivitet : Aktivitet()
+class BeregnOppgjorAktivitet : Aktivitet()
+class SendOppgjorAktivitet : Aktivitet()
+class VarsleSamhandlerAktivitet : Aktivitet()
+
+class StartOppgjorAktivitetProcessor(
+    private val samhandlerService: SamhandlerService,
+) : AktivitetProcessor<SpreadArgumentTestBehandling, StartOppgjorAktivitet>() {
+    override fun doProcess(aktivitet: StartOppgjorAktivitet): AktivitetResponse {
+        val ekstraSteg = samhandlerService.stegFor(aktivitet.sak)
+        // One edge per constructor, and a dynamic one for the steg only known at runtime
+        return nesteAktiviteter(
+            BeregnOppgjorAktivitet(),
+            SendOppgjorAktivitet(),
+            *ekstraSteg.toTypedArray(),
+            *arrayOf(VarsleSamhandlerAktivitet()),
+        )
+    }
+}
+
+class BeregnOppgjorAktivitetProcessor : AktivitetProcessor<SpreadArgumentTestBehandling, BeregnOppgjorAktivitet>() {
+    override fun doProcess(aktivitet: BeregnOppgjorAktivitet) = aktivitetFullfort()
+}
+
+class SendOppgjorAktivitetProcessor : AktivitetProcessor<SpreadArgumentTestBehandling, SendOppgjorAktivitet>() {
+    override fun doProcess(aktivitet: SendOppgjorAktivitet) = aktivitetFullfort()
+}
+
+class VarsleSamhandlerAktivitetProcessor : AktivitetProcessor<SpreadArgumentTestBehandling, VarsleSamhandlerAktivitet>() {
+    override fun doProcess(aktivitet: VarsleSamhandlerAktivitet) = aktivitetFullfort()
+}
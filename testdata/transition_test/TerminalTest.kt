@@ -0,0 +1,37 @@
+class TerminalTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VurderSakAktivitet {
+        return VurderSakAktivitet()
+    }
+}
+
+class VurderSakAktivitet : Aktivitet()
+class IverksettSakAktivitet : Aktivitet()
+class UkjentStegAktivitet : Aktivitet()
+
+class VurderSakAktivitetProcessor : AktivitetProcessor<TerminalTestBehandling, VurderSakAktivitet>() {
+    fun doProcess(aktivitet: VurderSakAktivitet): AktivitetResponse {
+        return when {
+            aktivitet.sak.erInnvilget -> nesteAktivitet(IverksettSakAktivitet())
+            aktivitet.sak.erUkjent -> nesteAktivitet(UkjentStegAktivitet())
+            // Ends the flow directly, with a result
+            else -> aktivitetFullfort(Resultat.AVSLATT)
+        }
+    }
+}
+
+class IverksettSakAktivitetProcessor : AktivitetProcessor<TerminalTestBehandling, IverksettSakAktivitet>() {
+    fun doProcess(aktivitet: IverksettSakAktivitet): AktivitetResponse {
+        iverksett(aktivitet.sak)
+        return avslutt()
+    }
+
+    // Ends the flow through a helper
+    private fun avslutt(): AktivitetResponse = aktivitetFullfort(Resultat.INNVILGET)
+}
+
+// The next step is chosen in a way that cannot be followed: drawn as END?, not END
+class UkjentStegAktivitetProcessor : AktivitetProcessor<TerminalTestBehandling, UkjentStegAktivitet>() {
+    fun doProcess(aktivitet: UkjentStegAktivitet): AktivitetResponse {
+        return stegVelger.velg(aktivitet)
+    }
+}
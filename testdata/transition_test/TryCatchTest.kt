@@ -0,0 +1,50 @@
+class TryCatchTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): HentOpplysningerAktivitet {
+        return HentOpplysningerAktivitet()
+    }
+}
+
+class HentOpplysningerAktivitet : Aktivitet()
+class VurderOpplysningerAktivitet : Aktivitet()
+class VentPaaOpplysningerAktivitet : Aktivitet()
+class SendBrevAktivitet : Aktivitet()
+
+class HentOpplysningerAktivitetProcessor :
+    AktivitetProcessor<TryCatchTestBehandling, HentOpplysningerAktivitet>() {
+    fun doProcess(aktivitet: HentOpplysningerAktivitet): AktivitetResponse {
+        // A catch block with a transition: error path labeled "catch TimeoutException"
+        return try {
+            hentOpplysninger(behandling)
+            nesteAktivitet(VurderOpplysningerAktivitet())
+        } catch (e: TimeoutException) {
+            nesteAktivitet(VentPaaOpplysningerAktivitet())
+        }
+    }
+}
+
+class VurderOpplysningerAktivitetProcessor :
+    AktivitetProcessor<TryCatchTestBehandling, VurderOpplysningerAktivitet>() {
+    fun doProcess(aktivitet: VurderOpplysningerAktivitet): AktivitetResponse {
+        // A catch block creating a manuell behandling: error path to "Manuell behandling"
+        try {
+            vurder(behandling)
+        } catch (e: IllegalStateException) {
+            behandling.manuellBehandling = ManuellBehandling("Kunne ikke vurdere opplysningene")
+            return aktivitetFullfort()
+        }
+        return nesteAktivitet(SendBrevAktivitet())
+    }
+}
+
+class VentPaaOpplysningerAktivitetProcessor :
+    AktivitetProcessor<TryCatchTestBehandling, VentPaaOpplysningerAktivitet>() {
+    fun doProcess(aktivitet: VentPaaOpplysningerAktivitet): AktivitetResponse {
+        return nesteAktivitet(HentOpplysningerAktivitet())
+    }
+}
+
+class SendBrevAktivitetProcessor : AktivitetProcessor<TryCatchTestBehandling, SendBrevAktivitet>() {
+    fun doProcess(aktivitet: SendBrevAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
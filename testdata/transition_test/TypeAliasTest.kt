@@ -0,0 +1,24 @@
+class TypeAliasTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaSoknadAktivitet {
+        return MottaSoknadAktivitet()
+    }
+}
+
+class MottaSoknadAktivitet : Aktivitet()
+class VurderSoknadAktivitet : Aktivitet()
+
+// Both names refer to the same aktivitet and should be one node in the graph
+typealias Steg2 = VurderSoknadAktivitet
+
+class MottaSoknadAktivitetProcessor :
+    AktivitetProcessor<TypeAliasTestBehandling, MottaSoknadAktivitet>() {
+    fun doProcess(aktivitet: MottaSoknadAktivitet): AktivitetResponse {
+        return nesteAktivitet(VurderSoknadAktivitet())
+    }
+}
+
+class Steg2Processor : AktivitetProcessor<TypeAliasTestBehandling, Steg2>() {
+    fun doProcess(aktivitet: Steg2): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,43 @@
+class WaitTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaMeldingAktivitet {
+        return MottaMeldingAktivitet()
+    }
+}
+
+class MottaMeldingAktivitet : Aktivitet()
+class SjekkDokumentasjonAktivitet : Aktivitet()
+class IverksettEndringAktivitet : Aktivitet()
+class SendPurringAktivitet : Aktivitet()
+
+class MottaMeldingAktivitetProcessor : AktivitetProcessor<WaitTestBehandling, MottaMeldingAktivitet>() {
+    fun doProcess(aktivitet: MottaMeldingAktivitet): AktivitetResponse {
+        // Postponed transition, chained on nesteAktivitet
+        return nesteAktivitet(SjekkDokumentasjonAktivitet()).utsettTil(aktivitet.fristDato)
+    }
+}
+
+// Not named Vent*, but waits for the documentation before it runs again
+class SjekkDokumentasjonAktivitetProcessor : AktivitetProcessor<WaitTestBehandling, SjekkDokumentasjonAktivitet>() {
+    fun doProcess(aktivitet: SjekkDokumentasjonAktivitet): AktivitetResponse {
+        if (aktivitet.dokumentasjon.mottatt) {
+            // Postponed transition, as a named argument
+            return nesteAktivitet(IverksettEndringAktivitet(), ventTil = aktivitet.virkningsdato)
+        }
+        if (aktivitet.fristPassert) {
+            return nesteAktivitet(SendPurringAktivitet())
+        }
+        return ventTil(LocalDate.now().plusDays(1))
+    }
+}
+
+class IverksettEndringAktivitetProcessor : AktivitetProcessor<WaitTestBehandling, IverksettEndringAktivitet>() {
+    fun doProcess(aktivitet: IverksettEndringAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class SendPurringAktivitetProcessor : AktivitetProcessor<WaitTestBehandling, SendPurringAktivitet>() {
+    fun doProcess(aktivitet: SendPurringAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
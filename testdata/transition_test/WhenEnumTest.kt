@@ -0,0 +1,37 @@
+import no.nav.pensjon.KravType.ALDER
+import no.nav.pensjon.KravType.UFORE
+
+class WhenEnumTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): FordelKravAktivitet {
+        return FordelKravAktivitet()
+    }
+}
+
+class FordelKravAktivitet : Aktivitet()
+class BeregnAlderAktivitet : Aktivitet()
+class BeregnUforeAktivitet : Aktivitet()
+class BeregnBarnepensjonAktivitet : Aktivitet()
+class SaerskiltVurderingAktivitet : Aktivitet()
+
+class FordelKravAktivitetProcessor : AktivitetProcessor<WhenEnumTestBehandling, FordelKravAktivitet>() {
+    fun doProcess(aktivitet: FordelKravAktivitet): AktivitetResponse {
+        // Imported enum entries and qualified ones label the edge with the entry names only
+        return when (krav.type) {
+            ALDER -> nesteAktivitet(BeregnAlderAktivitet())
+            UFORE, KravType.UFORE_TILLEGG -> nesteAktivitet(BeregnUforeAktivitet())
+            else -> nesteAktivitet(FordelKravVidereAktivitet())
+        }
+    }
+}
+
+class FordelKravVidereAktivitet : Aktivitet()
+
+class FordelKravVidereAktivitetProcessor : AktivitetProcessor<WhenEnumTestBehandling, FordelKravVidereAktivitet>() {
+    fun doProcess(aktivitet: FordelKravVidereAktivitet): AktivitetResponse {
+        // Values that are not enum entries keep the subject in the label
+        return when (krav.antallBarn) {
+            0 -> nesteAktivitet(SaerskiltVurderingAktivitet())
+            else -> nesteAktivitet(BeregnBarnepensjonAktivitet())
+        }
+    }
+}
@@ -0,0 +1,72 @@
+class WhenTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): VelgSporAktivitet {
+        return VelgSporAktivitet()
+    }
+}
+
+class VelgSporAktivitet : Aktivitet()
+class VurderUforeAktivitet : Aktivitet()
+class VurderAlderAktivitet : Aktivitet()
+class VurderGjenlevendeAktivitet : Aktivitet()
+class ManuellVurderingAktivitet : Aktivitet()
+class VurderAlderFraTidligereAktivitet : Aktivitet()
+class IverksettAktivitet : Aktivitet()
+
+class VelgSporAktivitetProcessor : AktivitetProcessor<WhenTestBehandling, VelgSporAktivitet>() {
+    fun doProcess(aktivitet: VelgSporAktivitet): AktivitetResponse {
+        // when with a subject: constants, type tests and several conditions in one branch
+        return when (behandling.kravType) {
+            KravType.UFOREP -> nesteAktivitet(VurderUforeAktivitet())
+            KravType.AP, KravType.AFP -> nesteAktivitet(VurderAlderAktivitet())
+            is GjenlevendeKrav -> nesteAktivitet(VurderGjenlevendeAktivitet())
+            else -> nesteAktivitet(ManuellVurderingAktivitet())
+        }
+    }
+}
+
+class VurderAlderAktivitetProcessor : AktivitetProcessor<WhenTestBehandling, VurderAlderAktivitet>() {
+    fun doProcess(aktivitet: VurderAlderAktivitet): AktivitetResponse {
+        // when without a subject and with a block body
+        when {
+            behandling.alder in 62..66 -> {
+                nesteAktivitet(VurderAlderFraTidligereAktivitet())
+            }
+            behandling.harUttak -> nesteAktivitet(IverksettAktivitet())
+            else -> nesteAktivitet(ManuellVurderingAktivitet())
+        }
+    }
+}
+
+class VurderUforeAktivitetProcessor : AktivitetProcessor<WhenTestBehandling, VurderUforeAktivitet>() {
+    fun doProcess(aktivitet: VurderUforeAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettAktivitet())
+    }
+}
+
+class VurderGjenlevendeAktivitetProcessor :
+    AktivitetProcessor<WhenTestBehandling, VurderGjenlevendeAktivitet>() {
+    fun doProcess(aktivitet: VurderGjenlevendeAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettAktivitet())
+    }
+}
+
+class VurderAlderFraTidligereAktivitetProcessor :
+    AktivitetProcessor<WhenTestBehandling, VurderAlderFraTidligereAktivitet>() {
+    fun doProcess(aktivitet: VurderAlderFraTidligereAktivitet): AktivitetResponse {
+        return nesteAktivitet(IverksettAktivitet())
+    }
+}
+
+class ManuellVurderingAktivitetProcessor :
+    AktivitetProcessor<WhenTestBehandling, ManuellVurderingAktivitet>() {
+    fun doProcess(aktivitet: ManuellVurderingAktivitet): AktivitetResponse {
+        behandling.manuellBehandling = ManuellBehandling("Vurder kravet manuelt")
+        return aktivitetFullfort()
+    }
+}
+
+class IverksettAktivitetProcessor : AktivitetProcessor<WhenTestBehandling, IverksettAktivitet>() {
+    fun doProcess(aktivitet: IverksettAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,49 @@
+class ConstantTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): StartAktivitet {
+        return StartAktivitet()
+    }
+}
+
+class StartAktivitet : Aktivitet()
+class VurderAktivitet : Aktivitet()
+class BeregnAktivitet : Aktivitet()
+class IverksettAktivitet : Aktivitet()
+
+val NESTE_STEG = VurderAktivitet()
+
+enum class Steg(val aktivitet: () -> Aktivitet) {
+    BEREGN({ BeregnAktivitet() }),
+    IVERKSETT({ IverksettAktivitet() }),
+}
+
+class StartAktivitetProcessor : AktivitetProcessor<ConstantTestBehandling, StartAktivitet>() {
+    fun doProcess(aktivitet: StartAktivitet): AktivitetResponse {
+        // A top-level constant
+        return nesteAktivitet(NESTE_STEG)
+    }
+}
+
+class VurderAktivitetProcessor : AktivitetProcessor<ConstantTestBehandling, VurderAktivitet>() {
+    fun doProcess(aktivitet: VurderAktivitet): AktivitetResponse {
+        // An enum entry wrapping the constructor
+        return nesteAktivitet(Steg.BEREGN.aktivitet())
+    }
+}
+
+class BeregnAktivitetProcessor : AktivitetProcessor<ConstantTestBehandling, BeregnAktivitet>() {
+    fun doProcess(aktivitet: BeregnAktivitet): AktivitetResponse {
+        // A companion object constant
+        return nesteAktivitet(IVERKSETTING)
+    }
+
+    companion object {
+        private val IVERKSETTING = IverksettAktivitet()
+    }
+}
+
+class IverksettAktivitetProcessor :
+    AktivitetProcessor<ConstantTestBehandling, IverksettAktivitet>() {
+    fun doProcess(aktivitet: IverksettAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,55 @@
+class FactoryTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaKravAktivitet {
+        return MottaKravAktivitet()
+    }
+}
+
+class MottaKravAktivitet : Aktivitet()
+
+class VurderKravAktivitet(val krav: Krav) : Aktivitet() {
+    companion object {
+        // The argument is a constructor too; the factory decides the aktivitet
+        fun forKrav(krav: Krav): VurderKravAktivitet = VurderKravAktivitet(Krav(krav.id))
+    }
+}
+
+abstract class VedtakAktivitet : Aktivitet() {
+    companion object {
+        // Declared as the base class; the created subclass is the transition
+        fun innvilget(): VedtakAktivitet {
+            return InnvilgetVedtakAktivitet()
+        }
+    }
+}
+
+class InnvilgetVedtakAktivitet : VedtakAktivitet()
+
+object Aktiviteter {
+    fun avslutt() = AvsluttKravAktivitet()
+}
+
+class AvsluttKravAktivitet : Aktivitet()
+
+class MottaKravAktivitetProcessor : AktivitetProcessor<FactoryTestBehandling, MottaKravAktivitet>() {
+    fun doProcess(aktivitet: MottaKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(VurderKravAktivitet.forKrav(Krav(behandling.kravId)))
+    }
+}
+
+class VurderKravAktivitetProcessor : AktivitetProcessor<FactoryTestBehandling, VurderKravAktivitet>() {
+    fun doProcess(aktivitet: VurderKravAktivitet): AktivitetResponse {
+        return nesteAktivitet(VedtakAktivitet.innvilget())
+    }
+}
+
+class InnvilgetVedtakAktivitetProcessor : AktivitetProcessor<FactoryTestBehandling, InnvilgetVedtakAktivitet>() {
+    fun doProcess(aktivitet: InnvilgetVedtakAktivitet): AktivitetResponse {
+        return nesteAktivitet(Aktiviteter.avslutt())
+    }
+}
+
+class AvsluttKravAktivitetProcessor : AktivitetProcessor<FactoryTestBehandling, AvsluttKravAktivitet>() {
+    fun doProcess(aktivitet: AvsluttKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
@@ -0,0 +1,32 @@
+class DelegationTestBehandling : Behandling() {
+    fun opprettInitiellAktivitet(): MottaKravAktivitet {
+        return MottaKravAktivitet()
+    }
+}
+
+class MottaKravAktivitet : Aktivitet()
+class VurderKravAktivitet : Aktivitet()
+class AvvisKravAktivitet : Aktivitet()
+
+class MottaKravAktivitetProcessor(
+    private val overgangService: OvergangService,
+) : AktivitetProcessor<DelegationTestBehandling, MottaKravAktivitet>() {
+    fun doProcess(aktivitet: MottaKravAktivitet): AktivitetResponse {
+        // The next aktivitet is chosen by a shared service in another file
+        return overgangService.nesteSteg(behandling)
+    }
+}
+
+class VurderKravAktivitetProcessor :
+    AktivitetProcessor<DelegationTestBehandling, VurderKravAktivitet>() {
+    fun doProcess(aktivitet: VurderKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class AvvisKravAktivitetProcessor :
+    AktivitetProcessor<DelegationTestBehandling, AvvisKravAktivitet>() {
+    fun doProcess(aktivitet: AvvisKravAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
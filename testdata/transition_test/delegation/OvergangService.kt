@@ -0,0 +1,12 @@
+class OvergangService(
+    private val kravRepository: KravRepository,
+) {
+    fun nesteSteg(behandling: DelegationTestBehandling): AktivitetResponse {
+        if (kravRepository.erGyldig(behandling.kravId)) {
+            return nesteAktivitet(VurderKravAktivitet())
+        }
+        return avvis()
+    }
+
+    private fun avvis(): AktivitetResponse = nesteAktivitet(AvvisKravAktivitet())
+}
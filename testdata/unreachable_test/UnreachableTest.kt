@@ -0,0 +1,33 @@
+package no.nav.test.unreachable
+
+class UnreachableTestBehandling : Behandling() {
+    override fun opprettInitiellAktivitet() = StartAktivitet()
+}
+
+class StartAktivitet : Aktivitet()
+class AvsluttAktivitet : Aktivitet()
+
+// Started only by a processor that is itself never reached
+class GammelKontrollAktivitet : Aktivitet()
+class GammelOppfolgingAktivitet : Aktivitet()
+
+// Declared but neither started nor handled anywhere
+class GlemtAktivitet : Aktivitet()
+
+class StartProcessor : AktivitetProcessor<UnreachableTestBehandling, StartAktivitet>() {
+    override fun doProcess(behandling: UnreachableTestBehandling, aktivitet: StartAktivitet): AktivitetResponse {
+        return nesteAktivitet(AvsluttAktivitet())
+    }
+}
+
+class AvsluttProcessor : AktivitetProcessor<UnreachableTestBehandling, AvsluttAktivitet>() {
+    override fun doProcess(behandling: UnreachableTestBehandling, aktivitet: AvsluttAktivitet): AktivitetResponse {
+        return aktivitetFullfort()
+    }
+}
+
+class GammelKontrollProcessor : AktivitetProcessor<UnreachableTestBehandling, GammelKontrollAktivitet>() {
+    override fun doProcess(behandling: UnreachableTestBehandling, aktivitet: GammelKontrollAktivitet): AktivitetResponse {
+        return nesteAktivitet(GammelOppfolgingAktivitet())
+    }
+}